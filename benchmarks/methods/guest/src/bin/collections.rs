@@ -0,0 +1,64 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, mem::size_of};
+
+use risc0_zkvm::guest::{
+    collections::{Arena, ArenaMap},
+    env,
+};
+
+fn main() {
+    let (use_arena, n): (bool, u32) = env::read();
+    let checksum = if use_arena {
+        with_arena(n)
+    } else {
+        with_std(n)
+    };
+    env::commit(&checksum);
+}
+
+fn keys(n: u32) -> impl Iterator<Item = u32> {
+    (0..n).map(|i| i.wrapping_mul(0x9e37_79b9))
+}
+
+fn with_std(n: u32) -> u32 {
+    let n = n as usize;
+    let mut values = Vec::with_capacity(n);
+    for key in keys(n as u32) {
+        values.push(key);
+    }
+    let mut map = HashMap::with_capacity(n);
+    for (i, key) in values.iter().enumerate() {
+        map.insert(*key, i as u32);
+    }
+    values.iter().map(|key| map[key]).fold(0, u32::wrapping_add)
+}
+
+fn with_arena(n: u32) -> u32 {
+    let n = n as usize;
+    let arena = Arena::with_capacity(n * size_of::<u32>() + ArenaMap::<u32, u32>::bytes_for(n));
+    let mut values = arena.vec(n);
+    for key in keys(n as u32) {
+        values.push(key);
+    }
+    let mut map = arena.map(n);
+    for (i, key) in values.iter().enumerate() {
+        map.insert(*key, i as u32);
+    }
+    values
+        .iter()
+        .map(|key| map.get(key).unwrap())
+        .fold(0, |sum, value| sum.wrapping_add(*value))
+}
@@ -39,6 +39,7 @@ enum Command {
     BigBlake3,
     BigKeccak,
     BigSha2,
+    Collections,
     EcdsaVerify,
     Ed25519Verify,
     Fibonacci,
@@ -61,6 +62,7 @@ impl Command {
             Command::BigBlake3 => big_blake3::new_jobs(),
             Command::BigKeccak => big_keccak::new_jobs(),
             Command::BigSha2 => big_sha2::new_jobs(),
+            Command::Collections => collections::new_jobs(),
             Command::EcdsaVerify => ecdsa_verify::new_jobs(),
             Command::Ed25519Verify => ed25519_verify::new_jobs(),
             Command::Fibonacci => fibonacci::new_jobs(),
@@ -0,0 +1,34 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+#![no_std]
+
+use risc0_zkvm::guest::env;
+
+risc0_zkvm::guest::entry!(main);
+
+// The same computation as the unwind guest, built with panic=abort and without
+// catching the panic, to compare the cycle counts against.
+fn divide(dividend: u32, divisor: u32) -> u32 {
+    if divisor == 0 {
+        panic!("cannot divide {dividend} by zero");
+    }
+    dividend / divisor
+}
+
+fn main() {
+    let (dividend, divisor): (u32, u32) = env::read();
+    env::commit(&divide(dividend, divisor));
+}
@@ -0,0 +1,44 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+
+use risc0_zkvm::guest::{catch_unwind, env};
+
+risc0_zkvm::guest::entry!(main);
+
+fn divide(dividend: u32, divisor: u32) -> u32 {
+    if divisor == 0 {
+        panic!("cannot divide {dividend} by zero");
+    }
+    dividend / divisor
+}
+
+fn main() {
+    let (dividend, divisor): (u32, u32) = env::read();
+    // The panic payload is the formatted panic message.
+    let result: Result<u32, String> =
+        catch_unwind(|| divide(dividend, divisor)).map_err(|payload| {
+            payload
+                .downcast::<String>()
+                .map(|msg| *msg)
+                .unwrap_or_default()
+        });
+    env::commit(&result);
+}
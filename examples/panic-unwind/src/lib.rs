@@ -0,0 +1,51 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../README.md")]
+
+use panic_unwind_methods::UNWIND_ELF;
+use risc0_zkvm::{default_executor, ExecutorEnv, SessionInfo};
+
+/// Executes the given guest on `dividend / divisor`.
+pub fn execute(elf: &[u8], dividend: u32, divisor: u32) -> SessionInfo {
+    let env = ExecutorEnv::builder()
+        .write(&(dividend, divisor))
+        .unwrap()
+        .build()
+        .unwrap();
+    default_executor().execute(env, elf).unwrap()
+}
+
+/// Divides `dividend` by `divisor` in the unwind guest, which catches the panic on a zero
+/// divisor and commits its message.
+pub fn divide(dividend: u32, divisor: u32) -> (Result<u32, String>, SessionInfo) {
+    let session = execute(UNWIND_ELF, dividend, divisor);
+    (session.journal.decode().unwrap(), session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catches_panic() {
+        assert_eq!(divide(42, 6).0, Ok(7));
+
+        let (result, session) = divide(42, 0);
+        let msg = result.unwrap_err();
+        assert!(msg.contains("cannot divide 42 by zero"), "{msg}");
+        // The guest returned normally after catching the panic.
+        assert_eq!(session.exit_code, risc0_zkvm::ExitCode::Halted(0));
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use panic_unwind::{divide, execute};
+use panic_unwind_methods::{BASELINE_ELF, UNWIND_ELF};
+
+fn main() {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+
+    let baseline = execute(BASELINE_ELF, 42, 6);
+    let (ok, no_panic) = divide(42, 6);
+    let (err, caught) = divide(42, 0);
+
+    println!(
+        "ELF size: panic=abort {} bytes, panic=unwind {} bytes",
+        BASELINE_ELF.len(),
+        UNWIND_ELF.len()
+    );
+    println!("panic=abort, no panic: {} user cycles", baseline.cycles());
+    println!(
+        "panic=unwind, no panic: {} user cycles ({ok:?})",
+        no_panic.cycles()
+    );
+    println!(
+        "panic=unwind, caught panic: {} user cycles ({err:?})",
+        caught.cycles()
+    );
+}
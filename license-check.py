@@ -28,6 +28,7 @@ EXTENSIONS = [
 ]
 
 SKIP_DIRS = [
+    str(Path.cwd()) + "/risc0/cargo-risczero/templates/hosts",
     str(Path.cwd()) + "/risc0/cargo-risczero/templates/rust-starter",
     str(Path.cwd()) + "/risc0/sys/cxx/vendor",
     str(Path.cwd()) + "/risc0/zkvm/src/host/protos",
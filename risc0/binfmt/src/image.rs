@@ -14,13 +14,25 @@
 
 extern crate alloc;
 
-use alloc::{collections::BTreeMap, vec, vec::Vec};
+#[cfg(test)]
+use alloc::rc::Rc;
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
 
-use anyhow::{anyhow, ensure, Result};
+use anyhow::{anyhow, bail, ensure, Result};
 use risc0_zkp::core::{
     digest::Digest,
     hash::sha::{Impl, Sha256, BLOCK_BYTES, SHA256_INIT},
 };
+#[cfg(test)]
+use risc0_zkp::{
+    core::hash::{hash_suite_from_name, HashFn},
+    field::baby_bear::{BabyBear, BabyBearElem},
+};
 use risc0_zkvm_platform::{
     memory::{GUEST_MAX_MEM, MEM_SIZE, PAGE_TABLE},
     syscall::DIGEST_BYTES,
@@ -47,6 +59,11 @@ pub struct MemoryImage {
     pub pc: u32,
 }
 
+// Name of the hash function used for pages, which is the one checked by preflight and the rv32im
+// circuit. Other hash suites are only accepted by tests of the page table itself, until the prover
+// can check them.
+const DEFAULT_PAGE_HASHFN: &str = "sha-256";
+
 #[derive(Clone, Serialize, Deserialize)]
 struct PersistentPageTableInfo;
 
@@ -76,6 +93,8 @@ pub struct PageTableInfo {
     /// smaller than what the full number of entries could cover.
     pub num_root_entries: u32,
     _layers: Vec<u32>,
+    /// Name of the hash function used for the pages and the Merkle tree.
+    hashfn: String,
     /// Hash of an uninitialized page containing all zeros.
     zero_page_hash: Digest,
 }
@@ -122,6 +141,13 @@ impl Default for PageTableInfo {
 impl PageTableInfo {
     /// Crate a new page table info struct with the given address and page size.
     pub fn new(page_table_addr: u32, page_size: u32) -> Result<Self> {
+        Self::with_hashfn(page_table_addr, page_size, DEFAULT_PAGE_HASHFN)
+    }
+
+    // Crate a new page table info struct with the given address and page size, whose pages are
+    // hashed with the named hash function.
+    fn with_hashfn(page_table_addr: u32, page_size: u32, hashfn: &str) -> Result<Self> {
+        let hasher = PageHasher::new(hashfn)?;
         let max_mem = page_table_addr;
         ensure!(max_mem >= page_size, "Max memory must be at least one page");
         ensure!(
@@ -148,7 +174,7 @@ impl PageTableInfo {
         let root_page_addr = root_idx * page_size;
         let num_root_entries = (root_addr - root_page_addr) / DIGEST_BYTES as u32;
         ensure!(root_idx == num_pages, "Invalid root index");
-        let zero_page_hash = hash_page_bytes(&vec![0_u8; page_size as usize]);
+        let zero_page_hash = hasher.hash(&vec![0_u8; page_size as usize]);
 
         tracing::debug!("root_page_addr: 0x{root_page_addr:08x}, root_addr: 0x{root_addr:08x}");
 
@@ -163,10 +189,16 @@ impl PageTableInfo {
             num_pages,
             num_root_entries,
             _layers: layers,
+            hashfn: hashfn.to_string(),
             zero_page_hash,
         })
     }
 
+    #[cfg(test)]
+    fn hashfn(&self) -> &str {
+        &self.hashfn
+    }
+
     /// Calculate the page address given its index.
     pub fn get_page_addr(&self, page_idx: u32) -> u32 {
         page_idx * self.page_size
@@ -190,8 +222,14 @@ impl MemoryImage {
     /// execution not yet begun), and with the page table Merkle tree
     /// constructed.
     pub fn new(program: &Program, page_size: u32) -> Result<Self> {
+        Self::with_hashfn(program, page_size, DEFAULT_PAGE_HASHFN)
+    }
+
+    // Construct the initial memory image for `program`, with pages hashed by the named hash
+    // function.
+    fn with_hashfn(program: &Program, page_size: u32, hashfn: &str) -> Result<Self> {
         // Compute the page table hashes except for the very last root hash.
-        let info = PageTableInfo::new(PAGE_TABLE.start() as u32, page_size)?;
+        let info = PageTableInfo::with_hashfn(PAGE_TABLE.start() as u32, page_size, hashfn)?;
         let mut img = Self {
             pages: BTreeMap::new(),
             info,
@@ -265,22 +303,33 @@ impl MemoryImage {
     /// Calculate and update the image merkle tree within this image based on
     /// the supplied page indices.
     pub fn hash_pages_iter<I: Iterator<Item = u32>>(&mut self, iter: I) {
+        let hasher = self.hasher();
         for page_idx in iter {
-            self.update_page(page_idx);
+            self.update_page_with(&hasher, page_idx);
         }
     }
 
     /// Calculate and update the image merkle tree within this image based on
     /// the supplied page index.
     pub fn update_page(&mut self, page_idx: u32) {
-        let digest = self.hash_page(page_idx);
+        let hasher = self.hasher();
+        self.update_page_with(&hasher, page_idx);
+    }
+
+    fn update_page_with(&mut self, hasher: &PageHasher, page_idx: u32) {
+        let digest = self.hash_page(hasher, page_idx);
         let entry_addr = self.info.get_page_entry_addr(page_idx);
         self.store_region_in_page(entry_addr, digest.as_bytes());
     }
 
-    fn hash_page(&self, page_idx: u32) -> Digest {
+    fn hasher(&self) -> PageHasher {
+        // The name was checked when the PageTableInfo was constructed.
+        PageHasher::new(&self.info.hashfn).unwrap()
+    }
+
+    fn hash_page(&self, hasher: &PageHasher, page_idx: u32) -> Digest {
         if let Some(page) = self.pages.get(&page_idx) {
-            hash_page_bytes(page)
+            hasher.hash(page)
         } else {
             self.info.zero_page_hash
         }
@@ -293,10 +342,11 @@ impl MemoryImage {
     /// entry.
     #[cfg(test)]
     fn check(&self, addr: u32) -> Result<()> {
+        let hasher = self.hasher();
         let mut page_idx = self.info.get_page_index(addr);
         while page_idx < self.info.root_idx {
             let page_addr = self.info.get_page_addr(page_idx);
-            let expected = self.hash_page(page_idx);
+            let expected = self.hash_page(&hasher, page_idx);
             let entry_addr = self.info.get_page_entry_addr(page_idx);
             let mut entry = [0_u8; DIGEST_BYTES];
             self.load_region_in_page(entry_addr, &mut entry)?;
@@ -314,7 +364,7 @@ impl MemoryImage {
         let root_page_bytes = self.info.num_root_entries * DIGEST_BYTES as u32;
         let mut root_page = vec![0_u8; root_page_bytes as usize];
         self.load_region_in_page(root_page_addr, &mut root_page)?;
-        let expected = hash_page_bytes(&root_page);
+        let expected = hasher.hash(&root_page);
         let root = self.compute_root_hash();
         if expected != root {
             anyhow::bail!("Invalid root hash: {} != {}", expected, root);
@@ -329,7 +379,8 @@ impl MemoryImage {
             .pages
             .get(&self.info.root_idx)
             .expect("Missing root page?");
-        hash_page_bytes(&root_page[..(self.info.root_addr - self.info.root_page_addr) as usize])
+        self.hasher()
+            .hash(&root_page[..(self.info.root_addr - self.info.root_page_addr) as usize])
     }
 
     /// Compute and return the ImageID of this image.
@@ -346,27 +397,64 @@ impl MemoryImage {
     }
 }
 
-fn hash_page_bytes(page: &[u8]) -> Digest {
-    let mut state = SHA256_INIT;
-    assert!(page.len() % BLOCK_BYTES == 0);
-    for block in page.chunks_exact(BLOCK_BYTES) {
-        let block1 = Digest::try_from(&block[0..DIGEST_BYTES]).unwrap();
-        let block2 = Digest::try_from(&block[DIGEST_BYTES..BLOCK_BYTES]).unwrap();
-        state = *Impl::compress(&state, &block1, &block2);
+/// The hash function applied to pages, selected by name from the prover's hash suites.
+enum PageHasher {
+    /// SHA-256 compression over the page without padding, as checked by the rv32im circuit.
+    Sha256,
+    /// An algebraic hash over the page as 16-bit little-endian limbs, one per field element.
+    #[cfg(test)]
+    Field(Rc<dyn HashFn<BabyBear>>),
+}
+
+impl PageHasher {
+    fn new(hashfn: &str) -> Result<Self> {
+        if hashfn == DEFAULT_PAGE_HASHFN {
+            return Ok(Self::Sha256);
+        }
+        // Images hashed with anything else could not be proven, nor persisted, since the
+        // serialized page table does not record the hash function.
+        #[cfg(test)]
+        if let Some(suite) = hash_suite_from_name(hashfn) {
+            return Ok(Self::Field(suite.hashfn));
+        }
+        bail!("Unsupported page hash function: {hashfn}")
+    }
+
+    fn hash(&self, page: &[u8]) -> Digest {
+        match self {
+            Self::Sha256 => {
+                let mut state = SHA256_INIT;
+                assert!(page.len() % BLOCK_BYTES == 0);
+                for block in page.chunks_exact(BLOCK_BYTES) {
+                    let block1 = Digest::try_from(&block[0..DIGEST_BYTES]).unwrap();
+                    let block2 = Digest::try_from(&block[DIGEST_BYTES..BLOCK_BYTES]).unwrap();
+                    state = *Impl::compress(&state, &block1, &block2);
+                }
+                state
+            }
+            #[cfg(test)]
+            Self::Field(hashfn) => {
+                let elems: Vec<BabyBearElem> = page
+                    .chunks_exact(2)
+                    .map(|limb| BabyBearElem::new(u16::from_le_bytes([limb[0], limb[1]]) as u32))
+                    .collect();
+                *hashfn.hash_elem_slice(&elems)
+            }
+        }
     }
-    state
 }
 
 #[cfg(test)]
 mod tests {
-    use risc0_zkvm_methods::MULTI_TEST_ELF;
+    use risc0_zkvm_methods::{MULTI_TEST_ELF, MULTI_TEST_ID};
     use risc0_zkvm_platform::{
         memory::{GUEST_MAX_MEM, PAGE_TABLE, STACK_TOP, SYSTEM, TEXT_START},
         syscall::DIGEST_BYTES,
     };
     use test_log::test;
 
-    use crate::{elf::Program, image::PageTableInfo, MemoryImage};
+    use super::DEFAULT_PAGE_HASHFN;
+    use crate::{compute_image_id, elf::Program, image::PageTableInfo, MemoryImage};
 
     fn page_table_size(max_mem: u32, page_size: u32) -> u32 {
         PageTableInfo::new(max_mem, page_size)
@@ -391,6 +479,23 @@ mod tests {
         image.check(image.info.root_page_addr).unwrap();
     }
 
+    #[test]
+    fn check_integrity_poseidon2() {
+        const PAGE_SIZE: u32 = 1024;
+        let program = Program::load_elf(MULTI_TEST_ELF, GUEST_MAX_MEM as u32).unwrap();
+        let image = MemoryImage::with_hashfn(&program, PAGE_SIZE, "poseidon2").unwrap();
+        assert_eq!(image.info.hashfn(), "poseidon2");
+        image.check(TEXT_START).unwrap();
+        image.check(STACK_TOP).unwrap();
+        image.check(image.info.root_page_addr).unwrap();
+
+        let sha_image = MemoryImage::new(&program, PAGE_SIZE).unwrap();
+        assert_eq!(sha_image.info.hashfn(), DEFAULT_PAGE_HASHFN);
+        assert_ne!(image.compute_id(), sha_image.compute_id());
+
+        assert!(MemoryImage::with_hashfn(&program, PAGE_SIZE, "md5").is_err());
+    }
+
     #[test]
     fn page_table_info() {
         const PAGE_SIZE_1K: u32 = 1024;
@@ -502,4 +607,11 @@ mod tests {
         let prog = Program::load_elf(data, GUEST_MAX_MEM as u32).unwrap();
         MemoryImage::new(&prog, PAGE_SIZE).unwrap();
     }
+
+    #[test]
+    fn image_id_from_elf() {
+        let image_id = compute_image_id(MULTI_TEST_ELF).unwrap();
+        assert_eq!(image_id, MULTI_TEST_ID.into());
+        assert!(compute_image_id(b"not an elf").is_err());
+    }
 }
@@ -36,6 +36,22 @@ pub use crate::{
 };
 
 /// Compute and return the ImageID of the specified ELF binary.
+///
+/// The image ID is the digest of the initial state of the zkVM when running the given guest
+/// program, and is the same value as the `<METHOD>_ID` constants generated by `risc0-build`. It is
+/// derived from the ELF alone, so a receipt can be checked against a guest program received as
+/// an artifact without building it.
+///
+/// Returns an error if the ELF is malformed or does not fit in guest memory.
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// let elf = std::fs::read("target/riscv-guest/riscv32im-risc0-zkvm-elf/release/my_guest")?;
+/// let image_id = risc0_binfmt::compute_image_id(&elf)?;
+/// println!("{image_id}");
+/// # Ok(())
+/// # }
+/// ```
 #[cfg(not(target_os = "zkvm"))]
 pub fn compute_image_id(elf: &[u8]) -> anyhow::Result<risc0_zkp::core::digest::Digest> {
     use risc0_zkvm_platform::{memory::GUEST_MAX_MEM, PAGE_SIZE};
@@ -44,3 +60,48 @@ pub fn compute_image_id(elf: &[u8]) -> anyhow::Result<risc0_zkp::core::digest::D
     let image = MemoryImage::new(&program, PAGE_SIZE as u32)?;
     Ok(image.compute_id())
 }
+
+/// Name of the ELF section in which a guest built with `GuestOptions::source_digest` in
+/// `risc0-build` embeds the digest of its source inputs.
+pub const SOURCE_DIGEST_SECTION: &str = ".risc0.source_digest";
+
+/// Return the digest of the source inputs embedded in the [SOURCE_DIGEST_SECTION] of the specified
+/// ELF binary, if the guest was built with one.
+///
+/// The digest is read from the initial memory image, so it is committed to by the image ID, and
+/// hence by the claim of any receipt for the guest. It states which sources the guest was built
+/// from, as claimed by whoever built it: a reproducible-build audit confirms it by rebuilding the
+/// guest from those sources and comparing the image IDs.
+#[cfg(not(target_os = "zkvm"))]
+pub fn source_digest(elf: &[u8]) -> anyhow::Result<Option<risc0_zkp::core::digest::Digest>> {
+    use anyhow::{anyhow, ensure, Context};
+    use elf::{abi::SHF_ALLOC, endian::LittleEndian, ElfBytes};
+    use risc0_zkp::core::digest::{Digest, DIGEST_WORDS};
+    use risc0_zkvm_platform::{memory::GUEST_MAX_MEM, WORD_SIZE};
+
+    let parsed = ElfBytes::<LittleEndian>::minimal_parse(elf)
+        .map_err(|err| anyhow!("Elf parse error: {err}"))?;
+    let header = parsed
+        .section_header_by_name(SOURCE_DIGEST_SECTION)
+        .map_err(|err| anyhow!("Elf parse error: {err}"))?;
+    let Some(header) = header.filter(|header| header.sh_size != 0) else {
+        return Ok(None);
+    };
+    ensure!(
+        header.sh_flags & SHF_ALLOC as u64 != 0
+            && header.sh_size == (DIGEST_WORDS * WORD_SIZE) as u64
+            && header.sh_addr % WORD_SIZE as u64 == 0,
+        "Invalid {SOURCE_DIGEST_SECTION} section"
+    );
+
+    let program = Program::load_elf(elf, GUEST_MAX_MEM as u32)?;
+    let mut words = [0u32; DIGEST_WORDS];
+    for (i, word) in words.iter_mut().enumerate() {
+        let addr = header.sh_addr as u32 + (i * WORD_SIZE) as u32;
+        *word = *program
+            .image
+            .get(&addr)
+            .with_context(|| format!("{SOURCE_DIGEST_SECTION} section is not loaded"))?;
+    }
+    Ok(Some(Digest::from(words)))
+}
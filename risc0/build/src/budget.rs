@@ -0,0 +1,231 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Enforcement of the size budget set in [GuestOptions](crate::GuestOptions).
+
+use std::{collections::BTreeSet, fmt::Write as _, path::Path};
+
+use anyhow::Result;
+use elf::{endian::LittleEndian, ElfBytes};
+use risc0_binfmt::Program;
+use risc0_zkvm_platform::{memory::GUEST_MAX_MEM, PAGE_SIZE};
+use rustc_demangle::demangle;
+
+use crate::{config::GuestBuildOptions, BuildError};
+
+/// Number of symbols listed when a guest exceeds its budget.
+const LARGEST_SYMBOLS: usize = 10;
+
+/// Checks the ELF at the given path against the budget in the options, returning an error with a
+/// report of the largest symbols if it is exceeded.
+pub(crate) fn check_elf_budget(
+    name: &str,
+    elf_path: &Path,
+    opts: &GuestBuildOptions,
+) -> Result<()> {
+    if opts.max_elf_size.is_none() && opts.max_image_pages.is_none() {
+        return Ok(());
+    }
+
+    let elf = std::fs::read(elf_path)?;
+    let mut violations = Vec::new();
+    if let Some(max_elf_size) = opts.max_elf_size {
+        if elf.len() > max_elf_size {
+            violations.push(format!(
+                "ELF is {} bytes, exceeding max_elf_size of {max_elf_size} bytes",
+                elf.len()
+            ));
+        }
+    }
+    if let Some(max_image_pages) = opts.max_image_pages {
+        let pages = image_pages(&elf)?;
+        if pages > max_image_pages {
+            violations.push(format!(
+                "memory image occupies {pages} pages, exceeding max_image_pages of {max_image_pages}"
+            ));
+        }
+    }
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    let mut report = format!("guest '{name}' exceeds its size budget:\n");
+    for violation in violations {
+        writeln!(report, "  {violation}")?;
+    }
+    writeln!(report, "largest symbols:")?;
+    for (size, symbol) in largest_symbols(&elf)? {
+        writeln!(report, "  {size:>10}  {symbol}")?;
+    }
+    Err(BuildError::BudgetExceeded(report).into())
+}
+
+/// Returns the number of memory pages occupied by the program loaded from the given ELF.
+fn image_pages(elf: &[u8]) -> Result<usize> {
+    let program = Program::load_elf(elf, GUEST_MAX_MEM as u32)?;
+    let pages: BTreeSet<_> = program
+        .image
+        .keys()
+        .map(|addr| *addr as usize / PAGE_SIZE)
+        .collect();
+    Ok(pages.len())
+}
+
+/// Returns the sizes and demangled names of the largest symbols in the given ELF.
+fn largest_symbols(elf: &[u8]) -> Result<Vec<(u64, String)>> {
+    let mut symbols = Vec::new();
+    let elf = ElfBytes::<LittleEndian>::minimal_parse(elf)?;
+    if let Some((symtab, strtab)) = elf.symbol_table()? {
+        for sym in symtab.iter().filter(|sym| sym.st_size > 0) {
+            let name = strtab.get(sym.st_name as usize)?;
+            symbols.push((sym.st_size, format!("{:#}", demangle(name))));
+        }
+    }
+    symbols.sort_by(|a, b| b.cmp(a));
+    symbols.truncate(LARGEST_SYMBOLS);
+    Ok(symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use risc0_zkvm_platform::PAGE_SIZE;
+    use tempfile::NamedTempFile;
+
+    use super::check_elf_budget;
+    use crate::config::GuestBuildOptions;
+
+    const BASE_ADDR: u32 = 0x0001_0000;
+
+    // Builds a RISC-V executable with a single zero-filled segment of `mem_size` bytes at
+    // `BASE_ADDR`, and a symbol table with the given symbols.
+    fn test_elf(mem_size: u32, symbols: &[(&str, u32)]) -> NamedTempFile {
+        const EHDR_SIZE: u32 = 52;
+        const PHDR_SIZE: u32 = 32;
+        const SHDR_SIZE: u32 = 40;
+        const SYM_SIZE: u32 = 16;
+
+        let mut strtab = vec![0u8];
+        let mut symtab = vec![0u8; SYM_SIZE as usize];
+        for (name, size) in symbols {
+            let st_name = strtab.len() as u32;
+            strtab.extend_from_slice(name.as_bytes());
+            strtab.push(0);
+            symtab.extend_from_slice(&st_name.to_le_bytes());
+            symtab.extend_from_slice(&BASE_ADDR.to_le_bytes());
+            symtab.extend_from_slice(&size.to_le_bytes());
+            // STB_GLOBAL, STT_FUNC, in section 1.
+            symtab.extend_from_slice(&[0x12, 0, 1, 0]);
+        }
+        strtab.resize(strtab.len().next_multiple_of(4), 0);
+        let strtab_offset = EHDR_SIZE + PHDR_SIZE;
+        let symtab_offset = strtab_offset + strtab.len() as u32;
+        let shdr_offset = symtab_offset + symtab.len() as u32;
+
+        let mut elf = Vec::new();
+        // ELF header: 32-bit, little endian, executable, RISC-V.
+        elf.extend_from_slice(&[0x7f, b'E', b'L', b'F', 1, 1, 1, 0]);
+        elf.extend_from_slice(&[0; 8]);
+        elf.extend_from_slice(&2u16.to_le_bytes());
+        elf.extend_from_slice(&243u16.to_le_bytes());
+        elf.extend_from_slice(&1u32.to_le_bytes());
+        elf.extend_from_slice(&BASE_ADDR.to_le_bytes());
+        elf.extend_from_slice(&EHDR_SIZE.to_le_bytes());
+        elf.extend_from_slice(&shdr_offset.to_le_bytes());
+        elf.extend_from_slice(&0u32.to_le_bytes());
+        for half in [EHDR_SIZE, PHDR_SIZE, 1, SHDR_SIZE, 3, 0] {
+            elf.extend_from_slice(&(half as u16).to_le_bytes());
+        }
+        // PT_LOAD segment with no file contents.
+        for word in [1, 0, BASE_ADDR, BASE_ADDR, 0, mem_size, 6, 4] {
+            elf.extend_from_slice(&word.to_le_bytes());
+        }
+        elf.extend_from_slice(&strtab);
+        elf.extend_from_slice(&symtab);
+        // Section headers: null, .strtab, and .symtab linked to .strtab.
+        elf.extend_from_slice(&[0; SHDR_SIZE as usize]);
+        for word in [0, 3, 0, 0, strtab_offset, strtab.len() as u32, 0, 0, 1, 0] {
+            elf.extend_from_slice(&word.to_le_bytes());
+        }
+        for word in [
+            0,
+            2,
+            0,
+            0,
+            symtab_offset,
+            symtab.len() as u32,
+            1,
+            1,
+            4,
+            SYM_SIZE,
+        ] {
+            elf.extend_from_slice(&word.to_le_bytes());
+        }
+
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&elf).unwrap();
+        file
+    }
+
+    fn budget(max_elf_size: Option<usize>, max_image_pages: Option<usize>) -> GuestBuildOptions {
+        GuestBuildOptions {
+            max_elf_size,
+            max_image_pages,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn under_budget() {
+        let elf = test_elf(3 * PAGE_SIZE as u32, &[("main", 64)]);
+        let len = elf.as_file().metadata().unwrap().len() as usize;
+        check_elf_budget("guest", elf.path(), &budget(Some(len), Some(3))).unwrap();
+
+        // Without a budget the ELF is not even read.
+        check_elf_budget("guest", "missing.elf".as_ref(), &budget(None, None)).unwrap();
+    }
+
+    #[test]
+    fn over_elf_size() {
+        let elf = test_elf(
+            PAGE_SIZE as u32,
+            &[("small", 8), ("large", 4096), ("medium", 512)],
+        );
+        let len = elf.as_file().metadata().unwrap().len() as usize;
+        let err = check_elf_budget("guest", elf.path(), &budget(Some(len - 1), Some(1)))
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("guest 'guest' exceeds its size budget:"));
+        assert!(err.contains(&format!("ELF is {len} bytes, exceeding max_elf_size")));
+        assert!(!err.contains("max_image_pages"));
+
+        // The largest symbols are listed first.
+        let large = err.find("  large\n").unwrap();
+        let medium = err.find("  medium\n").unwrap();
+        let small = err.find("  small\n").unwrap();
+        assert!(large < medium && medium < small);
+    }
+
+    #[test]
+    fn over_image_pages() {
+        let elf = test_elf(3 * PAGE_SIZE as u32, &[("main", 64)]);
+        let err = check_elf_budget("guest", elf.path(), &budget(None, Some(2)))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("memory image occupies 3 pages, exceeding max_image_pages of 2"));
+        assert!(!err.contains("max_elf_size"));
+        assert!(err.contains("  main\n"));
+    }
+}
@@ -16,6 +16,8 @@ use cargo_metadata::Package;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::PANIC_UNWIND_FLAGS;
+
 /// Options for configuring a docker build environment.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct DockerOptions {
@@ -34,6 +36,50 @@ pub struct GuestOptions {
 
     /// Use a docker environment for building.
     pub use_docker: Option<DockerOptions>,
+
+    /// Fail the build if the guest ELF is larger than this many bytes.
+    pub max_elf_size: Option<usize>,
+
+    /// Fail the build if the program loaded from the guest ELF occupies more than this many
+    /// memory pages.
+    pub max_image_pages: Option<usize>,
+
+    /// Gate the constants generated for each guest binary `foo` in this package behind a
+    /// `method-foo` feature of the methods crate, which must be declared in its `Cargo.toml`.
+    ///
+    /// Hosts which embed many large guests can then enable only the methods each binary needs,
+    /// so that the ELFs of the others are not compiled in. Gated methods are not listed in the
+    /// `methods.json` manifest.
+    pub method_features: bool,
+
+    /// Build the guest with `panic=unwind` instead of `panic=abort`, so that panics can be caught
+    /// with `risc0_zkvm::guest::catch_unwind`.
+    ///
+    /// The panic runtime of `std` aborts on the zkVM target, so the guest must be `no_std` and
+    /// enable the `unwind` feature of `risc0-zkvm`, which provides the unwinder. Guests and
+    /// libraries which call `std::panic::catch_unwind` are not supported; they fail to build with
+    /// this option, and abort on a panic without it.
+    ///
+    /// The prebuilt `core` and `alloc` of the toolchain are built with `panic=abort`, so the
+    /// `RISC0_RUST_SRC` environment variable must point at the source tree of the risc0 Rust
+    /// toolchain, from which they are rebuilt.
+    ///
+    /// Code that does not panic runs as before, but the unwind tables enlarge the guest ELF, so
+    /// more pages are loaded during execution, and each caught panic costs cycles proportional to
+    /// the depth of the unwound stack. The `panic-unwind` example reports both for a small guest.
+    pub panic_unwind: bool,
+
+    /// Embed a digest of the sources of the guest package, its path dependencies and the
+    /// `Cargo.lock` in the guest ELF.
+    ///
+    /// The digest is placed in a loaded section of the ELF by the `entry!` macro of `risc0-zkvm`,
+    /// so it is part of the image ID, and a receipt can be linked back to the exact source inputs
+    /// of its image ID when auditing a reproducible build. See [crate::source_digest] for the files
+    /// included. The host reads it from the ELF with `risc0_binfmt::source_digest`.
+    ///
+    /// The guest does not commit the digest: it is bound to the receipt through the image ID, so
+    /// committing it would only change the journal of every guest built with this option.
+    pub source_digest: bool,
 }
 
 /// Metadata defining options to build a guest
@@ -65,6 +111,21 @@ pub(crate) struct GuestBuildOptions {
 
     /// Configuration flags to build the guest with.
     pub(crate) rustc_flags: Vec<String>,
+
+    /// Maximum size of the guest ELF in bytes.
+    pub(crate) max_elf_size: Option<usize>,
+
+    /// Maximum number of memory pages occupied by the guest program.
+    pub(crate) max_image_pages: Option<usize>,
+
+    /// Gate the generated code for each method behind a `method-<name>` feature.
+    pub(crate) method_features: bool,
+
+    /// Build the guest with `panic=unwind`.
+    pub(crate) panic_unwind: bool,
+
+    /// Embed a digest of the guest sources in the guest ELF.
+    pub(crate) source_digest: bool,
 }
 
 impl From<GuestOptions> for GuestBuildOptions {
@@ -72,6 +133,11 @@ impl From<GuestOptions> for GuestBuildOptions {
         Self {
             features: value.features,
             use_docker: value.use_docker,
+            max_elf_size: value.max_elf_size,
+            max_image_pages: value.max_image_pages,
+            method_features: value.method_features,
+            panic_unwind: value.panic_unwind,
+            source_digest: value.source_digest,
             ..Default::default()
         }
     }
@@ -82,4 +148,13 @@ impl GuestBuildOptions {
         self.rustc_flags = metadata.rustc_flags.unwrap_or_default();
         self
     }
+
+    /// The rustc flags to build the guest with, including those implied by the options.
+    pub(crate) fn rust_flags(&self) -> Vec<&str> {
+        let mut flags: Vec<&str> = self.rustc_flags.iter().map(|s| s.as_str()).collect();
+        if self.panic_unwind {
+            flags.extend(PANIC_UNWIND_FLAGS);
+        }
+        flags
+    }
 }
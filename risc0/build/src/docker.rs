@@ -18,11 +18,15 @@ use anyhow::{bail, Context, Result};
 use cargo_metadata::MetadataCommand;
 use docker_generate::DockerFile;
 use risc0_binfmt::{MemoryImage, Program};
+use risc0_zkp::core::digest::Digest;
 use risc0_zkvm_platform::{memory::GUEST_MAX_MEM, PAGE_SIZE};
 use tempfile::tempdir;
 
+use crate::budget::check_elf_budget;
 use crate::config::GuestBuildOptions;
-use crate::{encode_rust_flags, get_env_var, GuestOptions};
+use crate::{
+    encode_rust_flags, get_env_var, source_digest, BuildError, GuestOptions, SOURCE_DIGEST_ENV,
+};
 
 const DOCKER_IGNORE: &str = r#"
 **/Dockerfile
@@ -79,22 +83,36 @@ pub(crate) fn build_guest_package_docker(
     if !Command::new("docker")
         .arg("--version")
         .status()
-        .context("Could not find or execute docker")?
+        .map_err(|err| BuildError::DockerUnavailable(Some(err)))?
         .success()
     {
-        bail!("`docker --version` failed");
+        bail!(BuildError::DockerUnavailable(None));
     }
 
     if let Err(err) = check_cargo_lock(&manifest_path) {
         eprintln!("{err}");
     }
 
+    let source_digest = guest_opts
+        .source_digest
+        .then(|| source_digest(manifest_path.parent().unwrap()))
+        .transpose()?;
+    if let Some(digest) = source_digest {
+        eprintln!("Source digest: {digest}");
+    }
+
     let pkg_name = pkg_name.replace('-', "_");
     {
         let temp_dir = tempdir()?;
         let temp_path = temp_dir.path();
         let rel_manifest_path = manifest_path.strip_prefix(&src_dir)?;
-        create_dockerfile(rel_manifest_path, temp_path, pkg_name.as_str(), guest_opts)?;
+        create_dockerfile(
+            rel_manifest_path,
+            temp_path,
+            pkg_name.as_str(),
+            guest_opts,
+            source_digest,
+        )?;
         build(&src_dir, temp_path)?;
     }
     println!("ELFs ready at:");
@@ -102,6 +120,7 @@ pub(crate) fn build_guest_package_docker(
     let target_dir = src_dir.join(TARGET_DIR);
     for target in root_pkg.targets.iter().filter(|t| t.is_bin()) {
         let elf_path = target_dir.join(&pkg_name).join(&target.name);
+        check_elf_budget(&target.name, &elf_path, guest_opts)?;
         let image_id = compute_image_id(&elf_path)?;
         let rel_elf_path = Path::new(TARGET_DIR).join(&pkg_name).join(&target.name);
         println!("ImageID: {} - {:?}", image_id, rel_elf_path);
@@ -118,15 +137,10 @@ fn create_dockerfile(
     temp_dir: &Path,
     pkg_name: &str,
     guest_opts: &GuestBuildOptions,
+    source_digest: Option<Digest>,
 ) -> Result<()> {
     let manifest_env = &[("CARGO_MANIFEST_PATH", manifest_path.to_str().unwrap())];
-    let encoded_rust_flags = encode_rust_flags(
-        &guest_opts
-            .rustc_flags
-            .iter()
-            .map(|s| s.as_str())
-            .collect::<Vec<_>>(),
-    );
+    let encoded_rust_flags = encode_rust_flags(&guest_opts.rust_flags());
     let rustflags_env = &[("CARGO_ENCODED_RUSTFLAGS", encoded_rust_flags.as_str())];
 
     let common_args = vec![
@@ -154,13 +168,17 @@ fn create_dockerfile(
     .concat()
     .join(" ");
 
-    let build = DockerFile::new()
+    let mut build = DockerFile::new()
         .from_alias("build", "risczero/risc0-guest-builder:r0.1.79.0-2")
         .workdir("/src")
         .copy(".", ".")
         .env(manifest_env)
         .env(rustflags_env)
-        .env(&[("CARGO_TARGET_DIR", "target")])
+        .env(&[("CARGO_TARGET_DIR", "target")]);
+    if let Some(digest) = source_digest {
+        build = build.env(&[(SOURCE_DIGEST_ENV, digest.to_string().as_str())]);
+    }
+    let build = build
         // Fetching separately allows docker to cache the downloads, assuming the Cargo.lock
         // doesn't change.
         .run(&fetch_cmd)
@@ -201,7 +219,7 @@ fn build(src_dir: &Path, temp_dir: &Path) -> Result<()> {
     {
         Ok(())
     } else {
-        Err(anyhow::anyhow!("docker build failed"))
+        Err(BuildError::DockerBuildFailed.into())
     }
 }
 
@@ -0,0 +1,61 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fmt, io};
+
+/// An error returned by the functions of this crate, which is assigned a stable numeric code by
+/// `risc0_zkvm::ErrorCode`.
+///
+/// Other errors, such as I/O errors or failures to read a Cargo manifest, are returned as is and
+/// have no code. Build scripts calling [embed_methods](crate::embed_methods) panic instead of
+/// returning errors.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BuildError {
+    /// Docker could not be executed, e.g. because it is not installed.
+    DockerUnavailable(Option<io::Error>),
+
+    /// Building the guest in Docker failed.
+    DockerBuildFailed,
+
+    /// A guest exceeded the size budget set in its [GuestOptions](crate::GuestOptions), with a
+    /// report of the violations and of its largest symbols.
+    BudgetExceeded(String),
+
+    /// The RISC Zero home directory could not be determined.
+    HomeDirUnavailable,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DockerUnavailable(_) => write!(f, "could not find or execute docker"),
+            Self::DockerBuildFailed => write!(f, "docker build failed"),
+            Self::BudgetExceeded(report) => write!(f, "{report}"),
+            Self::HomeDirUnavailable => write!(
+                f,
+                "could not determine risc0 home dir, set the RISC0_HOME env var"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::DockerUnavailable(Some(err)) => Some(err),
+            _ => None,
+        }
+    }
+}
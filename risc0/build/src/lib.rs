@@ -17,32 +17,39 @@
 #![deny(rustdoc::broken_intra_doc_links)]
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
+mod budget;
 mod config;
 mod docker;
+mod error;
+mod source_digest;
 
 use std::{
     borrow::Cow,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     default::Default,
     env,
-    fs::{self, File},
+    fs::{self, File, OpenOptions},
     io::{BufRead, BufReader, Write},
     path::{Path, PathBuf},
     process::{Command, Stdio},
 };
 
 use anyhow::{anyhow, Context, Result};
-use cargo_metadata::{Message, MetadataCommand, Package};
+use cargo_metadata::{Message, MetadataCommand, Package, Target};
+use fs2::FileExt;
 use risc0_binfmt::compute_image_id;
 use risc0_zkp::core::digest::{Digest, DIGEST_WORDS};
 use risc0_zkvm_platform::memory;
 use serde::Deserialize;
 
+use crate::budget::check_elf_budget;
 use crate::config::GuestBuildOptions;
 use crate::docker::build_guest_package_docker;
 use config::GuestMetadata;
 pub use config::{DockerOptions, GuestOptions};
 pub use docker::{docker_build, BuildStatus, TARGET_DIR};
+pub use error::BuildError;
+pub use source_digest::{source_digest, SOURCE_DIGEST_ENV};
 
 /// This const represents a filename that is used in the use to indicate to in
 /// order to indicate to the client and the risc0-build crate that the new rust
@@ -57,18 +64,25 @@ pub fn risc0_data() -> Result<PathBuf> {
     risc0_data_new().or_else(|_| risc0_data_compat())
 }
 
-// use the new location from rzup install.
-fn risc0_data_new() -> Result<PathBuf> {
-    let dir = if let Ok(dir) = std::env::var("RISC0_HOME") {
-        dir.into()
+/// Get the root of the RISC Zero files on this machine, `$RISC0_HOME` or `~/.risc0`.
+///
+/// Unlike [risc0_data], this does not require the root to have been created by rzup.
+pub fn risc0_root() -> Result<PathBuf> {
+    if let Ok(dir) = std::env::var("RISC0_HOME") {
+        Ok(dir.into())
     } else if let Some(home) = dirs::home_dir() {
-        home.join(".risc0")
+        Ok(home.join(".risc0"))
     } else {
-        anyhow::bail!("Could not determine risc0 home dir. Set RISC0_HOME env var.");
-    };
+        Err(BuildError::HomeDirUnavailable.into())
+    }
+}
+
+// use the new location from rzup install.
+fn risc0_data_new() -> Result<PathBuf> {
+    let dir = risc0_root()?;
 
     if !dir.join(RUST_RZUP_INDICATOR).exists() {
-        anyhow::bail!("Could not determine risc0 home dir. Set RISC0_HOME env var.");
+        return Err(BuildError::HomeDirUnavailable.into());
     }
 
     Ok(dir)
@@ -103,9 +117,12 @@ impl Risc0Metadata {
 
 trait GuestBuilder: Sized {
     fn build(name: &str, elf_path: &str) -> Result<Self>;
-    fn codegen_consts(&self) -> String;
+    fn name(&self) -> &str;
+    // The `cfg` attribute, if any, is applied to each generated constant.
+    fn codegen_consts(&self, cfg: &str) -> String;
     #[cfg(feature = "guest-list")]
     fn codegen_list_entry(&self) -> String;
+    fn manifest_entry(&self) -> Option<serde_json::Value>;
 }
 
 /// Represents an item in the generated list of compiled guest binaries
@@ -125,7 +142,11 @@ impl GuestBuilder for MinGuestListEntry {
         })
     }
 
-    fn codegen_consts(&self) -> String {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn codegen_consts(&self, cfg: &str) -> String {
         // Quick check for '#' to avoid injection of arbitrary Rust code into the
         // method.rs file. This would not be a serious issue since it would only
         // affect the user that set the path, but it's good to add a check.
@@ -136,7 +157,7 @@ impl GuestBuilder for MinGuestListEntry {
         let upper = self.name.to_uppercase().replace('-', "_");
         let elf_path: &str = &self.path;
 
-        format!(r##"pub const {upper}_PATH: &str = r#"{elf_path}"#;"##)
+        format!(r##"{cfg}pub const {upper}_PATH: &str = r#"{elf_path}"#;"##)
     }
 
     #[cfg(feature = "guest-list")]
@@ -150,6 +171,10 @@ impl GuestBuilder for MinGuestListEntry {
     }}"##
         )
     }
+
+    fn manifest_entry(&self) -> Option<serde_json::Value> {
+        None
+    }
 }
 
 /// Represents an item in the generated list of compiled guest binaries
@@ -209,7 +234,11 @@ impl GuestBuilder for GuestListEntry {
         })
     }
 
-    fn codegen_consts(&self) -> String {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn codegen_consts(&self, cfg: &str) -> String {
         // Quick check for '#' to avoid injection of arbitrary Rust code into the
         // method.rs file. This would not be a serious issue since it would only
         // affect the user that set the path, but it's good to add a check.
@@ -229,9 +258,9 @@ impl GuestBuilder for GuestListEntry {
 
         format!(
             r##"
-pub const {upper}_ELF: &[u8] = {elf_value};
-pub const {upper}_ID: [u32; 8] = {image_id:?};
-pub const {upper}_PATH: &str = "{elf_path}";
+{cfg}pub const {upper}_ELF: &[u8] = {elf_value};
+{cfg}pub const {upper}_ID: [u32; 8] = {image_id:?};
+{cfg}pub const {upper}_PATH: &str = "{elf_path}";
 "##
         )
     }
@@ -249,6 +278,14 @@ pub const {upper}_PATH: &str = "{elf_path}";
     }}"##
         )
     }
+
+    fn manifest_entry(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({
+            "name": self.name.to_uppercase().replace('-', "_"),
+            "image_id": Digest::from(self.image_id).to_string(),
+            "path": self.path,
+        }))
+    }
 }
 
 /// Returns the given cargo Package from the metadata in the Cargo.toml manifest
@@ -327,30 +364,47 @@ fn get_env_var(name: &str) -> String {
     env::var(name).unwrap_or_default()
 }
 
+/// Returns the binary targets of the given guest crate, sorted by name so that the methods are
+/// always embedded in the same order.
+fn guest_bin_targets(pkg: &Package) -> Vec<&Target> {
+    let mut targets: Vec<_> = pkg
+        .targets
+        .iter()
+        .filter(|target| target.kind.iter().any(|kind| kind == "bin"))
+        .collect();
+    targets.sort_by(|a, b| a.name.cmp(&b.name));
+    targets
+}
+
 /// Returns all methods associated with the given guest crate.
 fn guest_methods<G: GuestBuilder>(
     pkg: &Package,
     target_dir: impl AsRef<Path>,
-    guest_features: &[String],
+    guest_opts: &GuestBuildOptions,
 ) -> Vec<G> {
     let profile = if is_debug() { "debug" } else { "release" };
-    pkg.targets
-        .iter()
-        .filter(|target| target.kind.iter().any(|kind| kind == "bin"))
+    guest_bin_targets(pkg)
+        .into_iter()
         .filter(|target| {
             target
                 .required_features
                 .iter()
-                .all(|required_feature| guest_features.contains(required_feature))
+                .all(|required_feature| guest_opts.features.contains(required_feature))
         })
         .map(|target| {
-            G::build(
+            let elf_path = stage_elf(
+                pkg,
                 &target.name,
                 target_dir
                     .as_ref()
                     .join("riscv32im-risc0-zkvm-elf")
                     .join(profile)
-                    .join(&target.name)
+                    .join(&target.name),
+                guest_opts,
+            );
+            G::build(
+                &target.name,
+                elf_path
                     .to_str()
                     .context("elf path contains invalid unicode")
                     .unwrap(),
@@ -361,23 +415,32 @@ fn guest_methods<G: GuestBuilder>(
 }
 
 /// Returns all methods associated with the given guest crate.
-fn guest_methods_docker<P, G>(pkg: &Package, target_dir: P) -> Vec<G>
+fn guest_methods_docker<P, G>(
+    pkg: &Package,
+    target_dir: P,
+    guest_opts: &GuestBuildOptions,
+) -> Vec<G>
 where
     P: AsRef<Path>,
     G: GuestBuilder,
 {
-    pkg.targets
-        .iter()
-        .filter(|target| target.kind.iter().any(|kind| kind == "bin"))
+    guest_bin_targets(pkg)
+        .into_iter()
         .map(|target| {
-            G::build(
+            let elf_path = stage_elf(
+                pkg,
                 &target.name,
                 target_dir
                     .as_ref()
                     .join("riscv32im-risc0-zkvm-elf")
                     .join("docker")
                     .join(pkg.name.replace('-', "_"))
-                    .join(&target.name)
+                    .join(&target.name),
+                guest_opts,
+            );
+            G::build(
+                &target.name,
+                elf_path
                     .to_str()
                     .context("elf path contains invalid unicode")
                     .unwrap(),
@@ -387,6 +450,65 @@ where
         .collect()
 }
 
+/// Copies a guest ELF out of the shared guest target directory into `OUT_DIR`, namespaced by the
+/// guest package, so that it is not overwritten when another methods crate in the workspace builds
+/// a binary with the same name.
+///
+/// The build fails if the ELF exceeds the size budget in the options.
+fn stage_elf(
+    pkg: &Package,
+    name: &str,
+    elf_path: PathBuf,
+    guest_opts: &GuestBuildOptions,
+) -> PathBuf {
+    if is_skip_build() {
+        return elf_path;
+    }
+
+    if let Err(err) = check_elf_budget(name, &elf_path, guest_opts) {
+        panic!("{err}");
+    }
+
+    let out_dir = Path::new(&env::var_os("OUT_DIR").unwrap()).join(pkg.name.replace('-', "_"));
+    fs::create_dir_all(&out_dir).unwrap();
+    let staged_path = out_dir.join(name);
+    fs::copy(&elf_path, &staged_path)
+        .with_context(|| format!("failed to copy {}", elf_path.display()))
+        .unwrap();
+    staged_path
+}
+
+/// An exclusive lock on the guest target directory.
+///
+/// Every methods crate on the machine builds its guests into the same target directory, see
+/// [get_guest_dir]. The lock is held while guests are built and their ELFs staged, so that
+/// concurrent build scripts do not interleave.
+struct GuestDirLock(File);
+
+impl GuestDirLock {
+    fn acquire(lock_path: &Path) -> Self {
+        fs::create_dir_all(lock_path.parent().unwrap()).unwrap();
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .read(true)
+            .write(true)
+            .open(lock_path)
+            .unwrap();
+        if file.try_lock_exclusive().is_err() {
+            tty_println("Blocking waiting for lock on the guest target directory");
+            file.lock_exclusive().unwrap();
+        }
+        Self(file)
+    }
+}
+
+impl Drop for GuestDirLock {
+    fn drop(&mut self) {
+        drop(self.0.unlock());
+    }
+}
+
 /// Build a [Command] with CARGO and RUSTUP_TOOLCHAIN environment variables
 /// removed.
 fn sanitized_cmd(tool: &str) -> Command {
@@ -419,9 +541,20 @@ pub fn cargo_command(subcmd: &str, rust_flags: &[&str]) -> Command {
     }
 
     let rust_src = get_env_var("RISC0_RUST_SRC");
+    if rust_src.is_empty() && is_panic_unwind(rust_flags) {
+        // The prebuilt standard library of the toolchain is built with panic=abort.
+        panic!(
+            "guests built with panic=unwind must build the standard library from source; \
+             set RISC0_RUST_SRC to the source tree of the risc0 rust toolchain"
+        );
+    }
     if !rust_src.is_empty() {
         args.push("-Z");
-        args.push("build-std=alloc,core,proc_macro,panic_abort,std");
+        if is_panic_unwind(rust_flags) {
+            args.push("build-std=alloc,core,proc_macro,panic_abort,panic_unwind,std");
+        } else {
+            args.push("build-std=alloc,core,proc_macro,panic_abort,std");
+        }
         args.push("-Z");
         args.push("build-std-features=compiler-builtins-mem");
         cmd.env("__CARGO_TESTS_ONLY_SRC_ROOT", rust_src);
@@ -445,8 +578,55 @@ pub fn cargo_command(subcmd: &str, rust_flags: &[&str]) -> Command {
     cmd
 }
 
+/// The rustc flags used to build guests with `panic=unwind`.
+///
+/// Unwind tables are generated even for functions that cannot panic. The unwinder of the `unwind`
+/// feature of risc0-zkvm finds them through the bounds of the text and the start of `.eh_frame`,
+/// which the guest has no loader to report, so they are defined as symbols at link time.
+pub(crate) const PANIC_UNWIND_FLAGS: &[&str] = &[
+    "-C",
+    "panic=unwind",
+    "-C",
+    "force-unwind-tables=yes",
+    "-C",
+    "link-arg=--defsym=__executable_start=ADDR(.text)",
+    "-C",
+    "link-arg=--defsym=__etext=ADDR(.text)+SIZEOF(.text)",
+    "-C",
+    "link-arg=--defsym=__eh_frame=ADDR(.eh_frame)",
+];
+
+/// Returns true if the given rustc flags select the `unwind` panic strategy.
+///
+/// Codegen options may be given as `-C panic=unwind`, `-Cpanic=unwind`, `--codegen panic=unwind`
+/// or `--codegen=panic=unwind`, and the last one given takes effect.
+fn is_panic_unwind(rustc_flags: &[&str]) -> bool {
+    let mut strategy = None;
+    let mut codegen = false;
+    for flag in rustc_flags {
+        let opt = if codegen {
+            Some(*flag)
+        } else {
+            flag.strip_prefix("-C")
+                .or_else(|| flag.strip_prefix("--codegen="))
+                .filter(|opt| !opt.is_empty())
+        };
+        codegen = matches!(*flag, "-C" | "--codegen");
+        if let Some(value) = opt.and_then(|opt| opt.strip_prefix("panic=")) {
+            strategy = Some(value);
+        }
+    }
+    strategy == Some("unwind")
+}
+
 /// Returns a string that can be set as the value of CARGO_ENCODED_RUSTFLAGS when compiling guests
 pub(crate) fn encode_rust_flags(rustc_flags: &[&str]) -> String {
+    // Guests abort on panic, unless unwinding was requested.
+    let panic_flags: &[&str] = if is_panic_unwind(rustc_flags) {
+        &[]
+    } else {
+        &["-C", "panic=abort"]
+    };
     [
         // Append other rust flags
         rustc_flags,
@@ -465,9 +645,8 @@ pub(crate) fn encode_rust_flags(rustc_flags: &[&str]) -> String {
             // error out in this case.
             "-C",
             "link-arg=--fatal-warnings",
-            "-C",
-            "panic=abort",
         ],
+        panic_flags,
     ]
     .concat()
     .join("\x1f")
@@ -494,6 +673,7 @@ pub fn build_rust_runtime() -> String {
 /// Builds a static library and returns the name of the resultant file.
 fn build_staticlib(guest_pkg: &str, features: &[&str]) -> String {
     let guest_dir = get_guest_dir();
+    let _lock = GuestDirLock::acquire(&guest_dir_lock_path(&guest_dir));
 
     let mut cmd = cargo_command("rustc", &[]);
 
@@ -556,15 +736,19 @@ fn build_staticlib(guest_pkg: &str, features: &[&str]) -> String {
 // progress messages from the inner cargo so the user doesn't
 // think it's just hanging.
 fn tty_println(msg: &str) {
-    let tty_file = env::var("RISC0_GUEST_LOGFILE").unwrap_or_else(|_| "/dev/tty".to_string());
-
-    let mut tty = fs::OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .truncate(false)
-        .open(tty_file)
-        .ok();
+    let tty_file = env::var("RISC0_GUEST_LOGFILE")
+        .ok()
+        .or_else(|| cfg!(unix).then(|| "/dev/tty".to_string()));
+
+    let mut tty = tty_file.and_then(|tty_file| {
+        fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(tty_file)
+            .ok()
+    });
 
     if let Some(tty) = &mut tty {
         writeln!(tty, "{msg}").unwrap();
@@ -597,7 +781,7 @@ fn build_guest_package<P>(
             .iter()
             .map(|s| s.as_str())
             .collect::<Vec<_>>(),
-        guest_opts.rustc_flags.iter().map(|s| s.as_str()).collect(),
+        guest_opts.rust_flags(),
     ]
     .concat();
 
@@ -619,6 +803,15 @@ fn build_guest_package<P>(
         cmd.args(["--release"]);
     }
 
+    if guest_opts.source_digest {
+        let pkg_dir = pkg.manifest_path.parent().unwrap();
+        let digest = source_digest(pkg_dir.as_std_path()).expect("failed to compute source digest");
+        tty_println(&format!("{}: source digest {digest}", pkg.name));
+        cmd.env(SOURCE_DIGEST_ENV, digest.to_string());
+    } else {
+        cmd.env_remove(SOURCE_DIGEST_ENV);
+    }
+
     let mut child = cmd
         .stderr(Stdio::piped())
         .spawn()
@@ -662,7 +855,29 @@ fn detect_toolchain(name: &str) {
     }
 }
 
+/// Returns the target directory guests are built in.
+///
+/// The directory is shared by every methods crate on the machine, under [risc0_root], so that
+/// dependencies shared by the guests, including the standard library when it is built from
+/// `RISC0_RUST_SRC`, are compiled only once rather than once per workspace. If there is no risc0
+/// root, the `riscv-guest` directory in the target directory of the host build is used instead.
 fn get_guest_dir() -> PathBuf {
+    risc0_root()
+        .map(|root| shared_guest_dir(&root))
+        .unwrap_or_else(|_| get_host_guest_dir())
+}
+
+fn shared_guest_dir(root: &Path) -> PathBuf {
+    root.join("cache").join("riscv-guest")
+}
+
+// The lock file guarding the given guest target directory, which is kept outside of the directory
+// so that cleaning the directory does not remove a held lock.
+fn guest_dir_lock_path(guest_dir: &Path) -> PathBuf {
+    guest_dir.with_extension("lock")
+}
+
+fn get_host_guest_dir() -> PathBuf {
     // Determine the output directory, in the target folder, for the guest binary.
     let out_dir_env = env::var_os("OUT_DIR").unwrap();
     let out_dir = Path::new(&out_dir_env); // $ROOT/target/$profile/build/$crate/out
@@ -678,6 +893,19 @@ fn get_guest_dir() -> PathBuf {
         .join("riscv-guest")
 }
 
+// The attribute gating the generated code for the given method behind its feature.
+fn method_feature_cfg(pkg: &Package, name: &str) -> String {
+    let feature = format!("method-{name}");
+    if !pkg.features.contains_key(&feature) {
+        panic!(
+            "Error: method features are enabled for guest '{name}', but package '{}' does not \
+            declare the feature '{feature}'. Add `{feature} = []` to its [features] section.",
+            pkg.name
+        );
+    }
+    format!("#[cfg(feature = \"{feature}\")]\n")
+}
+
 /// Embeds methods built for RISC-V for use by host-side dependencies.
 /// Specify custom options for a guest package by defining its [GuestOptions].
 /// See [embed_methods].
@@ -704,12 +932,15 @@ pub fn embed_method_metadata_with_options(
 /// Embeds methods built for RISC-V for use by host-side dependencies.
 /// Specify custom options for a guest package by defining its [GuestOptions].
 /// See [embed_methods].
-fn do_embed_methods<G: GuestBuilder>(
-    mut guest_pkg_to_options: HashMap<&str, GuestOptions>,
-) -> Vec<G> {
+fn do_embed_methods<G: GuestBuilder>(guest_pkg_to_options: HashMap<&str, GuestOptions>) -> Vec<G> {
+    // Ordered so that the generated files, and any error, do not depend on the hash seed.
+    let mut guest_pkg_to_options: BTreeMap<_, _> = guest_pkg_to_options.into_iter().collect();
+
     let out_dir_env = env::var_os("OUT_DIR").unwrap();
     let out_dir = Path::new(&out_dir_env); // $ROOT/target/$profile/build/$crate/out
     let guest_dir = get_guest_dir();
+    // Docker builds export their ELFs to the target directory of the host build.
+    let docker_guest_dir = get_host_guest_dir();
     // Read the cargo metadata for info from `[package.metadata.risc0]`.
     let pkg = current_package();
     let guest_packages = guest_packages(&pkg);
@@ -731,7 +962,11 @@ fn do_embed_methods<G: GuestBuilder>(
         detect_toolchain(RUSTUP_TOOLCHAIN_NAME);
     }
 
+    let lock_path = guest_dir_lock_path(&guest_dir);
+    let _lock = (!is_skip_build()).then(|| GuestDirLock::acquire(&lock_path));
+
     let mut guest_list = vec![];
+    let mut manifest = vec![];
     for guest_pkg in guest_packages {
         println!("Building guest package {}.{}", pkg.name, guest_pkg.name);
 
@@ -752,19 +987,32 @@ fn do_embed_methods<G: GuestBuilder>(
                 &guest_build_opts,
             )
             .unwrap();
-            guest_methods_docker(&guest_pkg, &guest_dir)
+            guest_methods_docker(&guest_pkg, &docker_guest_dir, &guest_build_opts)
         } else {
             build_guest_package(&guest_pkg, &guest_dir, &guest_build_opts, None);
-            guest_methods(&guest_pkg, &guest_dir, &guest_build_opts.features)
+            guest_methods(&guest_pkg, &guest_dir, &guest_build_opts)
         };
 
         for method in methods {
+            let cfg = if guest_build_opts.method_features {
+                method_feature_cfg(&pkg, method.name())
+            } else {
+                String::new()
+            };
             methods_file
-                .write_all(method.codegen_consts().as_bytes())
+                .write_all(method.codegen_consts(&cfg).as_bytes())
                 .unwrap();
+            // A method behind a feature is left out of the manifest, as whether the feature is
+            // enabled is only known when the methods crate is compiled.
+            if !guest_build_opts.method_features {
+                manifest.extend(method.manifest_entry());
+            }
 
             #[cfg(feature = "guest-list")]
-            guest_list_codegen.push(method.codegen_list_entry());
+            guest_list_codegen.push(format!(
+                "\n    {cfg}{}",
+                method.codegen_list_entry().trim_start()
+            ));
             guest_list.push(method);
         }
     }
@@ -777,6 +1025,16 @@ fn do_embed_methods<G: GuestBuilder>(
         );
     }
 
+    // Also write a manifest of the methods, which can be loaded at runtime by the
+    // `MethodRegistry` in risc0-zkvm.
+    if !manifest.is_empty() {
+        std::fs::write(
+            out_dir.join("methods.json"),
+            serde_json::to_string_pretty(&manifest).unwrap(),
+        )
+        .unwrap();
+    }
+
     #[cfg(feature = "guest-list")]
     methods_file
         .write_all(
@@ -817,6 +1075,88 @@ fn do_embed_methods<G: GuestBuilder>(
 /// to uppercase.  For instance, if you have a method named
 /// "my_method", the image ID and elf contents will be defined as
 /// "MY_METHOD_ID" and "MY_METHOD_ELF" respectively.
+///
+/// A `methods.json` manifest listing the name, image ID, and ELF path of each
+/// method not gated behind a feature is also written to `OUT_DIR`, which can be
+/// loaded by `risc0_zkvm::MethodRegistry::load`.
+///
+/// A workspace may contain several methods crates. Their guests are built into
+/// a target directory under [risc0_root] shared by every methods crate on the
+/// machine, one crate at a time, and each ELF is copied into the `OUT_DIR` of
+/// the methods crate that embeds it, so guest binaries with the same name in
+/// different crates do not collide.
 pub fn embed_methods() -> Vec<GuestListEntry> {
     embed_methods_with_options(HashMap::new())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use fs2::FileExt;
+    use tempfile::tempdir;
+
+    use super::{
+        guest_dir_lock_path, is_panic_unwind, shared_guest_dir, GuestBuilder, GuestDirLock,
+        GuestListEntry,
+    };
+
+    #[test]
+    fn panic_unwind_flags() {
+        assert!(!is_panic_unwind(&[]));
+        assert!(is_panic_unwind(&["-C", "panic=unwind"]));
+        assert!(is_panic_unwind(&["-Cpanic=unwind"]));
+        assert!(is_panic_unwind(&["--codegen", "panic=unwind"]));
+        assert!(is_panic_unwind(&["--codegen=panic=unwind"]));
+        assert!(is_panic_unwind(super::PANIC_UNWIND_FLAGS));
+
+        // The last strategy given takes effect.
+        assert!(!is_panic_unwind(&["-Cpanic=unwind", "-C", "panic=abort"]));
+        assert!(is_panic_unwind(&["-Cpanic=abort", "-Cpanic=unwind"]));
+
+        // Other options are not mistaken for the panic strategy.
+        assert!(!is_panic_unwind(&["panic=unwind"]));
+        assert!(!is_panic_unwind(&["-C", "opt-level=3", "panic=unwind"]));
+    }
+
+    #[test]
+    fn shared_guest_dir_lock() {
+        let root = tempdir().unwrap();
+        let guest_dir = shared_guest_dir(root.path());
+        let lock_path = guest_dir_lock_path(&guest_dir);
+        assert!(guest_dir.starts_with(root.path()));
+        assert!(!lock_path.starts_with(&guest_dir));
+
+        // A build script holding the lock excludes every other one until it is dropped.
+        let lock = GuestDirLock::acquire(&lock_path);
+        let other = std::fs::File::open(&lock_path).unwrap();
+        assert!(other.try_lock_exclusive().is_err());
+        drop(lock);
+        other.try_lock_exclusive().unwrap();
+    }
+
+    #[test]
+    fn method_feature_codegen() {
+        let method = GuestListEntry {
+            name: "hello".into(),
+            elf: Cow::Borrowed(&[]),
+            image_id: [0; 8],
+            path: "/guest/hello".into(),
+        };
+        let cfg = "#[cfg(feature = \"method-hello\")]\n";
+
+        // Every constant generated for the method is gated, and nothing else is.
+        let gated = method.codegen_consts(cfg);
+        let consts: Vec<_> = gated
+            .lines()
+            .filter(|line| line.contains("const "))
+            .collect();
+        assert_eq!(consts.len(), 3);
+        for line in consts {
+            assert!(line.starts_with("pub const HELLO_"));
+        }
+        assert_eq!(gated.matches(cfg).count(), 3);
+        assert_eq!(gated.replace(cfg, ""), method.codegen_consts(""));
+        assert!(!method.codegen_consts("").contains("#[cfg"));
+    }
+}
@@ -0,0 +1,259 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Digest of the source inputs of a guest, embedded when
+//! [GuestOptions::source_digest](crate::GuestOptions::source_digest) is set.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use cargo_metadata::{DependencyKind, MetadataCommand};
+use risc0_zkp::core::{
+    digest::Digest,
+    hash::sha::{cpu::Impl, Sha256},
+};
+
+/// Environment variable through which the source digest is passed to the guest build.
+///
+/// The `entry!` macro of `risc0-zkvm` places the digest in the `.risc0.source_digest` section of
+/// the guest ELF, when compiling the guest crate.
+pub const SOURCE_DIGEST_ENV: &str = "RISC0_SOURCE_DIGEST";
+
+/// Directories which are never part of the source inputs.
+const EXCLUDED_DIRS: &[&str] = &["target"];
+
+/// Computes the digest of the source inputs of the guest package in `pkg_dir`.
+///
+/// Every file below `pkg_dir` is included, except those in `target` and in hidden directories.
+/// So is every file of each package the guest depends on through a path dependency, directly or
+/// transitively, as found with `cargo metadata`; dev-dependencies are not included. The nearest
+/// `Cargo.lock` found in `pkg_dir` or its ancestors is included last.
+///
+/// Each file contributes its path relative to its package and the SHA-256 digest of its contents,
+/// in order of path. The paths of files in path dependencies are prefixed with the name of their
+/// package, and the packages are in order of name. The result therefore depends neither on where
+/// the sources are checked out nor on file timestamps.
+pub fn source_digest(pkg_dir: &Path) -> Result<Digest> {
+    let pkg_dir = pkg_dir
+        .canonicalize()
+        .with_context(|| format!("Failed to read {pkg_dir:?}"))?;
+    let pkg_dir = pkg_dir.as_path();
+
+    let mut entries = Vec::new();
+    push_package(&mut entries, "", pkg_dir)?;
+    for (name, dep_dir) in path_dependencies(pkg_dir)? {
+        // Packages nested in the guest package are already included.
+        if !dep_dir.starts_with(pkg_dir) {
+            push_package(&mut entries, &format!("{name}/"), &dep_dir)?;
+        }
+    }
+    if let Some(lock_path) = find_cargo_lock(pkg_dir) {
+        if !lock_path.starts_with(pkg_dir) {
+            push_entry(&mut entries, "Cargo.lock", &lock_path)?;
+        }
+    }
+    Ok(*Impl::hash_bytes(&entries))
+}
+
+fn push_package(entries: &mut Vec<u8>, prefix: &str, dir: &Path) -> Result<()> {
+    let mut files = Vec::new();
+    collect_files(dir, dir, &mut files)?;
+    files.sort();
+    for rel_path in files.iter() {
+        push_entry(
+            entries,
+            &format!("{prefix}{}", rel_path_str(rel_path)),
+            &dir.join(rel_path),
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns the name and directory of each package reached from the package in `pkg_dir` through
+/// normal and build path dependencies, in order of name.
+fn path_dependencies(pkg_dir: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    let mut deps = BTreeMap::new();
+    let mut pending = vec![pkg_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let manifest_path = dir.join("Cargo.toml");
+        // Without resolving dependencies, cargo neither writes a Cargo.lock nor needs the network.
+        let meta = MetadataCommand::new()
+            .manifest_path(&manifest_path)
+            .no_deps()
+            .exec()
+            .with_context(|| format!("Failed to read metadata of {manifest_path:?}"))?;
+        let pkg = meta
+            .packages
+            .iter()
+            .find(|pkg| {
+                pkg.manifest_path
+                    .as_std_path()
+                    .canonicalize()
+                    .is_ok_and(|path| path == manifest_path)
+            })
+            .with_context(|| format!("No package found in {manifest_path:?}"))?;
+        for dep in pkg.dependencies.iter() {
+            let Some(dep_dir) = dep.path.as_ref() else {
+                continue;
+            };
+            if dep.kind == DependencyKind::Development || deps.contains_key(&dep.name) {
+                continue;
+            }
+            let dep_dir = dep_dir
+                .canonicalize()
+                .with_context(|| format!("Failed to read {dep_dir:?}"))?;
+            if dep_dir != pkg_dir {
+                deps.insert(dep.name.clone(), dep_dir.clone());
+                pending.push(dep_dir);
+            }
+        }
+    }
+    Ok(deps)
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {dir:?}"))? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if name.starts_with('.') || EXCLUDED_DIRS.contains(&name.as_ref()) {
+                continue;
+            }
+            collect_files(root, &path, files)?;
+        } else if file_type.is_file() {
+            files.push(path.strip_prefix(root)?.to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+/// The path with `/` separators, so that the digest is the same on every platform.
+fn rel_path_str(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn push_entry(entries: &mut Vec<u8>, name: &str, path: &Path) -> Result<()> {
+    let contents = fs::read(path).with_context(|| format!("Failed to read {path:?}"))?;
+    entries.extend_from_slice(&(name.len() as u32).to_le_bytes());
+    entries.extend_from_slice(name.as_bytes());
+    entries.extend_from_slice(Impl::hash_bytes(&contents).as_bytes());
+    Ok(())
+}
+
+fn find_cargo_lock(pkg_dir: &Path) -> Option<PathBuf> {
+    pkg_dir
+        .ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::source_digest;
+
+    fn write_package(dir: &Path, name: &str, deps: &str) {
+        fs::create_dir_all(dir.join("src")).unwrap();
+        let manifest = format!(
+            "[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+             [dependencies]\n{deps}"
+        );
+        fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    }
+
+    #[test]
+    fn source_digest_inputs() {
+        let workspace = tempfile::tempdir().unwrap();
+        let pkg_dir = workspace.path().join("guest");
+        write_package(&pkg_dir, "guest", "");
+        fs::write(pkg_dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+        let digest = source_digest(&pkg_dir).unwrap();
+
+        // Build outputs and hidden directories are not source inputs.
+        fs::create_dir_all(pkg_dir.join("target")).unwrap();
+        fs::write(pkg_dir.join("target/guest"), "elf").unwrap();
+        fs::create_dir_all(pkg_dir.join(".git")).unwrap();
+        fs::write(pkg_dir.join(".git/HEAD"), "ref").unwrap();
+        assert_eq!(source_digest(&pkg_dir).unwrap(), digest);
+
+        // The workspace Cargo.lock is.
+        fs::write(workspace.path().join("Cargo.lock"), "version = 3\n").unwrap();
+        let with_lock = source_digest(&pkg_dir).unwrap();
+        assert_ne!(with_lock, digest);
+
+        fs::write(pkg_dir.join("src/main.rs"), "fn main() { }\n").unwrap();
+        let edited = source_digest(&pkg_dir).unwrap();
+        assert_ne!(edited, with_lock);
+
+        // Renaming a file changes the digest even if its contents do not.
+        fs::rename(pkg_dir.join("src/main.rs"), pkg_dir.join("src/lib.rs")).unwrap();
+        assert_ne!(source_digest(&pkg_dir).unwrap(), edited);
+    }
+
+    #[test]
+    fn source_digest_path_dependencies() {
+        let workspace = tempfile::tempdir().unwrap();
+        let pkg_dir = workspace.path().join("guest");
+        let core_dir = workspace.path().join("core");
+        let util_dir = workspace.path().join("util");
+        let test_dir = workspace.path().join("test-util");
+        write_package(
+            &pkg_dir,
+            "guest",
+            "core = { path = \"../core\" }\n\n[dev-dependencies]\n\
+             test-util = { path = \"../test-util\" }\n",
+        );
+        fs::write(pkg_dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+        write_package(&core_dir, "core", "util = { path = \"../util\" }\n");
+        fs::write(core_dir.join("src/lib.rs"), "pub fn core() {}\n").unwrap();
+        write_package(&util_dir, "util", "");
+        fs::write(util_dir.join("src/lib.rs"), "pub fn util() {}\n").unwrap();
+        write_package(&test_dir, "test-util", "");
+        fs::write(test_dir.join("src/lib.rs"), "pub fn test_util() {}\n").unwrap();
+        let digest = source_digest(&pkg_dir).unwrap();
+
+        // Direct and transitive path dependencies are source inputs.
+        fs::write(core_dir.join("src/lib.rs"), "pub fn core() { }\n").unwrap();
+        let edited_core = source_digest(&pkg_dir).unwrap();
+        assert_ne!(edited_core, digest);
+        fs::write(util_dir.join("src/lib.rs"), "pub fn util() { }\n").unwrap();
+        let edited_util = source_digest(&pkg_dir).unwrap();
+        assert_ne!(edited_util, edited_core);
+
+        // Dev-dependencies are not.
+        fs::write(test_dir.join("src/lib.rs"), "pub fn test_util() { }\n").unwrap();
+        assert_eq!(source_digest(&pkg_dir).unwrap(), edited_util);
+
+        // The digest does not depend on where the sources are checked out.
+        let moved = tempfile::tempdir().unwrap();
+        for name in ["guest", "core", "util", "test-util"] {
+            fs::rename(workspace.path().join(name), moved.path().join(name)).unwrap();
+        }
+        assert_eq!(
+            source_digest(&moved.path().join("guest")).unwrap(),
+            edited_util
+        );
+    }
+}
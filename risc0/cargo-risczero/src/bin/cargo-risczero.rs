@@ -29,10 +29,13 @@ fn main() -> Result<()> {
         RisczeroCmd::Build(cmd) => cmd.run(),
         RisczeroCmd::BuildToolchain(cmd) => cmd.run(),
         RisczeroCmd::Install(cmd) => cmd.run(),
+        RisczeroCmd::Cache(cmd) => cmd.run(),
         RisczeroCmd::Datasheet(cmd) => cmd.run(),
         RisczeroCmd::New(cmd) => cmd.run(),
         RisczeroCmd::Deploy(cmd) => cmd.run(),
+        RisczeroCmd::Export(cmd) => cmd.run(),
         RisczeroCmd::Verify(cmd) => cmd.run(),
+        RisczeroCmd::Bench(cmd) => cmd.run(),
         #[cfg(feature = "experimental")]
         RisczeroCmd::BuildCrate(build) => build.run(BuildSubcommand::Build),
         #[cfg(feature = "experimental")]
@@ -0,0 +1,240 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use ::serde::Serialize;
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use risc0_zkvm::{
+    compute_image_id, sha::Digestible, ApiClient, Asset, AssetRequest, CompositeReceipt,
+    ExecutorEnv, InnerReceipt, ProverOpts, Receipt, SegmentReceipt, VerifierContext,
+};
+use serde_with::{serde_as, DurationSecondsWithFrac};
+use tabled::{settings::Style, Table, Tabled};
+
+use super::datasheet::display;
+
+/// `cargo risczero bench`
+#[derive(Parser)]
+#[non_exhaustive]
+pub struct BenchCommand {
+    /// Path to the guest ELF.
+    #[arg(long, value_name = "PATH")]
+    pub elf: PathBuf,
+
+    /// Path to a file whose contents are passed to the guest on stdin.
+    #[arg(long, value_name = "PATH")]
+    pub input: Option<PathBuf>,
+
+    /// Path to the RISC Zero server.
+    ///
+    /// The server proves with the HAL it was built with, so point this at a build of `r0vm` with
+    /// the `cuda` or `metal` feature to benchmark on a GPU.
+    #[arg(long, value_name = "PATH", env = "RISC0_SERVER_PATH")]
+    pub r0vm: Option<PathBuf>,
+
+    /// Hash function used to prove the segments.
+    #[arg(long, default_value = "sha-256", value_parser = ["sha-256", "poseidon2"])]
+    pub hashfn: String,
+
+    /// Kind of receipt to produce. Succinct and Groth16 receipts add a compression stage.
+    #[arg(long, value_enum, default_value_t = ReceiptKind::Composite)]
+    pub receipt_kind: ReceiptKind,
+
+    /// Maximum number of cycles in each segment, as a power of two.
+    #[arg(long, value_name = "PO2")]
+    pub segment_limit_po2: Option<u32>,
+
+    /// Path to the JSON report file.
+    #[arg(long, value_name = "PATH")]
+    pub json: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+#[non_exhaustive]
+pub enum ReceiptKind {
+    Composite,
+    Succinct,
+    Groth16,
+}
+
+impl BenchCommand {
+    /// Creates a connection to the zkVM server (`r0vm` executable).
+    ///
+    /// The path is provided by `--r0vm`, `$RISC0_SERVER_PATH`, or searching
+    /// `$PATH`.
+    fn connect_to_server(&self) -> Result<ApiClient> {
+        if let Some(server_path) = &self.r0vm {
+            ApiClient::new_sub_process(server_path)
+        } else {
+            ApiClient::from_env()
+        }
+    }
+
+    fn env(&self) -> Result<ExecutorEnv<'static>> {
+        let mut builder = ExecutorEnv::builder();
+        if let Some(input) = &self.input {
+            let input = fs::read(input).with_context(|| format!("failed to read {input:?}"))?;
+            builder.write_slice(&input);
+        }
+        if let Some(po2) = self.segment_limit_po2 {
+            builder.segment_limit_po2(po2);
+        }
+        builder.build()
+    }
+
+    pub fn run(&self) -> Result<()> {
+        let client = self.connect_to_server()?;
+        let elf = fs::read(&self.elf).with_context(|| format!("failed to read {:?}", self.elf))?;
+        let image_id = compute_image_id(&elf)?;
+        let env = self.env()?;
+        let opts = ProverOpts::default().with_hashfn(self.hashfn.clone());
+        let ctx = VerifierContext::default();
+        let mut stages = Vec::new();
+
+        println!("execute");
+        let mut segments = Vec::new();
+        let (session, duration) = try_time(|| {
+            client.execute(
+                &env,
+                Asset::Inline(elf.into()),
+                AssetRequest::Inline,
+                |_, segment| {
+                    segments.push(segment);
+                    Ok(())
+                },
+            )
+        })?;
+        stages.push(Stage::new("execute", duration));
+
+        println!("prove: {} segments", segments.len());
+        let (segment_receipts, duration) = try_time(|| {
+            segments
+                .into_iter()
+                .map(|segment| client.prove_segment(&opts, segment, AssetRequest::Inline))
+                .collect::<Result<Vec<SegmentReceipt>>>()
+        })?;
+        stages.push(Stage::new("prove", duration));
+
+        let verifier_parameters = ctx
+            .composite_verifier_parameters()
+            .context("composite receipt verifier parameters missing from context")?
+            .digest();
+        let mut receipt = Receipt::new(
+            InnerReceipt::Composite(CompositeReceipt {
+                segments: segment_receipts,
+                assumption_receipts: Vec::new(),
+                verifier_parameters,
+            }),
+            session.journal.bytes.clone(),
+        );
+
+        let compress_kind = match self.receipt_kind {
+            ReceiptKind::Composite => None,
+            ReceiptKind::Succinct => Some(risc0_zkvm::ReceiptKind::Succinct),
+            ReceiptKind::Groth16 => Some(risc0_zkvm::ReceiptKind::Groth16),
+        };
+        if let Some(kind) = compress_kind {
+            println!("compress");
+            let opts = opts.clone().with_receipt_kind(kind);
+            let (compressed, duration) =
+                try_time(|| client.compress(&opts, receipt.try_into()?, AssetRequest::Inline))?;
+            receipt = compressed;
+            stages.push(Stage::new("compress", duration));
+        }
+
+        println!("verify");
+        let ((), duration) = try_time(|| Ok(receipt.verify_with_context(&ctx, image_id)?))?;
+        stages.push(Stage::new("verify", duration));
+
+        let report = BenchReport {
+            image_id: image_id.to_string(),
+            hashfn: self.hashfn.clone(),
+            receipt_kind: format!("{:?}", self.receipt_kind).to_lowercase(),
+            user_cycles: session.cycles(),
+            total_cycles: session.segments.iter().map(|s| 1u64 << s.po2).sum(),
+            segments: session.segments.len() as u64,
+            seal: receipt.seal_size() as u64,
+            stages,
+        };
+
+        // Emit results to stdout as pretty tables.
+        println!("{}", Table::new([&report]).with(Style::modern()));
+        println!("{}", Table::new(&report.stages).with(Style::modern()));
+
+        // Emit results to path as JSON if requested.
+        if let Some(json_path) = &self.json {
+            let json = serde_json::to_string_pretty(&report)?;
+
+            if let Some(json_dir) = json_path.parent() {
+                fs::create_dir_all(json_dir)?;
+            }
+
+            fs::write(json_path, json)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Tabled)]
+struct BenchReport {
+    image_id: String,
+    hashfn: String,
+    receipt_kind: String,
+
+    /// Cycles spent in the guest, without the overhead of continuations or padding.
+    #[tabled(display_with = "display::cycles")]
+    user_cycles: u64,
+
+    /// Cycles proven, i.e. the sum over the segments of their padded size.
+    #[tabled(display_with = "display::cycles")]
+    total_cycles: u64,
+
+    segments: u64,
+
+    #[tabled(display_with = "display::bytes")]
+    seal: u64,
+
+    #[tabled(skip)]
+    stages: Vec<Stage>,
+}
+
+#[serde_as]
+#[derive(Debug, Serialize, Tabled)]
+struct Stage {
+    name: &'static str,
+
+    #[serde_as(as = "DurationSecondsWithFrac<f64>")]
+    #[tabled(display_with = "display::duration")]
+    duration: Duration,
+}
+
+impl Stage {
+    fn new(name: &'static str, duration: Duration) -> Self {
+        Self { name, duration }
+    }
+}
+
+/// Measures the duration for executing a fallible `operation` once.
+fn try_time<T>(operation: impl FnOnce() -> Result<T>) -> Result<(T, Duration)> {
+    let start = Instant::now();
+    let output = operation()?;
+    Ok((output, start.elapsed()))
+}
@@ -0,0 +1,222 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use downloader::{Download, Downloader};
+use human_repr::HumanCount;
+use risc0_zkvm::sha::{Impl, Sha256};
+use tempfile::tempdir;
+
+use crate::utils::flock;
+
+const ARTIFACTS_URL: &str = "https://risc0-artifacts.s3.us-west-2.amazonaws.com";
+
+/// `cargo risczero cache`
+///
+/// Manages the cache of prover artifacts under `~/.risc0/cache`. The location can be overridden
+/// with the `RISC0_CACHE_DIR` environment variable. Builds consult the cache before downloading,
+/// and fail instead of downloading when `RISC0_OFFLINE` is set.
+#[derive(Parser)]
+pub struct CacheCommand {
+    #[command(subcommand)]
+    command: CacheSubcommand,
+}
+
+#[derive(Subcommand)]
+enum CacheSubcommand {
+    /// Download the artifacts used by this version of risc0 into the cache.
+    Fetch,
+    /// List the cached artifacts.
+    List,
+    /// Check the integrity of the cached artifacts, removing any that are corrupt.
+    Verify,
+    /// Remove cached artifacts not used by this version of risc0.
+    Prune {
+        /// Remove all cached artifacts.
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+/// An artifact stored in the cache, identified by its SHA-256 digest.
+struct Artifact {
+    kind: &'static str,
+    sha256: &'static str,
+    ext: &'static str,
+}
+
+impl Artifact {
+    fn file_name(&self) -> String {
+        format!("{}.{}", self.sha256, self.ext)
+    }
+
+    fn path(&self, cache_dir: &Path) -> PathBuf {
+        cache_dir.join(self.kind).join(self.file_name())
+    }
+
+    fn url(&self) -> String {
+        format!("{ARTIFACTS_URL}/{}/{}", self.kind, self.file_name())
+    }
+}
+
+/// The artifacts used by this version of risc0.
+const ARTIFACTS: &[Artifact] = &[Artifact {
+    kind: "zkr",
+    sha256: risc0_circuit_recursion::ZKR_SHA256,
+    ext: "zip",
+}];
+
+impl CacheCommand {
+    pub fn run(&self) -> Result<()> {
+        let cache_dir = cache_dir()?;
+        let _lock = flock(&cache_dir.join(".lock"))?;
+        match self.command {
+            CacheSubcommand::Fetch => fetch(&cache_dir),
+            CacheSubcommand::List => list(&cache_dir),
+            CacheSubcommand::Verify => verify(&cache_dir),
+            CacheSubcommand::Prune { all } => prune(&cache_dir, all),
+        }
+    }
+}
+
+fn cache_dir() -> Result<PathBuf> {
+    if let Ok(dir) = std::env::var("RISC0_CACHE_DIR") {
+        Ok(dir.into())
+    } else if let Ok(dir) = std::env::var("RISC0_HOME") {
+        Ok(PathBuf::from(dir).join("cache"))
+    } else if let Some(home) = dirs::home_dir() {
+        Ok(home.join(".risc0").join("cache"))
+    } else {
+        bail!("Could not determine risc0 cache dir. Set RISC0_CACHE_DIR env var.")
+    }
+}
+
+fn sha256_hex(path: &Path) -> Result<String> {
+    let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(hex::encode(Impl::hash_bytes(&data).as_bytes()))
+}
+
+fn fetch(cache_dir: &Path) -> Result<()> {
+    for artifact in ARTIFACTS {
+        let path = artifact.path(cache_dir);
+        if path.exists() && sha256_hex(&path)? == artifact.sha256 {
+            eprintln!("{} is up to date", path.display());
+            continue;
+        }
+
+        let temp_dir = tempdir()?;
+        let mut downloader = Downloader::builder()
+            .download_folder(temp_dir.path())
+            .build()?;
+        let url = artifact.url();
+        eprintln!("Downloading {url}");
+        for result in downloader.download(&[Download::new(&url)])? {
+            let summary = result.with_context(|| format!("Failed to download {url}"))?;
+            let digest = sha256_hex(&summary.file_name)?;
+            if digest != artifact.sha256 {
+                bail!(
+                    "Digest mismatch for {url}: expected {}, got {digest}",
+                    artifact.sha256
+                );
+            }
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::copy(&summary.file_name, &path)?;
+        }
+        eprintln!("Cached {}", path.display());
+    }
+    Ok(())
+}
+
+// Iterate over the entries in the cache, as (kind, path) pairs.
+fn entries(cache_dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut entries = Vec::new();
+    if !cache_dir.exists() {
+        return Ok(entries);
+    }
+    for kind_dir in fs::read_dir(cache_dir)? {
+        let kind_dir = kind_dir?;
+        if !kind_dir.file_type()?.is_dir() {
+            continue;
+        }
+        let kind = kind_dir.file_name().to_string_lossy().into_owned();
+        for entry in fs::read_dir(kind_dir.path())? {
+            entries.push((kind.clone(), entry?.path()));
+        }
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+fn is_current(kind: &str, path: &Path) -> bool {
+    ARTIFACTS.iter().any(|artifact| {
+        artifact.kind == kind && path.file_name() == Some(artifact.file_name().as_ref())
+    })
+}
+
+fn list(cache_dir: &Path) -> Result<()> {
+    println!("{}", cache_dir.display());
+    for (kind, path) in entries(cache_dir)? {
+        let size = fs::metadata(&path)?.len();
+        let status = if is_current(&kind, &path) {
+            "current"
+        } else {
+            "stale"
+        };
+        println!(
+            "  {kind}/{} ({}, {status})",
+            path.file_name().unwrap().to_string_lossy(),
+            size.human_count_bytes()
+        );
+    }
+    Ok(())
+}
+
+fn verify(cache_dir: &Path) -> Result<()> {
+    let mut corrupt = 0;
+    for (kind, path) in entries(cache_dir)? {
+        let expected = path.file_stem().unwrap_or_default().to_string_lossy();
+        if sha256_hex(&path)? != expected {
+            eprintln!(
+                "Removing corrupt entry {kind}/{}",
+                path.file_name().unwrap().to_string_lossy()
+            );
+            fs::remove_file(&path)?;
+            corrupt += 1;
+        }
+    }
+    if corrupt > 0 {
+        bail!("Removed {corrupt} corrupt cache entries; run `cargo risczero cache fetch` to restore them.");
+    }
+    eprintln!("All cache entries are intact");
+    Ok(())
+}
+
+fn prune(cache_dir: &Path, all: bool) -> Result<()> {
+    for (kind, path) in entries(cache_dir)? {
+        if all || !is_current(&kind, &path) {
+            eprintln!(
+                "Removing {kind}/{}",
+                path.file_name().unwrap().to_string_lossy()
+            );
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
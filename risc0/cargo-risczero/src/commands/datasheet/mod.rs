@@ -458,7 +458,7 @@ mod benches {
 }
 
 /// Utilities to make data human-readable for displaying in a table.
-mod display {
+pub(crate) mod display {
     use human_repr::*;
 
     use super::*;
@@ -0,0 +1,203 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fs,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use cargo_metadata::{BuildScript, Message, MetadataCommand, PackageId};
+use clap::Parser;
+use risc0_zkvm::MethodRegistry;
+
+/// `cargo risczero export`
+///
+/// Builds the guests embedded by a methods crate, and writes the ELF and image ID of each to a
+/// directory, for hosts that are not written in Rust.
+#[derive(Parser)]
+pub struct ExportCommand {
+    /// Location of the Cargo.toml of the methods crate, which embeds the guests with
+    /// `risc0_build::embed_methods`.
+    #[arg(long, default_value = "methods/Cargo.toml")]
+    pub manifest_path: PathBuf,
+
+    /// Directory to write the guest ELFs and image IDs to.
+    ///
+    /// Each guest is written to `<NAME>.bin`, and its hex-encoded image ID to `<NAME>.id`, where
+    /// `NAME` is the name of the constants generated for the guest, e.g. `METHOD` for
+    /// `METHOD_ELF` and `METHOD_ID`.
+    #[arg(long, default_value = "elf")]
+    pub out_dir: PathBuf,
+}
+
+impl ExportCommand {
+    pub fn run(&self) -> Result<()> {
+        let manifest = build_methods(&self.manifest_path)?;
+        let registry = MethodRegistry::load(&manifest)?;
+        export(&registry, &self.out_dir)
+    }
+}
+
+/// Build the methods crate, and return the path of the `methods.json` written by its build
+/// script.
+fn build_methods(manifest_path: &Path) -> Result<PathBuf> {
+    let meta = MetadataCommand::new()
+        .manifest_path(manifest_path)
+        .exec()
+        .context("Manifest not found")?;
+    let root_pkg = meta.root_package().context("Failed to parse Cargo.toml")?;
+
+    let mut child = Command::new("cargo")
+        .args([
+            "build",
+            "--message-format=json-render-diagnostics",
+            "--manifest-path",
+        ])
+        .arg(manifest_path)
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let reader = BufReader::new(
+        child
+            .stdout
+            .take()
+            .ok_or(anyhow!("failed to read from cmd stdout"))?,
+    );
+    let out_dir = build_script_out_dir(reader, &root_pkg.id)?;
+
+    let output = child
+        .wait()
+        .with_context(|| "couldn't get cargo's exit status")?;
+    if !output.success() {
+        bail!("failed to build {}", root_pkg.name)
+    }
+
+    let out_dir = out_dir.ok_or(anyhow!("{} has no build script", root_pkg.name))?;
+    let manifest = out_dir.join("methods.json");
+    ensure!(
+        manifest.exists(),
+        "the build script of {} did not write {manifest:?}; guests are only exported when embedded \
+        with `risc0_build::embed_methods` and not gated behind a feature",
+        root_pkg.name
+    );
+    Ok(manifest)
+}
+
+/// Find the `OUT_DIR` of the build script of `package` in the JSON messages printed by cargo.
+fn build_script_out_dir(reader: impl BufRead, package: &PackageId) -> Result<Option<PathBuf>> {
+    let mut out_dir = None;
+    for message in Message::parse_stream(reader) {
+        match message? {
+            Message::BuildScriptExecuted(BuildScript {
+                package_id,
+                out_dir: dir,
+                ..
+            }) if &package_id == package => {
+                out_dir = Some(dir.into_std_path_buf());
+            }
+            Message::CompilerMessage(msg) => {
+                eprint!("{msg}");
+            }
+            _ => (),
+        }
+    }
+    Ok(out_dir)
+}
+
+/// Write the ELF and image ID of each method in the registry to `out_dir`.
+fn export(registry: &MethodRegistry, out_dir: &Path) -> Result<()> {
+    fs::create_dir_all(out_dir)?;
+    for method in registry.iter() {
+        let elf_path = out_dir.join(format!("{}.bin", method.name));
+        fs::write(&elf_path, &method.elf)
+            .with_context(|| format!("failed to write {elf_path:?}"))?;
+        let id_path = out_dir.join(format!("{}.id", method.name));
+        fs::write(&id_path, method.image_id.to_string())
+            .with_context(|| format!("failed to write {id_path:?}"))?;
+        println!(
+            "Exported `{}` with image ID `{}` to {}.",
+            method.name,
+            method.image_id,
+            elf_path.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use hex::FromHex;
+    use risc0_zkvm::{compute_image_id, sha::Digest};
+    use tempfile::tempdir;
+
+    use super::*;
+
+    const LOOP_ELF: &[u8] = include_bytes!("datasheet/loop.bin");
+
+    fn build_script_executed(package_id: &str, out_dir: &str) -> String {
+        serde_json::json!({
+            "reason": "build-script-executed",
+            "package_id": package_id,
+            "linked_libs": [],
+            "linked_paths": [],
+            "cfgs": [],
+            "env": [],
+            "out_dir": out_dir,
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn find_out_dir() {
+        let methods = PackageId {
+            repr: "path+file:///project/methods#0.1.0".to_string(),
+        };
+        let stream = [
+            build_script_executed("path+file:///project/other#0.1.0", "/target/other"),
+            build_script_executed(&methods.repr, "/target/methods"),
+            serde_json::json!({ "reason": "build-finished", "success": true }).to_string(),
+        ]
+        .join("\n");
+
+        let out_dir = build_script_out_dir(Cursor::new(stream), &methods).unwrap();
+        assert_eq!(out_dir, Some(PathBuf::from("/target/methods")));
+
+        let out_dir = build_script_out_dir(Cursor::new(""), &methods).unwrap();
+        assert_eq!(out_dir, None);
+    }
+
+    #[test]
+    fn export_methods() {
+        let build_dir = tempdir().unwrap();
+        let image_id = compute_image_id(LOOP_ELF).unwrap();
+        fs::write(build_dir.path().join("loop"), LOOP_ELF).unwrap();
+        let manifest = build_dir.path().join("methods.json");
+        let entries = serde_json::json!([
+            { "name": "LOOP", "image_id": image_id.to_string(), "path": "loop" },
+        ]);
+        fs::write(&manifest, entries.to_string()).unwrap();
+
+        let out_dir = tempdir().unwrap();
+        let registry = MethodRegistry::load(&manifest).unwrap();
+        export(&registry, out_dir.path()).unwrap();
+
+        assert_eq!(fs::read(out_dir.path().join("LOOP.bin")).unwrap(), LOOP_ELF);
+        let exported_id = fs::read_to_string(out_dir.path().join("LOOP.id")).unwrap();
+        assert_eq!(Digest::from_hex(exported_id).unwrap(), image_id);
+    }
+}
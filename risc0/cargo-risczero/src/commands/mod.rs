@@ -15,10 +15,13 @@
 #[cfg(feature = "experimental")]
 pub mod build;
 
+pub mod bench;
 pub mod build_guest;
 pub mod build_toolchain;
+pub mod cache;
 pub mod datasheet;
 pub mod deploy;
+pub mod export;
 pub mod install;
 pub mod new;
 pub mod verify;
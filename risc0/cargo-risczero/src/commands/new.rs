@@ -15,7 +15,7 @@
 use std::path::PathBuf;
 
 use anyhow::{anyhow, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use const_format::concatcp;
 use regex::Regex;
 use text_io::read;
@@ -43,6 +43,12 @@ static PROJECT_TEMPLATED_FILES: &[(&str, &str)] = &[
     ("methods/guest/src/main.rs", GUEST_MAIN),
 ];
 
+const HOST_README: &str = include_str!("../../templates/hosts/README.md");
+const PYTHON_HOST: &str = include_str!("../../templates/hosts/python/host.py");
+const NODE_HOST: &str = include_str!("../../templates/hosts/node/host.mjs");
+const GO_HOST: &str = include_str!("../../templates/hosts/go/main.go");
+const GO_MOD: &str = include_str!("../../templates/hosts/go/go-mod");
+
 const RUST_TOOLCHAIN_TOML: &str = include_str!("../../templates/rust-starter/rust-toolchain.toml");
 const README: &str = include_str!("../../templates/rust-starter/README.md");
 const GIT_IGNORE: &str = include_str!("../../templates/rust-starter/.gitignore");
@@ -54,6 +60,54 @@ static PROJECT_NON_TEMPLATED_FILES: &[(&str, &str)] = &[
     ("LICENSE", LICENSE),
 ];
 
+/// The language of the host of a generated project.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum HostLang {
+    /// A Rust host crate, using the `risc0-zkvm` crate.
+    #[default]
+    Rust,
+
+    /// A Python script.
+    Python,
+
+    /// A Node.js script.
+    Node,
+
+    /// A Go program.
+    Go,
+}
+
+impl HostLang {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Rust => "Rust",
+            Self::Python => "Python",
+            Self::Node => "Node.js",
+            Self::Go => "Go",
+        }
+    }
+
+    /// The command that runs the host from the project root.
+    fn run_command(&self) -> &'static str {
+        match self {
+            Self::Rust => "cargo run --release",
+            Self::Python => "python3 host/host.py",
+            Self::Node => "node host/host.mjs",
+            Self::Go => "cd host && go run .",
+        }
+    }
+
+    /// The templated host files, which replace the host crate of a Rust host.
+    fn files(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Rust => &[],
+            Self::Python => &[("host/host.py", PYTHON_HOST)],
+            Self::Node => &[("host/host.mjs", NODE_HOST)],
+            Self::Go => &[("host/main.go", GO_HOST), ("host/go.mod", GO_MOD)],
+        }
+    }
+}
+
 /// `cargo risczero new`
 #[derive(Parser)]
 pub struct NewCommand {
@@ -100,6 +154,16 @@ pub struct NewCommand {
     /// Sets the name of the guest method. This must be a valid rust identifier.
     #[arg(long)]
     pub guest_name: Option<String>,
+
+    /// Language of the host.
+    ///
+    /// A Rust host is a crate using `risc0-zkvm`. Hosts in other languages replace the host crate,
+    /// and use the `r0vm` and `cargo risczero` command line tools as their bindings to the zkVM:
+    /// they embed the prebuilt guest ELF and image ID exported to `elf/` by
+    /// `cargo risczero export`, prove the guest with `r0vm`, and verify the receipt with
+    /// `cargo risczero verify`.
+    #[arg(long, value_enum, default_value_t)]
+    pub host_lang: HostLang,
 }
 
 impl NewCommand {
@@ -156,6 +220,28 @@ impl NewCommand {
         })?;
 
         let guest_name_const = guest_name.replace('-', "_").to_ascii_uppercase();
+        template_variables.push((
+            Regex::new(r"\{\{ *workspace_members *\}\}")?,
+            match self.host_lang {
+                HostLang::Rust => "\"host\", \"methods\"",
+                _ => "\"methods\"",
+            }
+            .to_string(),
+        ));
+        if self.host_lang != HostLang::Rust {
+            template_variables.push((
+                Regex::new(r"\{\{ *guest_const *\}\}")?,
+                guest_name_const.clone(),
+            ));
+            template_variables.push((
+                Regex::new(r"\{\{ *host_lang *\}\}")?,
+                self.host_lang.name().to_string(),
+            ));
+            template_variables.push((
+                Regex::new(r"\{\{ *host_run *\}\}")?,
+                self.host_lang.run_command().to_string(),
+            ));
+        }
         template_variables.push((
             Regex::new(r"\{\{ *guest_package_name *\}\}")?,
             format!("\"{guest_name}\""),
@@ -197,11 +283,22 @@ risc0_zkvm::guest::entry!(main);\n";
         let root = dest.join(self.name.clone());
 
         // generate host directories
-        std::fs::create_dir_all(root.join("host/src"))?;
+        match self.host_lang {
+            HostLang::Rust => std::fs::create_dir_all(root.join("host/src"))?,
+            _ => std::fs::create_dir_all(root.join("host"))?,
+        }
         std::fs::create_dir_all(root.join("methods/src"))?;
         std::fs::create_dir_all(root.join("methods/guest/src"))?;
 
-        for (filepath, content) in PROJECT_TEMPLATED_FILES {
+        // Hosts in other languages replace the host crate.
+        let mut files = PROJECT_TEMPLATED_FILES.to_vec();
+        if self.host_lang != HostLang::Rust {
+            files.retain(|(filepath, _)| !filepath.starts_with("host/"));
+            files.push(("host/README.md", HOST_README));
+            files.extend_from_slice(self.host_lang.files());
+        }
+
+        for (filepath, content) in files {
             std::fs::write(
                 root.join(filepath),
                 &Self::gen_file(content, template_variables.clone()),
@@ -257,6 +354,7 @@ mod tests {
     fn basic_new() {
         let new = NewCommand::parse_from(["new", "--guest-name", "method", "my_project"]);
         assert_eq!(new.name, "my_project");
+        assert_eq!(new.host_lang, HostLang::Rust);
     }
 
     #[test]
@@ -315,6 +413,66 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn generate_host_lang() {
+        let risc0_root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+        for host_lang in HostLang::value_variants() {
+            let (tmpdir, proj_name) = make_test_env();
+            let name = host_lang.to_possible_value().unwrap();
+
+            let new = NewCommand::parse_from([
+                "new",
+                "--dest",
+                &tmpdir.path().to_string_lossy(),
+                "--path",
+                &risc0_root.to_string_lossy(),
+                "--host-lang",
+                name.get_name(),
+                "--guest-name",
+                "method",
+                proj_name,
+            ]);
+
+            new.run().unwrap();
+
+            let proj_path = tmpdir.path().join(proj_name);
+
+            // Hosts in other languages replace the host crate, leaving the methods crate as the
+            // only member of the workspace, with the default build script that
+            // `cargo risczero export` reads.
+            let meta = cargo_metadata::MetadataCommand::new()
+                .manifest_path(proj_path.join("Cargo.toml"))
+                .no_deps()
+                .exec()
+                .unwrap();
+            let members: Vec<_> = meta
+                .workspace_packages()
+                .into_iter()
+                .map(|pkg| pkg.name.as_str())
+                .collect();
+            if *host_lang == HostLang::Rust {
+                assert_eq!(members, ["host", "methods"]);
+                assert!(!proj_path.join("host/README.md").exists());
+                continue;
+            }
+            assert_eq!(members, ["methods"]);
+            assert_eq!(
+                std::fs::read_to_string(proj_path.join("methods/build.rs")).unwrap(),
+                METHODS_BUILD_SCRIPT
+            );
+
+            // Every template variable used by the host files was substituted.
+            for entry in std::fs::read_dir(proj_path.join("host")).unwrap() {
+                let path = entry.unwrap().path();
+                let content = std::fs::read_to_string(&path).unwrap();
+                assert!(
+                    !content.contains("{{"),
+                    "{path:?} has unsubstituted variables"
+                );
+            }
+        }
+    }
+
     #[test]
     fn generate_no_std() {
         let (tmpdir, proj_name) = make_test_env();
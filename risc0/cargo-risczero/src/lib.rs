@@ -29,8 +29,9 @@ use clap::{Parser, Subcommand};
 #[cfg(feature = "experimental")]
 use self::commands::build::BuildCommand;
 use self::commands::{
-    build_guest::BuildGuest, build_toolchain::BuildToolchain, datasheet::Datasheet,
-    deploy::DeployCommand, install::Install, new::NewCommand, verify::VerifyCommand,
+    bench::BenchCommand, build_guest::BuildGuest, build_toolchain::BuildToolchain,
+    cache::CacheCommand, datasheet::Datasheet, deploy::DeployCommand, export::ExportCommand,
+    install::Install, new::NewCommand, verify::VerifyCommand,
 };
 
 #[derive(Parser)]
@@ -61,6 +62,8 @@ pub enum RisczeroCmd {
     BuildToolchain(BuildToolchain),
     /// Install the riscv32im-risc0-zkvm-elf toolchain.
     Install(Install),
+    /// Manage the cache of prover artifacts.
+    Cache(CacheCommand),
     /// Perform a benchmark to evaluate zkVM performance for this machine's
     /// hardware.
     Datasheet(Datasheet),
@@ -68,8 +71,12 @@ pub enum RisczeroCmd {
     New(NewCommand),
     /// Uploads the guest code to Bonsai.
     Deploy(DeployCommand),
+    /// Builds the guests of a methods crate, and writes their ELFs and image IDs to a directory.
+    Export(ExportCommand),
     /// Verifies if a receipt is valid.
     Verify(VerifyCommand),
+    /// Executes, proves and verifies a guest, reporting the time spent in each stage.
+    Bench(BenchCommand),
     /// Build a crate for RISC Zero.
     #[cfg(feature = "experimental")]
     BuildCrate(BuildCommand),
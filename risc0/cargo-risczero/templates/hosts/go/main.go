@@ -0,0 +1,76 @@
+// Prove and verify the guest from a Go host.
+//
+// The guest is built by `cargo risczero export`, which writes its ELF and image ID to `elf/`.
+// Proving is done by the `r0vm` binary installed alongside `cargo risczero`, and receipts are
+// verified with `cargo risczero verify`.
+package main
+
+import (
+	"encoding/binary"
+	"fmt"
+	"log"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"strings"
+)
+
+const guest = "{{ guest_const }}"
+
+func run(root string, name string, args ...string) {
+	cmd := exec.Command(name, args...)
+	cmd.Dir = root
+	cmd.Stdout = os.Stdout
+	cmd.Stderr = os.Stderr
+	if err := cmd.Run(); err != nil {
+		log.Fatalf("%s failed: %v", name, err)
+	}
+}
+
+func main() {
+	root, err := filepath.Abs("..")
+	if err != nil {
+		log.Fatal(err)
+	}
+
+	run(root, "cargo", "risczero", "export")
+	elf := filepath.Join(root, "elf", guest+".bin")
+	id, err := os.ReadFile(filepath.Join(root, "elf", guest+".id"))
+	if err != nil {
+		log.Fatal(err)
+	}
+	imageID := strings.TrimSpace(string(id))
+
+	tmp, err := os.MkdirTemp("", "risc0-")
+	if err != nil {
+		log.Fatal(err)
+	}
+	defer os.RemoveAll(tmp)
+
+	// Guest input is read with `env::read`, which decodes words in the risc0 serde format.
+	// A u32 is a single little-endian word.
+	// TODO: Write the input for your guest here.
+	input := binary.LittleEndian.AppendUint32(nil, 15*(1<<27)+1)
+	if err := os.WriteFile(filepath.Join(tmp, "input.bin"), input, 0o644); err != nil {
+		log.Fatal(err)
+	}
+
+	receipt := filepath.Join(tmp, "receipt.bin")
+	journal := filepath.Join(tmp, "journal.bin")
+	run(root, "r0vm",
+		"--elf", elf,
+		"--initial-input", filepath.Join(tmp, "input.bin"),
+		"--receipt", receipt,
+		"--journal", journal,
+	)
+
+	// TODO: Decode the journal committed by your guest here.
+	output, err := os.ReadFile(journal)
+	if err != nil {
+		log.Fatal(err)
+	}
+	fmt.Printf("guest committed %d\n", binary.LittleEndian.Uint32(output))
+
+	// The receipt was verified at the end of proving, but this is how someone else could verify it.
+	run(root, "cargo", "risczero", "verify", "--path", receipt, imageID)
+}
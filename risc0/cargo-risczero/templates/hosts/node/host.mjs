@@ -0,0 +1,47 @@
+// Prove and verify the guest from a Node.js host.
+//
+// The guest is built by `cargo risczero export`, which writes its ELF and image ID to `elf/`.
+// Proving is done by the `r0vm` binary installed alongside `cargo risczero`, and receipts are
+// verified with `cargo risczero verify`.
+
+import { execFileSync } from "node:child_process";
+import { mkdtempSync, readFileSync, writeFileSync } from "node:fs";
+import { tmpdir } from "node:os";
+import { dirname, join } from "node:path";
+import { fileURLToPath } from "node:url";
+
+const root = join(dirname(fileURLToPath(import.meta.url)), "..");
+const guest = "{{ guest_const }}";
+const run = (cmd, args) => execFileSync(cmd, args, { cwd: root, stdio: "inherit" });
+
+run("cargo", ["risczero", "export"]);
+const elf = join(root, "elf", `${guest}.bin`);
+const imageId = readFileSync(join(root, "elf", `${guest}.id`), "utf8").trim();
+
+const tmp = mkdtempSync(join(tmpdir(), "risc0-"));
+// Guest input is read with `env::read`, which decodes words in the risc0 serde format.
+// A u32 is a single little-endian word.
+// TODO: Write the input for your guest here.
+const input = Buffer.alloc(4);
+input.writeUInt32LE(15 * 2 ** 27 + 1);
+writeFileSync(join(tmp, "input.bin"), input);
+
+const receipt = join(tmp, "receipt.bin");
+const journal = join(tmp, "journal.bin");
+run("r0vm", [
+  "--elf",
+  elf,
+  "--initial-input",
+  join(tmp, "input.bin"),
+  "--receipt",
+  receipt,
+  "--journal",
+  journal,
+]);
+
+// TODO: Decode the journal committed by your guest here.
+const output = readFileSync(journal).readUInt32LE(0);
+console.log(`guest committed ${output}`);
+
+// The receipt was verified at the end of proving, but this is how someone else could verify it.
+run("cargo", ["risczero", "verify", "--path", receipt, imageId]);
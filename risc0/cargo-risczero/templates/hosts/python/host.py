@@ -0,0 +1,55 @@
+"""Prove and verify the guest from a Python host.
+
+The guest is built by `cargo risczero export`, which writes its ELF and image ID to `elf/`.
+Proving is done by the `r0vm` binary installed alongside `cargo risczero`, and receipts are
+verified with `cargo risczero verify`.
+"""
+
+import struct
+import subprocess
+import tempfile
+from pathlib import Path
+
+ROOT = Path(__file__).resolve().parent.parent
+GUEST = "{{ guest_const }}"
+
+
+def main():
+    subprocess.run(["cargo", "risczero", "export"], cwd=ROOT, check=True)
+    elf = ROOT / "elf" / f"{GUEST}.bin"
+    image_id = (ROOT / "elf" / f"{GUEST}.id").read_text().strip()
+
+    with tempfile.TemporaryDirectory() as tmp:
+        tmp = Path(tmp)
+        # Guest input is read with `env::read`, which decodes words in the risc0 serde format.
+        # A u32 is a single little-endian word.
+        # TODO: Write the input for your guest here.
+        (tmp / "input.bin").write_bytes(struct.pack("<I", 15 * 2**27 + 1))
+
+        receipt = tmp / "receipt.bin"
+        journal = tmp / "journal.bin"
+        subprocess.run(
+            [
+                "r0vm",
+                "--elf", str(elf),
+                "--initial-input", str(tmp / "input.bin"),
+                "--receipt", str(receipt),
+                "--journal", str(journal),
+            ],
+            check=True,
+        )
+
+        # TODO: Decode the journal committed by your guest here.
+        (output,) = struct.unpack("<I", journal.read_bytes())
+        print(f"guest committed {output}")
+
+        # The receipt was verified at the end of proving, but this is how someone else could
+        # verify it.
+        subprocess.run(
+            ["cargo", "risczero", "verify", "--path", str(receipt), image_id],
+            check=True,
+        )
+
+
+if __name__ == "__main__":
+    main()
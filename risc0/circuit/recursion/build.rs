@@ -14,7 +14,15 @@
 
 use std::env;
 
+#[cfg(feature = "prove")]
+mod zkr_cache;
+
+// Digest of the recursion programs archive, which also names it in the artifact cache.
+const ZKR_SHA256: &str = "28e4eeff7a8f73d27408d99a1e3e8842c79a5f4353e5117ec0b7ffaa7c193612";
+
 fn main() {
+    println!("cargo:rustc-env=RECURSION_ZKR_SHA256={ZKR_SHA256}");
+
     if env::var("CARGO_FEATURE_PROVE").is_ok()
         && env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os == "macos" || os == "ios")
     {
@@ -37,18 +45,26 @@ fn download_zkr() {
     };
 
     use downloader::{verify, Download, DownloadSummary, Downloader};
-    use sha2::{Digest, Sha256};
+    use sha2::Sha256;
+
+    use crate::zkr_cache::{cache_dir, sha256_hex, warn, CacheEntry, CacheLock};
 
     const FILENAME: &str = "recursion_zkr.zip";
     const SRC_PATH: &str = "src/recursion_zkr.zip";
-    const SHA256_HASH: &str = "28e4eeff7a8f73d27408d99a1e3e8842c79a5f4353e5117ec0b7ffaa7c193612";
+    const SHA256_HASH: &str = ZKR_SHA256;
+    const ENTRY: CacheEntry = CacheEntry {
+        kind: "zkr",
+        sha256: SHA256_HASH,
+        ext: "zip",
+    };
 
     fn check_sha2(path: &Path) -> bool {
-        let data = fs::read(path).unwrap();
-        hex::encode(Sha256::digest(data)) == SHA256_HASH
+        sha256_hex(path).is_ok_and(|digest| digest == SHA256_HASH)
     }
 
     println!("cargo:rerun-if-env-changed=RECURSION_SRC_PATH");
+    println!("cargo:rerun-if-env-changed=RISC0_CACHE_DIR");
+    println!("cargo:rerun-if-env-changed=RISC0_OFFLINE");
 
     let src_path = env::var("RECURSION_SRC_PATH").unwrap_or(SRC_PATH.to_string());
     let src_path = PathBuf::from_str(src_path.as_str()).unwrap();
@@ -65,7 +81,7 @@ fn download_zkr() {
         if check_sha2(&out_path) {
             return;
         }
-        fs::remove_file(&out_path).unwrap();
+        let _ = fs::remove_file(&out_path);
     }
 
     if src_path.exists() && check_sha2(&src_path) {
@@ -73,6 +89,31 @@ fn download_zkr() {
         return;
     }
 
+    // The lock is held until the archive is stored in the cache, so that concurrent builds
+    // download it only once.
+    let cache_dir = cache_dir();
+    let _lock = cache_dir.as_ref().and_then(|dir| {
+        CacheLock::acquire(dir)
+            .map_err(|err| warn(&format!("Failed to lock the artifact cache: {err}")))
+            .ok()
+    });
+    if let Some(cache_dir) = &cache_dir {
+        if ENTRY.load(cache_dir, &out_path) {
+            return;
+        }
+    }
+
+    if env::var("RISC0_OFFLINE").is_ok() {
+        panic!(
+            "{FILENAME} is not in the artifact cache and RISC0_OFFLINE is set. \
+            Run `cargo risczero cache fetch` on a machine with network access and copy the cache \
+            to {}.",
+            cache_dir.map_or("RISC0_CACHE_DIR".to_string(), |dir| dir
+                .display()
+                .to_string())
+        );
+    }
+
     let mut downloader = Downloader::builder()
         .download_folder(out_dir)
         .build()
@@ -89,4 +130,8 @@ fn download_zkr() {
         let summary: DownloadSummary = result.unwrap();
         eprintln!("{summary}");
     }
+
+    if let Some(cache_dir) = &cache_dir {
+        ENTRY.store(cache_dir, &out_path);
+    }
 }
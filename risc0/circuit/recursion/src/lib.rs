@@ -49,6 +49,11 @@ pub mod prove;
 mod taps;
 #[cfg(feature = "prove")]
 pub mod zkr;
+// The artifact cache used by the build script.
+#[cfg(all(test, feature = "prove"))]
+#[allow(dead_code)]
+#[path = "../zkr_cache.rs"]
+mod zkr_cache;
 
 use risc0_core::field::baby_bear::{BabyBearElem, BabyBearExtElem};
 use risc0_zkp::{
@@ -67,6 +72,12 @@ pub const GLOBAL_OUT: usize = 1;
 
 pub const CHECKED_COEFFS_PER_POLY: usize = 16;
 
+/// SHA-256 digest of the archive of recursion programs used by this version of the crate.
+///
+/// The archive is fetched at build time, from the artifact cache under `~/.risc0/cache` if
+/// present. It can be pre-fetched into the cache with `cargo risczero cache fetch`.
+pub const ZKR_SHA256: &str = env!("RECURSION_ZKR_SHA256");
+
 /// This struct implements traits that are defined by code generated by the
 /// circuit definition.
 pub struct CircuitImpl;
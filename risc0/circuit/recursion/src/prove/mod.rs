@@ -32,8 +32,8 @@ use anyhow::{bail, Result};
 use rand::thread_rng;
 use risc0_core::scope;
 use risc0_zkp::{
-    adapter::{CircuitInfo, CircuitStepContext, TapsProvider, PROOF_SYSTEM_INFO},
-    core::{digest::Digest, hash::poseidon2::Poseidon2HashSuite},
+    adapter::{CircuitInfo, CircuitStepContext, TapsProvider},
+    core::{digest::Digest, hash::poseidon2::Poseidon2HashSuite, transcript},
     field::{
         baby_bear::{BabyBear, BabyBearElem, BabyBearExtElem},
         Elem,
@@ -218,6 +218,28 @@ cfg_if::cfg_if! {
     }
 }
 
+/// Returns false if this crate was built for a GPU that is not present, in which case the CPU HAL
+/// is used instead.
+fn gpu_available() -> bool {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "cuda")] {
+            let available = risc0_zkp::hal::capabilities().cuda;
+            if !available {
+                tracing::warn!("No CUDA device found, falling back to the CPU prover");
+            }
+            available
+        } else if #[cfg(any(all(target_os = "macos", target_arch = "aarch64"), target_os = "ios"))] {
+            let available = risc0_zkp::hal::capabilities().metal;
+            if !available {
+                tracing::warn!("No Metal device found, falling back to the CPU prover");
+            }
+            available
+        } else {
+            true
+        }
+    }
+}
+
 /// Kinds of digests recognized by the recursion program language.
 // NOTE: Default is additionally a recognized type in the recursion program language. It's not
 // yet supported here because some of the code in this module assumes Poseidon2 is Default.
@@ -266,7 +288,13 @@ impl Prover {
 
     /// Run the prover, producing a receipt of execution for the recursion circuit over the loaded
     /// program and input.
+    ///
+    /// Uses the GPU HAL this crate was built for, or the CPU HAL if no GPU is present.
     pub fn run(&mut self) -> Result<RecursionReceipt> {
+        if !gpu_available() {
+            return self.run_with_cpu();
+        }
+
         // NOTE: Code is repeated across match arms to satisfy generics.
         match self.hashfn.as_ref() {
             "poseidon2" => {
@@ -288,6 +316,28 @@ impl Prover {
         }
     }
 
+    fn run_with_cpu(&mut self) -> Result<RecursionReceipt> {
+        // NOTE: Code is repeated across match arms to satisfy generics.
+        match self.hashfn.as_ref() {
+            "poseidon2" => {
+                let hal_pair = cpu::poseidon2_hal_pair();
+                let (hal, circuit_hal) = (hal_pair.hal.as_ref(), hal_pair.circuit_hal.as_ref());
+                self.run_with_hal(hal, circuit_hal)
+            }
+            "poseidon_254" => {
+                let hal_pair = cpu::poseidon254_hal_pair();
+                let (hal, circuit_hal) = (hal_pair.hal.as_ref(), hal_pair.circuit_hal.as_ref());
+                self.run_with_hal(hal, circuit_hal)
+            }
+            "sha-256" => {
+                let hal_pair = cpu::sha256_hal_pair();
+                let (hal, circuit_hal) = (hal_pair.hal.as_ref(), hal_pair.circuit_hal.as_ref());
+                self.run_with_hal(hal, circuit_hal)
+            }
+            _ => bail!("no hal found for {}", self.hashfn),
+        }
+    }
+
     /// Run the prover, producing a receipt of execution for the recursion circuit over the loaded
     /// program and input, using the specified HAL.
     pub fn run_with_hal<H, C>(&mut self, hal: &H, circuit_hal: &C) -> Result<RecursionReceipt>
@@ -316,12 +366,15 @@ impl Prover {
             let (mix, io) = scope!("main", {
                 // At the start of the protocol, seed the Fiat-Shamir transcript with context information
                 // about the proof system and circuit.
-                prover
-                    .iop()
-                    .commit(&hashfn.hash_elem_slice(&PROOF_SYSTEM_INFO.encode()));
-                prover
-                    .iop()
-                    .commit(&hashfn.hash_elem_slice(&CircuitImpl::CIRCUIT_INFO.encode()));
+                let proof_system_info = prover.version().info();
+                prover.iop().commit_labeled(
+                    transcript::PROOF_SYSTEM_INFO,
+                    &hashfn.hash_elem_slice(&proof_system_info.encode()),
+                );
+                prover.iop().commit_labeled(
+                    transcript::CIRCUIT_INFO,
+                    &hashfn.hash_elem_slice(&CircuitImpl::CIRCUIT_INFO.encode()),
+                );
 
                 adapter.execute(prover.iop(), hal);
 
@@ -336,7 +389,7 @@ impl Prover {
                 // Make the mixing values
                 let mix = scope!("alloc+copy(mix)", {
                     let mix: Vec<_> = (0..CircuitImpl::MIX_SIZE)
-                        .map(|_| prover.iop().random_elem())
+                        .map(|_| prover.iop().random_elem_labeled(transcript::ACCUM_MIX))
                         .collect();
                     hal.copy_from_elem("mix", mix.as_slice())
                 });
@@ -382,7 +435,7 @@ impl Prover {
             });
 
             prover.finalize(&[&mix, &io], circuit_hal)
-        });
+        })?;
 
         Ok(RecursionReceipt {
             seal,
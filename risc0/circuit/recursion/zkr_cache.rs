@@ -0,0 +1,184 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Access to the artifact cache managed by `cargo risczero cache`, for the build script. The cache
+// is best-effort: failing to read or write it only costs a download, so errors are reported as
+// warnings rather than failing the build.
+
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+};
+
+use fs2::FileExt;
+use sha2::{Digest, Sha256};
+
+// Mirrors the cache location used by `cargo risczero cache`.
+pub fn cache_dir() -> Option<PathBuf> {
+    if let Ok(dir) = env::var("RISC0_CACHE_DIR") {
+        Some(PathBuf::from(dir))
+    } else if let Ok(dir) = env::var("RISC0_HOME") {
+        Some(PathBuf::from(dir).join("cache"))
+    } else {
+        let home = env::var("HOME").or_else(|_| env::var("USERPROFILE")).ok()?;
+        Some(PathBuf::from(home).join(".risc0").join("cache"))
+    }
+}
+
+pub fn sha256_hex(path: &Path) -> io::Result<String> {
+    Ok(hex::encode(Sha256::digest(fs::read(path)?)))
+}
+
+pub fn warn(msg: &str) {
+    println!("cargo:warning={msg}");
+}
+
+// An exclusive lock on the cache, the same one taken by `cargo risczero cache`, so that an
+// entry is never read while it is being written or pruned.
+pub struct CacheLock(File);
+
+impl CacheLock {
+    pub fn acquire(cache_dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(cache_dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .read(true)
+            .write(true)
+            .open(cache_dir.join(".lock"))?;
+        file.lock_exclusive()?;
+        Ok(Self(file))
+    }
+}
+
+impl Drop for CacheLock {
+    fn drop(&mut self) {
+        let _ = self.0.unlock();
+    }
+}
+
+// An artifact in the cache, stored at `<kind>/<sha256>.<ext>`.
+pub struct CacheEntry<'a> {
+    pub kind: &'a str,
+    pub sha256: &'a str,
+    pub ext: &'a str,
+}
+
+impl CacheEntry<'_> {
+    pub fn path(&self, cache_dir: &Path) -> PathBuf {
+        cache_dir
+            .join(self.kind)
+            .join(format!("{}.{}", self.sha256, self.ext))
+    }
+
+    // Copies the entry to `dest`, returning whether it was present and intact. A corrupt entry is
+    // removed, so that it is downloaded again.
+    pub fn load(&self, cache_dir: &Path, dest: &Path) -> bool {
+        let path = self.path(cache_dir);
+        match sha256_hex(&path) {
+            Ok(digest) if digest == self.sha256 => match fs::copy(&path, dest) {
+                Ok(_) => return true,
+                Err(err) => warn(&format!("Failed to copy {}: {err}", path.display())),
+            },
+            Ok(_) => {
+                warn(&format!("Removing corrupt cache entry {}", path.display()));
+                if let Err(err) = fs::remove_file(&path) {
+                    warn(&format!("Failed to remove {}: {err}", path.display()));
+                }
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => warn(&format!("Failed to read {}: {err}", path.display())),
+        }
+        false
+    }
+
+    // Stores a copy of `src` in the cache.
+    pub fn store(&self, cache_dir: &Path, src: &Path) {
+        let path = self.path(cache_dir);
+        let stored = fs::create_dir_all(path.parent().unwrap()).and_then(|_| fs::copy(src, &path));
+        if let Err(err) = stored {
+            warn(&format!("Failed to cache {}: {err}", path.display()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use fs2::FileExt;
+    use tempfile::tempdir;
+
+    use super::{sha256_hex, CacheEntry, CacheLock};
+
+    const DATA: &[u8] = b"recursion programs";
+
+    fn entry(sha256: &str) -> CacheEntry<'_> {
+        CacheEntry {
+            kind: "zkr",
+            sha256,
+            ext: "zip",
+        }
+    }
+
+    #[test]
+    fn load_and_store() {
+        let dir = tempdir().unwrap();
+        let cache_dir = dir.path().join("cache");
+        let src = dir.path().join("src.zip");
+        let dest = dir.path().join("dest.zip");
+        fs::write(&src, DATA).unwrap();
+        let sha256 = sha256_hex(&src).unwrap();
+        let entry = entry(&sha256);
+
+        assert!(!entry.load(&cache_dir, &dest));
+        entry.store(&cache_dir, &src);
+        assert_eq!(
+            entry.path(&cache_dir),
+            cache_dir.join("zkr").join(format!("{sha256}.zip"))
+        );
+        assert!(entry.load(&cache_dir, &dest));
+        assert_eq!(fs::read(&dest).unwrap(), DATA);
+    }
+
+    #[test]
+    fn corrupt_entry() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src.zip");
+        fs::write(&src, DATA).unwrap();
+        let sha256 = sha256_hex(&src).unwrap();
+        let entry = entry(&sha256);
+        let path = entry.path(dir.path());
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, b"corrupt").unwrap();
+
+        // The corrupt entry is removed instead of being copied.
+        let dest = dir.path().join("dest.zip");
+        assert!(!entry.load(dir.path(), &dest));
+        assert!(!path.exists());
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn lock() {
+        let dir = tempdir().unwrap();
+        let lock = CacheLock::acquire(dir.path()).unwrap();
+        let other = fs::File::open(dir.path().join(".lock")).unwrap();
+        assert!(other.try_lock_exclusive().is_err());
+        drop(lock);
+        other.try_lock_exclusive().unwrap();
+    }
+}
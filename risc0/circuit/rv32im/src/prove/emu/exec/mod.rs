@@ -15,7 +15,7 @@
 #[cfg(test)]
 mod tests;
 
-use std::{array, cell::RefCell, collections::BTreeSet, mem, rc::Rc};
+use std::{array, cell::RefCell, collections::BTreeSet, fmt, mem, rc::Rc};
 
 use anyhow::{bail, ensure, Result};
 use crypto_bigint::{CheckedMul as _, Encoding as _, NonZero, U256, U512};
@@ -57,6 +57,18 @@ use crate::{
 
 pub const DEFAULT_SEGMENT_LIMIT_PO2: usize = 20;
 
+/// Error returned when execution exceeds the session limit.
+#[derive(Debug)]
+pub struct SessionLimitExceeded;
+
+impl fmt::Display for SessionLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Session limit exceeded")
+    }
+}
+
+impl std::error::Error for SessionLimitExceeded {}
+
 /// A host-side implementation of a system call.
 pub trait Syscall {
     /// Invokes the system call.
@@ -136,6 +148,23 @@ struct PendingState {
     events: BTreeSet<TraceEvent>,
 }
 
+/// A load or store by a guest instruction, passed to the check set with
+/// [Executor::set_memory_check].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryAccess {
+    /// The address of the instruction making the access.
+    pub pc: u32,
+
+    /// The address of the first byte accessed.
+    pub addr: u32,
+
+    /// The number of bytes accessed.
+    pub size: u32,
+
+    /// Whether the access is a store, rather than a load.
+    pub store: bool,
+}
+
 pub struct Executor<'a, 'b, S: Syscall> {
     pc: ByteAddr,
     insn_cycles: usize,
@@ -148,6 +177,10 @@ pub struct Executor<'a, 'b, S: Syscall> {
     pending: PendingState,
     trace: Vec<Rc<RefCell<dyn TraceCallback + 'b>>>,
     cycles: SessionCycles,
+    interrupt: Option<Box<dyn Fn() -> bool + 'b>>,
+    memory_check: Option<Box<dyn FnMut(&MemoryAccess) -> Result<()> + 'b>>,
+    page_check: Option<Box<dyn FnMut(u32) -> Result<()> + 'b>>,
+    compressed: bool,
 }
 
 impl PendingState {
@@ -188,9 +221,39 @@ impl<'a, 'b, S: Syscall> Executor<'a, 'b, S> {
             },
             trace,
             cycles: SessionCycles::default(),
+            interrupt: None,
+            memory_check: None,
+            page_check: None,
+            compressed: false,
         }
     }
 
+    /// Sets a check which is polled at the start of each basic block. Once it returns true,
+    /// execution stops with [ExitCode::SystemSplit], so that it can be resumed from the resulting
+    /// image.
+    pub fn set_interrupt(&mut self, interrupt: impl Fn() -> bool + 'b) {
+        self.interrupt = Some(Box::new(interrupt));
+    }
+
+    /// Sets a check which is called before each load and store by a guest instruction. Execution
+    /// fails with the error returned by the check, if any.
+    pub fn set_memory_check(&mut self, memory_check: impl FnMut(&MemoryAccess) -> Result<()> + 'b) {
+        self.memory_check = Some(Box::new(memory_check));
+    }
+
+    /// Sets a check which is called with the index of each page, including page table pages, as it
+    /// is first accessed within a segment. Execution fails with the error returned by the check, if
+    /// any.
+    pub fn set_page_check(&mut self, page_check: impl FnMut(u32) -> Result<()> + 'b) {
+        self.page_check = Some(Box::new(page_check));
+    }
+
+    /// Accept RV32C compressed instructions, e.g. for guests built for rv32imc. Segments which
+    /// execute compressed instructions cannot be proven.
+    pub fn set_compressed(&mut self, compressed: bool) {
+        self.compressed = compressed;
+    }
+
     pub fn run<F: FnMut(Segment) -> Result<()>>(
         &mut self,
         segment_po2: usize,
@@ -209,8 +272,11 @@ impl<'a, 'b, S: Syscall> Executor<'a, 'b, S> {
         self.reset();
 
         let mut emu = Emulator::new();
+        emu.set_compressed(self.compressed);
         let mut segments = 0;
         let initial_state = self.pager.image.get_system_state();
+        let mut block_start = true;
+        let mut interrupted = false;
 
         loop {
             if self.exit_code.is_some() {
@@ -219,15 +285,28 @@ impl<'a, 'b, S: Syscall> Executor<'a, 'b, S> {
 
             if let Some(max_cycles) = max_cycles {
                 if self.cycles.user >= max_cycles as usize {
-                    bail!("Session limit exceeded");
+                    return Err(SessionLimitExceeded.into());
                 }
             }
 
+            if block_start && self.interrupt.as_ref().is_some_and(|interrupt| interrupt()) {
+                tracing::debug!("interrupted at pc: {:?}", self.pc);
+                interrupted = true;
+                break;
+            }
+
+            let pc = self.pc;
             emu.step(self)?;
 
             let segment_cycles = self.insn_cycles + self.pager.cycles + self.pending.cycles;
             if segment_cycles < segment_limit {
                 self.advance()?;
+                block_start = self.pc != pc + emu.insn_len();
+                if let Some(page_check) = &mut self.page_check {
+                    for page_idx in self.pager.take_paged_in() {
+                        page_check(page_idx)?;
+                    }
+                }
             } else if self.insn_cycles == 0 {
                 bail!(
                     "segment limit ({segment_limit}) too small for instruction at pc: {:?}",
@@ -266,13 +345,19 @@ impl<'a, 'b, S: Syscall> Executor<'a, 'b, S> {
                 // replay the current instruction in a new segment
                 self.pending.pc = self.pc;
                 self.pending.cycles = 0;
+                block_start = false;
             }
         }
 
         let (pre_state, partial_image, post_state) = self.pager.commit(self.pc);
         let segment_cycles = self.insn_cycles + self.pager.cycles + RESERVED_CYCLES;
         let po2 = log2_ceil(segment_cycles.next_power_of_two());
-        let exit_code = self.exit_code.unwrap();
+        // An interrupted session ends in a system split, so that it can be resumed.
+        let exit_code = if interrupted {
+            ExitCode::SystemSplit
+        } else {
+            self.exit_code.unwrap()
+        };
 
         callback(Segment {
             partial_image,
@@ -671,6 +756,18 @@ impl<'a, 'b, S: Syscall> EmuContext for Executor<'a, 'b, S> {
         is_guest_memory(addr.0)
     }
 
+    fn on_data_access(&mut self, addr: ByteAddr, size: u32, store: bool) -> Result<()> {
+        match &mut self.memory_check {
+            Some(memory_check) => memory_check(&MemoryAccess {
+                pc: self.pending.pc.0,
+                addr: addr.0,
+                size,
+                store,
+            }),
+            None => Ok(()),
+        }
+    }
+
     fn on_insn_decoded(&self, insn: &Instruction, _decoded: &DecodedInstruction) {
         tracing::trace!("{:?}> {:?}", self.pc, insn.kind);
     }
@@ -12,7 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::cell::RefCell;
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeSet,
+};
 
 use anyhow::Result;
 use risc0_binfmt::{Digestible, ExitCode, MemoryImage};
@@ -23,7 +26,7 @@ use risc0_zkvm_platform::{
 };
 use test_log::test;
 
-use super::{Syscall, SyscallContext};
+use super::{Executor, Syscall, SyscallContext};
 use crate::prove::emu::{
     addr::ByteAddr,
     exec::DEFAULT_SEGMENT_LIMIT_PO2,
@@ -102,6 +105,46 @@ fn basic() {
     assert_eq!(segment.exit_code, ExitCode::Halted(0));
 }
 
+#[test]
+fn compressed() {
+    let program = testutil::compressed();
+    let image = MemoryImage::new(&program, PAGE_SIZE as u32).unwrap();
+
+    // Compressed instructions are rejected unless enabled.
+    let syscall = BasicSyscall::default();
+    let mut exec = Executor::new(image.clone(), &syscall, None, Vec::new());
+    assert!(exec
+        .run(DEFAULT_SEGMENT_LIMIT_PO2, DEFAULT_SESSION_LIMIT, |_| Ok(()))
+        .is_err());
+
+    let blocks = Cell::new(0);
+    let mut segments = Vec::new();
+    let mut exec = Executor::new(image, &syscall, None, Vec::new());
+    exec.set_compressed(true);
+    exec.set_interrupt(|| {
+        blocks.set(blocks.get() + 1);
+        false
+    });
+    let result = exec
+        .run(
+            DEFAULT_SEGMENT_LIMIT_PO2,
+            DEFAULT_SESSION_LIMIT,
+            |segment| {
+                segments.push(segment);
+                Ok(())
+            },
+        )
+        .unwrap();
+    assert_eq!(result.exit_code, ExitCode::Halted(0));
+
+    // 2 initial instructions, 5 iterations of the loop, and c.nop, lui and ecall.
+    assert_eq!(segments[0].insn_cycles, 2 + 5 * 2 + 3);
+
+    // Only the entry point and the 4 taken branches start a basic block, even though most
+    // instructions are 2 bytes long.
+    assert_eq!(blocks.get(), 1 + 4);
+}
+
 #[test]
 fn system_split() {
     let program = testutil::simple_loop();
@@ -129,3 +172,72 @@ fn system_split() {
         segments[0].post_state.digest::<ShaImpl>()
     );
 }
+
+#[test]
+fn page_check() {
+    let program = testutil::simple_loop();
+    let image = MemoryImage::new(&program, PAGE_SIZE as u32).unwrap();
+    let syscall = BasicSyscall::default();
+
+    // Each page of each segment is checked once, before the segment completes.
+    let pages = RefCell::new(BTreeSet::new());
+    let mut segments = 0;
+    let mut exec = Executor::new(image, &syscall, None, Vec::new());
+    exec.set_page_check(|page_idx| {
+        assert!(pages.borrow_mut().insert(page_idx));
+        Ok(())
+    });
+    exec.run(14, DEFAULT_SESSION_LIMIT, |segment| {
+        let expected: BTreeSet<_> = segment.partial_image.pages.keys().copied().collect();
+        assert_eq!(pages.take(), expected);
+        segments += 1;
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(segments, 2);
+}
+
+#[test]
+fn interrupt() {
+    let program = testutil::simple_loop();
+    let image = MemoryImage::new(&program, PAGE_SIZE as u32).unwrap();
+    let syscall = BasicSyscall::default();
+
+    // Interrupt at the start of the 10th basic block.
+    let blocks = Cell::new(0);
+    let mut segments = Vec::new();
+    let mut exec = Executor::new(image, &syscall, None, Vec::new());
+    exec.set_interrupt(|| {
+        blocks.set(blocks.get() + 1);
+        blocks.get() >= 10
+    });
+    let result = exec
+        .run(
+            DEFAULT_SEGMENT_LIMIT_PO2,
+            DEFAULT_SESSION_LIMIT,
+            |segment| {
+                segments.push(segment);
+                Ok(())
+            },
+        )
+        .unwrap();
+    assert_eq!(result.exit_code, ExitCode::SystemSplit);
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0].exit_code, ExitCode::SystemSplit);
+    assert!(result.user_cycles < 100);
+
+    // Resume from the interrupted image.
+    let resumed = super::execute(
+        result.post_image,
+        DEFAULT_SEGMENT_LIMIT_PO2,
+        DEFAULT_SESSION_LIMIT,
+        &syscall,
+        None,
+    )
+    .unwrap();
+    assert_eq!(resumed.result.exit_code, ExitCode::Halted(0));
+    assert_eq!(
+        resumed.segments[0].pre_state.digest::<ShaImpl>(),
+        segments[0].post_state.digest::<ShaImpl>()
+    );
+}
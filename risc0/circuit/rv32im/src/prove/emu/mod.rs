@@ -18,6 +18,7 @@ pub mod mux;
 mod pager;
 pub mod preflight;
 pub mod rv32im;
+pub mod rvc;
 pub mod testutil;
 
 use risc0_zkvm_platform::{memory::SYSTEM, syscall::DIGEST_WORDS};
@@ -68,6 +68,7 @@ pub struct PagedMemory {
     page_states: BTreeMap<u32, PageState>,
     pub cycles: usize,
     pending_actions: Vec<Action>,
+    paged_in: Vec<u32>,
 }
 
 impl PagedMemory {
@@ -79,6 +80,7 @@ impl PagedMemory {
             page_states: BTreeMap::new(),
             cycles: 0,
             pending_actions: Vec::new(),
+            paged_in: Vec::new(),
         }
     }
 
@@ -202,8 +204,14 @@ impl PagedMemory {
         self.pending_actions.clear();
     }
 
+    /// Take the pages first accessed in the current segment since the last call.
+    pub fn take_paged_in(&mut self) -> Vec<u32> {
+        take(&mut self.paged_in)
+    }
+
     pub fn clear(&mut self) {
         self.pending_actions.clear();
+        self.paged_in.clear();
         self.page_cache.clear();
         self.page_states.clear();
         self.page_table.fill(INVALID_IDX);
@@ -275,6 +283,9 @@ impl PagedMemory {
         self.cycles += page_cycles;
 
         let old = self.page_states.insert(page_idx, state);
+        if old.is_none() {
+            self.paged_in.push(page_idx);
+        }
         let action = match state {
             PageState::Loaded => Action::PageRead(page_idx, page_cycles),
             PageState::Dirty => Action::PageWrite(page_idx, page_cycles, old.is_some()),
@@ -15,7 +15,10 @@
 use anyhow::Result;
 use risc0_zkvm_platform::WORD_SIZE;
 
-use super::addr::{ByteAddr, WordAddr};
+use super::{
+    addr::{ByteAddr, WordAddr},
+    rvc,
+};
 
 pub trait EmuContext {
     // Handle environment call
@@ -65,11 +68,22 @@ pub trait EmuContext {
     fn check_data_store(&self, _addr: ByteAddr) -> bool {
         true
     }
+
+    // Called before a data load or store of `size` bytes, e.g. to check it against watchpoints
+    fn on_data_access(&mut self, _addr: ByteAddr, _size: u32, _store: bool) -> Result<()> {
+        Ok(())
+    }
 }
 
 #[derive(Default)]
 pub struct Emulator {
     table: FastDecodeTable,
+
+    // Whether RV32C compressed instructions are accepted.
+    compressed: bool,
+
+    // The length in bytes of the instruction being executed.
+    insn_len: usize,
 }
 
 #[derive(Debug)]
@@ -354,9 +368,25 @@ impl Emulator {
     pub fn new() -> Self {
         Self {
             table: FastDecodeTable::new(),
+            compressed: false,
+            insn_len: WORD_SIZE,
         }
     }
 
+    /// Accept RV32C compressed instructions, which are expanded to their RV32I equivalents before
+    /// being executed. Instructions may then be aligned to 2 bytes rather than 4.
+    ///
+    /// Compressed instructions are not supported by the circuit, so execution which uses them
+    /// cannot be proven.
+    pub fn set_compressed(&mut self, compressed: bool) {
+        self.compressed = compressed;
+    }
+
+    /// The length in bytes of the instruction most recently fetched by [Emulator::step].
+    pub fn insn_len(&self) -> usize {
+        self.insn_len
+    }
+
     pub fn step<C: EmuContext>(&mut self, ctx: &mut C) -> Result<()> {
         let pc = ctx.get_pc();
 
@@ -365,11 +395,9 @@ impl Emulator {
             return Ok(());
         }
 
-        let word = ctx.load_memory(pc.waddr())?;
-        if word & 0x03 != 0x03 {
-            ctx.trap(TrapCause::IllegalInstruction(word))?;
+        let Some(word) = self.fetch(ctx, pc)? else {
             return Ok(());
-        }
+        };
 
         let decoded = DecodedInstruction::new(word);
         let insn = self.table.lookup(&decoded);
@@ -388,6 +416,55 @@ impl Emulator {
         Ok(())
     }
 
+    // Fetches the instruction at pc, expanding it if it is compressed. Returns None if the
+    // instruction trapped.
+    fn fetch<C: EmuContext>(&mut self, ctx: &mut C, pc: ByteAddr) -> Result<Option<u32>> {
+        let word = ctx.load_memory(pc.waddr())?;
+        self.insn_len = WORD_SIZE;
+        if !self.compressed {
+            if word & 0x03 != 0x03 {
+                ctx.trap(TrapCause::IllegalInstruction(word))?;
+                return Ok(None);
+            }
+            return Ok(Some(word));
+        }
+
+        let halfword = if pc.is_aligned() {
+            word & 0xffff
+        } else {
+            word >> 16
+        };
+        if !rvc::is_full_width(halfword) {
+            self.insn_len = WORD_SIZE / 2;
+            let Some(word) = rvc::expand(halfword as u16) else {
+                ctx.trap(TrapCause::IllegalInstruction(halfword))?;
+                return Ok(None);
+            };
+            return Ok(Some(word));
+        }
+        if pc.is_aligned() {
+            return Ok(Some(word));
+        }
+
+        // A 32-bit instruction which starts halfway through a word.
+        let next = pc + WORD_SIZE / 2;
+        if !ctx.check_insn_load(next) {
+            ctx.trap(TrapCause::InstructionAccessFault)?;
+            return Ok(None);
+        }
+        let high = ctx.load_memory(next.waddr())? & 0xffff;
+        Ok(Some(halfword | (high << 16)))
+    }
+
+    // Returns true if the given address is a valid target for a jump or branch.
+    fn is_insn_aligned(&self, addr: ByteAddr) -> bool {
+        if self.compressed {
+            addr.0 % 2 == 0
+        } else {
+            addr.is_aligned()
+        }
+    }
+
     fn step_compute<M: EmuContext>(
         &mut self,
         ctx: &mut M,
@@ -395,7 +472,7 @@ impl Emulator {
         decoded: &DecodedInstruction,
     ) -> Result<bool> {
         let pc = ctx.get_pc();
-        let mut new_pc = pc + WORD_SIZE;
+        let mut new_pc = pc + self.insn_len;
         let mut rd = decoded.rd;
         let rs1 = ctx.load_register(decoded.rs1 as usize)?;
         let rs2 = ctx.load_register(decoded.rs2 as usize)?;
@@ -459,11 +536,11 @@ impl Emulator {
             InsnKind::BGEU => br_cond(rs1 >= rs2),
             InsnKind::JAL => {
                 new_pc = pc.wrapping_add(decoded.imm_j());
-                (pc + WORD_SIZE).0
+                (pc + self.insn_len).0
             }
             InsnKind::JALR => {
                 new_pc = ByteAddr(rs1.wrapping_add(imm_i) & 0xfffffffe);
-                (pc + WORD_SIZE).0
+                (pc + self.insn_len).0
             }
             InsnKind::LUI => decoded.imm_u(),
             InsnKind::AUIPC => (pc.wrapping_add(decoded.imm_u())).0,
@@ -503,7 +580,7 @@ impl Emulator {
             }
             _ => unreachable!(),
         };
-        if !new_pc.is_aligned() {
+        if !self.is_insn_aligned(new_pc) {
             return ctx.trap(TrapCause::InstructionAddressMisaligned);
         }
         ctx.store_register(rd as usize, out)?;
@@ -523,6 +600,7 @@ impl Emulator {
         if !ctx.check_data_load(addr) {
             return ctx.trap(TrapCause::LoadAccessFault(addr));
         }
+        ctx.on_data_access(addr, access_size(kind), false)?;
         let data = ctx.load_memory(addr.waddr())?;
         let shift = 8 * (addr.0 & 3);
         let out = match kind {
@@ -559,7 +637,7 @@ impl Emulator {
             _ => unreachable!(),
         };
         ctx.store_register(decoded.rd as usize, out)?;
-        ctx.set_pc(ctx.get_pc() + WORD_SIZE);
+        ctx.set_pc(ctx.get_pc() + self.insn_len);
         Ok(true)
     }
 
@@ -576,6 +654,7 @@ impl Emulator {
         if !ctx.check_data_store(addr) {
             return ctx.trap(TrapCause::StoreAccessFault);
         }
+        ctx.on_data_access(addr, access_size(kind), true)?;
         let mut data = ctx.load_memory(addr.waddr())?;
         match kind {
             InsnKind::SB => {
@@ -600,7 +679,7 @@ impl Emulator {
             _ => unreachable!(),
         }
         ctx.store_memory(addr.waddr(), data)?;
-        ctx.set_pc(ctx.get_pc() + WORD_SIZE);
+        ctx.set_pc(ctx.get_pc() + self.insn_len);
         Ok(true)
     }
 
@@ -625,3 +704,12 @@ impl Emulator {
 fn sign_extend_u32(x: u32) -> i64 {
     (x as i32) as i64
 }
+
+// The number of bytes accessed by a load or store instruction.
+fn access_size(kind: InsnKind) -> u32 {
+    match kind {
+        InsnKind::LB | InsnKind::LBU | InsnKind::SB => 1,
+        InsnKind::LH | InsnKind::LHU | InsnKind::SH => 2,
+        _ => 4,
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Expansion of RV32C compressed instructions into their RV32I equivalents.
+//!
+//! Floating point instructions, and encodings reserved in RV32C, are not expanded.
+
+const OPCODE_LOAD: u32 = 0x03;
+const OPCODE_OP_IMM: u32 = 0x13;
+const OPCODE_STORE: u32 = 0x23;
+const OPCODE_OP: u32 = 0x33;
+const OPCODE_LUI: u32 = 0x37;
+const OPCODE_BRANCH: u32 = 0x63;
+const OPCODE_JALR: u32 = 0x67;
+const OPCODE_JAL: u32 = 0x6f;
+
+const EBREAK: u32 = 0x00100073;
+
+const REG_RA: u32 = 1;
+const REG_SP: u32 = 2;
+
+/// Returns true if the given halfword is the low half of a 32-bit instruction, rather than a
+/// compressed instruction.
+pub fn is_full_width(halfword: u32) -> bool {
+    halfword & 0x03 == 0x03
+}
+
+/// Expands a compressed instruction into the equivalent 32-bit instruction, or returns `None` if
+/// it is illegal or not supported.
+pub fn expand(insn: u16) -> Option<u32> {
+    let insn = insn as u32;
+    let funct3 = bits(insn, 15, 13);
+    let rd = bits(insn, 11, 7);
+    let rs2 = bits(insn, 6, 2);
+    // The 3-bit register fields of the CIW, CL, CS, CA and CB formats address x8..x15.
+    let rd_p = 8 + bits(insn, 4, 2);
+    let rs1_p = 8 + bits(insn, 9, 7);
+    let imm6 = sign_extend((bits(insn, 12, 12) << 5) | bits(insn, 6, 2), 6);
+
+    match (insn & 0x03, funct3) {
+        // C.ADDI4SPN
+        (0, 0b000) => {
+            let imm = (bits(insn, 12, 11) << 4)
+                | (bits(insn, 10, 7) << 6)
+                | (bits(insn, 6, 6) << 2)
+                | (bits(insn, 5, 5) << 3);
+            (imm != 0).then(|| i_type(imm, REG_SP, 0b000, rd_p, OPCODE_OP_IMM))
+        }
+        // C.LW
+        (0, 0b010) => Some(i_type(cl_offset(insn), rs1_p, 0b010, rd_p, OPCODE_LOAD)),
+        // C.SW
+        (0, 0b110) => Some(s_type(cl_offset(insn), rd_p, rs1_p, 0b010)),
+        // C.ADDI, C.NOP
+        (1, 0b000) => Some(i_type(imm6, rd, 0b000, rd, OPCODE_OP_IMM)),
+        // C.JAL
+        (1, 0b001) => Some(j_type(cj_offset(insn), REG_RA)),
+        // C.LI
+        (1, 0b010) => Some(i_type(imm6, 0, 0b000, rd, OPCODE_OP_IMM)),
+        // C.ADDI16SP
+        (1, 0b011) if rd == REG_SP => {
+            let imm = sign_extend(
+                (bits(insn, 12, 12) << 9)
+                    | (bits(insn, 6, 6) << 4)
+                    | (bits(insn, 5, 5) << 6)
+                    | (bits(insn, 4, 3) << 7)
+                    | (bits(insn, 2, 2) << 5),
+                10,
+            );
+            (imm != 0).then(|| i_type(imm, REG_SP, 0b000, REG_SP, OPCODE_OP_IMM))
+        }
+        // C.LUI
+        (1, 0b011) => (imm6 != 0).then(|| (imm6 << 12) | (rd << 7) | OPCODE_LUI),
+        (1, 0b100) => match bits(insn, 11, 10) {
+            // Shift amounts of 32 or more are reserved in RV32C.
+            0b00 | 0b01 if bits(insn, 12, 12) != 0 => None,
+            // C.SRLI
+            0b00 => Some(r_type(0, rs2, rs1_p, 0b101, rs1_p, OPCODE_OP_IMM)),
+            // C.SRAI
+            0b01 => Some(r_type(0x20, rs2, rs1_p, 0b101, rs1_p, OPCODE_OP_IMM)),
+            // C.ANDI
+            0b10 => Some(i_type(imm6, rs1_p, 0b111, rs1_p, OPCODE_OP_IMM)),
+            // C.SUBW, C.ADDW and the reserved encodings are RV64C only.
+            _ if bits(insn, 12, 12) != 0 => None,
+            _ => {
+                let (funct7, funct3) = match bits(insn, 6, 5) {
+                    // C.SUB
+                    0b00 => (0x20, 0b000),
+                    // C.XOR
+                    0b01 => (0, 0b100),
+                    // C.OR
+                    0b10 => (0, 0b110),
+                    // C.AND
+                    _ => (0, 0b111),
+                };
+                Some(r_type(funct7, rd_p, rs1_p, funct3, rs1_p, OPCODE_OP))
+            }
+        },
+        // C.J
+        (1, 0b101) => Some(j_type(cj_offset(insn), 0)),
+        // C.BEQZ
+        (1, 0b110) => Some(b_type(cb_offset(insn), 0, rs1_p, 0b000)),
+        // C.BNEZ
+        (1, 0b111) => Some(b_type(cb_offset(insn), 0, rs1_p, 0b001)),
+        // C.SLLI
+        (2, 0b000) => {
+            (bits(insn, 12, 12) == 0).then(|| r_type(0, rs2, rd, 0b001, rd, OPCODE_OP_IMM))
+        }
+        // C.LWSP
+        (2, 0b010) => {
+            let imm = (bits(insn, 12, 12) << 5) | (bits(insn, 6, 4) << 2) | (bits(insn, 3, 2) << 6);
+            (rd != 0).then(|| i_type(imm, REG_SP, 0b010, rd, OPCODE_LOAD))
+        }
+        (2, 0b100) => match (bits(insn, 12, 12), rd, rs2) {
+            (0, 0, 0) => None,
+            // C.JR
+            (0, _, 0) => Some(i_type(0, rd, 0b000, 0, OPCODE_JALR)),
+            // C.MV
+            (0, _, _) => Some(r_type(0, rs2, 0, 0b000, rd, OPCODE_OP)),
+            // C.EBREAK
+            (_, 0, 0) => Some(EBREAK),
+            // C.JALR
+            (_, _, 0) => Some(i_type(0, rd, 0b000, REG_RA, OPCODE_JALR)),
+            // C.ADD
+            _ => Some(r_type(0, rs2, rd, 0b000, rd, OPCODE_OP)),
+        },
+        // C.SWSP
+        (2, 0b110) => {
+            let imm = (bits(insn, 12, 9) << 2) | (bits(insn, 8, 7) << 6);
+            Some(s_type(imm, rs2, REG_SP, 0b010))
+        }
+        _ => None,
+    }
+}
+
+// Extracts bits hi..=lo of the given value.
+fn bits(value: u32, hi: u32, lo: u32) -> u32 {
+    (value >> lo) & ((1 << (hi - lo + 1)) - 1)
+}
+
+// Sign extends the low `width` bits of the given value.
+fn sign_extend(value: u32, width: u32) -> u32 {
+    let shift = 32 - width;
+    (((value << shift) as i32) >> shift) as u32
+}
+
+// The offset of C.LW and C.SW.
+fn cl_offset(insn: u32) -> u32 {
+    (bits(insn, 12, 10) << 3) | (bits(insn, 6, 6) << 2) | (bits(insn, 5, 5) << 6)
+}
+
+// The offset of C.J and C.JAL.
+fn cj_offset(insn: u32) -> u32 {
+    sign_extend(
+        (bits(insn, 12, 12) << 11)
+            | (bits(insn, 11, 11) << 4)
+            | (bits(insn, 10, 9) << 8)
+            | (bits(insn, 8, 8) << 10)
+            | (bits(insn, 7, 7) << 6)
+            | (bits(insn, 6, 6) << 7)
+            | (bits(insn, 5, 3) << 1)
+            | (bits(insn, 2, 2) << 5),
+        12,
+    )
+}
+
+// The offset of C.BEQZ and C.BNEZ.
+fn cb_offset(insn: u32) -> u32 {
+    sign_extend(
+        (bits(insn, 12, 12) << 8)
+            | (bits(insn, 11, 10) << 3)
+            | (bits(insn, 6, 5) << 6)
+            | (bits(insn, 4, 3) << 1)
+            | (bits(insn, 2, 2) << 5),
+        9,
+    )
+}
+
+fn r_type(funct7: u32, rs2: u32, rs1: u32, funct3: u32, rd: u32, opcode: u32) -> u32 {
+    (funct7 << 25) | (rs2 << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | opcode
+}
+
+fn i_type(imm: u32, rs1: u32, funct3: u32, rd: u32, opcode: u32) -> u32 {
+    ((imm & 0xfff) << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | opcode
+}
+
+fn s_type(imm: u32, rs2: u32, rs1: u32, funct3: u32) -> u32 {
+    (bits(imm, 11, 5) << 25)
+        | (rs2 << 20)
+        | (rs1 << 15)
+        | (funct3 << 12)
+        | (bits(imm, 4, 0) << 7)
+        | OPCODE_STORE
+}
+
+fn b_type(imm: u32, rs2: u32, rs1: u32, funct3: u32) -> u32 {
+    (bits(imm, 12, 12) << 31)
+        | (bits(imm, 10, 5) << 25)
+        | (rs2 << 20)
+        | (rs1 << 15)
+        | (funct3 << 12)
+        | (bits(imm, 4, 1) << 8)
+        | (bits(imm, 11, 11) << 7)
+        | OPCODE_BRANCH
+}
+
+fn j_type(imm: u32, rd: u32) -> u32 {
+    (bits(imm, 20, 20) << 31)
+        | (bits(imm, 10, 1) << 21)
+        | (bits(imm, 11, 11) << 20)
+        | (bits(imm, 19, 12) << 12)
+        | (rd << 7)
+        | OPCODE_JAL
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expand;
+
+    #[test]
+    fn expand_compressed() {
+        for (compressed, expanded) in [
+            (0x0001, 0x00000013), // c.nop
+            (0x0800, 0x01010413), // c.addi4spn s0, sp, 16
+            (0x4108, 0x00052503), // c.lw a0, 0(a0)
+            (0x1141, 0xff010113), // c.addi sp, -16
+            (0x0141, 0x01010113), // c.addi sp, 16
+            (0x7179, 0xfd010113), // c.addi16sp sp, -48
+            (0x4505, 0x00100513), // c.li a0, 1
+            (0x6505, 0x00001537), // c.lui a0, 1
+            (0xa001, 0x0000006f), // c.j 0
+            (0xc101, 0x00050063), // c.beqz a0, 0
+            (0x40b2, 0x00c12083), // c.lwsp ra, 12(sp)
+            (0xc606, 0x00112623), // c.swsp ra, 12(sp)
+            (0x8082, 0x00008067), // c.jr ra
+            (0x852e, 0x00b00533), // c.mv a0, a1
+            (0x952e, 0x00b50533), // c.add a0, a1
+            (0x9002, 0x00100073), // c.ebreak
+        ] {
+            assert_eq!(expand(compressed), Some(expanded), "{compressed:04x}");
+        }
+
+        // The all-zero instruction is illegal, and floating point loads are not supported.
+        assert_eq!(expand(0x0000), None);
+        assert_eq!(expand(0x2000), None);
+    }
+}
@@ -64,6 +64,19 @@ pub fn basic() -> Program {
     )
 }
 
+pub fn compressed() -> Program {
+    program_from_instructions(
+        0x4000,
+        [
+            0x47954701, // c.li a4, 0; c.li a5, 5
+            0x4fe30705, // loop: c.addi a4, 1; blt a4, a5, loop (low half)
+            0x0001fef7, // blt a4, a5, loop (high half); c.nop
+            0x000045b7, // lui a1, 0x4
+            0x00000073, // ecall(halt)
+        ],
+    )
+}
+
 pub fn simple_loop() -> Program {
     // loop.asm:
     //
@@ -24,7 +24,8 @@ use anyhow::Result;
 use rand::thread_rng;
 use risc0_core::scope;
 use risc0_zkp::{
-    adapter::{CircuitInfo, TapsProvider, PROOF_SYSTEM_INFO},
+    adapter::{CircuitInfo, ProofSystemVersion, TapsProvider},
+    core::transcript,
     field::{
         baby_bear::{BabyBear, BabyBearElem, BabyBearExtElem},
         Elem as _,
@@ -65,7 +66,11 @@ where
     H: Hal<Field = BabyBear, Elem = BabyBearElem, ExtElem = BabyBearExtElem>,
     C: CircuitHal<H> + CircuitWitnessGenerator<H>,
 {
-    fn prove_segment(&self, segment: &Segment) -> Result<Seal> {
+    fn prove_segment_with_version(
+        &self,
+        segment: &Segment,
+        version: ProofSystemVersion,
+    ) -> Result<Seal> {
         scope!("prove_segment");
 
         let trace = segment.preflight()?;
@@ -81,19 +86,22 @@ where
         );
         let steps = witgen.steps;
 
-        Ok(scope!("prove", {
-            let mut prover = Prover::new(self.hal.as_ref(), CIRCUIT.get_taps());
+        scope!("prove", {
+            let mut prover = Prover::with_version(self.hal.as_ref(), CIRCUIT.get_taps(), version);
             let hashfn = &self.hal.get_hash_suite().hashfn;
 
             let mix = scope!("main", {
                 // At the start of the protocol, seed the Fiat-Shamir transcript with context information
                 // about the proof system and circuit.
-                prover
-                    .iop()
-                    .commit(&hashfn.hash_elem_slice(&PROOF_SYSTEM_INFO.encode()));
-                prover
-                    .iop()
-                    .commit(&hashfn.hash_elem_slice(&CircuitImpl::CIRCUIT_INFO.encode()));
+                let proof_system_info = prover.version().info();
+                prover.iop().commit_labeled(
+                    transcript::PROOF_SYSTEM_INFO,
+                    &hashfn.hash_elem_slice(&proof_system_info.encode()),
+                );
+                prover.iop().commit_labeled(
+                    transcript::CIRCUIT_INFO,
+                    &hashfn.hash_elem_slice(&CircuitImpl::CIRCUIT_INFO.encode()),
+                );
 
                 // Concat io (i.e. globals) and po2 into a vector.
                 let mut io_po2 = vec![BabyBearElem::ZERO; io.len() + 1];
@@ -106,7 +114,9 @@ where
                 });
 
                 let io_po2_digest = hashfn.hash_elem_slice(&io_po2);
-                prover.iop().commit(&io_po2_digest);
+                prover
+                    .iop()
+                    .commit_labeled(transcript::GLOBALS, &io_po2_digest);
                 prover.iop().write_field_elem_slice(io_po2.as_slice());
                 prover.set_po2(segment.po2);
 
@@ -117,7 +127,7 @@ where
                 let mix: Vec<_> = scope!(
                     "mix",
                     (0..CircuitImpl::MIX_SIZE)
-                        .map(|_| prover.iop().random_elem())
+                        .map(|_| prover.iop().random_elem_labeled(transcript::ACCUM_MIX))
                         .collect()
                 );
 
@@ -164,6 +174,6 @@ where
             });
 
             prover.finalize(&[&mix, &witgen.io], self.circuit_hal.as_ref())
-        }))
+        })
     }
 }
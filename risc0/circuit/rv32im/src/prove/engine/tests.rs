@@ -12,16 +12,33 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::rc::Rc;
+use std::{
+    iter::zip,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use anyhow::Result;
 use cfg_if::cfg_if;
 use risc0_binfmt::{MemoryImage, Program};
 use risc0_zkp::{
-    core::{digest::Digest, hash::sha::Sha256HashSuite},
-    field::baby_bear::BabyBearElem,
+    adapter::ProofSystemVersion,
+    core::{
+        digest::Digest,
+        hash::{
+            sha::{cpu, Sha256, Sha256HashSuite},
+            HashFn, HashSuite, Rng, RngFactory,
+        },
+    },
+    field::{
+        baby_bear::{BabyBear, BabyBearElem, BabyBearExtElem},
+        ExtElem as _,
+    },
     hal::{cpu::CpuHal, Buffer as _, Hal},
-    verify::VerificationError,
+    verify::{cost::verifier_cost_with_version, verify_with_version, VerificationError},
 };
 use risc0_zkvm_platform::PAGE_SIZE;
 use test_log::test;
@@ -141,6 +158,205 @@ fn basic() {
     risc0_zkp::verify::verify(&CIRCUIT, &suite, &seal, |x, y| checker.check_ctrl(x, y)).unwrap();
 }
 
+#[test]
+fn proof_system_versions() {
+    let program = testutil::basic();
+    let image = MemoryImage::new(&program, PAGE_SIZE as u32).unwrap();
+    let result = execute(
+        image,
+        DEFAULT_SEGMENT_LIMIT_PO2,
+        DEFAULT_SESSION_LIMIT,
+        &NullSyscall,
+        None,
+    )
+    .unwrap();
+    let segment = result.segments.first().unwrap();
+
+    let prover = segment_prover("sha-256").unwrap();
+    let suite = Sha256HashSuite::new_suite();
+    let hal = CpuHal::new(suite.clone());
+    let checker = ControlCheck::new(&hal, segment.po2);
+    let check_ctrl = |x, y: &Digest| checker.check_ctrl(x, y);
+
+    let mut seals = Vec::new();
+    for version in [
+        ProofSystemVersion::V1,
+        ProofSystemVersion::V2,
+        ProofSystemVersion::V3,
+    ] {
+        let seal = prover.prove_segment_with_version(segment, version).unwrap();
+        verify_with_version(&CIRCUIT, &suite, version, &seal, check_ctrl).unwrap();
+        tracing::info!("{version:?}: {} bytes", seal.len() * 4);
+        seals.push(seal);
+    }
+
+    // A seal only verifies under the version it was produced with.
+    let v1 = &seals[0];
+    let v2 = &seals[1];
+    assert!(verify_with_version(&CIRCUIT, &suite, ProofSystemVersion::V2, v1, check_ctrl).is_err());
+    assert!(verify_with_version(&CIRCUIT, &suite, ProofSystemVersion::V1, v2, check_ctrl).is_err());
+
+    // The last FRI round of this segment has few enough rows that some are opened by more than
+    // one query, so eliding the repeats shrinks the seal.
+    assert!(v2.len() < v1.len());
+    let v3 = &seals[2];
+    assert!(v3.len() <= v1.len());
+}
+
+// Counts the hashes and Fiat-Shamir operations performed by the verifier.
+#[derive(Default)]
+struct OpCounter {
+    hash_pairs: AtomicUsize,
+    hash_slices: AtomicUsize,
+    hashed_words: AtomicUsize,
+    mixes: AtomicUsize,
+    draws: AtomicUsize,
+}
+
+impl OpCounter {
+    fn count(counter: &AtomicUsize, n: usize) {
+        counter.fetch_add(n, Ordering::Relaxed);
+    }
+
+    fn get(counter: &AtomicUsize) -> usize {
+        counter.load(Ordering::Relaxed)
+    }
+
+    // A SHA-256 hash suite which counts its operations.
+    fn suite(self: &Arc<Self>) -> HashSuite<BabyBear> {
+        HashSuite {
+            name: "sha-256".into(),
+            hashfn: Rc::new(CountingHashFn(self.clone())),
+            rng: Rc::new(CountingRngFactory(self.clone())),
+        }
+    }
+}
+
+struct CountingHashFn(Arc<OpCounter>);
+
+impl HashFn<BabyBear> for CountingHashFn {
+    fn hash_pair(&self, a: &Digest, b: &Digest) -> Box<Digest> {
+        OpCounter::count(&self.0.hash_pairs, 1);
+        (*cpu::Impl::hash_pair(a, b)).into()
+    }
+
+    fn hash_elem_slice(&self, slice: &[BabyBearElem]) -> Box<Digest> {
+        OpCounter::count(&self.0.hash_slices, 1);
+        OpCounter::count(&self.0.hashed_words, slice.len());
+        (*cpu::Impl::hash_raw_data_slice(slice)).into()
+    }
+
+    fn hash_ext_elem_slice(&self, slice: &[BabyBearExtElem]) -> Box<Digest> {
+        OpCounter::count(&self.0.hash_slices, 1);
+        OpCounter::count(
+            &self.0.hashed_words,
+            slice.len() * BabyBearExtElem::EXT_SIZE,
+        );
+        (*cpu::Impl::hash_raw_data_slice(slice)).into()
+    }
+}
+
+struct CountingRngFactory(Arc<OpCounter>);
+
+impl RngFactory<BabyBear> for CountingRngFactory {
+    fn new_rng(&self) -> Box<dyn Rng<BabyBear>> {
+        Box::new(CountingRng {
+            inner: Sha256HashSuite::<BabyBear>::new_suite().rng.new_rng(),
+            counter: self.0.clone(),
+        })
+    }
+}
+
+struct CountingRng {
+    inner: Box<dyn Rng<BabyBear>>,
+    counter: Arc<OpCounter>,
+}
+
+impl Rng<BabyBear> for CountingRng {
+    fn mix(&mut self, val: &Digest) {
+        OpCounter::count(&self.counter.mixes, 1);
+        self.inner.mix(val)
+    }
+
+    fn random_bits(&mut self, bits: usize) -> u32 {
+        OpCounter::count(&self.counter.draws, 1);
+        self.inner.random_bits(bits)
+    }
+
+    fn random_elem(&mut self) -> BabyBearElem {
+        OpCounter::count(&self.counter.draws, 1);
+        self.inner.random_elem()
+    }
+
+    fn random_ext_elem(&mut self) -> BabyBearExtElem {
+        OpCounter::count(&self.counter.draws, 1);
+        self.inner.random_ext_elem()
+    }
+}
+
+#[test]
+fn verifier_cost() {
+    let program = testutil::basic();
+    let image = MemoryImage::new(&program, PAGE_SIZE as u32).unwrap();
+    let result = execute(
+        image,
+        DEFAULT_SEGMENT_LIMIT_PO2,
+        DEFAULT_SESSION_LIMIT,
+        &NullSyscall,
+        None,
+    )
+    .unwrap();
+    let segment = result.segments.first().unwrap();
+
+    let prover = segment_prover("sha-256").unwrap();
+    let hal = CpuHal::new(Sha256HashSuite::<BabyBear>::new_suite());
+    let checker = ControlCheck::new(&hal, segment.po2);
+    let check_ctrl = |x, y: &Digest| checker.check_ctrl(x, y);
+
+    for version in [
+        ProofSystemVersion::V1,
+        ProofSystemVersion::V2,
+        ProofSystemVersion::V3,
+    ] {
+        let seal = prover.prove_segment_with_version(segment, version).unwrap();
+        let counter = Arc::new(OpCounter::default());
+        verify_with_version(&CIRCUIT, &counter.suite(), version, &seal, check_ctrl).unwrap();
+
+        let cost = verifier_cost_with_version(&CIRCUIT, segment.po2, version).total();
+        let measured = [
+            OpCounter::get(&counter.hash_pairs),
+            OpCounter::get(&counter.hash_slices),
+            OpCounter::get(&counter.hashed_words),
+            seal.len() * 4,
+        ];
+        let expected = [
+            cost.hash_pairs,
+            cost.hash_slices,
+            cost.hashed_words,
+            cost.seal_bytes,
+        ];
+        if version.consolidates_openings() {
+            // Rows opened by several queries are read and hashed once.
+            assert!(zip(measured, expected).all(|(measured, expected)| measured <= expected));
+        } else {
+            assert_eq!(measured, expected);
+        }
+
+        // Query positions drawn again are redrawn, each under a label of its own.
+        let draws = OpCounter::get(&counter.draws);
+        let redraws = draws - cost.challenges;
+        if !version.distinct_queries() {
+            assert_eq!(redraws, 0);
+        }
+        let redrawn_labels = if cost.label_hashes > 0 { redraws } else { 0 };
+        assert_eq!(
+            OpCounter::get(&counter.mixes),
+            cost.transcript_mixes + redrawn_labels
+        );
+        assert_eq!(cost.constraint_evals, 1);
+    }
+}
+
 #[test]
 fn system_split() {
     let program = testutil::simple_loop();
@@ -19,23 +19,37 @@ pub mod segment;
 
 use anyhow::Result;
 use cfg_if::cfg_if;
+use risc0_zkp::adapter::ProofSystemVersion;
 
 use self::segment::Segment;
 
 pub type Seal = Vec<u32>;
 
 pub trait SegmentProver {
-    fn prove_segment(&self, segment: &Segment) -> Result<Seal>;
+    fn prove_segment(&self, segment: &Segment) -> Result<Seal> {
+        self.prove_segment_with_version(segment, ProofSystemVersion::default())
+    }
+
+    fn prove_segment_with_version(
+        &self,
+        segment: &Segment,
+        version: ProofSystemVersion,
+    ) -> Result<Seal>;
 }
 
 pub fn segment_prover(hashfn: &str) -> Result<Box<dyn SegmentProver>> {
     cfg_if! {
         if #[cfg(feature = "cuda")] {
-            self::hal::cuda::segment_prover(hashfn)
+            if risc0_zkp::hal::capabilities().cuda {
+                return self::hal::cuda::segment_prover(hashfn);
+            }
+            tracing::warn!("No CUDA device found, falling back to the CPU prover");
         } else if #[cfg(any(all(target_os = "macos", target_arch = "aarch64"), target_os = "ios"))] {
-            self::hal::metal::segment_prover(hashfn)
-        } else {
-            self::hal::cpu::segment_prover(hashfn)
+            if risc0_zkp::hal::capabilities().metal {
+                return self::hal::metal::segment_prover(hashfn);
+            }
+            tracing::warn!("No Metal device found, falling back to the CPU prover");
         }
     }
+    self::hal::cpu::segment_prover(hashfn)
 }
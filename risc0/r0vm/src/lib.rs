@@ -16,8 +16,8 @@ use std::{fs, io, path::PathBuf, rc::Rc};
 
 use clap::{Args, Parser, ValueEnum};
 use risc0_zkvm::{
-    compute_image_id, get_prover_server, ApiServer, ExecutorEnv, ExecutorImpl, ProverOpts,
-    ProverServer, VerifierContext,
+    compute_image_id, get_prover_server, ApiServer, ExecutorEnv, ExecutorImpl, MethodRegistry,
+    ProverOpts, ProverServer, VerifierContext,
 };
 
 /// Runs a RISC-V ELF binary within the RISC Zero ZKVM.
@@ -31,6 +31,13 @@ struct Cli {
     #[arg(long)]
     receipt: Option<PathBuf>,
 
+    /// Journal output file.
+    ///
+    /// The raw bytes committed by the guest are written to this file, so that hosts which
+    /// cannot decode a receipt can still read the output.
+    #[arg(long)]
+    journal: Option<PathBuf>,
+
     /// The hash function to use to produce a proof.
     #[arg(long, value_enum, default_value_t = HashFn::Poseidon2)]
     hashfn: HashFn,
@@ -71,6 +78,11 @@ struct Cli {
     /// Compute the image_id for the specified ELF
     #[arg(long)]
     id: bool,
+
+    /// A methods.json manifest written by risc0-build, used to look up methods
+    /// by name or image ID.
+    #[arg(long, env = "RISC0_METHODS")]
+    methods: Option<PathBuf>,
 }
 
 #[derive(Args)]
@@ -86,6 +98,10 @@ struct Mode {
     /// The image to execute
     #[arg(long)]
     image: Option<PathBuf>,
+
+    /// The name of a method in the manifest given by `--methods` to execute
+    #[arg(long, requires = "methods")]
+    method: Option<String>,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -112,14 +128,26 @@ pub fn main() {
         .init();
 
     let args = Cli::parse();
+    let registry = match args.methods.as_ref() {
+        Some(path) => MethodRegistry::load(path).unwrap(),
+        None => MethodRegistry::new(),
+    };
     if let Some(port) = args.mode.port {
-        run_server(port);
+        run_server(port, registry);
         return;
     }
 
+    let method = args.mode.method.as_ref().map(|name| {
+        registry
+            .get(name)
+            .unwrap_or_else(|| panic!("Method {name} not found in {:?}", args.methods))
+    });
+
     if args.id {
-        let elf = fs::read(args.mode.elf.unwrap()).unwrap();
-        let image_id = compute_image_id(&elf).unwrap();
+        let image_id = match method {
+            Some(method) => method.image_id,
+            None => compute_image_id(&fs::read(args.mode.elf.unwrap()).unwrap()).unwrap(),
+        };
         println!("{image_id}");
         return;
     }
@@ -151,6 +179,8 @@ pub fn main() {
         let mut exec = if let Some(ref elf_path) = args.mode.elf {
             let elf_contents = fs::read(elf_path).unwrap();
             ExecutorImpl::from_elf(env, &elf_contents).unwrap()
+        } else if let Some(method) = method {
+            ExecutorImpl::from_elf(env, &method.elf).unwrap()
         } else if let Some(ref image_path) = args.mode.image {
             let image_contents = fs::read(image_path).unwrap();
             let image = bincode::deserialize(&image_contents).unwrap();
@@ -165,6 +195,10 @@ pub fn main() {
     let ctx = VerifierContext::default();
     let receipt = prover.prove_session(&ctx, &session).unwrap().receipt;
 
+    if let Some(journal_file) = args.journal.as_ref() {
+        fs::write(journal_file, &receipt.journal.bytes).expect("Unable to write journal file");
+    }
+
     let receipt_data = bincode::serialize(&receipt).unwrap();
     let receipt_bytes = bytemuck::cast_slice(&receipt_data);
     if let Some(receipt_file) = args.receipt.as_ref() {
@@ -197,8 +231,8 @@ impl Cli {
     }
 }
 
-fn run_server(port: u16) {
+fn run_server(port: u16, registry: MethodRegistry) {
     let addr = format!("127.0.0.1:{port}");
-    let server = ApiServer::new_tcp(addr);
+    let server = ApiServer::new_tcp(addr).with_registry(registry);
     server.run().unwrap()
 }
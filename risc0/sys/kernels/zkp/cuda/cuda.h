@@ -14,6 +14,7 @@
 
 #pragma once
 
+#include <algorithm>
 #include <cstring>
 #include <cuda_runtime.h>
 #include <stdexcept>
@@ -61,26 +62,30 @@ struct LaunchConfig {
   LaunchConfig(int grid, int block, size_t shared = 0) : grid(grid), block(block), shared(shared) {}
 };
 
-inline LaunchConfig getSimpleConfig(uint32_t count) {
+// Returns a launch configuration for `count` threads, with `block` threads per block, or a quarter
+// of the maximum number of threads per block if `block` is 0.
+inline LaunchConfig getSimpleConfig(uint32_t count, uint32_t block = 0) {
   int device;
   CUDA_OK(cudaGetDevice(&device));
 
   int maxThreads;
   CUDA_OK(cudaDeviceGetAttribute(&maxThreads, cudaDevAttrMaxThreadsPerBlock, device));
 
-  int block = maxThreads / 4;
-  int grid = (count + block - 1) / block;
-  return LaunchConfig{grid, block, 0};
+  int threads = block ? std::min(maxThreads, int(block)) : maxThreads / 4;
+  int grid = (count + threads - 1) / threads;
+  return LaunchConfig{grid, threads, 0};
 }
 
+// Launches `kernel` for `count` threads with `block` threads per block, see getSimpleConfig.
 template <typename... ExpTypes, typename... ActTypes>
-const char* launchKernel(void (*kernel)(ExpTypes...),
-                         uint32_t count,
-                         uint32_t shared_size,
-                         ActTypes&&... args) {
+const char* launchKernelWithBlock(void (*kernel)(ExpTypes...),
+                                  uint32_t count,
+                                  uint32_t block,
+                                  uint32_t shared_size,
+                                  ActTypes&&... args) {
   try {
     CudaStream stream;
-    LaunchConfig cfg = getSimpleConfig(count);
+    LaunchConfig cfg = getSimpleConfig(count, block);
     cudaLaunchConfig_t config;
     config.attrs = nullptr;
     config.numAttrs = 0;
@@ -95,3 +100,11 @@ const char* launchKernel(void (*kernel)(ExpTypes...),
   }
   return nullptr;
 }
+
+template <typename... ExpTypes, typename... ActTypes>
+const char* launchKernel(void (*kernel)(ExpTypes...),
+                         uint32_t count,
+                         uint32_t shared_size,
+                         ActTypes&&... args) {
+  return launchKernelWithBlock(kernel, count, 0, shared_size, std::forward<ActTypes>(args)...);
+}
@@ -68,8 +68,9 @@ const char* risc0_zkp_cuda_eltwise_zeroize_fpext(FpExt* elems, const uint32_t co
   return launchKernel(eltwise_zeroize_fpext, count, 0, elems);
 }
 
-const char* risc0_zkp_cuda_fri_fold(Fp* out, const Fp* in, const FpExt* mix, const uint32_t count) {
-  return launchKernel(fri_fold, count, 0, out, in, mix, count);
+const char* risc0_zkp_cuda_fri_fold(
+    Fp* out, const Fp* in, const FpExt* mix, const uint32_t count, uint32_t block) {
+  return launchKernelWithBlock(fri_fold, count, block, 0, out, in, mix, count);
 }
 
 const char* risc0_zkp_cuda_mix_poly_coeffs(FpExt* out,
@@ -109,13 +110,16 @@ const char* risc0_zkp_cuda_scatter(Fp* into,
   return launchKernel(scatter, count, 0, into, index, offsets, values, count);
 }
 
-const char*
-risc0_zkp_cuda_sha_rows(ShaDigest* output, const Fp* matrix, uint32_t rowSize, uint32_t colSize) {
-  return launchKernel(sha_rows, rowSize, 0, output, matrix, rowSize, colSize);
+const char* risc0_zkp_cuda_sha_rows(
+    ShaDigest* output, const Fp* matrix, uint32_t rowSize, uint32_t colSize, uint32_t block) {
+  return launchKernelWithBlock(sha_rows, rowSize, block, 0, output, matrix, rowSize, colSize);
 }
 
-const char* risc0_zkp_cuda_sha_fold(ShaDigest* output, const ShaDigest* input, uint32_t count) {
-  return launchKernel(sha_fold, count, 0, output, input, count);
+const char* risc0_zkp_cuda_sha_fold(ShaDigest* output,
+                                    const ShaDigest* input,
+                                    uint32_t count,
+                                    uint32_t block) {
+  return launchKernelWithBlock(sha_fold, count, block, 0, output, input, count);
 }
 
 } // extern "C"
@@ -9,12 +9,25 @@
 #include "calc_prefix_operation.cuh"
 #include "poseidon_baby_bear/poseidon2.cu"
 
-extern "C" RustError::by_value
-sppark_poseidon2_fold(poseidon_out_t* d_out, const poseidon_in_t* d_in, size_t num_hashes) {
+// The largest number of threads per block allowed by the launch bounds of the Poseidon2 kernels,
+// which is also the default.
+constexpr size_t POSEIDON2_MAX_BLOCK = 256;
+
+// Returns the number of threads per block for `count` hashes, given the requested `block` size, or
+// 0 for the default.
+static size_t poseidon2_block_size(size_t count, uint32_t block) {
+  size_t max_block = block ? std::min<size_t>(block, POSEIDON2_MAX_BLOCK) : POSEIDON2_MAX_BLOCK;
+  return std::min(count, max_block);
+}
+
+extern "C" RustError::by_value sppark_poseidon2_fold(poseidon_out_t* d_out,
+                                                     const poseidon_in_t* d_in,
+                                                     size_t num_hashes,
+                                                     uint32_t block) {
   const gpu_t& gpu = select_gpu();
 
-  size_t block_size = num_hashes < 256 ? num_hashes : 256;
-  size_t num_blocks = num_hashes < 256 ? 1 : num_hashes / 256;
+  size_t block_size = poseidon2_block_size(num_hashes, block);
+  size_t num_blocks = (num_hashes + block_size - 1) / block_size;
 
   try {
     CUDA_OK(cudaDeviceSynchronize());
@@ -32,11 +45,11 @@ sppark_poseidon2_fold(poseidon_out_t* d_out, const poseidon_in_t* d_in, size_t n
   return RustError{cudaSuccess};
 }
 
-extern "C" RustError::by_value
-sppark_poseidon2_rows(poseidon_out_t* d_out, const fr_t* d_in, uint32_t count, uint32_t col_size) {
+extern "C" RustError::by_value sppark_poseidon2_rows(
+    poseidon_out_t* d_out, const fr_t* d_in, uint32_t count, uint32_t col_size, uint32_t block) {
   const gpu_t& gpu = select_gpu();
 
-  size_t block_size = count < 256 ? count : 256;
+  size_t block_size = poseidon2_block_size(count, block);
   size_t num_blocks = (count + block_size - 1) / block_size;
 
   try {
@@ -1,6 +1,17 @@
+#include <algorithm>
+
 #include <ff/baby_bear.hpp>
 #include <ntt/ntt.cuh>
 
+// Returns the stream to transform polynomial `c` of a batch on. If `streams` is zero, every
+// polynomial is transformed on the default stream of the GPU, one after another. Otherwise
+// consecutive polynomials are transformed concurrently on up to `streams` streams.
+static stream_t& batch_stream(const gpu_t& gpu, size_t c, uint32_t streams) {
+  if (streams == 0)
+    return gpu;
+  return gpu[c % std::min<size_t>(streams, gpu_t::FLIP_FLOP)];
+}
+
 extern "C" RustError::by_value sppark_init() {
   uint32_t lg_domain_size = 1;
   uint32_t domain_size = 1U << lg_domain_size;
@@ -56,8 +67,10 @@ extern "C" RustError::by_value sppark_batch_expand(
   return RustError{cudaSuccess};
 }
 
-extern "C" RustError::by_value
-sppark_batch_NTT(fr_t* d_inout, uint32_t lg_domain_size, uint32_t poly_count) {
+extern "C" RustError::by_value sppark_batch_NTT(fr_t* d_inout,
+                                                uint32_t lg_domain_size,
+                                                uint32_t poly_count,
+                                                uint32_t streams) {
   if (lg_domain_size == 0)
     return RustError{cudaSuccess};
 
@@ -69,7 +82,7 @@ sppark_batch_NTT(fr_t* d_inout, uint32_t lg_domain_size, uint32_t poly_count) {
     CUDA_OK(cudaDeviceSynchronize());
 
     for (size_t c = 0; c < poly_count; c++) {
-      NTT::Base_dev_ptr(gpu,
+      NTT::Base_dev_ptr(batch_stream(gpu, c, streams),
                         &d_inout[c * domain_size],
                         lg_domain_size,
                         NTT::InputOutputOrder::RN,
@@ -86,8 +99,10 @@ sppark_batch_NTT(fr_t* d_inout, uint32_t lg_domain_size, uint32_t poly_count) {
   return RustError{cudaSuccess};
 }
 
-extern "C" RustError::by_value
-sppark_batch_iNTT(fr_t* d_inout, uint32_t lg_domain_size, uint32_t poly_count) {
+extern "C" RustError::by_value sppark_batch_iNTT(fr_t* d_inout,
+                                                 uint32_t lg_domain_size,
+                                                 uint32_t poly_count,
+                                                 uint32_t streams) {
   if (lg_domain_size == 0)
     return RustError{cudaSuccess};
 
@@ -99,7 +114,7 @@ sppark_batch_iNTT(fr_t* d_inout, uint32_t lg_domain_size, uint32_t poly_count) {
     CUDA_OK(cudaDeviceSynchronize());
 
     for (size_t c = 0; c < poly_count; c++) {
-      NTT::Base_dev_ptr(gpu,
+      NTT::Base_dev_ptr(batch_stream(gpu, c, streams),
                         &d_inout[c * domain_size],
                         lg_domain_size,
                         NTT::InputOutputOrder::NR,
@@ -31,12 +31,14 @@ extern "C" {
         d_inout: DevicePointer<u8>,
         lg_domain_size: u32,
         poly_count: u32,
+        streams: u32,
     ) -> sppark::Error;
 
     pub fn sppark_batch_iNTT(
         d_inout: DevicePointer<u8>,
         lg_domain_size: u32,
         poly_count: u32,
+        streams: u32,
     ) -> sppark::Error;
 
     pub fn sppark_batch_zk_shift(
@@ -49,6 +51,7 @@ extern "C" {
         d_out: DevicePointer<u8>,
         d_in: DevicePointer<u8>,
         num_hashes: usize,
+        block: u32,
     ) -> sppark::Error;
 
     pub fn sppark_poseidon2_rows(
@@ -56,5 +59,6 @@ extern "C" {
         d_in: DevicePointer<u8>,
         count: u32,
         col_size: u32,
+        block: u32,
     ) -> sppark::Error;
 }
@@ -0,0 +1,35 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use risc0_core::field::baby_bear::BabyBear;
+use risc0_zkp::{
+    core::hash::poseidon2::Poseidon2HashSuite,
+    hal::{bench::workloads, cpu::CpuHal},
+};
+
+fn benchmark_hal(c: &mut Criterion) {
+    let hal: CpuHal<BabyBear> = CpuHal::new(Poseidon2HashSuite::new_suite());
+    let mut group = c.benchmark_group("hal");
+    group.sample_size(10);
+    for po2 in [16, 20] {
+        for mut workload in workloads(&hal, po2) {
+            group.throughput(Throughput::Elements(workload.elements() as u64));
+            group.bench_function(workload.name().to_string(), |b| b.iter(|| workload.run()));
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_hal);
+criterion_main!(benches);
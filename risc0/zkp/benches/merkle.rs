@@ -0,0 +1,60 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use risc0_core::field::{
+    baby_bear::{BabyBear, BabyBearElem},
+    Elem,
+};
+use risc0_zkp::{
+    core::hash::poseidon2::Poseidon2HashSuite,
+    hal::{cpu::CpuHal, Buffer, Hal},
+};
+
+const COLS: usize = 16;
+
+fn benchmark_merkle(c: &mut Criterion) {
+    let hal: CpuHal<BabyBear> = CpuHal::new(Poseidon2HashSuite::new_suite());
+    let mut rng = rand::thread_rng();
+    let mut group = c.benchmark_group("merkle");
+    group.sample_size(10);
+    for po2 in [16, 22] {
+        let rows = 1 << po2;
+        let matrix = hal.alloc_elem("matrix", rows * COLS);
+        matrix.view_mut(|view| {
+            for elem in view.iter_mut() {
+                *elem = BabyBearElem::random(&mut rng);
+            }
+        });
+        let nodes = hal.alloc_digest("nodes", rows * 2);
+
+        group.bench_with_input(BenchmarkId::new("hash_tree", po2), &rows, |b, _| {
+            b.iter(|| hal.hash_tree(&nodes, &matrix))
+        });
+        group.bench_with_input(BenchmarkId::new("hash_layers", po2), &rows, |b, &rows| {
+            b.iter(|| {
+                hal.hash_rows(&nodes.slice(rows, rows), &matrix);
+                let mut layer_size = rows / 2;
+                while layer_size > 0 {
+                    hal.hash_fold(&nodes, layer_size * 2, layer_size);
+                    layer_size /= 2;
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_merkle);
+criterion_main!(benches);
@@ -21,7 +21,7 @@ use anyhow::Result;
 use risc0_core::field::{Elem, ExtElem, Field};
 use serde::{Deserialize, Serialize};
 
-use crate::{hal::cpu::SyncSlice, taps::TapSet};
+use crate::{core::transcript::TranscriptMode, hal::cpu::SyncSlice, taps::TapSet};
 
 // TODO: Remove references to these constants so we don't depend on a
 // fixed set of register groups.
@@ -163,6 +163,78 @@ impl fmt::Display for ProtocolInfo {
 /// change to checks applied by the verifier.
 pub const PROOF_SYSTEM_INFO: ProtocolInfo = ProtocolInfo(*b"RISC0_STARK:v1__");
 
+/// Versioned info string for [ProofSystemVersion::V2].
+pub const PROOF_SYSTEM_INFO_V2: ProtocolInfo = ProtocolInfo(*b"RISC0_STARK:v2__");
+
+/// Versioned info string for [ProofSystemVersion::V3].
+pub const PROOF_SYSTEM_INFO_V3: ProtocolInfo = ProtocolInfo(*b"RISC0_STARK:v3__");
+
+/// Version of the STARK proof system used to produce and check a seal.
+///
+/// Each version has its own [ProtocolInfo], which is committed to the Fiat-Shamir transcript,
+/// so a seal can only be verified under the version it was produced with.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum ProofSystemVersion {
+    /// The original proof system, identified by [PROOF_SYSTEM_INFO].
+    #[default]
+    V1,
+
+    /// Labels the Fiat-Shamir transcript and writes each Merkle row opened by more than one query
+    /// to the seal only once.
+    ///
+    /// The DEEP-ALI checks already derive the first FRI layer of each query from the rows opened
+    /// in the trace trees, so a query has no separate opening to merge. What remains to share is
+    /// rows opened by several queries, which are most common in the small trees of the last FRI
+    /// rounds.
+    V2,
+
+    /// As [ProofSystemVersion::V2], but samples the FRI query positions without replacement, so
+    /// that no query is wasted on a position that was already checked.
+    V3,
+}
+
+impl ProofSystemVersion {
+    /// The [ProtocolInfo] committed to the transcript for this version.
+    pub const fn info(&self) -> ProtocolInfo {
+        match self {
+            ProofSystemVersion::V1 => PROOF_SYSTEM_INFO,
+            ProofSystemVersion::V2 => PROOF_SYSTEM_INFO_V2,
+            ProofSystemVersion::V3 => PROOF_SYSTEM_INFO_V3,
+        }
+    }
+
+    /// Look up the version identified by the given [ProtocolInfo].
+    pub fn from_info(info: &ProtocolInfo) -> Option<Self> {
+        [
+            ProofSystemVersion::V1,
+            ProofSystemVersion::V2,
+            ProofSystemVersion::V3,
+        ]
+        .into_iter()
+        .find(|version| version.info() == *info)
+    }
+
+    /// The [TranscriptMode] used by this version.
+    pub const fn transcript_mode(&self) -> TranscriptMode {
+        match self {
+            ProofSystemVersion::V1 => TranscriptMode::Unlabeled,
+            ProofSystemVersion::V2 | ProofSystemVersion::V3 => TranscriptMode::Labeled,
+        }
+    }
+
+    /// Whether a Merkle opening already present in the seal is omitted when the same row is
+    /// queried again.
+    pub const fn consolidates_openings(&self) -> bool {
+        matches!(self, ProofSystemVersion::V2 | ProofSystemVersion::V3)
+    }
+
+    /// Whether the FRI query positions are distinct, i.e. sampled without replacement.
+    pub const fn distinct_queries(&self) -> bool {
+        matches!(self, ProofSystemVersion::V3)
+    }
+}
+
 pub trait CircuitInfo {
     const CIRCUIT_INFO: ProtocolInfo;
     const OUTPUT_SIZE: usize;
@@ -19,6 +19,7 @@ pub mod poseidon2;
 #[cfg(feature = "prove")]
 pub mod poseidon_254;
 pub mod sha;
+pub mod sponge;
 
 use alloc::{boxed::Box, rc::Rc, string::String};
 
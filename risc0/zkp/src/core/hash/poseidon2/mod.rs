@@ -27,8 +27,11 @@ use risc0_core::field::{
 };
 
 use self::consts::{M_INT_DIAG_HZN, ROUNDS_HALF_FULL, ROUNDS_PARTIAL, ROUND_CONSTANTS};
-pub use self::{consts::CELLS, rng::Poseidon2Rng};
-use super::{HashFn, HashSuite, Rng, RngFactory};
+pub use self::{
+    consts::CELLS,
+    rng::{Poseidon2Rng, Poseidon2Sponge},
+};
+use super::{sponge::SpongeRngFactory, HashFn, HashSuite};
 use crate::core::digest::{Digest, DIGEST_WORDS};
 
 /// The 'rate' of the sponge, i.e. how much we can safely add/remove per mixing.
@@ -66,14 +69,6 @@ impl HashFn<BabyBear> for Poseidon2HashFn {
     }
 }
 
-struct Poseidon2RngFactory;
-
-impl RngFactory<BabyBear> for Poseidon2RngFactory {
-    fn new_rng(&self) -> Box<dyn Rng<BabyBear>> {
-        Box::new(Poseidon2Rng::new())
-    }
-}
-
 /// A hash suite using Poseidon2 for both MT hashes and RNG
 pub struct Poseidon2HashSuite;
 
@@ -83,7 +78,7 @@ impl Poseidon2HashSuite {
         HashSuite {
             name: "poseidon2".into(),
             hashfn: Rc::new(Poseidon2HashFn {}),
-            rng: Rc::new(Poseidon2RngFactory {}),
+            rng: Rc::new(SpongeRngFactory::<Poseidon2Sponge>::new()),
         }
     }
 }
@@ -20,52 +20,56 @@ use risc0_core::field::{
 };
 
 use super::{consts::CELLS, poseidon2_mix, CELLS_OUT, CELLS_RATE};
-use crate::core::{digest::Digest, hash::Rng};
+use crate::core::{
+    digest::Digest,
+    hash::sponge::{DuplexRng, FieldSponge, Sponge},
+};
 
 /// A random number generator driven by Poseidon2
+pub type Poseidon2Rng = DuplexRng<Poseidon2Sponge>;
+
+/// A [Sponge] driven by Poseidon2
 #[derive(Clone, Debug)]
-pub struct Poseidon2Rng {
+pub struct Poseidon2Sponge {
     // The cells of the sponge
     cells: [Elem; CELLS],
-    // How many cells have used so far
-    pool_used: usize,
 }
 
-impl Default for Poseidon2Rng {
+impl Default for Poseidon2Sponge {
     fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl Poseidon2Rng {
-    /// Construct a new Poseidon2Rng
-    pub fn new() -> Self {
         Self {
             cells: [Elem::new(0); CELLS],
-            pool_used: 0,
         }
     }
 }
 
-impl Rng<BabyBear> for Poseidon2Rng {
-    fn mix(&mut self, val: &Digest) {
-        // if switching from squeezing, do a mix
-        if self.pool_used != 0 {
-            poseidon2_mix(&mut self.cells);
-            self.pool_used = 0;
-        }
+impl Sponge for Poseidon2Sponge {
+    const RATE: usize = CELLS_RATE;
+
+    const PERMUTE_BEFORE_ABSORB: bool = true;
+
+    fn absorb(&mut self, val: &Digest) {
         // Add in CELLS_OUT elements (also # of digest words)
         for i in 0..CELLS_OUT {
             self.cells[i] += Elem::new_raw(val.as_words()[i]);
         }
-        // Mix
+    }
+
+    fn permute(&mut self) {
         poseidon2_mix(&mut self.cells);
     }
 
-    fn random_bits(&mut self, bits: usize) -> u32 {
-        let mut val = self.random_elem().as_u32();
+    fn squeeze(&self, idx: usize) -> u32 {
+        self.cells[idx].as_u32_montgomery()
+    }
+}
+
+// The cells of the sponge are field elements, so they are squeezed directly.
+impl FieldSponge<BabyBear> for Poseidon2Sponge {
+    fn random_bits(rng: &mut Poseidon2Rng, bits: usize) -> u32 {
+        let mut val = Self::random_elem(rng).as_u32();
         for _ in 0..3 {
-            let new_val = self.random_elem().as_u32();
+            let new_val = Self::random_elem(rng).as_u32();
             if val == 0 {
                 val = new_val;
             }
@@ -73,17 +77,11 @@ impl Rng<BabyBear> for Poseidon2Rng {
         ((1 << bits) - 1) & val
     }
 
-    fn random_elem(&mut self) -> Elem {
-        if self.pool_used == CELLS_RATE {
-            poseidon2_mix(&mut self.cells);
-            self.pool_used = 0;
-        }
-        let out = self.cells[self.pool_used];
-        self.pool_used += 1;
-        out
+    fn random_elem(rng: &mut Poseidon2Rng) -> Elem {
+        Elem::new_raw(rand_core::RngCore::next_u32(rng))
     }
 
-    fn random_ext_elem(&mut self) -> BabyBearExtElem {
-        ExtElem::from_subelems((0..4).map(|_| self.random_elem()))
+    fn random_ext_elem(rng: &mut Poseidon2Rng) -> BabyBearExtElem {
+        ExtElem::from_subelems((0..4).map(|_| Self::random_elem(rng)))
     }
 }
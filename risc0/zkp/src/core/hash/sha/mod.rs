@@ -19,6 +19,8 @@ pub mod guest;
 mod rng;
 pub mod rust_crypto;
 
+pub use self::rng::{ShaRng, ShaSponge};
+
 // Pick the appropriate implementation of SHA-256 depending on whether we are
 // in the zkVM guest.
 cfg_if::cfg_if! {
@@ -42,7 +44,10 @@ use risc0_core::field::Field;
 pub use risc0_zkvm_platform::WORD_SIZE;
 use serde::{Deserialize, Serialize};
 
-use crate::core::digest::{Digest, DIGEST_BYTES, DIGEST_WORDS};
+use crate::core::{
+    digest::{Digest, DIGEST_BYTES, DIGEST_WORDS},
+    hash::sponge::SpongeRngFactory,
+};
 
 /// The number of words in the representation of a [Block].
 ///
@@ -325,14 +330,6 @@ impl<F: Field> super::HashFn<F> for Sha256HashFn {
     }
 }
 
-struct Sha256RngFactory;
-
-impl<F: Field> super::RngFactory<F> for Sha256RngFactory {
-    fn new_rng(&self) -> Box<dyn super::Rng<F>> {
-        Box::new(rng::ShaRng::new())
-    }
-}
-
 /// Make a hash suite from a Sha256 trait
 pub struct Sha256HashSuite<F: Field> {
     phantom: PhantomData<F>,
@@ -345,7 +342,7 @@ impl<F: Field> Sha256HashSuite<F> {
         super::HashSuite {
             name: "sha-256".into(),
             hashfn: Rc::new(Sha256HashFn {}),
-            rng: Rc::new(Sha256RngFactory {}),
+            rng: Rc::new(SpongeRngFactory::<ShaSponge>::new()),
         }
     }
 }
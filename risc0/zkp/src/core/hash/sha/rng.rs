@@ -16,93 +16,60 @@
 
 use alloc::boxed::Box;
 
-use rand_core::{impls, Error, RngCore};
-use risc0_core::field::{Elem, Field};
+use risc0_core::field::Field;
 
 use super::{Digest, Impl, Sha256, DIGEST_WORDS};
-use crate::core::hash::Rng;
+use crate::core::hash::sponge::{DuplexRng, FieldSponge, Sponge};
 
 /// A random number generator driven by a [Sha256].
+pub type ShaRng = DuplexRng<ShaSponge>;
+
+/// A [Sponge] driven by a [Sha256].
 #[derive(Clone, Debug)]
-pub struct ShaRng {
+pub struct ShaSponge {
     // Pool 0 receives new entropy and is where values are drawn from.
     pool0: Box<Digest>,
     // Pool 1 provides secret state in the step function. It is never observable.
     pool1: Box<Digest>,
-    pool_used: usize,
 }
 
-impl Default for ShaRng {
+impl Default for ShaSponge {
     fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl ShaRng {
-    /// Create a new [ShaRng] from a given [Sha256].
-    pub fn new() -> Self {
         Self {
             pool0: (*Impl::hash_bytes(b"Hello")).into(),
             pool1: (*Impl::hash_bytes(b"World")).into(),
-            pool_used: 0,
         }
     }
+}
 
-    /// Mix the pool with a specified [Digest].
-    pub fn inner_mix(&mut self, val: &Digest) {
+impl Sponge for ShaSponge {
+    const RATE: usize = DIGEST_WORDS;
+
+    const PERMUTE_BEFORE_ABSORB: bool = false;
+
+    fn absorb(&mut self, val: &Digest) {
         for i in 0..DIGEST_WORDS {
             self.pool0.as_mut_words()[i] = self.pool0.as_words()[i] ^ val.as_words()[i];
         }
-        self.step();
     }
 
-    fn step(&mut self) {
+    fn permute(&mut self) {
         self.pool0 = (*Impl::hash_pair(&self.pool0, &self.pool1)).into();
         self.pool1 = (*Impl::hash_pair(&self.pool0, &self.pool1)).into();
-        self.pool_used = 0;
-    }
-}
-
-impl RngCore for ShaRng {
-    fn next_u32(&mut self) -> u32 {
-        if self.pool_used == DIGEST_WORDS {
-            self.step();
-        }
-        let out = self.pool0.as_words()[self.pool_used];
-        // Mark this word as used.
-        self.pool_used += 1;
-        out
-    }
-
-    fn next_u64(&mut self) -> u64 {
-        ((self.next_u32() as u64) << 32) | (self.next_u32() as u64)
     }
 
-    fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_next(self, dest);
-    }
-
-    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        self.fill_bytes(dest);
-        Ok(())
+    fn squeeze(&self, idx: usize) -> u32 {
+        self.pool0.as_words()[idx]
     }
 }
 
-impl<F: Field> Rng<F> for ShaRng {
-    fn mix(&mut self, val: &Digest) {
-        self.inner_mix(val);
-    }
+impl<F: Field> FieldSponge<F> for ShaSponge {}
 
-    fn random_bits(&mut self, bits: usize) -> u32 {
-        ((1 << bits) - 1) & self.next_u32()
-    }
-
-    fn random_elem(&mut self) -> F::Elem {
-        F::Elem::random(self)
-    }
-
-    fn random_ext_elem(&mut self) -> F::ExtElem {
-        F::ExtElem::random(self)
+impl ShaRng {
+    /// Mix the pool with a specified [Digest].
+    #[deprecated(note = "use `ShaRng::absorb` instead")]
+    pub fn inner_mix(&mut self, val: &Digest) {
+        self.absorb(val);
     }
 }
 
@@ -122,7 +89,7 @@ mod tests {
             x.next_u32();
         }
         assert_eq!(x.next_u32(), 785921476);
-        x.inner_mix(&Impl::hash_bytes(b"foo"));
+        x.absorb(&Impl::hash_bytes(b"foo"));
         assert_eq!(x.next_u32(), 4167871101);
     }
 }
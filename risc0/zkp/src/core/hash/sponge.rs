@@ -0,0 +1,232 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A duplex sponge construction for the Fiat-Shamir transcript.
+//!
+//! Each hash suite provides a [Sponge], i.e. a state with a permutation, and [DuplexRng] builds the
+//! transcript from it: commitments are absorbed into the state, and challenges are squeezed from
+//! it. This keeps the transcript native to the hash of the suite, so that a verifier of a
+//! Poseidon2 seal (e.g. the recursion circuit) never needs to evaluate SHA-256.
+
+use alloc::boxed::Box;
+use core::marker::PhantomData;
+
+use rand_core::{impls, Error, RngCore};
+use risc0_core::field::{Elem, Field};
+
+use super::{Rng, RngFactory};
+use crate::core::digest::Digest;
+
+/// The state and permutation of a duplex sponge.
+pub trait Sponge: Send + 'static {
+    /// The number of words which can be squeezed from the state between two permutations.
+    const RATE: usize;
+
+    /// Whether the state is permuted before absorbing, when words have been squeezed from it
+    /// since the last permutation.
+    const PERMUTE_BEFORE_ABSORB: bool;
+
+    /// Absorb the given digest into the state. The state is permuted afterwards.
+    fn absorb(&mut self, val: &Digest);
+
+    /// Apply the permutation to the state.
+    fn permute(&mut self);
+
+    /// The word of the state at the given index, which is less than [Sponge::RATE].
+    fn squeeze(&self, idx: usize) -> u32;
+}
+
+/// How challenges in a field are derived from the words squeezed from a [Sponge].
+///
+/// The default methods reduce uniform words by rejection sampling, which suits sponges whose
+/// state is made of bytes. Sponges whose state is made of field elements override them to
+/// squeeze elements directly.
+pub trait FieldSponge<F: Field>: Sponge + Sized {
+    /// Squeeze a uniform set of bits, as the low order bits of a u32.
+    fn random_bits(rng: &mut DuplexRng<Self>, bits: usize) -> u32 {
+        ((1 << bits) - 1) & rng.next_u32()
+    }
+
+    /// Squeeze a uniform field element.
+    fn random_elem(rng: &mut DuplexRng<Self>) -> F::Elem {
+        F::Elem::random(rng)
+    }
+
+    /// Squeeze a uniform extension field element.
+    fn random_ext_elem(rng: &mut DuplexRng<Self>) -> F::ExtElem {
+        F::ExtElem::random(rng)
+    }
+}
+
+/// A Fiat-Shamir [Rng] built on a duplex [Sponge].
+#[derive(Clone, Debug)]
+pub struct DuplexRng<S> {
+    sponge: S,
+    // How many words of the rate have been squeezed since the last permutation.
+    pool_used: usize,
+}
+
+impl<S: Sponge + Default> Default for DuplexRng<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Sponge + Default> DuplexRng<S> {
+    /// Construct a new [DuplexRng] from the initial state of the sponge.
+    pub fn new() -> Self {
+        Self::from_sponge(S::default())
+    }
+}
+
+impl<S: Sponge> DuplexRng<S> {
+    /// Construct a new [DuplexRng] from the given sponge.
+    pub fn from_sponge(sponge: S) -> Self {
+        Self {
+            sponge,
+            pool_used: 0,
+        }
+    }
+
+    /// Absorb the given digest into the sponge.
+    pub fn absorb(&mut self, val: &Digest) {
+        if S::PERMUTE_BEFORE_ABSORB && self.pool_used != 0 {
+            self.sponge.permute();
+        }
+        self.sponge.absorb(val);
+        self.sponge.permute();
+        self.pool_used = 0;
+    }
+}
+
+impl<S: Sponge> RngCore for DuplexRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        if self.pool_used == S::RATE {
+            self.sponge.permute();
+            self.pool_used = 0;
+        }
+        let out = self.sponge.squeeze(self.pool_used);
+        self.pool_used += 1;
+        out
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | (self.next_u32() as u64)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl<F: Field, S: FieldSponge<F>> Rng<F> for DuplexRng<S> {
+    fn mix(&mut self, val: &Digest) {
+        self.absorb(val);
+    }
+
+    fn random_bits(&mut self, bits: usize) -> u32 {
+        S::random_bits(self, bits)
+    }
+
+    fn random_elem(&mut self) -> F::Elem {
+        S::random_elem(self)
+    }
+
+    fn random_ext_elem(&mut self) -> F::ExtElem {
+        S::random_ext_elem(self)
+    }
+}
+
+/// An [RngFactory] producing a [DuplexRng] from the initial state of a [Sponge].
+pub struct SpongeRngFactory<S> {
+    phantom: PhantomData<S>,
+}
+
+impl<S> SpongeRngFactory<S> {
+    /// Construct a new [SpongeRngFactory].
+    pub const fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<S> Default for SpongeRngFactory<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: Field, S: FieldSponge<F> + Default> RngFactory<F> for SpongeRngFactory<S> {
+    fn new_rng(&self) -> Box<dyn Rng<F>> {
+        Box::new(DuplexRng::<S>::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand_core::RngCore;
+
+    use super::{DuplexRng, Sponge};
+    use crate::core::digest::Digest;
+
+    // A sponge whose state records how many times it was permuted.
+    #[derive(Default)]
+    struct CountingSponge<const PERMUTE_BEFORE_ABSORB: bool> {
+        absorbed: u32,
+        permutations: u32,
+    }
+
+    impl<const PERMUTE_BEFORE_ABSORB: bool> Sponge for CountingSponge<PERMUTE_BEFORE_ABSORB> {
+        const RATE: usize = 2;
+
+        const PERMUTE_BEFORE_ABSORB: bool = PERMUTE_BEFORE_ABSORB;
+
+        fn absorb(&mut self, val: &Digest) {
+            self.absorbed += val.as_words()[0];
+        }
+
+        fn permute(&mut self) {
+            self.permutations += 1;
+        }
+
+        fn squeeze(&self, idx: usize) -> u32 {
+            self.absorbed * 100 + self.permutations * 10 + idx as u32
+        }
+    }
+
+    #[test]
+    fn duplex() {
+        let mut rng = DuplexRng::<CountingSponge<true>>::new();
+        // The rate is exhausted after two words.
+        assert_eq!([rng.next_u32(), rng.next_u32(), rng.next_u32()], [0, 1, 10]);
+        // Absorbing after squeezing permutes before and after absorbing.
+        rng.absorb(&Digest::from([1; 8]));
+        assert_eq!(rng.next_u32(), 130);
+        // Absorbing without squeezing only permutes after absorbing.
+        rng.absorb(&Digest::from([1; 8]));
+        rng.absorb(&Digest::from([1; 8]));
+        assert_eq!(rng.next_u32(), 360);
+
+        let mut rng = DuplexRng::<CountingSponge<false>>::new();
+        rng.next_u32();
+        rng.absorb(&Digest::from([1; 8]));
+        assert_eq!(rng.next_u32(), 110);
+    }
+}
@@ -22,6 +22,7 @@ pub mod digest;
 pub mod hash;
 pub mod ntt;
 pub mod poly;
+pub mod transcript;
 
 use rand_core::RngCore;
 
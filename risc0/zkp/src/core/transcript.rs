@@ -0,0 +1,137 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Domain separation labels for the Fiat-Shamir transcript.
+//!
+//! Commitments and challenges on a `WriteIOP` or [ReadIOP](crate::verify::ReadIOP) may carry a
+//! label naming the sub-protocol step they belong to (e.g. a FRI fold or a DEEP query). When the
+//! transcript is in [TranscriptMode::Labeled], the digest of the label is mixed into the
+//! Fiat-Shamir state ahead of each labeled operation, so that composing sub-protocols cannot
+//! produce ambiguous transcripts. Operations are also emitted as trace events and can be recorded
+//! as a list of [TranscriptEvent]s for diagnostics.
+
+use alloc::vec::Vec;
+
+use super::{
+    digest::Digest,
+    hash::sha::{Impl, Sha256},
+};
+
+/// Label for the commitment to the proof system info.
+pub const PROOF_SYSTEM_INFO: &str = "risc0.proof_system_info";
+
+/// Label for the commitment to the circuit info.
+pub const CIRCUIT_INFO: &str = "risc0.circuit_info";
+
+/// Label for the commitment to the globals (i.e. outputs) and po2.
+pub const GLOBALS: &str = "risc0.globals";
+
+/// Label for the commitment to a Merkle tree root.
+pub const MERKLE_ROOT: &str = "risc0.merkle.root";
+
+/// Label for the challenges used to mix the accumulations.
+pub const ACCUM_MIX: &str = "risc0.accum.mix";
+
+/// Label for the challenge used to mix the constraint polynomials.
+pub const POLY_MIX: &str = "risc0.deep_ali.poly_mix";
+
+/// Label for the DEEP out-of-domain query point.
+pub const DEEP_POINT: &str = "risc0.deep_ali.z";
+
+/// Label for the commitment to the DEEP evaluations of the taps.
+pub const DEEP_COEFFS: &str = "risc0.deep_ali.coeffs";
+
+/// Label for the challenge used to batch the DEEP polynomials into FRI.
+pub const FRI_BATCH_MIX: &str = "risc0.fri.batch_mix";
+
+/// Label for the challenge used to fold a FRI round.
+pub const FRI_FOLD_MIX: &str = "risc0.fri.fold_mix";
+
+/// Label for the commitment to the final FRI polynomial.
+pub const FRI_FINAL_COEFFS: &str = "risc0.fri.final_coeffs";
+
+/// Label for the challenge selecting a FRI query position.
+pub const FRI_QUERY: &str = "risc0.fri.query";
+
+/// Controls whether labels are mixed into the Fiat-Shamir state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TranscriptMode {
+    /// Labels are recorded for diagnostics only. This is the transcript used by the current
+    /// version of the proof system, and by the recursion programs that verify it.
+    #[default]
+    Unlabeled,
+
+    /// The digest of each label is mixed into the Fiat-Shamir state before the labeled
+    /// commitment or challenge.
+    Labeled,
+}
+
+/// A single operation on the Fiat-Shamir transcript.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TranscriptEvent {
+    /// A commitment to the given digest.
+    Commit {
+        /// The label of the commitment, if any.
+        label: Option<&'static str>,
+        /// The committed digest.
+        digest: Digest,
+    },
+
+    /// A challenge drawn from the transcript.
+    Challenge {
+        /// The label of the challenge, if any.
+        label: Option<&'static str>,
+    },
+}
+
+/// Compute the digest that is mixed into the Fiat-Shamir state for the given label.
+pub fn label_digest(label: &str) -> Digest {
+    *Impl::hash_bytes(label.as_bytes())
+}
+
+/// Bookkeeping shared by the prover and verifier IOPs for labeled operations.
+pub(crate) struct Transcript {
+    pub(crate) mode: TranscriptMode,
+    events: Option<Vec<TranscriptEvent>>,
+}
+
+impl Transcript {
+    pub(crate) fn new(mode: TranscriptMode) -> Self {
+        Self { mode, events: None }
+    }
+
+    pub(crate) fn record(&mut self) {
+        self.events.get_or_insert_with(Vec::new);
+    }
+
+    pub(crate) fn events(&self) -> &[TranscriptEvent] {
+        self.events.as_deref().unwrap_or_default()
+    }
+
+    /// Log the event and return the label digest to mix, if any.
+    pub(crate) fn push(&mut self, event: TranscriptEvent) -> Option<Digest> {
+        #[cfg(not(target_os = "zkvm"))]
+        tracing::trace!("transcript: {event:?}");
+        let label = match event {
+            TranscriptEvent::Commit { label, .. } | TranscriptEvent::Challenge { label } => label,
+        };
+        let label_digest = label
+            .filter(|_| self.mode == TranscriptMode::Labeled)
+            .map(label_digest);
+        if let Some(events) = self.events.as_mut() {
+            events.push(event);
+        }
+        label_digest
+    }
+}
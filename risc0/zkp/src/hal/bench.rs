@@ -0,0 +1,313 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Standardized workloads for benchmarking [Hal] implementations.
+//!
+//! Each [Workload] exercises a single HAL operation (NTT, FRI folding, Merkle hashing, or an
+//! element-wise op) at a given size, with the same shape on every backend. This allows the CPU,
+//! CUDA and Metal implementations to be compared directly, and a [Baseline] recorded on one run
+//! to gate regressions in later runs.
+//!
+//! Workloads are plain closures, so they can be driven by criterion:
+//!
+//! ```ignore
+//! for mut workload in bench::workloads(&hal, 16) {
+//!     c.bench_function(workload.name(), |b| b.iter(|| workload.run()));
+//! }
+//! ```
+//!
+//! or timed directly with [measure], e.g. in a CI job:
+//!
+//! ```ignore
+//! let measurements: Vec<_> = bench::workloads(&hal, 16)
+//!     .iter_mut()
+//!     .map(|workload| bench::measure(workload, 10))
+//!     .collect();
+//! let baseline = Baseline::parse(&std::fs::read_to_string("baseline.txt")?)?;
+//! let regressions = baseline.check(&measurements, 0.1);
+//! assert!(regressions.is_empty(), "{regressions:?}");
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write as _},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use rand::thread_rng;
+use risc0_core::field::{Elem, ExtElem};
+
+use super::{Buffer, Hal};
+use crate::{FRI_FOLD, INV_RATE};
+
+/// Number of columns processed by the batched workloads, e.g. the number of polynomials in an
+/// NTT.
+pub const COLUMNS: usize = 16;
+
+/// A single benchmark workload, with its inputs allocated on the HAL.
+pub struct Workload<'a> {
+    name: String,
+    elements: usize,
+    run: Box<dyn FnMut() + 'a>,
+}
+
+impl<'a> Workload<'a> {
+    fn new(name: impl Into<String>, elements: usize, run: impl FnMut() + 'a) -> Self {
+        Self {
+            name: name.into(),
+            elements,
+            run: Box::new(run),
+        }
+    }
+
+    /// The name of this workload, of the form `{op}/{po2}`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The number of field elements processed by one run, for reporting throughput.
+    pub fn elements(&self) -> usize {
+        self.elements
+    }
+
+    /// Run the workload once.
+    pub fn run(&mut self) {
+        (self.run)()
+    }
+}
+
+/// The standard set of workloads on the given HAL, with `1 << po2` rows per column.
+pub fn workloads<H: Hal>(hal: &H, po2: usize) -> Vec<Workload<'_>> {
+    let rows = 1 << po2;
+    let domain = rows * INV_RATE;
+    let mut rng = thread_rng();
+    let mut random_elems = |name, size| {
+        let values: Vec<H::Elem> = (0..size).map(|_| H::Elem::random(&mut rng)).collect();
+        hal.copy_from_elem(name, &values)
+    };
+
+    let coeffs = random_elems("coeffs", COLUMNS * rows);
+    let evals = hal.alloc_elem("evals", COLUMNS * domain);
+    let interpolate = random_elems("interpolate", COLUMNS * domain);
+    let fold_input = random_elems("fold_input", rows * H::ExtElem::EXT_SIZE);
+    let fold_output = hal.alloc_elem("fold_output", rows / FRI_FOLD * H::ExtElem::EXT_SIZE);
+    let matrix = random_elems("matrix", COLUMNS * rows);
+    let nodes = hal.alloc_digest("nodes", rows * 2);
+    let lhs = random_elems("lhs", rows);
+    let rhs = random_elems("rhs", rows);
+    let sum = hal.alloc_elem("sum", rows);
+    let mix = H::ExtElem::random(&mut thread_rng());
+    let extelems: Vec<H::ExtElem> = (0..COLUMNS * rows)
+        .map(|_| H::ExtElem::random(&mut thread_rng()))
+        .collect();
+    let extelems = hal.copy_from_extelem("extelems", &extelems);
+    let totals = hal.alloc_elem("totals", rows * H::ExtElem::EXT_SIZE);
+
+    vec![
+        Workload::new(format!("ntt_expand/{po2}"), evals.size(), move || {
+            hal.batch_expand_into_evaluate_ntt(&evals, &coeffs, COLUMNS, INV_RATE.ilog2() as usize)
+        }),
+        Workload::new(
+            format!("ntt_interpolate/{po2}"),
+            interpolate.size(),
+            move || hal.batch_interpolate_ntt(&interpolate, COLUMNS),
+        ),
+        Workload::new(format!("fri_fold/{po2}"), fold_input.size(), move || {
+            hal.fri_fold(&fold_output, &fold_input, &mix)
+        }),
+        Workload::new(format!("hash_tree/{po2}"), matrix.size(), move || {
+            hal.hash_tree(&nodes, &matrix)
+        }),
+        Workload::new(format!("eltwise_add_elem/{po2}"), lhs.size(), move || {
+            hal.eltwise_add_elem(&sum, &lhs, &rhs)
+        }),
+        Workload::new(
+            format!("eltwise_sum_extelem/{po2}"),
+            extelems.size() * H::ExtElem::EXT_SIZE,
+            move || hal.eltwise_sum_extelem(&totals, &extelems),
+        ),
+    ]
+}
+
+/// The time taken by one run of a [Workload].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Measurement {
+    /// The name of the workload.
+    pub name: String,
+
+    /// The median time of a single run.
+    pub time: Duration,
+}
+
+/// Time `iters` runs of the workload after a warmup run, returning the median.
+pub fn measure(workload: &mut Workload, iters: usize) -> Measurement {
+    // Warm up, so that one-time costs such as kernel compilation are not measured.
+    workload.run();
+    let mut times: Vec<Duration> = (0..iters.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            workload.run();
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    Measurement {
+        name: workload.name().to_string(),
+        time: times[times.len() / 2],
+    }
+}
+
+/// A workload which ran slower than its baseline by more than the allowed tolerance.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Regression {
+    /// The name of the workload.
+    pub name: String,
+
+    /// The time recorded in the baseline.
+    pub baseline: Duration,
+
+    /// The time measured in this run.
+    pub measured: Duration,
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {:?} -> {:?} ({:+.1}%)",
+            self.name,
+            self.baseline,
+            self.measured,
+            (self.measured.as_secs_f64() / self.baseline.as_secs_f64() - 1.0) * 100.0
+        )
+    }
+}
+
+/// Recorded workload times to compare later runs against.
+///
+/// Baselines are stored as text, one workload per line holding its name and time in
+/// nanoseconds separated by a space. Blank lines and lines starting with `#` are ignored.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Baseline {
+    entries: BTreeMap<String, Duration>,
+}
+
+impl Baseline {
+    /// Record the given measurements as a baseline.
+    pub fn new(measurements: &[Measurement]) -> Self {
+        Self {
+            entries: measurements
+                .iter()
+                .map(|m| (m.name.clone(), m.time))
+                .collect(),
+        }
+    }
+
+    /// Parse a baseline from its text form.
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut entries = BTreeMap::new();
+        for (idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, nanos) = line
+                .rsplit_once(' ')
+                .and_then(|(name, nanos)| Some((name.trim(), nanos.parse().ok()?)))
+                .ok_or_else(|| anyhow!("malformed baseline on line {}: {line}", idx + 1))?;
+            entries.insert(name.to_string(), Duration::from_nanos(nanos));
+        }
+        Ok(Self { entries })
+    }
+
+    /// The recorded time for the named workload.
+    pub fn get(&self, name: &str) -> Option<Duration> {
+        self.entries.get(name).copied()
+    }
+
+    /// Compare measurements against this baseline, returning those more than `tolerance` (as a
+    /// fraction, e.g. `0.1` for 10%) slower than recorded.
+    ///
+    /// Workloads missing from the baseline are not considered regressions.
+    pub fn check(&self, measurements: &[Measurement], tolerance: f64) -> Vec<Regression> {
+        measurements
+            .iter()
+            .filter_map(|m| {
+                let baseline = self.get(&m.name)?;
+                (m.time.as_secs_f64() > baseline.as_secs_f64() * (1.0 + tolerance)).then(|| {
+                    Regression {
+                        name: m.name.clone(),
+                        baseline,
+                        measured: m.time,
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Baseline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut contents = String::new();
+        for (name, time) in &self.entries {
+            writeln!(contents, "{name} {}", time.as_nanos()).unwrap();
+        }
+        f.write_str(&contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use risc0_core::field::baby_bear::BabyBear;
+
+    use super::{measure, workloads, Baseline, Measurement};
+    use crate::{core::hash::poseidon2::Poseidon2HashSuite, hal::cpu::CpuHal};
+
+    #[test]
+    fn cpu_workloads() {
+        let hal: CpuHal<BabyBear> = CpuHal::new(Poseidon2HashSuite::new_suite());
+        let measurements: Vec<_> = workloads(&hal, 8)
+            .iter_mut()
+            .map(|workload| measure(workload, 1))
+            .collect();
+        assert_eq!(measurements.len(), 6);
+        assert!(measurements.iter().all(|m| m.name.ends_with("/8")));
+    }
+
+    #[test]
+    fn baseline() {
+        let measurement = |name: &str, nanos| Measurement {
+            name: name.to_string(),
+            time: Duration::from_nanos(nanos),
+        };
+        let baseline = Baseline::new(&[measurement("fri_fold/8", 1000), measurement("ntt/8", 500)]);
+        let parsed = Baseline::parse(&format!("# comment\n\n{baseline}")).unwrap();
+        assert_eq!(parsed, baseline);
+        assert!(Baseline::parse("fri_fold/8 fast").is_err());
+
+        let regressions = baseline.check(
+            &[
+                measurement("fri_fold/8", 1050),
+                measurement("ntt/8", 600),
+                measurement("hash_tree/8", 1),
+            ],
+            0.1,
+        );
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].name, "ntt/8");
+        assert_eq!(regressions[0].to_string(), "ntt/8: 500ns -> 600ns (+20.0%)");
+    }
+}
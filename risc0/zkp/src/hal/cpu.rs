@@ -34,6 +34,10 @@ use crate::{
     FRI_FOLD,
 };
 
+// The number of subtrees per thread built by `hash_tree`. More subtrees balance better across
+// threads, at the cost of more layers hashed serially above them.
+const MERKLE_CHUNKS_PER_THREAD: usize = 4;
+
 pub struct CpuHal<F: Field> {
     suite: HashSuite<F>,
 }
@@ -580,6 +584,47 @@ impl<F: Field> Hal for CpuHal<F> {
         });
     }
 
+    fn hash_tree(&self, nodes: &Self::Buffer<Digest>, matrix: &Self::Buffer<Self::Elem>) {
+        let rows = nodes.size() / 2;
+        let col_size = matrix.size() / rows;
+        assert!(rows.is_power_of_two());
+        assert_eq!(matrix.size(), col_size * rows);
+        let nodes = nodes.as_slice_sync();
+        let matrix = &*matrix.as_slice();
+        let hashfn = self.suite.hashfn.as_ref();
+
+        // Split the leaves into chunks, each of which is hashed up to the root of its own subtree
+        // by a single task. The subtrees are disjoint, so tasks never wait on each other, and
+        // rayon balances the chunks across threads. Only the layers above the chunks, which are
+        // small, are hashed one layer at a time.
+        let chunks = (rayon::current_num_threads() * MERKLE_CHUNKS_PER_THREAD)
+            .next_power_of_two()
+            .min(rows);
+        let chunk_size = rows / chunks;
+        (0..chunks).into_par_iter().for_each(|chunk| {
+            let first_row = chunk * chunk_size;
+            for row in first_row..first_row + chunk_size {
+                let column: Vec<Self::Elem> =
+                    (0..col_size).map(|i| matrix[i * rows + row]).collect();
+                nodes.set(rows + row, *hashfn.hash_elem_slice(&column));
+            }
+            let mut start = rows + first_row;
+            let mut size = chunk_size;
+            while size > 1 {
+                start /= 2;
+                size /= 2;
+                for idx in start..start + size {
+                    let hash = hashfn.hash_pair(&nodes.get(2 * idx), &nodes.get(2 * idx + 1));
+                    nodes.set(idx, *hash);
+                }
+            }
+        });
+        for idx in (1..chunks).rev() {
+            let hash = hashfn.hash_pair(&nodes.get(2 * idx), &nodes.get(2 * idx + 1));
+            nodes.set(idx, *hash);
+        }
+    }
+
     fn gather_sample(
         &self,
         dst: &Self::Buffer<Self::Elem>,
@@ -650,7 +695,7 @@ impl<F: Field> Hal for CpuHal<F> {
 mod tests {
     use hex::FromHex;
     use rand::thread_rng;
-    use risc0_core::field::baby_bear::{BabyBear, BabyBearExtElem};
+    use risc0_core::field::baby_bear::{BabyBear, BabyBearElem, BabyBearExtElem};
 
     use super::*;
     use crate::core::hash::sha::Sha256HashSuite;
@@ -729,6 +774,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hash_tree() {
+        let hal: CpuHal<BabyBear> = CpuHal::new(Sha256HashSuite::new_suite());
+        let mut rng = thread_rng();
+        for rows in [1, 2, 4, 1 << 10, 1 << 13] {
+            let cols = 8;
+            let matrix = hal.alloc_elem("matrix", rows * cols);
+            matrix.view_mut(|view| {
+                for elem in view.iter_mut() {
+                    *elem = BabyBearElem::random(&mut rng);
+                }
+            });
+
+            let expected = hal.alloc_digest("expected", rows * 2);
+            hal.hash_rows(&expected.slice(rows, rows), &matrix);
+            let mut layer_size = rows / 2;
+            while layer_size > 0 {
+                hal.hash_fold(&expected, layer_size * 2, layer_size);
+                layer_size /= 2;
+            }
+
+            let nodes = hal.alloc_digest("nodes", rows * 2);
+            hal.hash_tree(&nodes, &matrix);
+            assert_eq!(nodes.to_vec()[1..], expected.to_vec()[1..]);
+        }
+    }
+
     #[test]
     fn prefix_products() {
         let hal: CpuHal<BabyBear> = CpuHal::new(Sha256HashSuite::new_suite());
@@ -12,7 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{cell::RefCell, fmt::Debug, marker::PhantomData, rc::Rc, sync::OnceLock};
+use std::{
+    cell::{OnceCell, RefCell},
+    fmt::Debug,
+    marker::PhantomData,
+    rc::Rc,
+    sync::OnceLock,
+};
 
 use cust::{
     device::DeviceAttribute,
@@ -29,7 +35,11 @@ use risc0_core::{
 };
 use risc0_sys::{cuda::*, CppError};
 
-use super::{tracker, Buffer, Hal};
+use super::{
+    tracker,
+    tune::{block_sizes, tuning_key, TuningCache},
+    Buffer, Hal,
+};
 use crate::{
     core::{
         digest::Digest,
@@ -39,6 +49,17 @@ use crate::{
     FRI_FOLD,
 };
 
+// The largest number of threads per block allowed by the launch bounds of the Poseidon2 kernels.
+const POSEIDON2_MAX_BLOCK: u32 = 256;
+
+// The candidate numbers of streams to spread a batch of NTTs over, where 0 transforms the
+// polynomials one after another on the default stream.
+const NTT_STREAMS: &[u32] = &[0, 2, 3];
+
+pub(crate) fn is_available() -> bool {
+    cust::init(CudaFlags::empty()).is_ok() && Device::num_devices().is_ok_and(|count| count > 0)
+}
+
 fn context() -> &'static Context {
     static ONCE: OnceLock<Context> = OnceLock::new();
     ONCE.get_or_init(|| {
@@ -70,10 +91,15 @@ pub trait CudaHash {
     fn new(hal: &CudaHal<Self>) -> Self;
 
     /// Run the hash_fold function
-    fn hash_fold(&self, io: &BufferImpl<Digest>, output_size: usize);
+    fn hash_fold(&self, hal: &CudaHal<Self>, io: &BufferImpl<Digest>, output_size: usize);
 
     /// Run the hash_rows function
-    fn hash_rows(&self, output: &BufferImpl<Digest>, matrix: &BufferImpl<BabyBearElem>);
+    fn hash_rows(
+        &self,
+        hal: &CudaHal<Self>,
+        output: &BufferImpl<Digest>,
+        matrix: &BufferImpl<BabyBearElem>,
+    );
 
     /// Return the HashSuite
     fn get_hash_suite(&self) -> &HashSuite<BabyBear>;
@@ -90,7 +116,7 @@ impl CudaHash for CudaHashSha256 {
         }
     }
 
-    fn hash_fold(&self, io: &BufferImpl<Digest>, output_size: usize) {
+    fn hash_fold(&self, hal: &CudaHal<Self>, io: &BufferImpl<Digest>, output_size: usize) {
         let input = io.as_device_ptr_with_offset(2 * output_size);
         let output = io.as_device_ptr_with_offset(output_size);
 
@@ -99,15 +125,21 @@ impl CudaHash for CudaHashSha256 {
                 output: DevicePointer<u8>,
                 input: DevicePointer<u8>,
                 count: u32,
+                block: u32,
             ) -> CppError;
         }
 
-        unsafe {
-            risc0_zkp_cuda_sha_fold(output, input, output_size as u32).unwrap();
-        }
+        hal.launch_tuned("sha_fold", output_size, hal.max_threads, |block| unsafe {
+            risc0_zkp_cuda_sha_fold(output, input, output_size as u32, block).unwrap();
+        });
     }
 
-    fn hash_rows(&self, output: &BufferImpl<Digest>, matrix: &BufferImpl<BabyBearElem>) {
+    fn hash_rows(
+        &self,
+        hal: &CudaHal<Self>,
+        output: &BufferImpl<Digest>,
+        matrix: &BufferImpl<BabyBearElem>,
+    ) {
         let row_size = output.size();
         let col_size = matrix.size() / output.size();
         assert_eq!(matrix.size(), col_size * row_size);
@@ -118,18 +150,20 @@ impl CudaHash for CudaHashSha256 {
                 matrix: DevicePointer<u8>,
                 row_size: u32,
                 col_size: u32,
+                block: u32,
             ) -> CppError;
         }
 
-        unsafe {
+        hal.launch_tuned("sha_rows", row_size, hal.max_threads, |block| unsafe {
             risc0_zkp_cuda_sha_rows(
                 output.as_device_ptr(),
                 matrix.as_device_ptr(),
                 row_size as u32,
                 col_size as u32,
+                block,
             )
             .unwrap();
-        }
+        });
     }
 
     fn get_hash_suite(&self) -> &HashSuite<BabyBear> {
@@ -148,33 +182,46 @@ impl CudaHash for CudaHashPoseidon2 {
         }
     }
 
-    fn hash_fold(&self, io: &BufferImpl<Digest>, output_size: usize) {
-        let err = unsafe {
-            let input = io.as_device_ptr_with_offset(2 * output_size);
-            let output = io.as_device_ptr_with_offset(output_size);
-            sppark_poseidon2_fold(output, input, output_size)
-        };
-        if err.code != 0 {
-            panic!("Failure during hash_fold: {err}");
-        }
-    }
-
-    fn hash_rows(&self, output: &BufferImpl<Digest>, matrix: &BufferImpl<BabyBearElem>) {
+    fn hash_fold(&self, hal: &CudaHal<Self>, io: &BufferImpl<Digest>, output_size: usize) {
+        let input = io.as_device_ptr_with_offset(2 * output_size);
+        let output = io.as_device_ptr_with_offset(output_size);
+        hal.launch_tuned(
+            "poseidon2_fold",
+            output_size,
+            POSEIDON2_MAX_BLOCK,
+            |block| {
+                let err = unsafe { sppark_poseidon2_fold(output, input, output_size, block) };
+                if err.code != 0 {
+                    panic!("Failure during hash_fold: {err}");
+                }
+            },
+        );
+    }
+
+    fn hash_rows(
+        &self,
+        hal: &CudaHal<Self>,
+        output: &BufferImpl<Digest>,
+        matrix: &BufferImpl<BabyBearElem>,
+    ) {
         let row_size = output.size();
         let col_size = matrix.size() / output.size();
         assert_eq!(matrix.size(), col_size * row_size);
 
-        let err = unsafe {
-            sppark_poseidon2_rows(
-                output.as_device_ptr(),
-                matrix.as_device_ptr(),
-                row_size.try_into().unwrap(),
-                col_size.try_into().unwrap(),
-            )
-        };
-        if err.code != 0 {
-            panic!("Failure during hash_rows: {err}");
-        }
+        hal.launch_tuned("poseidon2_rows", row_size, POSEIDON2_MAX_BLOCK, |block| {
+            let err = unsafe {
+                sppark_poseidon2_rows(
+                    output.as_device_ptr(),
+                    matrix.as_device_ptr(),
+                    row_size.try_into().unwrap(),
+                    col_size.try_into().unwrap(),
+                    block,
+                )
+            };
+            if err.code != 0 {
+                panic!("Failure during hash_rows: {err}");
+            }
+        });
     }
 
     fn get_hash_suite(&self) -> &HashSuite<BabyBear> {
@@ -185,6 +232,7 @@ impl CudaHash for CudaHashPoseidon2 {
 pub struct CudaHal<Hash: CudaHash + ?Sized> {
     pub max_threads: u32,
     hash: Option<Box<Hash>>,
+    tuning: TuningCache,
     _context: Context,
     _lock: ReentrantMutexGuard<'static, ()>,
 }
@@ -360,17 +408,59 @@ impl<CH: CudaHash> CudaHal<CH> {
         let max_threads = device
             .get_attribute(DeviceAttribute::MaxThreadsPerBlock)
             .unwrap();
+        let tuning = TuningCache::load(&device.name().unwrap());
         let _context = context().clone();
         let mut hal = Self {
             max_threads: max_threads as u32,
             _context,
             hash: None,
+            tuning,
             _lock,
         };
         let hash = Box::new(CH::new(&hal));
         hal.hash = Some(hash);
         hal
     }
+
+    /// Run `launch` with the tuned number of threads per block, up to `max_block`, for the named
+    /// kernel and input size.
+    ///
+    /// `launch` is passed the number of threads per block to launch the kernel with, where 0
+    /// selects the default of the native library. It may be called several times while tuning,
+    /// so it must not modify its inputs.
+    pub fn launch_tuned(&self, name: &str, count: usize, max_block: u32, launch: impl Fn(u32)) {
+        let key = tuning_key(name, count);
+        let block = self
+            .tuning
+            .get_or_tune(&key, 0, &block_sizes(max_block), &launch);
+        launch(block);
+    }
+
+    /// Run `launch` on `io` with the tuned number of streams for the named batch of NTTs.
+    ///
+    /// `launch` is passed the buffer to transform in place and the number of streams to spread
+    /// the polynomials over, where 0 transforms them one after another. While tuning, candidates
+    /// are timed on a copy of `io`, so that `io` is only transformed once.
+    fn launch_ntt_tuned(
+        &self,
+        name: &str,
+        io: &BufferImpl<BabyBearElem>,
+        n_bits: usize,
+        poly_count: usize,
+        launch: impl Fn(DevicePointer<u8>, u32),
+    ) {
+        let key = tuning_key(&format!("{name}/{n_bits}"), poly_count);
+        let scratch = OnceCell::new();
+        let streams = self.tuning.get_or_tune(&key, 0, NTT_STREAMS, |streams| {
+            let scratch = scratch.get_or_init(|| {
+                let scratch = self.alloc_elem("ntt_tuning", io.size());
+                self.eltwise_copy_elem(&scratch, io);
+                scratch
+            });
+            launch(scratch.as_device_ptr(), streams);
+        });
+        launch(io.as_device_ptr(), streams);
+    }
 }
 
 impl<CH: CudaHash> Hal for CudaHal<CH> {
@@ -474,16 +564,19 @@ impl<CH: CudaHash> Hal for CudaHal<CH> {
             assert!(n_bits >= expand_bits);
             assert!(n_bits < Self::Elem::MAX_ROU_PO2);
 
-            let err = unsafe {
-                sppark_batch_NTT(
-                    output.as_device_ptr(),
-                    n_bits.try_into().unwrap(),
-                    poly_count.try_into().unwrap(),
-                )
-            };
-            if err.code != 0 {
-                panic!("Failure during batch_evaluate_ntt: {err}");
-            }
+            self.launch_ntt_tuned("ntt", output, n_bits, poly_count, |io, streams| {
+                let err = unsafe {
+                    sppark_batch_NTT(
+                        io,
+                        n_bits.try_into().unwrap(),
+                        poly_count.try_into().unwrap(),
+                        streams,
+                    )
+                };
+                if err.code != 0 {
+                    panic!("Failure during batch_evaluate_ntt: {err}");
+                }
+            });
         }
     }
 
@@ -494,16 +587,19 @@ impl<CH: CudaHash> Hal for CudaHal<CH> {
         assert_eq!(row_size, 1 << n_bits);
         assert!(n_bits < Self::Elem::MAX_ROU_PO2);
 
-        let err = unsafe {
-            sppark_batch_iNTT(
-                io.as_device_ptr(),
-                n_bits.try_into().unwrap(),
-                count.try_into().unwrap(),
-            )
-        };
-        if err.code != 0 {
-            panic!("Failure during batch_interpolate_ntt: {err}");
-        }
+        self.launch_ntt_tuned("intt", io, n_bits, count, |io, streams| {
+            let err = unsafe {
+                sppark_batch_iNTT(
+                    io,
+                    n_bits.try_into().unwrap(),
+                    count.try_into().unwrap(),
+                    streams,
+                )
+            };
+            if err.code != 0 {
+                panic!("Failure during batch_interpolate_ntt: {err}");
+            }
+        });
     }
 
     fn batch_bit_reverse(&self, io: &Self::Buffer<Self::Elem>, count: usize) {
@@ -851,27 +947,29 @@ impl<CH: CudaHash> Hal for CudaHal<CH> {
                 input: DevicePointer<u8>,
                 mix: DevicePointer<u8>,
                 count: u32,
+                block: u32,
             ) -> CppError;
         }
 
-        unsafe {
+        self.launch_tuned("fri_fold", count, self.max_threads, |block| unsafe {
             risc0_zkp_cuda_fri_fold(
                 output.as_device_ptr(),
                 input.as_device_ptr(),
                 mix.as_device_ptr(),
                 count as u32,
+                block,
             )
             .unwrap();
-        }
+        });
     }
 
     fn hash_fold(&self, io: &Self::Buffer<Digest>, input_size: usize, output_size: usize) {
         assert_eq!(input_size, 2 * output_size);
-        self.hash.as_ref().unwrap().hash_fold(io, output_size);
+        self.hash.as_ref().unwrap().hash_fold(self, io, output_size);
     }
 
     fn hash_rows(&self, output: &Self::Buffer<Digest>, matrix: &Self::Buffer<Self::Elem>) {
-        self.hash.as_ref().unwrap().hash_rows(output, matrix);
+        self.hash.as_ref().unwrap().hash_rows(self, output, matrix);
     }
 
     fn get_hash_suite(&self) -> &HashSuite<Self::Field> {
@@ -889,10 +987,15 @@ impl<CH: CudaHash> Hal for CudaHal<CH> {
 
 #[cfg(test)]
 mod tests {
+    use risc0_core::field::baby_bear::BabyBearElem;
+    use risc0_sys::cuda::{sppark_batch_NTT, sppark_poseidon2_fold, sppark_poseidon2_rows};
     use test_log::test;
 
-    use super::{CudaHalPoseidon2, CudaHalSha256};
-    use crate::hal::testutil;
+    use super::{CudaHalPoseidon2, CudaHalSha256, NTT_STREAMS, POSEIDON2_MAX_BLOCK};
+    use crate::{
+        core::digest::Digest,
+        hal::{testutil, tune::block_sizes, Buffer, Hal},
+    };
 
     #[test]
     #[should_panic]
@@ -930,6 +1033,16 @@ mod tests {
         testutil::hash_rows(CudaHalPoseidon2::new());
     }
 
+    #[test]
+    fn hash_tree_sha256() {
+        testutil::hash_tree(CudaHalSha256::new());
+    }
+
+    #[test]
+    fn hash_tree_poseidon2() {
+        testutil::hash_tree(CudaHalPoseidon2::new());
+    }
+
     #[test]
     fn hash_fold_poseidon2() {
         testutil::hash_fold(CudaHalPoseidon2::new());
@@ -950,6 +1063,69 @@ mod tests {
         testutil::batch_interpolate_ntt(CudaHalSha256::new());
     }
 
+    #[test]
+    fn ntt_streams() {
+        // Spreading a batch of NTTs over several streams does not change the result.
+        let hal = CudaHalSha256::new();
+        let (n_bits, poly_count) = (10, 8);
+        let input: Vec<_> = (0..poly_count << n_bits).map(BabyBearElem::new).collect();
+        let outputs: Vec<_> = NTT_STREAMS
+            .iter()
+            .map(|&streams| {
+                let io = hal.copy_from_elem("io", &input);
+                let err =
+                    unsafe { sppark_batch_NTT(io.as_device_ptr(), n_bits, poly_count, streams) };
+                assert_eq!(err.code, 0, "{err}");
+                io.to_vec()
+            })
+            .collect();
+        for output in &outputs[1..] {
+            assert_eq!(output, &outputs[0]);
+        }
+    }
+
+    #[test]
+    fn poseidon2_block_sizes() {
+        // The hashes do not depend on the block size, including when the number of hashes is not
+        // a multiple of it.
+        let hal = CudaHalPoseidon2::new();
+        let (rows, cols) = (1000, 16);
+        let matrix: Vec<_> = (0..rows * cols).map(BabyBearElem::new).collect();
+        let matrix = hal.copy_from_elem("matrix", &matrix);
+        let digests: Vec<_> = (0..4 * rows).map(|i| Digest::from([i; 8])).collect();
+
+        let hash = |block| {
+            let output = hal.alloc_digest("output", rows as usize);
+            let err = unsafe {
+                sppark_poseidon2_rows(
+                    output.as_device_ptr(),
+                    matrix.as_device_ptr(),
+                    rows,
+                    cols,
+                    block,
+                )
+            };
+            assert_eq!(err.code, 0, "{err}");
+
+            let io = hal.copy_from_digest("io", &digests);
+            let err = unsafe {
+                sppark_poseidon2_fold(
+                    io.as_device_ptr_with_offset(rows as usize),
+                    io.as_device_ptr_with_offset(2 * rows as usize),
+                    rows as usize,
+                    block,
+                )
+            };
+            assert_eq!(err.code, 0, "{err}");
+            (output.to_vec(), io.to_vec())
+        };
+
+        let expected = hash(0);
+        for block in block_sizes(POSEIDON2_MAX_BLOCK) {
+            assert_eq!(hash(block), expected, "block size {block}");
+        }
+    }
+
     #[test]
     fn batch_bit_reverse() {
         testutil::batch_bit_reverse(CudaHalSha256::new());
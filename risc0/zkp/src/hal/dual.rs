@@ -334,6 +334,12 @@ where
         io.assert_eq();
     }
 
+    fn hash_tree(&self, nodes: &Self::Buffer<Digest>, matrix: &Self::Buffer<Self::Elem>) {
+        self.lhs.hash_tree(&nodes.lhs, &matrix.lhs);
+        self.rhs.hash_tree(&nodes.rhs, &matrix.rhs);
+        nodes.assert_eq();
+    }
+
     fn has_unified_memory(&self) -> bool {
         self.rhs.has_unified_memory()
     }
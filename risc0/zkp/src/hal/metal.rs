@@ -30,7 +30,11 @@ use risc0_core::{
     scope,
 };
 
-use super::{tracker, Buffer, Hal};
+use super::{
+    tracker,
+    tune::{block_sizes, tuning_key, TuningCache},
+    Buffer, Hal,
+};
 use crate::{
     core::{
         digest::Digest,
@@ -46,6 +50,9 @@ use crate::{
 
 const METAL_LIB: &[u8] = include_bytes!(env!("ZKP_METAL_PATH"));
 
+// The largest number of threads per threadgroup supported by Apple GPUs.
+const MAX_THREADS: u32 = 1024;
+
 const KERNEL_NAMES: &[&str] = &[
     "batch_expand",
     "eltwise_add_fp",
@@ -68,6 +75,10 @@ const KERNEL_NAMES: &[&str] = &[
     "zk_shift",
 ];
 
+pub(crate) fn is_available() -> bool {
+    Device::system_default().is_some()
+}
+
 // The GPU becomes unstable as the number of concurrent provers grow.
 fn singleton() -> &'static ReentrantMutex<()> {
     static ONCE: OnceLock<ReentrantMutex<()>> = OnceLock::new();
@@ -109,7 +120,7 @@ impl MetalHash for MetalHashSha256 {
             io.as_arg_with_offset(output_size),
             io.as_arg_with_offset(output_size * 2),
         ];
-        hal.dispatch_by_name("sha_fold", args, output_size as u64);
+        hal.dispatch_tuned("sha_fold", args, output_size as u64);
     }
 
     fn hash_rows(
@@ -127,7 +138,7 @@ impl MetalHash for MetalHashSha256 {
             KernelArg::Integer(row_size as u32),
             KernelArg::Integer(col_size as u32),
         ];
-        hal.dispatch_by_name("sha_rows", args, row_size as u64);
+        hal.dispatch_tuned("sha_rows", args, row_size as u64);
     }
 
     fn get_hash_suite(&self) -> &HashSuite<BabyBear> {
@@ -160,7 +171,7 @@ impl MetalHash for MetalHashPoseidon2 {
             io.as_arg_with_offset(output_size),
             io.as_arg_with_offset(output_size * 2),
         ];
-        hal.dispatch_by_name("poseidon2_fold", args, output_size as u64);
+        hal.dispatch_tuned("poseidon2_fold", args, output_size as u64);
     }
 
     fn hash_rows(
@@ -180,7 +191,7 @@ impl MetalHash for MetalHashPoseidon2 {
             KernelArg::Integer(row_size as u32),
             KernelArg::Integer(col_size as u32),
         ];
-        hal.dispatch_by_name("poseidon2_rows", args, row_size as u64);
+        hal.dispatch_tuned("poseidon2_rows", args, row_size as u64);
     }
 
     fn get_hash_suite(&self) -> &HashSuite<BabyBear> {
@@ -194,6 +205,7 @@ pub struct MetalHal<Hash: MetalHash + ?Sized> {
     pub cmd_queue: CommandQueue,
     kernels: HashMap<String, ComputePipelineDescriptor>,
     hash: Option<Box<Hash>>,
+    tuning: TuningCache,
     _lock: ReentrantMutexGuard<'static, ()>,
 }
 
@@ -395,11 +407,13 @@ impl<MH: MetalHash> MetalHal<MH> {
             pipeline.set_compute_function(Some(&function));
             kernels.insert(name.to_string(), pipeline);
         }
+        let tuning = TuningCache::load(device.name());
         let mut hal = Self {
             device,
             cmd_queue,
             kernels,
             hash: None,
+            tuning,
             _lock: lock,
         };
         hal.hash = Some(Box::new(MH::new(&hal)));
@@ -421,6 +435,21 @@ impl<MH: MetalHash> MetalHal<MH> {
         self.dispatch_with_resources(kernel, args, count, opts, |_| {});
     }
 
+    /// Dispatch the named kernel over `count` threads, using the tuned number of threads per
+    /// threadgroup for this device and input size.
+    ///
+    /// The kernel may be launched several times while tuning, so it must not modify its inputs.
+    pub fn dispatch_tuned(&self, name: &str, args: &[KernelArg], count: u64) {
+        let kernel = self.kernels.get(name).unwrap();
+        let key = tuning_key(name, count as usize);
+        let threads =
+            self.tuning
+                .get_or_tune(&key, MAX_THREADS, &block_sizes(MAX_THREADS), |threads| {
+                    self.dispatch_inner(kernel, args, count, None, Some(threads), |_| {})
+                });
+        self.dispatch_inner(kernel, args, count, None, Some(threads), |_| {});
+    }
+
     pub fn dispatch_with_resources<F: Fn(&ComputeCommandEncoderRef)>(
         &self,
         kernel: &ComputePipelineDescriptor,
@@ -428,6 +457,18 @@ impl<MH: MetalHash> MetalHal<MH> {
         count: u64,
         opts: Option<(MTLSize, MTLSize)>,
         callback: F,
+    ) {
+        self.dispatch_inner(kernel, args, count, opts, None, callback);
+    }
+
+    fn dispatch_inner<F: Fn(&ComputeCommandEncoderRef)>(
+        &self,
+        kernel: &ComputePipelineDescriptor,
+        args: &[KernelArg],
+        count: u64,
+        opts: Option<(MTLSize, MTLSize)>,
+        threads: Option<u32>,
+        callback: F,
     ) {
         let cmd_buffer = self.cmd_queue.new_command_buffer();
         let cmd_encoder = cmd_buffer.new_compute_command_encoder();
@@ -464,8 +505,10 @@ impl<MH: MetalHash> MetalHal<MH> {
             }
             None => {
                 let threads_per_grid = MTLSize::new(count, 1, 1);
-                let threads_per_threadgroup =
-                    MTLSize::new(pipeline_state.max_total_threads_per_threadgroup(), 1, 1);
+                let max_threads = pipeline_state.max_total_threads_per_threadgroup();
+                let threads =
+                    threads.map_or(max_threads, |threads| max_threads.min(threads.into()));
+                let threads_per_threadgroup = MTLSize::new(threads, 1, 1);
                 cmd_encoder.dispatch_threads(threads_per_grid, threads_per_threadgroup);
             }
         };
@@ -477,6 +520,46 @@ impl<MH: MetalHash> MetalHal<MH> {
     }
 }
 
+impl<MH: MetalHash> MetalHal<MH> {
+    // Runs the given steps of an in-place NTT. Tuning runs on scratch space, since the steps
+    // modify their input.
+    fn ntt_steps(
+        &self,
+        name: &str,
+        io: &BufferImpl<BabyBearElem>,
+        rou: &BufferImpl<BabyBearElem>,
+        n_bits: usize,
+        steps: &[usize],
+        count: usize,
+    ) {
+        let kernel = self.kernels.get(name).unwrap();
+        let run = |io: &BufferImpl<BabyBearElem>, threads: u32| {
+            for &s_bits in steps {
+                let args = &[
+                    io.as_arg(),
+                    rou.as_arg(),
+                    KernelArg::Integer(n_bits as u32),
+                    KernelArg::Integer(s_bits as u32),
+                    KernelArg::Integer(count as u32),
+                ];
+                let params =
+                    compute_launch_params(n_bits as u32, s_bits as u32, count as u32, threads);
+                self.dispatch(kernel, args, count as u64, Some(params));
+            }
+        };
+
+        let mut scratch = None;
+        let key = tuning_key(name, io.size());
+        let threads = self
+            .tuning
+            .get_or_tune(&key, 128, &block_sizes(MAX_THREADS), |threads| {
+                let scratch = scratch.get_or_insert_with(|| self.alloc_elem("scratch", io.size()));
+                run(scratch, threads);
+            });
+        run(io, threads);
+    }
+}
+
 #[allow(unused_variables)]
 impl<MH: MetalHash> Hal for MetalHal<MH> {
     type Elem = BabyBearElem;
@@ -575,18 +658,8 @@ impl<MH: MetalHash> Hal for MetalHal<MH> {
             assert!(n_bits >= expand_bits);
             assert!(n_bits < Self::Elem::MAX_ROU_PO2);
             let rou = self.copy_from_elem("rou", Self::Elem::ROU_FWD);
-            let kernel = self.kernels.get("multi_ntt_fwd_step").unwrap();
-            for s_bits in 1 + expand_bits..=n_bits {
-                let args = &[
-                    output.as_arg(),
-                    rou.as_arg(),
-                    KernelArg::Integer(n_bits as u32),
-                    KernelArg::Integer(s_bits as u32),
-                    KernelArg::Integer(count as u32),
-                ];
-                let params = compute_launch_params(n_bits as u32, s_bits as u32, count as u32);
-                self.dispatch(kernel, args, count as u64, Some(params));
-            }
+            let steps: Vec<_> = (1 + expand_bits..=n_bits).collect();
+            self.ntt_steps("multi_ntt_fwd_step", output, &rou, n_bits, &steps, count);
         });
     }
 
@@ -600,18 +673,8 @@ impl<MH: MetalHash> Hal for MetalHal<MH> {
         assert!(n_bits < Self::Elem::MAX_ROU_PO2);
 
         let rou = self.copy_from_elem("rou", Self::Elem::ROU_REV);
-        let kernel = self.kernels.get("multi_ntt_rev_step").unwrap();
-        for s_bits in (1..=n_bits).rev() {
-            let args = &[
-                io.as_arg(),
-                rou.as_arg(),
-                KernelArg::Integer(n_bits as u32),
-                KernelArg::Integer(s_bits as u32),
-                KernelArg::Integer(count as u32),
-            ];
-            let params = compute_launch_params(n_bits as u32, s_bits as u32, count as u32);
-            self.dispatch(kernel, args, count as u64, Some(params));
-        }
+        let steps: Vec<_> = (1..=n_bits).rev().collect();
+        self.ntt_steps("multi_ntt_rev_step", io, &rou, n_bits, &steps, count);
 
         let norm = self.copy_from_elem("norm", &[Self::Elem::new(row_size as u32).inv()]);
         let args = &[io.as_arg(), norm.as_arg()];
@@ -703,7 +766,7 @@ impl<MH: MetalHash> Hal for MetalHal<MH> {
             mix.as_arg(),
             KernelArg::Integer(count as u32),
         ];
-        self.dispatch_by_name("fri_fold", args, count as u64);
+        self.dispatch_tuned("fri_fold", args, count as u64);
     }
 
     fn mix_poly_coeffs(
@@ -884,14 +947,19 @@ fn simple_launch_params(count: u32, threads_per_group: u32) -> (MTLSize, MTLSize
     )
 }
 
-fn compute_launch_params(n_bits: u32, s_bits: u32, c_size: u32) -> (MTLSize, MTLSize) {
+fn compute_launch_params(
+    n_bits: u32,
+    s_bits: u32,
+    c_size: u32,
+    threads: u32,
+) -> (MTLSize, MTLSize) {
     let s_size = 1 << (s_bits - 1);
     let g_size = 1 << (n_bits - s_bits);
 
     let mut grid = MTLSize::new(1, 1, 1);
     let mut block = MTLSize::new(1, 1, 1);
 
-    let mut threads = 128;
+    let mut threads = threads as u64;
     // First thread over S
     block.width = threads.min(s_size);
     threads /= block.width;
@@ -988,6 +1056,16 @@ mod tests {
         testutil::hash_rows(MetalHalPoseidon2::new());
     }
 
+    #[test]
+    fn hash_tree_sha256() {
+        testutil::hash_tree(MetalHalSha256::new());
+    }
+
+    #[test]
+    fn hash_tree_poseidon2() {
+        testutil::hash_tree(MetalHalPoseidon2::new());
+    }
+
     #[test]
     fn slice() {
         testutil::slice(MetalHalSha256::new());
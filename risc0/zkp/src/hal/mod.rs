@@ -14,12 +14,19 @@
 
 //! Hardware Abstraction Layer (HAL) for accelerating the ZKP system.
 
+pub mod bench;
 pub mod cpu;
 #[cfg(feature = "cuda")]
 pub mod cuda;
 pub mod dual;
 #[cfg(any(all(target_os = "macos", target_arch = "aarch64"), target_os = "ios"))]
 pub mod metal;
+#[cfg(any(
+    feature = "cuda",
+    all(target_os = "macos", target_arch = "aarch64"),
+    target_os = "ios"
+))]
+pub mod tune;
 
 use std::{
     fmt::Debug,
@@ -176,6 +183,21 @@ pub trait Hal {
 
     fn hash_fold(&self, io: &Self::Buffer<Digest>, input_size: usize, output_size: usize);
 
+    /// Build a merkle tree over the columns of `matrix`.
+    ///
+    /// `nodes` is a heap style array of `2 * rows` digests, where `rows` is a power of 2. The
+    /// column hashes are written to the leaves at offset `rows`, and each layer is hashed up to
+    /// the root at offset 1. The default implementation hashes one layer at a time.
+    fn hash_tree(&self, nodes: &Self::Buffer<Digest>, matrix: &Self::Buffer<Self::Elem>) {
+        let rows = nodes.size() / 2;
+        self.hash_rows(&nodes.slice(rows, rows), matrix);
+        let mut layer_size = rows / 2;
+        while layer_size > 0 {
+            self.hash_fold(nodes, layer_size * 2, layer_size);
+            layer_size /= 2;
+        }
+    }
+
     fn gather_sample(
         &self,
         dst: &Self::Buffer<Self::Elem>,
@@ -221,6 +243,56 @@ pub trait CircuitHal<H: Hal> {
     );
 }
 
+/// The accelerators available to the prover, determined by both the compiled features and the
+/// devices present on this machine.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HalCapabilities {
+    /// CUDA support is compiled in and a CUDA device is present.
+    pub cuda: bool,
+
+    /// Metal support is compiled in and a Metal device is present.
+    pub metal: bool,
+
+    /// The CPU supports the vector extensions used by the hash functions of the CPU HAL: AVX2 on
+    /// x86_64 and NEON on aarch64. Without them, the CPU HAL uses portable implementations, which
+    /// are slower but produce the same proofs.
+    pub simd: bool,
+}
+
+impl HalCapabilities {
+    /// Returns true if any GPU accelerator is available.
+    pub fn has_gpu(&self) -> bool {
+        self.cuda || self.metal
+    }
+}
+
+/// Query the accelerators available to the prover. Provers fall back to the CPU when no GPU is
+/// available.
+pub fn capabilities() -> HalCapabilities {
+    static ONCE: OnceLock<HalCapabilities> = OnceLock::new();
+    *ONCE.get_or_init(|| HalCapabilities {
+        #[cfg(feature = "cuda")]
+        cuda: cuda::is_available(),
+        #[cfg(any(all(target_os = "macos", target_arch = "aarch64"), target_os = "ios"))]
+        metal: metal::is_available(),
+        simd: has_simd(),
+        ..Default::default()
+    })
+}
+
+fn has_simd() -> bool {
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "x86_64")] {
+            std::arch::is_x86_feature_detected!("avx2")
+        } else if #[cfg(target_arch = "aarch64")] {
+            std::arch::is_aarch64_feature_detected!("neon")
+        } else {
+            false
+        }
+    }
+}
+
 pub fn tracker() -> &'static Mutex<MemoryTracker> {
     static ONCE: OnceLock<Mutex<MemoryTracker>> = OnceLock::new();
     ONCE.get_or_init(|| Mutex::new(MemoryTracker::default()))
@@ -520,6 +592,17 @@ mod testutil {
         }
     }
 
+    pub(crate) fn hash_tree<H: Hal<Elem = BabyBearElem>>(hal_gpu: H) {
+        let mut rng = thread_rng();
+        let hal_cpu = CpuHal::new(hal_gpu.get_hash_suite().clone());
+        let hal = DualHal::new(Rc::new(hal_cpu), Rc::new(hal_gpu));
+        for rows in [1, 2, 1 << 10, 1 << 14] {
+            let matrix = generate_elem(&hal, &mut rng, rows * 16);
+            let nodes = hal.alloc_digest("nodes", rows * 2);
+            hal.hash_tree(&nodes, &matrix);
+        }
+    }
+
     pub(crate) fn slice<H: Hal<Elem = BabyBearElem>>(hal_gpu: H) {
         let mut rng = thread_rng();
         let hal_cpu = CpuHal::new(hal_gpu.get_hash_suite().clone());
@@ -546,3 +629,19 @@ mod testutil {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn capabilities() {
+        let caps = super::capabilities();
+        #[cfg(not(feature = "cuda"))]
+        assert!(!caps.cuda);
+        #[cfg(not(any(all(target_os = "macos", target_arch = "aarch64"), target_os = "ios")))]
+        assert!(!caps.metal);
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(caps.simd, std::arch::is_x86_feature_detected!("avx2"));
+        #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+        assert!(!caps.simd);
+    }
+}
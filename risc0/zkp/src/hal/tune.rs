@@ -0,0 +1,218 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Autotuning of GPU kernel launch parameters.
+//!
+//! The best launch parameters for a kernel, such as the number of threads per block, depend on
+//! the GPU and on the size of the input. Tuning is opt-in: when `RISC0_TUNING=on` is set, the
+//! first time a tunable kernel is launched for a given input size, each candidate parameter is
+//! timed and the fastest is kept. Results are persisted per device under `~/.risc0/tuning` (or
+//! `$RISC0_TUNING_DIR`), so tuning only happens once per machine.
+//!
+//! Otherwise, the default launch parameters are used, and persisted results are ignored.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Number of timed launches per candidate.
+const TRIALS: u32 = 3;
+
+/// A persistent cache of tuned launch parameters for a single device.
+#[derive(Debug)]
+pub struct TuningCache {
+    path: Option<PathBuf>,
+    entries: Mutex<BTreeMap<String, u32>>,
+}
+
+impl TuningCache {
+    /// Load the cache for the named device, or an empty one if tuning is not enabled.
+    pub fn load(device: &str) -> Self {
+        let path = tuning_dir().map(|dir| {
+            let name: String = device
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect();
+            dir.join(format!("{name}.txt"))
+        });
+        let entries = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|contents| parse(&contents))
+            .unwrap_or_default();
+        Self {
+            path,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    /// Returns true if tuning is enabled.
+    pub fn enabled(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// Returns the tuned value for the given key, tuning it first if needed by calling `launch`
+    /// with each of the candidates.
+    ///
+    /// `launch` must be safe to call repeatedly, i.e. it must not modify its own inputs. If
+    /// tuning is disabled, `default` is returned without calling `launch`.
+    pub fn get_or_tune(
+        &self,
+        key: &str,
+        default: u32,
+        candidates: &[u32],
+        mut launch: impl FnMut(u32),
+    ) -> u32 {
+        if !self.enabled() {
+            return default;
+        }
+        if let Some(value) = self.entries.lock().unwrap().get(key) {
+            return *value;
+        }
+
+        let mut best = (Duration::MAX, default);
+        for &candidate in candidates {
+            // Warm up, so that one-time costs such as pipeline creation are not measured.
+            launch(candidate);
+            let start = Instant::now();
+            for _ in 0..TRIALS {
+                launch(candidate);
+            }
+            let elapsed = start.elapsed();
+            tracing::debug!("tune {key}: {candidate} took {elapsed:?}");
+            if elapsed < best.0 {
+                best = (elapsed, candidate);
+            }
+        }
+        tracing::info!("tuned {key}: {}", best.1);
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(key.to_string(), best.1);
+        self.save(&entries);
+        best.1
+    }
+
+    fn save(&self, entries: &BTreeMap<String, u32>) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut contents = String::new();
+        for (key, value) in entries {
+            writeln!(contents, "{key} {value}").unwrap();
+        }
+        // A failure to persist the results only means that tuning is repeated next time.
+        let result = std::fs::create_dir_all(path.parent().unwrap())
+            .and_then(|_| std::fs::write(path, contents));
+        if let Err(err) = result {
+            tracing::warn!("failed to save tuning cache {}: {err}", path.display());
+        }
+    }
+}
+
+fn tuning_dir() -> Option<PathBuf> {
+    if !tuning_enabled(std::env::var("RISC0_TUNING").ok().as_deref()) {
+        return None;
+    }
+    if let Ok(dir) = std::env::var("RISC0_TUNING_DIR") {
+        return Some(dir.into());
+    }
+    let home = std::env::var("RISC0_HOME").map(PathBuf::from).or_else(|_| {
+        std::env::var("HOME")
+            .or_else(|_| std::env::var("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".risc0"))
+    });
+    home.ok().map(|home| home.join("tuning"))
+}
+
+// Tuning is enabled by setting `RISC0_TUNING` to `on` or `1`.
+fn tuning_enabled(value: Option<&str>) -> bool {
+    matches!(value, Some("on" | "1"))
+}
+
+// Each line of the cache holds a key and its tuned value, separated by a space.
+fn parse(contents: &str) -> BTreeMap<String, u32> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.rsplit_once(' ')?;
+            Some((key.to_string(), value.parse().ok()?))
+        })
+        .collect()
+}
+
+/// The key for tuning the named kernel on inputs of the given size.
+///
+/// Sizes are bucketed by their base-2 logarithm, rounded up.
+pub fn tuning_key(kernel: &str, count: usize) -> String {
+    format!("{kernel}/{}", count.next_power_of_two().trailing_zeros())
+}
+
+/// The powers of two from 32 up to `max` inclusive, which are the candidate block sizes.
+pub fn block_sizes(max: u32) -> Vec<u32> {
+    (5..=10)
+        .map(|bits| 1 << bits)
+        .filter(|&size| size <= max)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{block_sizes, parse, tuning_enabled, tuning_key, TuningCache};
+
+    #[test]
+    fn cache() {
+        let path = std::env::temp_dir().join(format!("risc0-tuning-{}.txt", std::process::id()));
+        let cache = TuningCache {
+            path: Some(path.clone()),
+            entries: Default::default(),
+        };
+
+        let mut launched = Vec::new();
+        let value = cache.get_or_tune("fri_fold/10", 256, &[64, 128], |size| {
+            launched.push(size);
+            if size == 64 {
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+        });
+        assert_eq!(value, 128);
+        assert_eq!(launched.len(), 8);
+
+        // Tuned values are persisted, and not tuned again.
+        let saved = parse(&std::fs::read_to_string(&path).unwrap());
+        assert_eq!(saved["fri_fold/10"], 128);
+        let value = cache.get_or_tune("fri_fold/10", 256, &[64, 128], |_| unreachable!());
+        assert_eq!(value, 128);
+
+        let disabled = TuningCache {
+            path: None,
+            entries: Default::default(),
+        };
+        assert_eq!(
+            disabled.get_or_tune("x", 256, &[64], |_| unreachable!()),
+            256
+        );
+
+        assert!(tuning_enabled(Some("on")));
+        assert!(!tuning_enabled(Some("off")));
+        assert!(!tuning_enabled(None));
+
+        assert_eq!(tuning_key("sha_rows", 1000), "sha_rows/10");
+        assert_eq!(block_sizes(256), [32, 64, 128, 256]);
+        std::fs::remove_file(path).unwrap();
+    }
+}
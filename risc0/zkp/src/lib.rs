@@ -17,6 +17,10 @@
 #![deny(rustdoc::broken_intra_doc_links)]
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
+// The CUDA driver is loaded dynamically, which statically linked musl binaries cannot do.
+#[cfg(all(feature = "cuda", target_env = "musl"))]
+compile_error!("the `cuda` feature is not supported on musl targets; build for a gnu target");
+
 extern crate alloc;
 
 pub mod adapter;
@@ -16,6 +16,7 @@ use risc0_core::field::{Elem, Field};
 
 use crate::{
     adapter::{CircuitProveDef, CircuitStepHandler},
+    core::transcript,
     hal::{cpu::CpuBuffer, Hal},
     prove::{executor::Executor, write_iop::WriteIOP},
     taps::TapSet,
@@ -66,7 +67,10 @@ where
             .copied()
             .collect();
 
-        iop.commit(&hal.get_hash_suite().hashfn.hash_elem_slice(&vec));
+        iop.commit_labeled(
+            transcript::GLOBALS,
+            &hal.get_hash_suite().hashfn.hash_elem_slice(&vec),
+        );
         iop.write_field_elem_slice(vec.as_slice());
     }
 
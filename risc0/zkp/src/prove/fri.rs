@@ -14,11 +14,12 @@
 
 use alloc::vec::Vec;
 
+use anyhow::Result;
 use risc0_core::{field::ExtElem, scope};
 use tracing::debug;
 
 use crate::{
-    core::log2_ceil,
+    core::{log2_ceil, transcript},
     hal::{Buffer, Hal},
     prove::{merkle::MerkleTreeProver, write_iop::WriteIOP},
     FRI_FOLD, FRI_MIN_DEGREE, INV_RATE, QUERIES,
@@ -61,7 +62,7 @@ impl<H: Hal> ProveRoundInfo<H> {
         // Send the merkle tree (as a commitment) to the virtual IOP verifier
         merkle.commit(iop);
         // Retrieve from the IOP verifier a random value to mix the polynomial slices.
-        let fold_mix = iop.random_ext_elem();
+        let fold_mix = iop.random_ext_elem_labeled(transcript::FRI_FOLD_MIX);
         // Create a buffer to hold the mixture of slices.
         let out_coeffs = hal.alloc_elem("out_coeffs", size / FRI_FOLD * ext_size);
         // Compute the folded polynomial
@@ -88,7 +89,8 @@ pub fn fri_prove<H: Hal, F>(
     iop: &mut WriteIOP<H::Field>,
     coeffs: &H::Buffer<H::Elem>,
     inner: F,
-) where
+) -> Result<()>
+where
     F: Fn(&mut WriteIOP<H::Field>, usize),
 {
     scope!("fri_prove");
@@ -109,13 +111,13 @@ pub fn fri_prove<H: Hal, F>(
     final_coeffs.view(|view| {
         iop.write_field_elem_slice::<H::Elem>(view);
         let digest = hal.get_hash_suite().hashfn.hash_elem_slice(view);
-        iop.commit(&digest);
+        iop.commit_labeled(transcript::FRI_FINAL_COEFFS, &digest);
     });
     // Do queries
     debug!("Doing Queries");
     for _ in 0..QUERIES {
         // Get a 'random' index.
-        let mut pos = iop.random_bits(log2_ceil(orig_domain)) as usize;
+        let mut pos = iop.random_query(transcript::FRI_QUERY, log2_ceil(orig_domain))? as usize;
         // Do the 'inner' proof for this index
         inner(iop, pos);
         // Write the per-round proofs
@@ -123,4 +125,5 @@ pub fn fri_prove<H: Hal, F>(
             round.prove_query(hal, iop, &mut pos);
         }
     }
+    Ok(())
 }
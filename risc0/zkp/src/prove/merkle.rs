@@ -17,7 +17,7 @@ use alloc::vec::Vec;
 use risc0_core::scope;
 
 use crate::{
-    core::digest::Digest,
+    core::{digest::Digest, transcript},
     hal::{Buffer, Hal},
     merkle::MerkleTreeParams,
     prove::write_iop::WriteIOP,
@@ -62,15 +62,8 @@ impl<H: Hal> MerkleTreeProver<H> {
         let params = MerkleTreeParams::new(rows, cols, queries);
         // Allocate nodes
         let nodes = hal.alloc_digest("nodes", rows * 2);
-        // hash each column
-        hal.hash_rows(&nodes.slice(rows, rows), matrix);
-        // For each layer, hash up the layer below
-        scope!("hash_fold", {
-            for i in (0..params.layers).rev() {
-                let layer_size = 1 << i;
-                hal.hash_fold(&nodes, layer_size * 2, layer_size);
-            }
-        });
+        // Hash each column, and then each layer up to the root
+        scope!("hash_tree", hal.hash_tree(&nodes, matrix));
         let root = nodes.get_at(1);
         MerkleTreeProver {
             params,
@@ -88,7 +81,7 @@ impl<H: Hal> MerkleTreeProver<H> {
         slice.view(|view| {
             iop.write_pod_slice(view);
         });
-        iop.commit(self.root());
+        iop.commit_labeled(transcript::MERKLE_ROOT, self.root());
     }
 
     /// Get the root digest of the tree.
@@ -102,6 +95,8 @@ impl<H: Hal> MerkleTreeProver<H> {
     /// 1) The column itself
     /// 2) The 'other' digests up to the top.
     ///
+    /// If the IOP consolidates openings and this row was already opened, nothing is written.
+    ///
     /// It is presumed the verifier is given the index of the row from other
     /// parts of the protocol, and verification will of course fail if the
     /// wrong row is specified.
@@ -127,6 +122,9 @@ impl<H: Hal> MerkleTreeProver<H> {
                 out.extend_from_slice(view);
             });
         }
+        if !iop.open_merkle_row(&self.root, idx) {
+            return out;
+        }
         iop.write_field_elem_slice::<H::Elem>(out.as_slice());
         let mut idx = idx + self.params.row_size;
         while idx >= 2 * self.params.top_size {
@@ -150,9 +148,12 @@ mod tests {
 
     use super::*;
     use crate::{
+        adapter::ProofSystemVersion,
         core::{
+            digest::DIGEST_WORDS,
             hash::{poseidon2::Poseidon2HashSuite, sha::Sha256HashSuite, HashSuite},
             log2_ceil,
+            transcript::{TranscriptEvent, TranscriptMode},
         },
         hal::cpu::CpuHal,
         verify::{MerkleTreeVerifier, ReadIOP, VerificationError},
@@ -203,7 +204,7 @@ mod tests {
         let mut iop = WriteIOP::new(rng);
         prover.commit(&mut iop);
         for _query in 0..queries {
-            let r_idx = iop.rng.random_bits(log2_ceil(rows)) as usize;
+            let r_idx = iop.random_bits_labeled("test.query", log2_ceil(rows)) as usize;
             let col = prover.prove(&hal, &mut iop, r_idx);
             for (c_idx, col) in col.iter().enumerate() {
                 assert_eq!(
@@ -222,7 +223,7 @@ mod tests {
         assert_eq!(verifier.root(), prover.root());
         let mut err = false;
         for query in 0..queries {
-            let r_idx = r_iop.random_bits(log2_ceil(rows)) as usize;
+            let r_idx = r_iop.random_bits_labeled("test.query", log2_ceil(rows)) as usize;
             if query == bad_query {
                 assert_ne!(
                     rows, 1,
@@ -392,4 +393,109 @@ mod tests {
             possibly_bad_verify_all(rows, cols, queries, queries + 1, true);
         }
     }
+
+    #[test]
+    fn merkle_cpu_labeled_transcript() {
+        let hal = CpuHal::new(Sha256HashSuite::new_suite());
+        let hashfn = hal.get_hash_suite().hashfn.as_ref();
+        let rng = hal.get_hash_suite().rng.as_ref();
+        let (rows, cols, queries) = (16, 4, 2);
+        let prover = init_prover(&hal, rows, cols, queries);
+
+        let mut challenges = Vec::new();
+        for mode in [TranscriptMode::Unlabeled, TranscriptMode::Labeled] {
+            let mut iop = WriteIOP::with_mode(rng, mode);
+            iop.record_transcript();
+            prover.commit(&mut iop);
+            let challenge = iop.random_elem_labeled("test.challenge");
+
+            let mut r_iop = ReadIOP::with_mode(&iop.proof, rng, mode);
+            r_iop.record_transcript();
+            MerkleTreeVerifier::new(&mut r_iop, hashfn, rows, cols, queries);
+            assert_eq!(r_iop.random_elem_labeled("test.challenge"), challenge);
+
+            assert_eq!(iop.transcript(), r_iop.transcript());
+            assert_eq!(
+                iop.transcript(),
+                &[
+                    TranscriptEvent::Commit {
+                        label: Some(transcript::MERKLE_ROOT),
+                        digest: *prover.root(),
+                    },
+                    TranscriptEvent::Challenge {
+                        label: Some("test.challenge")
+                    },
+                ]
+            );
+            challenges.push(challenge);
+        }
+
+        // Mixing in the labels must change the challenges drawn from the transcript.
+        assert_ne!(challenges[0], challenges[1]);
+    }
+
+    #[test]
+    fn merkle_cpu_consolidated_openings() {
+        let hal = CpuHal::new(Poseidon2HashSuite::new_suite());
+        let hashfn = hal.get_hash_suite().hashfn.as_ref();
+        let rng = hal.get_hash_suite().rng.as_ref();
+        let (rows, cols, queries) = (1024, 4, 8);
+        let prover = init_prover(&hal, rows, cols, queries);
+        let opened = [3, 7, 3, 3, 7];
+
+        let prove = |version| {
+            let mut iop = WriteIOP::with_version(rng, version);
+            prover.commit(&mut iop);
+            for idx in opened {
+                prover.prove(&hal, &mut iop, idx);
+            }
+            iop.proof
+        };
+        let v1 = prove(ProofSystemVersion::V1);
+        let v2 = prove(ProofSystemVersion::V2);
+
+        // Each repeated opening is omitted.
+        let top_size = MerkleTreeParams::new(rows, cols, queries).top_size;
+        let opening_size = cols + (log2_ceil(rows) - log2_ceil(top_size)) * DIGEST_WORDS;
+        assert_eq!(v1.len() - v2.len(), 3 * opening_size);
+
+        let mut r_iop = ReadIOP::with_version(&v2, rng, ProofSystemVersion::V2);
+        let verifier = MerkleTreeVerifier::new(&mut r_iop, hashfn, rows, cols, queries);
+        for idx in opened {
+            let col = verifier.verify(&mut r_iop, hashfn, idx).unwrap();
+            for (c_idx, cell) in col.iter().enumerate() {
+                assert_eq!(
+                    *cell,
+                    BabyBearElem::from((u32::MAX / 2) - ((idx + c_idx * rows) as u32))
+                );
+            }
+        }
+        r_iop.verify_complete();
+    }
+
+    #[test]
+    fn distinct_queries() {
+        let hal = CpuHal::new(Poseidon2HashSuite::new_suite());
+        let rng = hal.get_hash_suite().rng.as_ref();
+        let bits = 4;
+
+        // Drawing as many queries as there are positions must yield every position once.
+        let mut w_iop = WriteIOP::<BabyBear>::with_version(rng, ProofSystemVersion::V3);
+        let mut r_iop = ReadIOP::<BabyBear>::with_version(&[], rng, ProofSystemVersion::V3);
+        let mut positions = Vec::new();
+        for _ in 0..1 << bits {
+            let pos = w_iop.random_query("test.query", bits).unwrap();
+            assert_eq!(r_iop.random_query("test.query", bits).unwrap(), pos);
+            positions.push(pos);
+        }
+        positions.sort();
+        assert_eq!(positions, (0..1 << bits).collect::<Vec<u32>>());
+
+        // Once every position has been drawn, both sides must fail rather than loop forever.
+        assert!(w_iop.random_query("test.query", bits).is_err());
+        assert_eq!(
+            r_iop.random_query("test.query", bits),
+            Err(VerificationError::QueryDomainExhausted { domain: 1 << bits })
+        );
+    }
 }
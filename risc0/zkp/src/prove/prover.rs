@@ -19,7 +19,11 @@ use risc0_core::{
 };
 
 use crate::{
-    core::poly::{poly_divide, poly_interpolate},
+    adapter::ProofSystemVersion,
+    core::{
+        poly::{poly_divide, poly_interpolate},
+        transcript,
+    },
     hal::{Buffer, CircuitHal, Hal},
     prove::{fri::fri_prove, poly_group::PolyGroup, write_iop::WriteIOP},
     taps::TapSet,
@@ -31,6 +35,7 @@ pub struct Prover<'a, H: Hal> {
     hal: &'a H,
     taps: &'a TapSet<'a>,
     iop: WriteIOP<H::Field>,
+    version: ProofSystemVersion,
     groups: Vec<Option<PolyGroup<H>>>,
     cycles: usize,
     po2: usize,
@@ -51,10 +56,16 @@ fn make_coeffs<H: Hal>(hal: &H, witness: &H::Buffer<H::Elem>, count: usize) -> H
 impl<'a, H: Hal> Prover<'a, H> {
     /// Creates a new prover.
     pub fn new(hal: &'a H, taps: &'a TapSet) -> Self {
+        Self::with_version(hal, taps, ProofSystemVersion::default())
+    }
+
+    /// Creates a new prover producing seals for the given [ProofSystemVersion].
+    pub fn with_version(hal: &'a H, taps: &'a TapSet, version: ProofSystemVersion) -> Self {
         Self {
             hal,
             taps,
-            iop: WriteIOP::new(hal.get_hash_suite().rng.as_ref()),
+            iop: WriteIOP::with_version(hal.get_hash_suite().rng.as_ref(), version),
+            version,
             groups: std::iter::repeat_with(|| None)
                 .take(taps.num_groups())
                 .collect(),
@@ -63,6 +74,13 @@ impl<'a, H: Hal> Prover<'a, H> {
         }
     }
 
+    /// The [ProofSystemVersion] of the seals produced by this prover.
+    ///
+    /// Callers must commit its [info](ProofSystemVersion::info) at the start of the protocol.
+    pub fn version(&self) -> ProofSystemVersion {
+        self.version
+    }
+
     /// Accesses the prover's IOP to commit or read random data.
     pub fn iop(&mut self) -> &mut WriteIOP<H::Field> {
         &mut self.iop
@@ -109,7 +127,11 @@ impl<'a, H: Hal> Prover<'a, H> {
     }
 
     /// Generates the proof and returns the seal.
-    pub fn finalize<C>(mut self, globals: &[&H::Buffer<H::Elem>], circuit_hal: &C) -> Vec<u32>
+    pub fn finalize<C>(
+        mut self,
+        globals: &[&H::Buffer<H::Elem>],
+        circuit_hal: &C,
+    ) -> Result<Vec<u32>>
     where
         C: CircuitHal<H>,
     {
@@ -117,7 +139,7 @@ impl<'a, H: Hal> Prover<'a, H> {
 
         // Set the poly mix value, which is used for constraint compression in the
         // DEEP-ALI protocol.
-        let poly_mix = self.iop.random_ext_elem();
+        let poly_mix = self.iop.random_ext_elem_labeled(transcript::POLY_MIX);
         let domain = self.cycles * INV_RATE;
         let ext_size = H::ExtElem::EXT_SIZE;
 
@@ -174,7 +196,7 @@ impl<'a, H: Hal> Prover<'a, H> {
         tracing::debug!("checkGroup: {}", check_group.merkle.root());
 
         // Now pick a value for Z, which is used as the DEEP-ALI query point.
-        let z = self.iop.random_ext_elem();
+        let z = self.iop.random_ext_elem_labeled(transcript::DEEP_POINT);
         // #ifdef CIRCUIT_DEBUG
         //   if (badZ != FpExt(0)) {
         //     Z = badZ;
@@ -253,12 +275,12 @@ impl<'a, H: Hal> Prover<'a, H> {
                 .get_hash_suite()
                 .hashfn
                 .hash_ext_elem_slice(coeff_u.as_slice());
-            self.iop.commit(&hash_u);
+            self.iop.commit_labeled(transcript::DEEP_COEFFS, &hash_u);
 
             // Set the mix value, which is used for FRI batching.
         });
 
-        let mix = self.iop.random_ext_elem();
+        let mix = self.iop.random_ext_elem_labeled(transcript::FRI_BATCH_MIX);
         tracing::debug!("Mix = {mix:?}");
 
         // Do the coefficient mixing
@@ -374,19 +396,25 @@ impl<'a, H: Hal> Prover<'a, H> {
                 pg.merkle.prove(self.hal, iop, idx);
             }
             check_group.merkle.prove(self.hal, iop, idx);
-        });
+        })?;
 
-        let proven_soundness_error =
-            super::soundness::proven::<H>(self.taps, final_poly_coeffs.size());
+        let proven_soundness_error = super::soundness::proven_with_version::<H>(
+            self.taps,
+            final_poly_coeffs.size(),
+            self.version,
+        );
         tracing::debug!("proven_soundness_error: {proven_soundness_error:?}");
 
-        let conjectured_security =
-            super::soundness::toy_model_security::<H>(self.taps, final_poly_coeffs.size());
+        let conjectured_security = super::soundness::toy_model_security_with_version::<H>(
+            self.taps,
+            final_poly_coeffs.size(),
+            self.version,
+        );
         tracing::debug!("conjectured_security: {conjectured_security:?}");
 
         // Return final proof
         let proof = self.iop.proof;
         tracing::debug!("Proof size = {}", proof.len());
-        proof
+        Ok(proof)
     }
 }
@@ -33,11 +33,16 @@
 //!
 //! Running the calculator results in a terminal printout for scenarios (1) and (3) in the list above.
 //! The calculator also includes code for scenarios (2) and (4).
+//!
+//! Each calculation has a `_with_version` variant, which accounts for the way the given
+//! [ProofSystemVersion] samples the FRI queries. When the queries are distinct, the chance that
+//! every query lands in the agreement set of a far codeword is that of drawing QUERIES positions
+//! without replacement, which is smaller than (1 - θ)^QUERIES, especially at small domains.
 
 use risc0_core::field::{baby_bear, ExtElem};
 
 use crate::{
-    adapter::{REGISTER_GROUP_ACCUM, REGISTER_GROUP_CODE, REGISTER_GROUP_DATA},
+    adapter::{ProofSystemVersion, REGISTER_GROUP_ACCUM, REGISTER_GROUP_CODE, REGISTER_GROUP_DATA},
     hal::Hal,
     taps::TapSet,
     FRI_FOLD, FRI_MIN_DEGREE, INV_RATE,
@@ -56,7 +61,18 @@ const ETA: f32 = 0.05;
 /// Compute the security level of the system based on the proven FRI
 /// list-decoding regime (up to 1-sqrt(rate)).
 pub fn proven<H: Hal>(taps: &TapSet, coeffs_size: usize) -> f32 {
-    let params = parameters::<H>(taps, coeffs_size);
+    proven_with_version::<H>(taps, coeffs_size, ProofSystemVersion::default())
+}
+
+/// Compute the security level of the system based on the proven FRI
+/// list-decoding regime (up to 1-sqrt(rate)), for seals produced under the
+/// given [ProofSystemVersion].
+pub fn proven_with_version<H: Hal>(
+    taps: &TapSet,
+    coeffs_size: usize,
+    version: ProofSystemVersion,
+) -> f32 {
+    let params = parameters::<H>(taps, coeffs_size, version);
     let e_proximity_gap = params.e_proximity_gap_proven();
 
     // α = (1 + 1/2m) * sqrt(ρ)
@@ -76,7 +92,18 @@ pub fn proven<H: Hal>(taps: &TapSet, coeffs_size: usize) -> f32 {
 /// Compute the security level of the system based on the FRI list-decoding
 /// conjecture (up to 1-rate).
 pub fn conjectured_strict<H: Hal>(taps: &TapSet, coeffs_size: usize) -> f32 {
-    let params = parameters::<H>(taps, coeffs_size);
+    conjectured_strict_with_version::<H>(taps, coeffs_size, ProofSystemVersion::default())
+}
+
+/// Compute the security level of the system based on the FRI list-decoding
+/// conjecture (up to 1-rate), for seals produced under the given
+/// [ProofSystemVersion].
+pub fn conjectured_strict_with_version<H: Hal>(
+    taps: &TapSet,
+    coeffs_size: usize,
+    version: ProofSystemVersion,
+) -> f32 {
+    let params = parameters::<H>(taps, coeffs_size, version);
     let theta = 1.0 - RHO - ETA;
     let e_proximity_gap = params.e_proximity_gap_conjectured();
     let l_plus = {
@@ -95,14 +122,24 @@ pub fn conjectured_strict<H: Hal>(taps: &TapSet, coeffs_size: usize) -> f32 {
 /// 2. The security of FRI matches its known upper bound (rather than the proven
 ///    lower bound).
 pub fn toy_model_security<H: Hal>(taps: &TapSet, coeffs_size: usize) -> f32 {
-    let params = parameters::<H>(taps, coeffs_size);
+    toy_model_security_with_version::<H>(taps, coeffs_size, ProofSystemVersion::default())
+}
+
+/// Compute the system security following the Toy Model conjecture of ethSTARK,
+/// for seals produced under the given [ProofSystemVersion].
+pub fn toy_model_security_with_version<H: Hal>(
+    taps: &TapSet,
+    coeffs_size: usize,
+    version: ProofSystemVersion,
+) -> f32 {
+    let params = parameters::<H>(taps, coeffs_size, version);
     let ext_size = H::ExtElem::EXT_SIZE as f32;
     let field_size = baby_bear::P as f32;
     let ext_field_size = field_size.powf(ext_size);
 
     let plonk_plookup_error = params.plonk_plookup_error();
     let constraints_error = 1f32 / ext_field_size;
-    let fri_error = RHO.powi(crate::QUERIES as i32);
+    let fri_error = params.e_fri_queries(1.0 - RHO);
 
     let sum = plonk_plookup_error + constraints_error + fri_error;
     sum.log2().abs()
@@ -117,11 +154,6 @@ fn soundness_error(params: &Params, theta: f32, e_proximity_gap: f32, l_plus: f3
     sum.log2().abs()
 }
 
-/// (1 - θ)^QUERIES
-fn e_fri_queries(theta: f32) -> f32 {
-    (1.0 - theta).powi(crate::QUERIES as i32)
-}
-
 /// Compute the number of folding rounds
 fn num_folding_rounds(coeffs_size: usize, ext_size: usize) -> usize {
     let mut num_folding_rounds = 0;
@@ -155,11 +187,13 @@ struct Params {
     lde_domain_size: f32,
     /// Number of folding rounds in FRI
     num_folding_rounds: usize,
+    /// Whether the FRI queries are sampled without replacement
+    distinct_queries: bool,
 }
 
 /// Compute circuit parameters given a tapset, number of trace rows and all the
 /// global constants.
-fn parameters<H: Hal>(taps: &TapSet, coeffs_size: usize) -> Params {
+fn parameters<H: Hal>(taps: &TapSet, coeffs_size: usize, version: ProofSystemVersion) -> Params {
     // Circuit-specific info
     let w_accum = taps.group_size(REGISTER_GROUP_ACCUM) as f32;
 
@@ -199,6 +233,7 @@ fn parameters<H: Hal>(taps: &TapSet, coeffs_size: usize) -> Params {
         trace_domain_size,
         lde_domain_size,
         num_folding_rounds,
+        distinct_queries: version.distinct_queries(),
     }
 }
 
@@ -264,11 +299,24 @@ impl Params {
     fn e_fri(&self, theta: f32, e_proximity_gap: f32) -> f32 {
         let e_fri_constant = self.e_fri_constant(e_proximity_gap);
 
-        let e_fri_queries = e_fri_queries(theta);
+        let e_fri_queries = self.e_fri_queries(theta);
 
         e_fri_constant + e_fri_queries
     }
 
+    /// (1 - θ)^QUERIES, or when the queries are distinct,
+    /// ∏_{i < QUERIES} ((1 - θ)|D| - i) / (|D| - i)
+    fn e_fri_queries(&self, theta: f32) -> f32 {
+        if !self.distinct_queries {
+            return (1.0 - theta).powi(crate::QUERIES as i32);
+        }
+        let agreement = (1.0 - theta) * self.lde_domain_size;
+        (0..crate::QUERIES)
+            .map(|i| i as f32)
+            .map(|i| ((agreement - i) / (self.lde_domain_size - i)).max(0.0))
+            .product()
+    }
+
     fn e_ali(&self, l_plus: f32) -> f32 {
         l_plus * self.n_trace_polys / self.ext_field_size
     }
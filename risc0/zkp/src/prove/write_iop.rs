@@ -12,26 +12,81 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use alloc::vec::Vec;
+use alloc::{collections::BTreeSet, vec::Vec};
 
+use anyhow::{ensure, Result};
 use risc0_core::field::{Elem, Field};
 
-use crate::core::{
-    digest::Digest,
-    hash::{Rng, RngFactory},
+use crate::{
+    adapter::ProofSystemVersion,
+    core::{
+        digest::Digest,
+        hash::{Rng, RngFactory},
+        transcript::{Transcript, TranscriptEvent, TranscriptMode},
+    },
 };
 
 pub struct WriteIOP<F: Field> {
     pub proof: Vec<u32>,
     pub rng: Box<dyn Rng<F>>,
+    transcript: Transcript,
+    // Merkle openings (root, row) already written, when openings are consolidated.
+    opened: Option<BTreeSet<(Digest, usize)>>,
+    // Query positions already drawn, when queries are distinct.
+    queried: Option<BTreeSet<u32>>,
 }
 
 impl<F: Field> WriteIOP<F> {
     /// Create a new empty proof
     pub fn new(rng: &dyn RngFactory<F>) -> Self {
+        Self::with_mode(rng, TranscriptMode::default())
+    }
+
+    /// Create a new empty proof using the given [TranscriptMode].
+    pub fn with_mode(rng: &dyn RngFactory<F>, mode: TranscriptMode) -> Self {
         Self {
             proof: Vec::new(),
             rng: rng.new_rng(),
+            transcript: Transcript::new(mode),
+            opened: None,
+            queried: None,
+        }
+    }
+
+    /// Create a new empty proof following the given [ProofSystemVersion].
+    pub fn with_version(rng: &dyn RngFactory<F>, version: ProofSystemVersion) -> Self {
+        let mut iop = Self::with_mode(rng, version.transcript_mode());
+        if version.consolidates_openings() {
+            iop.opened = Some(BTreeSet::new());
+        }
+        if version.distinct_queries() {
+            iop.queried = Some(BTreeSet::new());
+        }
+        iop
+    }
+
+    /// The [TranscriptMode] used by this IOP.
+    pub fn mode(&self) -> TranscriptMode {
+        self.transcript.mode
+    }
+
+    /// Start recording the operations on this IOP, for diagnostics.
+    pub fn record_transcript(&mut self) {
+        self.transcript.record();
+    }
+
+    /// The operations recorded since [WriteIOP::record_transcript] was called.
+    pub fn transcript(&self) -> &[TranscriptEvent] {
+        self.transcript.events()
+    }
+
+    /// Returns true if the opening of the given row of the Merkle tree with the given root must
+    /// be written. When openings are consolidated, a row is only written the first time it is
+    /// opened.
+    pub(crate) fn open_merkle_row(&mut self, root: &Digest, idx: usize) -> bool {
+        match &mut self.opened {
+            Some(opened) => opened.insert((*root, idx)),
+            None => true,
         }
     }
 
@@ -56,21 +111,81 @@ impl<F: Field> WriteIOP<F> {
     /// Called by the prover to commit to some hash (usually data written
     /// earlier or a Merkle root).
     pub fn commit(&mut self, message: &Digest) {
+        self.mix_label(TranscriptEvent::Commit {
+            label: None,
+            digest: *message,
+        });
+        self.rng.mix(message);
+    }
+
+    /// Commit to some hash under the given label. See [WriteIOP::commit].
+    pub fn commit_labeled(&mut self, label: &'static str, message: &Digest) {
+        self.mix_label(TranscriptEvent::Commit {
+            label: Some(label),
+            digest: *message,
+        });
         self.rng.mix(message);
     }
 
     /// Get a cryptographically uniform u32
     pub fn random_bits(&mut self, bits: usize) -> u32 {
+        self.mix_label(TranscriptEvent::Challenge { label: None });
         self.rng.random_bits(bits)
     }
 
+    /// Get a cryptographically uniform u32 under the given label.
+    pub fn random_bits_labeled(&mut self, label: &'static str, bits: usize) -> u32 {
+        self.mix_label(TranscriptEvent::Challenge { label: Some(label) });
+        self.rng.random_bits(bits)
+    }
+
+    /// Get a query position of the given number of bits. When queries are distinct, positions
+    /// already drawn are rejected and a new one is drawn in their place, so an error is returned
+    /// once every position has been drawn.
+    pub(crate) fn random_query(&mut self, label: &'static str, bits: usize) -> Result<u32> {
+        if let Some(queried) = &self.queried {
+            ensure!(
+                queried.len() < 1 << bits,
+                "cannot draw more distinct queries than the {} positions of the domain",
+                1u64 << bits
+            );
+        }
+        loop {
+            let pos = self.random_bits_labeled(label, bits);
+            match &mut self.queried {
+                Some(queried) if !queried.insert(pos) => continue,
+                _ => return Ok(pos),
+            }
+        }
+    }
+
     /// Get a cryptographically uniform field element
     pub fn random_elem(&mut self) -> F::Elem {
+        self.mix_label(TranscriptEvent::Challenge { label: None });
+        self.rng.random_elem()
+    }
+
+    /// Get a cryptographically uniform field element under the given label.
+    pub fn random_elem_labeled(&mut self, label: &'static str) -> F::Elem {
+        self.mix_label(TranscriptEvent::Challenge { label: Some(label) });
         self.rng.random_elem()
     }
 
     /// Get a cryptographically uniform extension field element
     pub fn random_ext_elem(&mut self) -> F::ExtElem {
+        self.mix_label(TranscriptEvent::Challenge { label: None });
+        self.rng.random_ext_elem()
+    }
+
+    /// Get a cryptographically uniform extension field element under the given label.
+    pub fn random_ext_elem_labeled(&mut self, label: &'static str) -> F::ExtElem {
+        self.mix_label(TranscriptEvent::Challenge { label: Some(label) });
         self.rng.random_ext_elem()
     }
+
+    fn mix_label(&mut self, event: TranscriptEvent) {
+        if let Some(label_digest) = self.transcript.push(event) {
+            self.rng.mix(&label_digest);
+        }
+    }
 }
@@ -0,0 +1,344 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Analysis of the work done by the verifier, for planning on-chain and embedded verification.
+//!
+//! [verifier_cost] counts the operations performed by [verify](super::verify) on a seal of a given
+//! circuit and size, broken down by phase of the protocol. The counts follow the verifier step by
+//! step, so a port of the verifier to another environment (e.g. a Solidity contract) can multiply
+//! them by the cost of each primitive in that environment to estimate its total cost.
+//!
+//! Hash invocations, Fiat-Shamir operations and seal bytes match those of the verifier for seals
+//! produced under [ProofSystemVersion::V1]. Later versions write a Merkle row opened by several
+//! queries only once, so the hash and seal counts are upper bounds for them, and
+//! [ProofSystemVersion::V3] redraws query positions already drawn, so its challenge count is a
+//! lower bound. Field operations are counted from the code of the verifier. The evaluation of the
+//! constraint polynomial is counted as a whole, as its cost is specific to the circuit.
+
+use alloc::vec::Vec;
+use core::ops::{Add, AddAssign};
+
+use risc0_core::field::{ExtElem, Field};
+
+use crate::{
+    adapter::{
+        CircuitCoreDef, ProofSystemVersion, REGISTER_GROUP_ACCUM, REGISTER_GROUP_CODE,
+        REGISTER_GROUP_DATA,
+    },
+    core::{digest::DIGEST_WORDS, log2_ceil, transcript::TranscriptMode},
+    merkle::MerkleTreeParams,
+    FRI_FOLD, FRI_MIN_DEGREE, INV_RATE, QUERIES,
+};
+
+const WORD_BYTES: usize = core::mem::size_of::<u32>();
+
+/// Counts of the operations performed by the verifier.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct OpCounts {
+    /// Invocations of the hash function on a pair of digests, e.g. a Merkle tree node.
+    pub hash_pairs: usize,
+
+    /// Invocations of the hash function on a slice of field elements, e.g. a Merkle tree leaf.
+    pub hash_slices: usize,
+
+    /// Total number of words hashed by [hash_slices](Self::hash_slices).
+    pub hashed_words: usize,
+
+    /// Digests committed to the Fiat-Shamir transcript.
+    pub commits: usize,
+
+    /// Challenges drawn from the Fiat-Shamir transcript.
+    pub challenges: usize,
+
+    /// Hashes of the labels of commitments and challenges, under a labeled transcript.
+    pub label_hashes: usize,
+
+    /// Digests mixed into the Fiat-Shamir state, i.e. the commitments and the label hashes.
+    pub transcript_mixes: usize,
+
+    /// Additions and subtractions in the extension field.
+    pub ext_adds: usize,
+
+    /// Multiplications in the extension field, including by elements of the base field.
+    pub ext_muls: usize,
+
+    /// Inversions in the extension field.
+    pub ext_invs: usize,
+
+    /// Exponentiations in the base or extension field.
+    pub exps: usize,
+
+    /// Evaluations of the constraint polynomial of the circuit.
+    pub constraint_evals: usize,
+
+    /// Bytes of the seal read.
+    pub seal_bytes: usize,
+}
+
+impl Add for OpCounts {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        self += rhs;
+        self
+    }
+}
+
+impl AddAssign for OpCounts {
+    fn add_assign(&mut self, rhs: Self) {
+        self.hash_pairs += rhs.hash_pairs;
+        self.hash_slices += rhs.hash_slices;
+        self.hashed_words += rhs.hashed_words;
+        self.commits += rhs.commits;
+        self.challenges += rhs.challenges;
+        self.label_hashes += rhs.label_hashes;
+        self.transcript_mixes += rhs.transcript_mixes;
+        self.ext_adds += rhs.ext_adds;
+        self.ext_muls += rhs.ext_muls;
+        self.ext_invs += rhs.ext_invs;
+        self.exps += rhs.exps;
+        self.constraint_evals += rhs.constraint_evals;
+        self.seal_bytes += rhs.seal_bytes;
+    }
+}
+
+impl OpCounts {
+    /// Counts for `n` repetitions of these operations.
+    pub fn times(self, n: usize) -> Self {
+        Self {
+            hash_pairs: self.hash_pairs * n,
+            hash_slices: self.hash_slices * n,
+            hashed_words: self.hashed_words * n,
+            commits: self.commits * n,
+            challenges: self.challenges * n,
+            label_hashes: self.label_hashes * n,
+            transcript_mixes: self.transcript_mixes * n,
+            ext_adds: self.ext_adds * n,
+            ext_muls: self.ext_muls * n,
+            ext_invs: self.ext_invs * n,
+            exps: self.exps * n,
+            constraint_evals: self.constraint_evals * n,
+            seal_bytes: self.seal_bytes * n,
+        }
+    }
+
+    fn hash_slice(words: usize) -> Self {
+        Self {
+            hash_slices: 1,
+            hashed_words: words,
+            ..Default::default()
+        }
+    }
+
+    fn read(words: usize) -> Self {
+        Self {
+            seal_bytes: words * WORD_BYTES,
+            ..Default::default()
+        }
+    }
+
+    fn ext_ops(adds: usize, muls: usize) -> Self {
+        Self {
+            ext_adds: adds,
+            ext_muls: muls,
+            ..Default::default()
+        }
+    }
+
+    fn commit() -> Self {
+        Self {
+            commits: 1,
+            ..Default::default()
+        }
+    }
+
+    fn challenges(n: usize) -> Self {
+        Self {
+            challenges: n,
+            ..Default::default()
+        }
+    }
+
+    fn exps(n: usize) -> Self {
+        Self {
+            exps: n,
+            ..Default::default()
+        }
+    }
+
+    // Evaluation of a polynomial with `n` coefficients using `Verifier::poly_eval`.
+    fn poly_eval(n: usize) -> Self {
+        Self::ext_ops(n, 2 * n)
+    }
+
+    // Adds the hashing of labels and the mixing into the Fiat-Shamir state done for the
+    // commitments and challenges of a phase, following `ReadIOP`.
+    fn transcript(mut self, mode: TranscriptMode) -> Self {
+        if mode == TranscriptMode::Labeled {
+            self.label_hashes = self.commits + self.challenges;
+        }
+        self.transcript_mixes = self.commits + self.label_hashes;
+        self
+    }
+}
+
+/// The operations performed by the verifier, broken down by phase of the protocol.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct VerifierCost {
+    /// Committing to the proof system and circuit, and reading the globals.
+    pub setup: OpCounts,
+
+    /// Committing to the code, data, accum and check Merkle trees, and checking a branch of each
+    /// for every query.
+    pub merkle: OpCounts,
+
+    /// Checking the DEEP-ALI constraint at the DEEP point, and combining the opened rows of the
+    /// trees into the FRI polynomial for every query.
+    pub deep: OpCounts,
+
+    /// Each FRI folding round, including its Merkle tree.
+    pub fri_rounds: Vec<OpCounts>,
+
+    /// Reading the final FRI polynomial and evaluating it for every query.
+    pub final_poly: OpCounts,
+}
+
+impl VerifierCost {
+    /// The total of all phases.
+    pub fn total(&self) -> OpCounts {
+        self.fri_rounds.iter().fold(
+            self.setup + self.merkle + self.deep + self.final_poly,
+            |acc, round| acc + *round,
+        )
+    }
+}
+
+/// Returns the operations performed to verify a seal of the given circuit with `2^po2` cycles,
+/// produced under the default [ProofSystemVersion].
+pub fn verifier_cost<F: Field, C: CircuitCoreDef<F>>(circuit: &C, po2: usize) -> VerifierCost {
+    verifier_cost_with_version(circuit, po2, ProofSystemVersion::default())
+}
+
+/// Returns the operations performed to verify a seal of the given circuit with `2^po2` cycles,
+/// produced under the given [ProofSystemVersion].
+pub fn verifier_cost_with_version<F: Field, C: CircuitCoreDef<F>>(
+    circuit: &C,
+    po2: usize,
+    version: ProofSystemVersion,
+) -> VerifierCost {
+    let mode = version.transcript_mode();
+    let ext_size = F::ExtElem::EXT_SIZE;
+    let check_size = INV_RATE * ext_size;
+    let taps = circuit.get_taps();
+    let size = 1 << po2;
+    let domain = INV_RATE * size;
+
+    let setup = (OpCounts::hash_slice(16) + OpCounts::commit()).times(2)
+        + OpCounts::read(C::OUTPUT_SIZE + 1)
+        + OpCounts::hash_slice(C::OUTPUT_SIZE + 1)
+        + OpCounts::commit();
+
+    let merkle = [
+        taps.group_size(REGISTER_GROUP_CODE),
+        taps.group_size(REGISTER_GROUP_DATA),
+        taps.group_size(REGISTER_GROUP_ACCUM),
+        check_size,
+    ]
+    .into_iter()
+    .fold(OpCounts::challenges(C::MIX_SIZE + 1), |acc, cols| {
+        acc + merkle_cost(domain, cols)
+    });
+
+    let num_taps = taps.tap_size();
+    let coeffs = num_taps + check_size;
+    let mut deep = OpCounts::challenges(2)
+        + OpCounts::read(coeffs * ext_size)
+        + OpCounts::hash_slice(coeffs * ext_size)
+        + OpCounts::commit();
+    // Evaluating the taps at the DEEP point.
+    for reg in taps.regs() {
+        deep += (OpCounts::exps(1) + OpCounts::ext_ops(0, 1) + OpCounts::poly_eval(reg.size()))
+            .times(reg.size());
+    }
+    // Evaluating the constraints, and reconstructing and checking the check polynomial.
+    deep.constraint_evals += 1;
+    deep += OpCounts::ext_ops(check_size, 2 * check_size) + OpCounts::exps(check_size);
+    deep += OpCounts::ext_ops(1, 2) + OpCounts::exps(1);
+    // Mixing the coefficients into the combos, and caching the powers of the mix for the queries.
+    let mix_pows = taps.reg_count() + check_size;
+    deep += OpCounts::ext_ops(coeffs, coeffs + mix_pows) + OpCounts::ext_ops(0, mix_pows);
+    // Combining the opened rows into the FRI polynomial, for every query.
+    let mut query = OpCounts::exps(1) + OpCounts::ext_ops(mix_pows, mix_pows);
+    for combo in taps.combos() {
+        query += OpCounts::poly_eval(combo.size()) + OpCounts::ext_ops(1, 0);
+        query += (OpCounts::exps(1) + OpCounts::ext_ops(1, 2)).times(combo.size());
+        query += OpCounts::ext_ops(1, 1);
+        query.ext_invs += 1;
+    }
+    query += OpCounts::exps(1) + OpCounts::ext_ops(3, 1);
+    query.ext_invs += 1;
+    deep += query.times(QUERIES) + OpCounts::challenges(QUERIES);
+
+    let mut fri_rounds = Vec::new();
+    let mut degree = size;
+    let mut round_domain = domain;
+    while degree > FRI_MIN_DEGREE {
+        round_domain /= FRI_FOLD;
+        degree /= FRI_FOLD;
+        let mut round = merkle_cost(round_domain, FRI_FOLD * ext_size) + OpCounts::challenges(1);
+        // Interpolating the opened coset, and evaluating it at the folding point.
+        let log_fold = log2_ceil(FRI_FOLD);
+        let interpolate =
+            OpCounts::ext_ops(FRI_FOLD * log_fold, FRI_FOLD / 2 * log_fold + FRI_FOLD);
+        let eval = OpCounts::exps(1) + OpCounts::poly_eval(FRI_FOLD) + OpCounts::ext_ops(0, 1);
+        round += (interpolate + eval).times(QUERIES);
+        fri_rounds.push(round.transcript(mode));
+    }
+
+    let final_coeffs = ext_size * degree;
+    let final_poly = OpCounts::read(final_coeffs)
+        + OpCounts::hash_slice(final_coeffs)
+        + OpCounts::commit()
+        + (OpCounts::exps(1) + OpCounts::poly_eval(degree)).times(QUERIES);
+
+    VerifierCost {
+        setup: setup.transcript(mode),
+        merkle: merkle.transcript(mode),
+        deep: deep.transcript(mode),
+        fri_rounds,
+        final_poly: final_poly.transcript(mode),
+    }
+}
+
+// Committing to a Merkle tree and checking a branch for every query, following
+// `MerkleTreeVerifier`.
+fn merkle_cost(rows: usize, cols: usize) -> OpCounts {
+    let params = MerkleTreeParams::new(rows, cols, QUERIES);
+    let branch_len = log2_ceil(rows) - log2_ceil(params.top_size);
+    let commit = OpCounts::read(params.top_size * DIGEST_WORDS)
+        + OpCounts {
+            hash_pairs: params.top_size - 1,
+            ..Default::default()
+        }
+        + OpCounts::commit();
+    let branch = OpCounts::read(cols + branch_len * DIGEST_WORDS)
+        + OpCounts::hash_slice(cols)
+        + OpCounts {
+            hash_pairs: branch_len,
+            ..Default::default()
+        };
+    commit + branch.times(QUERIES)
+}
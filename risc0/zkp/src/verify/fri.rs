@@ -23,6 +23,7 @@ use crate::{
         hash::HashFn,
         log2_ceil,
         ntt::{bit_reverse, interpolate_ntt},
+        transcript,
     },
     verify::{merkle::MerkleTreeVerifier, read_iop::ReadIOP, VerificationError},
     FRI_FOLD, FRI_FOLD_PO2, FRI_MIN_DEGREE, INV_RATE, QUERIES,
@@ -49,7 +50,7 @@ impl<'a, F: Field> VerifyRoundInfo<'a, F> {
                 FRI_FOLD * F::ExtElem::EXT_SIZE,
                 QUERIES,
             ),
-            mix: iop.random_ext_elem(),
+            mix: iop.random_ext_elem_labeled(transcript::FRI_FOLD_MIX),
         }
     }
 }
@@ -130,13 +131,13 @@ where
         // Grab the final coeffs + commit
         let final_coeffs = iop.read_field_elem_slice(F::ExtElem::EXT_SIZE * degree);
         let final_digest = hashfn.hash_elem_slice(final_coeffs);
-        iop.commit(&final_digest);
+        iop.commit_labeled(transcript::FRI_FINAL_COEFFS, &final_digest);
         // Get the generator for the final polynomial evaluations
         let gen = <F::Elem as RootsOfUnity>::ROU_FWD[log2_ceil(domain)];
         // Do queries
         let mut poly_buf: Vec<F::ExtElem> = Vec::with_capacity(degree);
         for _ in 0..QUERIES {
-            let mut pos = iop.random_bits(log2_ceil(orig_domain)) as usize;
+            let mut pos = iop.random_query(transcript::FRI_QUERY, log2_ceil(orig_domain))? as usize;
             // Do the 'inner' verification for this index
             let mut goal = inner(iop, pos)?;
             // Verify the per-round proofs
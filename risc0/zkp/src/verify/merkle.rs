@@ -14,10 +14,10 @@
 
 use alloc::{boxed::Box, vec::Vec};
 
-use risc0_core::field::Field;
+use risc0_core::field::{Elem, Field};
 
 use crate::{
-    core::{digest::Digest, hash::HashFn},
+    core::{digest::Digest, hash::HashFn, transcript},
     merkle::MerkleTreeParams,
     verify::{read_iop::ReadIOP, VerificationError},
 };
@@ -122,7 +122,7 @@ impl<'a> MerkleTreeVerifier<'a> {
 
         // Commit to root (index 1).
         let verifier = MerkleTreeVerifier { params, top, rest };
-        iop.commit(verifier.root());
+        iop.commit_labeled(transcript::MERKLE_ROOT, verifier.root());
         verifier
     }
 
@@ -148,6 +148,11 @@ impl<'a> MerkleTreeVerifier<'a> {
                 rows: self.params.row_size,
             });
         }
+        let row = idx;
+        if let Some(out) = iop.opened_merkle_row(self.root(), row) {
+            // This row was already opened and verified earlier in the proof.
+            return Ok(F::Elem::from_u32_slice(out));
+        }
         // Initialize a vector to hold field elements.
         let out: &[F::Elem] = iop.read_field_elem_slice(self.params.col_size);
         // Get the hash at the leaf of the tree by hashing these field elements.
@@ -179,6 +184,7 @@ impl<'a> MerkleTreeVerifier<'a> {
             &self.rest[self.params.idx_to_rest(idx)]
         };
         if *present_hash == *cur {
+            iop.record_merkle_row(self.root(), row, F::Elem::as_u32_slice(out));
             Ok(out)
         } else {
             Err(VerificationError::InvalidProof)
@@ -14,6 +14,7 @@
 
 //! Cryptographic algorithms for verifying a ZK proof of compute
 
+pub mod cost;
 mod fri;
 mod merkle;
 mod read_iop;
@@ -27,10 +28,10 @@ use risc0_core::field::{Elem, ExtElem, Field, RootsOfUnity};
 
 use crate::{
     adapter::{
-        CircuitCoreDef, ProtocolInfo, PROOF_SYSTEM_INFO, REGISTER_GROUP_ACCUM, REGISTER_GROUP_CODE,
-        REGISTER_GROUP_DATA,
+        CircuitCoreDef, ProofSystemVersion, ProtocolInfo, REGISTER_GROUP_ACCUM,
+        REGISTER_GROUP_CODE, REGISTER_GROUP_DATA,
     },
-    core::{digest::Digest, hash::HashSuite, log2_ceil},
+    core::{digest::Digest, hash::HashSuite, log2_ceil, transcript},
     taps::TapSet,
     INV_RATE, MAX_CYCLES_PO2, QUERIES,
 };
@@ -71,6 +72,9 @@ pub enum VerificationError {
     UnresolvedAssumption {
         digest: Digest,
     },
+    QueryDomainExhausted {
+        domain: usize,
+    },
 }
 
 impl fmt::Debug for VerificationError {
@@ -115,6 +119,12 @@ impl fmt::Display for VerificationError {
             VerificationError::UnresolvedAssumption { digest } => {
                 write!(f, "receipt contains an unresolved assumption: {digest}")
             }
+            VerificationError::QueryDomainExhausted { domain } => {
+                write!(
+                    f,
+                    "cannot draw more distinct queries than the {domain} positions of the domain"
+                )
+            }
         }
     }
 }
@@ -144,6 +154,7 @@ where
     out: Option<&'a [F::Elem]>,
     mix: Vec<F::Elem>,
     tap_cache: RefCell<Option<TapCache<F>>>,
+    version: ProofSystemVersion,
 }
 
 impl<'a, F: Field, C> VerifyParams<F> for Verifier<'a, F, C> {}
@@ -153,7 +164,7 @@ where
     F: Field,
     C: CircuitCoreDef<F>,
 {
-    fn new(circuit: &'a C, suite: &'a HashSuite<F>) -> Self {
+    fn new(circuit: &'a C, suite: &'a HashSuite<F>, version: ProofSystemVersion) -> Self {
         Self {
             circuit,
             suite,
@@ -162,6 +173,7 @@ where
             out: None,
             mix: Vec::new(),
             tap_cache: RefCell::new(None),
+            version,
         }
     }
 
@@ -257,12 +269,18 @@ where
         let hashfn = self.suite.hashfn.as_ref();
 
         // Make IOP
-        let mut iop = ReadIOP::new(seal, self.suite.rng.as_ref());
+        let mut iop = ReadIOP::with_version(seal, self.suite.rng.as_ref(), self.version);
 
         // At the start of the protocol, seed the Fiat-Shamir transcript with context information
         // about the proof system and circuit.
-        iop.commit(&hashfn.hash_elem_slice(&PROOF_SYSTEM_INFO.encode()));
-        iop.commit(&hashfn.hash_elem_slice(&C::CIRCUIT_INFO.encode()));
+        iop.commit_labeled(
+            transcript::PROOF_SYSTEM_INFO,
+            &hashfn.hash_elem_slice(&self.version.info().encode()),
+        );
+        iop.commit_labeled(
+            transcript::CIRCUIT_INFO,
+            &hashfn.hash_elem_slice(&C::CIRCUIT_INFO.encode()),
+        );
 
         // Read any execution state
         self.execute(&mut iop);
@@ -299,7 +317,9 @@ where
         #[cfg(not(target_os = "zkvm"))]
         tracing::debug!("accumulate");
         // Fill in accum mix
-        self.mix = (0..C::MIX_SIZE).map(|_| iop.random_elem()).collect();
+        self.mix = (0..C::MIX_SIZE)
+            .map(|_| iop.random_elem_labeled(transcript::ACCUM_MIX))
+            .collect();
 
         // Get merkle root for the accum merkle tree.
         // The accum merkle tree contains the accumulations for two permutation check
@@ -317,7 +337,7 @@ where
 
         // Get a pseudorandom value with which to mix the constraint polynomials.
         // See DEEP-ALI protocol from DEEP-FRI paper for details on constraint mixing.
-        let poly_mix = iop.random_ext_elem();
+        let poly_mix = iop.random_ext_elem_labeled(transcript::POLY_MIX);
 
         #[cfg(not(target_os = "zkvm"))]
         tracing::debug!("check_merkle");
@@ -327,7 +347,7 @@ where
 
         // Get a pseudorandom DEEP query point
         // See DEEP-ALI protocol from DEEP-FRI paper for details on DEEP query.
-        let z = iop.random_ext_elem();
+        let z = iop.random_ext_elem_labeled(transcript::DEEP_POINT);
         // tracing::debug!("Z = {z:?}");
         let back_one = F::Elem::ROU_REV[self.po2 as usize];
 
@@ -335,7 +355,7 @@ where
         let num_taps = taps.tap_size();
         let coeff_u = iop.read_field_elem_slice(num_taps + Self::CHECK_SIZE);
         let hash_u = self.suite.hashfn.hash_ext_elem_slice(coeff_u);
-        iop.commit(&hash_u);
+        iop.commit_labeled(transcript::DEEP_COEFFS, &hash_u);
 
         // Now, convert U polynomials from coefficient form to evaluation form
         let mut cur_pos = 0;
@@ -396,7 +416,7 @@ where
         }
 
         // Set the mix value, pseudorandom value used for FRI batching
-        let mix = iop.random_ext_elem();
+        let mix = iop.random_ext_elem_labeled(transcript::FRI_BATCH_MIX);
         // tracing::debug!("mix = {mix:?}");
 
         // Make the mixed U polynomials.
@@ -463,7 +483,10 @@ where
     /// verifier by the prover, and therefore should be committed at the start of verification.
     fn execute(&mut self, iop: &mut ReadIOP<'a, F>) {
         let slice = iop.read_field_elem_slice(C::OUTPUT_SIZE + 1);
-        iop.commit(&self.suite.hashfn.hash_elem_slice(slice));
+        iop.commit_labeled(
+            transcript::GLOBALS,
+            &self.suite.hashfn.hash_elem_slice(slice),
+        );
 
         // Extract the out buffer and po2 from slice while checking sizes.
         let (out, &[po2_elem]) = slice.split_at(C::OUTPUT_SIZE) else {
@@ -502,5 +525,28 @@ where
     C: CircuitCoreDef<F>,
     CheckCode: Fn(u32, &Digest) -> Result<(), VerificationError>,
 {
-    Verifier::<F, C>::new(circuit, suite).verify(seal, check_code)
+    verify_with_version(
+        circuit,
+        suite,
+        ProofSystemVersion::default(),
+        seal,
+        check_code,
+    )
+}
+
+/// Verify a seal is valid for the given circuit, and code checking function, where the seal was
+/// produced under the given [ProofSystemVersion].
+pub fn verify_with_version<F, C, CheckCode>(
+    circuit: &C,
+    suite: &HashSuite<F>,
+    version: ProofSystemVersion,
+    seal: &[u32],
+    check_code: CheckCode,
+) -> Result<(), VerificationError>
+where
+    F: Field,
+    C: CircuitCoreDef<F>,
+    CheckCode: Fn(u32, &Digest) -> Result<(), VerificationError>,
+{
+    Verifier::<F, C>::new(circuit, suite, version).verify(seal, check_code)
 }
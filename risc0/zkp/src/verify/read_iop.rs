@@ -12,25 +12,91 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use alloc::boxed::Box;
+use alloc::{
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+};
 
 use risc0_core::field::{Elem, Field};
 
-use crate::core::{
-    digest::Digest,
-    hash::{Rng, RngFactory},
+use crate::{
+    adapter::ProofSystemVersion,
+    core::{
+        digest::Digest,
+        hash::{Rng, RngFactory},
+        transcript::{Transcript, TranscriptEvent, TranscriptMode},
+    },
+    verify::VerificationError,
 };
 
 pub struct ReadIOP<'a, F: Field> {
     proof: &'a [u32],
     rng: Box<dyn Rng<F>>,
+    transcript: Transcript,
+    // Verified Merkle rows keyed by (root, row), when openings are consolidated.
+    opened: Option<BTreeMap<(Digest, usize), &'a [u32]>>,
+    // Query positions already drawn, when queries are distinct.
+    queried: Option<BTreeSet<u32>>,
 }
 
 impl<'a, F: Field> ReadIOP<'a, F> {
     pub fn new(proof: &'a [u32], rng: &dyn RngFactory<F>) -> Self {
+        Self::with_mode(proof, rng, TranscriptMode::default())
+    }
+
+    /// Create a new IOP reading the given proof, using the given [TranscriptMode].
+    pub fn with_mode(proof: &'a [u32], rng: &dyn RngFactory<F>, mode: TranscriptMode) -> Self {
         ReadIOP {
             proof,
             rng: rng.new_rng(),
+            transcript: Transcript::new(mode),
+            opened: None,
+            queried: None,
+        }
+    }
+
+    /// Create a new IOP reading the given proof, following the given [ProofSystemVersion].
+    pub fn with_version(
+        proof: &'a [u32],
+        rng: &dyn RngFactory<F>,
+        version: ProofSystemVersion,
+    ) -> Self {
+        let mut iop = Self::with_mode(proof, rng, version.transcript_mode());
+        if version.consolidates_openings() {
+            iop.opened = Some(BTreeMap::new());
+        }
+        if version.distinct_queries() {
+            iop.queried = Some(BTreeSet::new());
+        }
+        iop
+    }
+
+    /// The [TranscriptMode] used by this IOP.
+    pub fn mode(&self) -> TranscriptMode {
+        self.transcript.mode
+    }
+
+    /// Start recording the operations on this IOP, for diagnostics.
+    pub fn record_transcript(&mut self) {
+        self.transcript.record();
+    }
+
+    /// The operations recorded since [ReadIOP::record_transcript] was called.
+    pub fn transcript(&self) -> &[TranscriptEvent] {
+        self.transcript.events()
+    }
+
+    /// Returns the previously verified row of the Merkle tree with the given root, if openings
+    /// are consolidated and that row has already been opened.
+    pub(crate) fn opened_merkle_row(&self, root: &Digest, idx: usize) -> Option<&'a [u32]> {
+        self.opened.as_ref()?.get(&(*root, idx)).copied()
+    }
+
+    /// Records a verified row of the Merkle tree with the given root, so that later openings of
+    /// the same row can be omitted from the proof.
+    pub(crate) fn record_merkle_row(&mut self, root: &Digest, idx: usize, row: &'a [u32]) {
+        if let Some(opened) = &mut self.opened {
+            opened.insert((*root, idx), row);
         }
     }
 
@@ -59,6 +125,19 @@ impl<'a, F: Field> ReadIOP<'a, F> {
     }
 
     pub fn commit(&mut self, digest: &Digest) {
+        self.mix_label(TranscriptEvent::Commit {
+            label: None,
+            digest: *digest,
+        });
+        self.rng.mix(digest);
+    }
+
+    /// Commit to a digest under the given label. See [ReadIOP::commit].
+    pub fn commit_labeled(&mut self, label: &'static str, digest: &Digest) {
+        self.mix_label(TranscriptEvent::Commit {
+            label: Some(label),
+            digest: *digest,
+        });
         self.rng.mix(digest);
     }
 
@@ -69,16 +148,65 @@ impl<'a, F: Field> ReadIOP<'a, F> {
 
     /// Get a cryptographically uniform u32
     pub fn random_bits(&mut self, bits: usize) -> u32 {
+        self.mix_label(TranscriptEvent::Challenge { label: None });
+        self.rng.random_bits(bits)
+    }
+
+    /// Get a cryptographically uniform u32 under the given label.
+    pub fn random_bits_labeled(&mut self, label: &'static str, bits: usize) -> u32 {
+        self.mix_label(TranscriptEvent::Challenge { label: Some(label) });
         self.rng.random_bits(bits)
     }
 
+    /// Get a query position of the given number of bits. When queries are distinct, positions
+    /// already drawn are rejected and a new one is drawn in their place, so an error is returned
+    /// once every position has been drawn.
+    pub(crate) fn random_query(
+        &mut self,
+        label: &'static str,
+        bits: usize,
+    ) -> Result<u32, VerificationError> {
+        if let Some(queried) = &self.queried {
+            if queried.len() >= 1 << bits {
+                return Err(VerificationError::QueryDomainExhausted { domain: 1 << bits });
+            }
+        }
+        loop {
+            let pos = self.random_bits_labeled(label, bits);
+            match &mut self.queried {
+                Some(queried) if !queried.insert(pos) => continue,
+                _ => return Ok(pos),
+            }
+        }
+    }
+
     /// Get a cryptographically uniform field element
     pub fn random_elem(&mut self) -> F::Elem {
+        self.mix_label(TranscriptEvent::Challenge { label: None });
+        self.rng.random_elem()
+    }
+
+    /// Get a cryptographically uniform field element under the given label.
+    pub fn random_elem_labeled(&mut self, label: &'static str) -> F::Elem {
+        self.mix_label(TranscriptEvent::Challenge { label: Some(label) });
         self.rng.random_elem()
     }
 
     /// Get a cryptographically uniform extension field element
     pub fn random_ext_elem(&mut self) -> F::ExtElem {
+        self.mix_label(TranscriptEvent::Challenge { label: None });
         self.rng.random_ext_elem()
     }
+
+    /// Get a cryptographically uniform extension field element under the given label.
+    pub fn random_ext_elem_labeled(&mut self, label: &'static str) -> F::ExtElem {
+        self.mix_label(TranscriptEvent::Challenge { label: Some(label) });
+        self.rng.random_ext_elem()
+    }
+
+    fn mix_label(&mut self, event: TranscriptEvent) {
+        if let Some(label_digest) = self.transcript.push(event) {
+            self.rng.mix(&label_digest);
+        }
+    }
 }
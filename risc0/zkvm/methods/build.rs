@@ -45,6 +45,13 @@ fn main() {
                 ..Default::default()
             },
         ),
+        (
+            "risc0-zkvm-methods-source-digest",
+            GuestOptions {
+                source_digest: true,
+                ..Default::default()
+            },
+        ),
         (
             "risc0-zkvm-methods-std",
             GuestOptions {
@@ -0,0 +1,42 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_std]
+#![no_main]
+
+// Exercises the `#[risc0_test]` harness; `fails` is expected to fail and `ignored` to be skipped.
+#[risc0_zkvm::risc0_test]
+mod tests {
+    #[test]
+    fn passes() {
+        assert_eq!(1 + 1, 2);
+    }
+
+    #[test]
+    fn fails() {
+        assert_eq!(1 + 1, 3, "deliberately failing test");
+    }
+
+    #[test]
+    #[ignore]
+    fn ignored() {
+        panic!("ignored tests are not run");
+    }
+
+    #[test]
+    #[should_panic(expected = "expected panic")]
+    fn panics() {
+        panic!("expected panic");
+    }
+}
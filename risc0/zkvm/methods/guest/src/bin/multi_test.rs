@@ -32,10 +32,13 @@ use risc0_zkvm::{
         env::{self, FdReader, FdWriter, Read as _, Write as _},
         memory_barrier, sha,
     },
+    oracle::Client,
     sha::{Digest, Sha256},
-    Assumption, ReceiptClaim,
+    Assumption, JournalHash, ReceiptClaim,
+};
+use risc0_zkvm_methods::multi_test::{
+    MultiTestSpec, TestOracle, SYS_MULTI_TEST, SYS_MULTI_TEST_WORDS,
 };
-use risc0_zkvm_methods::multi_test::{MultiTestSpec, SYS_MULTI_TEST, SYS_MULTI_TEST_WORDS};
 use risc0_zkvm_platform::{
     fileno,
     memory::{self, SYSTEM},
@@ -118,6 +121,11 @@ fn main() {
             env::pause(exit_code);
             env::log("after");
         }
+        MultiTestSpec::PauseResumeCommit(bytes) => {
+            env::commit_slice(&bytes);
+            env::pause(0);
+            env::commit_slice(&bytes);
+        }
         MultiTestSpec::ReadWriteMem { values } => {
             for (addr, value) in values.into_iter() {
                 if value != 0 {
@@ -206,6 +214,11 @@ fn main() {
             let assumption: Assumption = risc0_zkvm::serde::from_slice(&assumption_words).unwrap();
             env::verify_assumption(assumption.claim, assumption.control_root).unwrap();
         }
+        MultiTestSpec::CommitAssumptionsDigest { image_id, journal } => {
+            env::verify(image_id, &journal).unwrap();
+            assert_eq!(env::assumptions().len(), 1);
+            env::commit(&env::assumptions_digest());
+        }
         MultiTestSpec::Echo { bytes } => {
             env::commit_slice(&bytes);
         }
@@ -226,6 +239,9 @@ fn main() {
             unsafe { sys_read_words(fd, buf.as_mut_ptr(), nwords) };
             env::commit_slice(&buf);
         }
+        MultiTestSpec::CommitWords { words } => {
+            env::commit_words(&words);
+        }
         MultiTestSpec::BusyLoop { cycles } => {
             let mut last_cycles = env::cycle_count();
 
@@ -424,5 +440,40 @@ fn main() {
             env::verify_assumption(claim_digest, control_root)
                 .expect("env::verify_integrity returned error");
         }
+        MultiTestSpec::Oracle { commit } => {
+            let mut oracle = if commit {
+                Client::<dyn TestOracle>::recording()
+            } else {
+                Client::<dyn TestOracle>::new()
+            };
+            let sum = oracle.add(2, 3);
+            let greeting = oracle.greet("zkVM".into());
+            env::commit(&(sum, greeting));
+            if commit {
+                env::commit(&oracle.take_calls());
+            }
+        }
+        MultiTestSpec::Poseidon2Journal(data) => {
+            env::set_journal_hash(JournalHash::Poseidon2);
+            env::commit_slice(&data);
+        }
+        MultiTestSpec::CommitStream { chunks, chunk_len } => {
+            let mut stream = env::commit_stream();
+            for i in 0..chunks {
+                stream.write_slice(&vec![i as u8; chunk_len as usize]);
+            }
+            stream.finish();
+        }
+        MultiTestSpec::HeapAlignment => {
+            let before = env::heap_stats().total;
+            let (byte, word) = unsafe {
+                (
+                    alloc_zeroed(Layout::from_size_align(1, 256).unwrap()),
+                    alloc_zeroed(Layout::from_size_align(4, 256).unwrap()),
+                )
+            };
+            let growth = env::heap_stats().total - before;
+            env::commit(&(growth as u32, (word as usize + 4 - byte as usize) as u32));
+        }
     }
 }
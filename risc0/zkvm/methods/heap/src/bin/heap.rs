@@ -21,4 +21,6 @@ fn main() {
         let tmp = vec![0; 10 * 1024 * 1024];
         println!("{:#?}", tmp.as_ptr());
     }
+    let stats = env::heap_stats();
+    env::commit(&(stats.peak as u32, stats.total as u32));
 }
@@ -15,7 +15,7 @@
 // Definitions for test selection codes used by the "multi_test" test.
 extern crate alloc;
 
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 
 use risc0_zkvm::{declare_syscall, sha::Digest};
 use risc0_zkvm_platform::syscall::bigint;
@@ -39,6 +39,8 @@ pub enum MultiTestSpec {
     Fault,
     Halt(u8),
     PauseResume(u8),
+    // Commit the bytes, pause, and commit them again once resumed.
+    PauseResumeCommit(Vec<u8>),
     ReadWriteMem {
         /// Tuples of (address, value). Zero means read the value and
         /// output it; nonzero means write that value.
@@ -66,6 +68,11 @@ pub enum MultiTestSpec {
         // Assumption: Field is serialized to avoid circular dependency issues.
         assumption_words: Vec<u32>,
     },
+    // Verify the receipt and commit the digest of the assumptions of the guest.
+    CommitAssumptionsDigest {
+        image_id: Digest,
+        journal: Vec<u8>,
+    },
     Echo {
         bytes: Vec<u8>,
     },
@@ -77,6 +84,9 @@ pub enum MultiTestSpec {
         fd: u32,
         nwords: u32,
     },
+    CommitWords {
+        words: Vec<u32>,
+    },
     BigInt {
         x: [u32; bigint::WIDTH_WORDS],
         y: [u32; bigint::WIDTH_WORDS],
@@ -115,6 +125,27 @@ pub enum MultiTestSpec {
         claim_digest: Digest,
         control_root: Digest,
     },
+    Oracle {
+        // True to record the oracle calls and commit them after the results.
+        commit: bool,
+    },
+    // Select the Poseidon2 journal hash, then commit the given bytes.
+    Poseidon2Journal(Vec<u8>),
+    // Stream `chunks` chunks of `chunk_len` bytes with env::commit_stream, where chunk `i` is
+    // filled with the byte `i`.
+    CommitStream {
+        chunks: u32,
+        chunk_len: u32,
+    },
+    // Allocate a byte and then a word, both 256-byte aligned, and commit the growth of the heap
+    // statistics and the distance from the start of the byte to the end of the word.
+    HeapAlignment,
+}
+
+#[risc0_zkvm::oracle]
+pub trait TestOracle {
+    fn add(&mut self, a: u32, b: u32) -> u32;
+    fn greet(&mut self, name: String) -> String;
 }
 
 declare_syscall!(pub SYS_MULTI_TEST);
@@ -0,0 +1,261 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `#[oracle]` attribute, which declares a trait the guest calls and the host implements.
+//!
+//! This crate is re-exported by `risc0-zkvm` with the `oracle` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Error, FnArg, ItemTrait, Pat, ReturnType, TraitItem, TraitItemFn, Type,
+};
+
+/// Declare an oracle trait, which the guest calls and the host implements.
+///
+/// Each method takes `&mut self` followed by arguments bound to plain names, and the arguments
+/// and the return value must be serializable. The trait may not be generic.
+///
+/// The attribute implements `risc0_zkvm::oracle::Oracle` for the `dyn` type of the trait, which
+/// the host registers with `ExecutorEnvBuilder::oracle`, and implements the trait for
+/// `risc0_zkvm::oracle::Client` in the guest.
+///
+/// # Example
+///
+/// ```ignore
+/// #[risc0_zkvm::oracle]
+/// pub trait PriceOracle {
+///     /// Returns the price of the given asset, in cents.
+///     fn price(&mut self, symbol: String) -> u64;
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn oracle(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return Error::new_spanned(TokenStream2::from(attr), "unexpected arguments")
+            .into_compile_error()
+            .into();
+    }
+    let item = parse_macro_input!(item as ItemTrait);
+    expand(item)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(item: ItemTrait) -> syn::Result<TokenStream2> {
+    if !item.generics.params.is_empty() || item.generics.where_clause.is_some() {
+        return Err(Error::new_spanned(
+            &item.generics,
+            "#[oracle] traits may not be generic",
+        ));
+    }
+
+    let mut methods = Vec::new();
+    for trait_item in item.items.iter() {
+        let TraitItem::Fn(method) = trait_item else {
+            return Err(Error::new_spanned(
+                trait_item,
+                "#[oracle] traits may only contain methods",
+            ));
+        };
+        methods.push(Method::parse(method)?);
+    }
+
+    let name = &item.ident;
+    let dispatch_arms = methods.iter().map(|method| {
+        let Method {
+            ident, args, tys, ..
+        } = method;
+        let method_name = ident.to_string();
+        quote! {
+            #method_name => {
+                let (#(#args,)*): (#(#tys,)*) = ::risc0_zkvm::serde::from_slice(args)?;
+                ::risc0_zkvm::serde::to_vec(&self.#ident(#(#args),*))
+            }
+        }
+    });
+    let client_methods = methods.iter().map(|method| {
+        let Method {
+            ident,
+            args,
+            tys,
+            output,
+        } = method;
+        let method_name = ident.to_string();
+        quote! {
+            fn #ident(&mut self, #(#args: #tys),*) #output {
+                self.call(#method_name, &(#(#args,)*))
+            }
+        }
+    });
+
+    Ok(quote! {
+        #item
+
+        impl<'a> ::risc0_zkvm::oracle::Oracle for dyn #name + 'a {
+            const CHANNEL: ::risc0_zkvm::oracle::SyscallName =
+                match ::core::ffi::CStr::from_bytes_until_nul(
+                    ::core::concat!(
+                        ::core::module_path!(),
+                        "::",
+                        ::core::stringify!(#name),
+                        "\0"
+                    )
+                    .as_bytes(),
+                ) {
+                    Ok(c_str) => match ::risc0_zkvm::oracle::SyscallName::from_c_str(c_str) {
+                        Ok(name) => name,
+                        Err(_) => ::core::unreachable!(),
+                    },
+                    Err(_) => ::core::unreachable!(),
+                };
+
+            fn dispatch(
+                &mut self,
+                method: &str,
+                args: &[u32],
+            ) -> ::core::result::Result<
+                ::risc0_zkvm::oracle::Vec<u32>,
+                ::risc0_zkvm::serde::Error,
+            > {
+                match method {
+                    #(#dispatch_arms)*
+                    _ => Err(::risc0_zkvm::serde::Error::NotSupported),
+                }
+            }
+        }
+
+        #[cfg(target_os = "zkvm")]
+        impl #name for ::risc0_zkvm::oracle::Client<dyn #name> {
+            #(#client_methods)*
+        }
+    })
+}
+
+struct Method {
+    ident: syn::Ident,
+    args: Vec<syn::Ident>,
+    tys: Vec<Type>,
+    output: ReturnType,
+}
+
+impl Method {
+    fn parse(method: &TraitItemFn) -> syn::Result<Self> {
+        let sig = &method.sig;
+        if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+            return Err(Error::new_spanned(
+                &sig.generics,
+                "#[oracle] methods may not be generic",
+            ));
+        }
+        if sig.asyncness.is_some() || sig.unsafety.is_some() || sig.variadic.is_some() {
+            return Err(Error::new_spanned(
+                sig,
+                "#[oracle] methods must be safe, synchronous functions",
+            ));
+        }
+
+        let mut inputs = sig.inputs.iter();
+        match inputs.next() {
+            Some(FnArg::Receiver(receiver))
+                if receiver.reference.is_some() && receiver.mutability.is_some() => {}
+            _ => {
+                return Err(Error::new_spanned(
+                    sig,
+                    "#[oracle] methods must take `&mut self`",
+                ))
+            }
+        }
+
+        let mut args = Vec::new();
+        let mut tys = Vec::new();
+        for input in inputs {
+            let FnArg::Typed(arg) = input else {
+                unreachable!("only the first argument can be a receiver");
+            };
+            let Pat::Ident(pat) = arg.pat.as_ref() else {
+                return Err(Error::new_spanned(
+                    &arg.pat,
+                    "#[oracle] method arguments must be plain names",
+                ));
+            };
+            args.push(pat.ident.clone());
+            tys.push(arg.ty.as_ref().clone());
+        }
+
+        Ok(Self {
+            ident: sig.ident.clone(),
+            args,
+            tys,
+            output: sig.output.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+    use syn::parse_quote;
+
+    use super::expand;
+
+    #[test]
+    fn expands_trait() {
+        let expanded = expand(parse_quote! {
+            pub trait PriceOracle {
+                fn price(&mut self, symbol: String) -> u64;
+            }
+        })
+        .unwrap()
+        .to_string();
+        let item = quote! {
+            pub trait PriceOracle {
+                fn price(&mut self, symbol: String) -> u64;
+            }
+        };
+        assert!(expanded.starts_with(&item.to_string()));
+        assert!(expanded.contains(&quote!(for dyn PriceOracle + 'a).to_string()));
+        let call = quote!(self.call("price", &(symbol,)));
+        assert!(expanded.contains(&call.to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_traits() {
+        assert!(expand(parse_quote! {
+            trait Generic<T> {
+                fn get(&mut self) -> T;
+            }
+        })
+        .is_err());
+        assert!(expand(parse_quote! {
+            trait Shared {
+                fn get(&self) -> u32;
+            }
+        })
+        .is_err());
+        assert!(expand(parse_quote! {
+            trait Pattern {
+                fn add(&mut self, (a, b): (u32, u32)) -> u32;
+            }
+        })
+        .is_err());
+        assert!(expand(parse_quote! {
+            trait Constant {
+                const ID: u32;
+            }
+        })
+        .is_err());
+    }
+}
@@ -15,18 +15,44 @@
 use crate::syscall::sys_alloc_aligned;
 use core::alloc::{GlobalAlloc, Layout};
 
+use super::record_alloc;
+
 #[global_allocator]
 pub static HEAP: BumpPointerAlloc = BumpPointerAlloc;
 
 pub struct BumpPointerAlloc;
 
+// End of the most recent allocation, or 0 before the first one.
+static mut HEAP_END: usize = 0;
+
 unsafe impl GlobalAlloc for BumpPointerAlloc {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        sys_alloc_aligned(layout.size(), layout.align())
+        let ptr = sys_alloc_aligned(layout.size(), layout.align());
+        let end = ptr as usize + layout.size();
+        // Count the alignment padding before the allocation too, so that the statistics follow
+        // the bump pointer.
+        // SAFETY: Single threaded, so nothing else can touch this while we're working.
+        let start = match HEAP_END {
+            0 => ptr as usize,
+            heap_end => heap_end,
+        };
+        record_alloc(end - start);
+        HEAP_END = end;
+        ptr
     }
 
-    unsafe fn dealloc(&self, _: *mut u8, _: Layout) {
-        // this allocator never deallocates memory
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {
+        // this allocator never deallocates memory, so the heap statistics are left unchanged
+
+        // Report the free to the host, so that it can detect later uses of the memory.
+        #[cfg(feature = "heap-sanitizer")]
+        crate::syscall::syscall_2(
+            crate::syscall::nr::SYS_HEAP_FREE,
+            core::ptr::null_mut(),
+            0,
+            _ptr as u32,
+            _layout.size() as u32,
+        );
     }
 
     unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
@@ -12,12 +12,45 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use core::alloc::{GlobalAlloc, Layout};
+
 use critical_section::RawRestoreState;
 use embedded_alloc::Heap;
 
-#[global_allocator]
+use super::{record_alloc, record_dealloc};
+
 pub static HEAP: Heap = Heap::empty();
 
+#[global_allocator]
+static ALLOCATOR: EmbeddedAlloc = EmbeddedAlloc;
+
+/// Wraps [HEAP] to record heap usage statistics.
+struct EmbeddedAlloc;
+
+unsafe impl GlobalAlloc for EmbeddedAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = HEAP.alloc(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        record_dealloc(layout.size());
+        HEAP.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = HEAP.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            record_dealloc(layout.size());
+            record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
 struct CriticalSection;
 critical_section::set_impl!(CriticalSection);
 
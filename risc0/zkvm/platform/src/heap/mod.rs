@@ -12,8 +12,68 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+//! Guest heap allocators and usage statistics.
+//!
+//! The default allocator is a bump-pointer allocator which never frees memory. Enabling the
+//! `heap-embedded-alloc` feature selects a linked-list allocator that reclaims freed memory at
+//! the cost of additional cycles per allocation.
+//!
+//! Enabling the `heap-sanitizer` feature reports each allocation and free made by the
+//! bump-pointer allocator to the host, which can then reject accesses to freed or unallocated
+//! heap memory. The reports cost a system call each, so this is intended for debugging only.
+//! It has no effect with `heap-embedded-alloc`.
+
 #[cfg(not(feature = "heap-embedded-alloc"))]
 mod bump;
 
 #[cfg(feature = "heap-embedded-alloc")]
 pub mod embedded;
+
+// The guest is single-threaded, so these counters are accessed without synchronization.
+static mut ALLOCATED: usize = 0;
+static mut PEAK: usize = 0;
+static mut TOTAL: usize = 0;
+
+/// Statistics about heap usage by the guest.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HeapStats {
+    /// Number of bytes currently allocated and not yet reclaimed.
+    ///
+    /// The default bump-pointer allocator never reclaims memory, so with it this is the high-water
+    /// mark of the heap, including the padding inserted to align allocations, and equal to `total`.
+    pub allocated: usize,
+
+    /// Largest value of `allocated` observed since the guest started.
+    pub peak: usize,
+
+    /// Total number of bytes allocated since the guest started, including memory that has since
+    /// been freed.
+    pub total: usize,
+}
+
+/// Returns the current [HeapStats].
+pub fn stats() -> HeapStats {
+    // SAFETY: Single threaded, so nothing else can touch these while we're reading.
+    unsafe {
+        HeapStats {
+            allocated: ALLOCATED,
+            peak: PEAK,
+            total: TOTAL,
+        }
+    }
+}
+
+fn record_alloc(bytes: usize) {
+    // SAFETY: Single threaded, so nothing else can touch these while we're working.
+    unsafe {
+        ALLOCATED += bytes;
+        TOTAL += bytes;
+        PEAK = usize::max(PEAK, ALLOCATED);
+    }
+}
+
+#[cfg(feature = "heap-embedded-alloc")]
+fn record_dealloc(bytes: usize) {
+    // SAFETY: Single threaded, so nothing else can touch this while we're working.
+    unsafe { ALLOCATED -= bytes };
+}
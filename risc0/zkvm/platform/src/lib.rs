@@ -38,11 +38,19 @@ pub const WORD_SIZE: usize = core::mem::size_of::<u32>();
 pub const PAGE_SIZE: usize = 1024;
 
 /// Standard IO file descriptors for use with sys_read and sys_write.
+///
+/// File descriptors up to [fileno::RESERVED] are reserved by the zkVM. Hosts registering their
+/// own file descriptors should use larger numbers.
 pub mod fileno {
     pub const STDIN: u32 = 0;
     pub const STDOUT: u32 = 1;
     pub const STDERR: u32 = 2;
     pub const JOURNAL: u32 = 3;
+    /// Outputs streamed with `env::commit_stream`, which are only committed to the journal by
+    /// their digest and length. Discarded unless the host registers a writer for them.
+    pub const JOURNAL_STREAM: u32 = 4;
+    /// The largest file descriptor reserved by the zkVM.
+    pub const RESERVED: u32 = JOURNAL_STREAM;
 }
 
 /// Align address upwards.
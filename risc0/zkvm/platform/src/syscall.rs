@@ -135,6 +135,9 @@ pub mod nr {
     declare_syscall!(pub SYS_EXIT);
     declare_syscall!(pub SYS_FORK);
     declare_syscall!(pub SYS_GETENV);
+    declare_syscall!(pub SYS_HEAP_ALLOC);
+    declare_syscall!(pub SYS_HEAP_FREE);
+    declare_syscall!(pub SYS_JOURNAL_HASH);
     declare_syscall!(pub SYS_LOG);
     declare_syscall!(pub SYS_PANIC);
     declare_syscall!(pub SYS_PIPE);
@@ -448,6 +451,13 @@ pub unsafe extern "C" fn sys_log(msg_ptr: *const u8, len: usize) {
     syscall_2(nr::SYS_LOG, null_mut(), 0, msg_ptr as u32, len as u32);
 }
 
+/// Report the hash function selected for the journal to the host, identified by its number in the
+/// receipt claim, so that the host records the same choice in the claim.
+#[cfg_attr(feature = "export-syscalls", no_mangle)]
+pub extern "C" fn sys_journal_hash(hash: u32) {
+    unsafe { syscall_1(nr::SYS_JOURNAL_HASH, null_mut(), 0, hash) };
+}
+
 #[cfg_attr(feature = "export-syscalls", no_mangle)]
 pub extern "C" fn sys_cycle_count() -> u64 {
     let Return(hi, lo) = unsafe { syscall_0(nr::SYS_CYCLE_COUNT, null_mut(), 0) };
@@ -743,6 +753,13 @@ pub unsafe extern "C" fn sys_alloc_aligned(bytes: usize, align: usize) -> *mut u
     }
 
     unsafe { HEAP_POS = heap_pos };
+
+    // Report the allocation to the host, so that it can check accesses to the heap.
+    #[cfg(feature = "heap-sanitizer")]
+    unsafe {
+        syscall_2(nr::SYS_HEAP_ALLOC, null_mut(), 0, ptr as u32, bytes as u32)
+    };
+
     ptr
 }
 
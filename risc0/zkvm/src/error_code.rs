@@ -0,0 +1,380 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Stable numeric codes for the errors returned by the zkVM.
+//!
+//! Codes are grouped by [ErrorCategory], with the category given by the thousands digit of the
+//! code. Once assigned, a code is never reused or reassigned, so clients which are not written in
+//! Rust (e.g. over the gRPC API, or the C interface in `risc0_zkvm::ffi`) can branch on failure
+//! modes across versions.
+//!
+//! Errors of `risc0_build` are covered by the [ErrorCategory::Build] codes when the `client`
+//! feature is enabled. Only the errors returned as a `risc0_build::BuildError` have codes; other
+//! failures of a guest build, such as I/O errors or a missing Cargo manifest, are
+//! [ErrorCode::Unknown].
+
+use core::fmt;
+
+use risc0_binfmt::InvalidExitCodeError;
+use risc0_zkp::verify::VerificationError;
+
+use crate::{receipt_claim::DecodeError, serde, PrunedValueError};
+
+/// The category of an [ErrorCode].
+///
+/// The numeric value of a category is the thousands digit of its codes, and is as stable as the
+/// codes themselves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+#[repr(u32)]
+pub enum ErrorCategory {
+    /// The error does not have an assigned code.
+    Unknown = 0,
+
+    /// The error was raised while executing a guest program.
+    Executor = 1,
+
+    /// The error was raised while proving.
+    Prover = 2,
+
+    /// The error was raised while verifying a receipt.
+    Verifier = 3,
+
+    /// The error was raised while encoding or decoding data.
+    Codec = 4,
+
+    /// The error was raised while building a guest.
+    Build = 5,
+}
+
+impl ErrorCategory {
+    /// Returns the numeric value of the category.
+    pub const fn code(self) -> u32 {
+        self as u32
+    }
+}
+
+macro_rules! error_codes {
+    ($($(#[$meta:meta])* $name:ident = $code:literal,)*) => {
+        /// A stable numeric code for an error returned by the zkVM.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        #[repr(u32)]
+        pub enum ErrorCode {
+            $($(#[$meta])* $name = $code,)*
+        }
+
+        impl ErrorCode {
+            /// Returns the [ErrorCode] with the given numeric code, if it is known.
+            pub const fn from_code(code: u32) -> Option<Self> {
+                match code {
+                    $($code => Some(Self::$name),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    /// The error does not have an assigned code.
+    Unknown = 0,
+
+    /// Execution reached `ResourceLimits::max_cycles`.
+    CycleLimitExceeded = 1000,
+    /// Execution reached `ResourceLimits::max_memory_pages`.
+    MemoryLimitExceeded = 1001,
+    /// Execution reached `ResourceLimits::max_journal_bytes`.
+    JournalLimitExceeded = 1002,
+    /// Execution reached `ResourceLimits::max_syscalls`.
+    SyscallLimitExceeded = 1003,
+    /// Execution reached the session limit set on the executor environment.
+    SessionLimitExceeded = 1004,
+
+    /// A value required by the prover was pruned.
+    PrunedValue = 2000,
+
+    /// See [VerificationError::ReceiptFormatError].
+    ReceiptFormat = 3000,
+    /// See [VerificationError::ControlVerificationError].
+    ControlVerification = 3001,
+    /// See [VerificationError::ImageVerificationError].
+    ImageVerification = 3002,
+    /// See [VerificationError::MerkleQueryOutOfRange].
+    MerkleQueryOutOfRange = 3003,
+    /// See [VerificationError::InvalidProof].
+    InvalidProof = 3004,
+    /// See [VerificationError::JournalDigestMismatch].
+    JournalDigestMismatch = 3005,
+    /// See [VerificationError::ClaimDigestMismatch].
+    ClaimDigestMismatch = 3006,
+    /// See [VerificationError::UnexpectedExitCode].
+    UnexpectedExitCode = 3007,
+    /// See [VerificationError::InvalidHashSuite].
+    InvalidHashSuite = 3008,
+    /// See [VerificationError::VerifierParametersMissing].
+    VerifierParametersMissing = 3009,
+    /// See [VerificationError::VerifierParametersMismatch].
+    VerifierParametersMismatch = 3010,
+    /// See [VerificationError::ProofSystemInfoMismatch].
+    ProofSystemInfoMismatch = 3011,
+    /// See [VerificationError::CircuitInfoMismatch].
+    CircuitInfoMismatch = 3012,
+    /// See [VerificationError::UnresolvedAssumption].
+    UnresolvedAssumption = 3013,
+    /// See [VerificationError::QueryDomainExhausted].
+    QueryDomainExhausted = 3014,
+
+    /// See [serde::Error::Custom].
+    SerdeCustom = 4000,
+    /// See [serde::Error::DeserializeBadBool].
+    SerdeBadBool = 4001,
+    /// See [serde::Error::DeserializeBadChar].
+    SerdeBadChar = 4002,
+    /// See [serde::Error::DeserializeBadOption].
+    SerdeBadOption = 4003,
+    /// See [serde::Error::DeserializeBadUtf8].
+    SerdeBadUtf8 = 4004,
+    /// See [serde::Error::DeserializeUnexpectedEnd].
+    SerdeUnexpectedEnd = 4005,
+    /// See [serde::Error::NotSupported].
+    SerdeNotSupported = 4006,
+    /// See [serde::Error::SerializeBufferFull].
+    SerdeBufferFull = 4007,
+    /// An exit code could not be decoded.
+    InvalidExitCode = 4100,
+    /// A system state could not be decoded.
+    InvalidSystemState = 4101,
+
+    /// Docker could not be executed to build a guest.
+    DockerUnavailable = 5000,
+    /// Building a guest in Docker failed.
+    DockerBuildFailed = 5001,
+    /// A guest exceeded its size budget.
+    BudgetExceeded = 5002,
+    /// The RISC Zero home directory could not be determined.
+    HomeDirUnavailable = 5003,
+}
+
+impl ErrorCode {
+    /// Returns the numeric code.
+    pub const fn code(self) -> u32 {
+        self as u32
+    }
+
+    /// Returns the [ErrorCategory] of this code.
+    pub const fn category(self) -> ErrorCategory {
+        match self.code() / 1000 {
+            1 => ErrorCategory::Executor,
+            2 => ErrorCategory::Prover,
+            3 => ErrorCategory::Verifier,
+            4 => ErrorCategory::Codec,
+            5 => ErrorCategory::Build,
+            _ => ErrorCategory::Unknown,
+        }
+    }
+
+    /// Returns the [ErrorCode] of the first error in the chain of `err` which has one.
+    #[cfg(feature = "std")]
+    pub fn of(err: &anyhow::Error) -> Self {
+        err.chain()
+            .find_map(Self::of_cause)
+            .unwrap_or(Self::Unknown)
+    }
+
+    #[cfg(feature = "std")]
+    fn of_cause(cause: &(dyn std::error::Error + 'static)) -> Option<Self> {
+        macro_rules! downcast {
+            ($($ty:ty),*) => {
+                $(if let Some(err) = cause.downcast_ref::<$ty>() {
+                    return Some(err.into());
+                })*
+            };
+        }
+        downcast!(
+            VerificationError,
+            serde::Error,
+            PrunedValueError,
+            DecodeError,
+            InvalidExitCodeError,
+            risc0_binfmt::DecodeError
+        );
+        #[cfg(all(feature = "client", not(target_os = "zkvm")))]
+        downcast!(
+            crate::ResourceLimitExceeded,
+            crate::host::api::RemoteError,
+            risc0_build::BuildError
+        );
+        #[cfg(all(feature = "prove", not(target_os = "zkvm")))]
+        downcast!(risc0_circuit_rv32im::prove::emu::exec::SessionLimitExceeded);
+        None
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "E{:04}", self.code())
+    }
+}
+
+impl From<&VerificationError> for ErrorCode {
+    fn from(err: &VerificationError) -> Self {
+        match err {
+            VerificationError::ReceiptFormatError => Self::ReceiptFormat,
+            VerificationError::ControlVerificationError { .. } => Self::ControlVerification,
+            VerificationError::ImageVerificationError => Self::ImageVerification,
+            VerificationError::MerkleQueryOutOfRange { .. } => Self::MerkleQueryOutOfRange,
+            VerificationError::InvalidProof => Self::InvalidProof,
+            VerificationError::JournalDigestMismatch => Self::JournalDigestMismatch,
+            VerificationError::ClaimDigestMismatch { .. } => Self::ClaimDigestMismatch,
+            VerificationError::UnexpectedExitCode => Self::UnexpectedExitCode,
+            VerificationError::InvalidHashSuite => Self::InvalidHashSuite,
+            VerificationError::VerifierParametersMissing => Self::VerifierParametersMissing,
+            VerificationError::VerifierParametersMismatch { .. } => {
+                Self::VerifierParametersMismatch
+            }
+            VerificationError::ProofSystemInfoMismatch { .. } => Self::ProofSystemInfoMismatch,
+            VerificationError::CircuitInfoMismatch { .. } => Self::CircuitInfoMismatch,
+            VerificationError::UnresolvedAssumption { .. } => Self::UnresolvedAssumption,
+            VerificationError::QueryDomainExhausted { .. } => Self::QueryDomainExhausted,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl From<&serde::Error> for ErrorCode {
+    fn from(err: &serde::Error) -> Self {
+        match err {
+            serde::Error::Custom(_) => Self::SerdeCustom,
+            serde::Error::DeserializeBadBool => Self::SerdeBadBool,
+            serde::Error::DeserializeBadChar => Self::SerdeBadChar,
+            serde::Error::DeserializeBadOption => Self::SerdeBadOption,
+            serde::Error::DeserializeBadUtf8 => Self::SerdeBadUtf8,
+            serde::Error::DeserializeUnexpectedEnd => Self::SerdeUnexpectedEnd,
+            serde::Error::NotSupported => Self::SerdeNotSupported,
+            serde::Error::SerializeBufferFull => Self::SerdeBufferFull,
+        }
+    }
+}
+
+impl From<&PrunedValueError> for ErrorCode {
+    fn from(_: &PrunedValueError) -> Self {
+        Self::PrunedValue
+    }
+}
+
+impl From<&DecodeError> for ErrorCode {
+    fn from(err: &DecodeError) -> Self {
+        match err {
+            DecodeError::InvalidExitCode(err) => err.into(),
+            DecodeError::Decode(err) => err.into(),
+        }
+    }
+}
+
+impl From<&InvalidExitCodeError> for ErrorCode {
+    fn from(_: &InvalidExitCodeError) -> Self {
+        Self::InvalidExitCode
+    }
+}
+
+impl From<&risc0_binfmt::DecodeError> for ErrorCode {
+    fn from(_: &risc0_binfmt::DecodeError) -> Self {
+        Self::InvalidSystemState
+    }
+}
+
+#[cfg(all(feature = "client", not(target_os = "zkvm")))]
+impl From<&crate::ResourceLimitExceeded> for ErrorCode {
+    fn from(err: &crate::ResourceLimitExceeded) -> Self {
+        match err {
+            crate::ResourceLimitExceeded::Cycles { .. } => Self::CycleLimitExceeded,
+            crate::ResourceLimitExceeded::MemoryPages { .. } => Self::MemoryLimitExceeded,
+            crate::ResourceLimitExceeded::JournalBytes { .. } => Self::JournalLimitExceeded,
+            crate::ResourceLimitExceeded::Syscalls { .. } => Self::SyscallLimitExceeded,
+        }
+    }
+}
+
+#[cfg(all(feature = "client", not(target_os = "zkvm")))]
+impl From<&crate::host::api::RemoteError> for ErrorCode {
+    fn from(err: &crate::host::api::RemoteError) -> Self {
+        Self::from_code(err.code).unwrap_or(Self::Unknown)
+    }
+}
+
+#[cfg(all(feature = "client", not(target_os = "zkvm")))]
+impl From<&risc0_build::BuildError> for ErrorCode {
+    fn from(err: &risc0_build::BuildError) -> Self {
+        match err {
+            risc0_build::BuildError::DockerUnavailable(_) => Self::DockerUnavailable,
+            risc0_build::BuildError::DockerBuildFailed => Self::DockerBuildFailed,
+            risc0_build::BuildError::BudgetExceeded(_) => Self::BudgetExceeded,
+            risc0_build::BuildError::HomeDirUnavailable => Self::HomeDirUnavailable,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[cfg(all(feature = "prove", not(target_os = "zkvm")))]
+impl From<&risc0_circuit_rv32im::prove::emu::exec::SessionLimitExceeded> for ErrorCode {
+    fn from(_: &risc0_circuit_rv32im::prove::emu::exec::SessionLimitExceeded) -> Self {
+        Self::SessionLimitExceeded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkp::verify::VerificationError;
+
+    use super::{ErrorCategory, ErrorCode};
+
+    #[test]
+    fn codes() {
+        for code in 0..6000 {
+            if let Some(err) = ErrorCode::from_code(code) {
+                assert_eq!(err.code(), code);
+            }
+        }
+        assert_eq!(ErrorCode::from_code(1), None);
+        assert_eq!(ErrorCode::InvalidProof.code(), 3004);
+        assert_eq!(ErrorCode::InvalidProof.category(), ErrorCategory::Verifier);
+        assert_eq!(ErrorCode::Unknown.category(), ErrorCategory::Unknown);
+        assert_eq!(ErrorCode::InvalidProof.to_string(), "E3004");
+        assert_eq!(ErrorCode::BudgetExceeded.category(), ErrorCategory::Build);
+        assert_eq!(ErrorCode::InvalidProof.category().code(), 3);
+    }
+
+    #[test]
+    fn anyhow_chain() {
+        let err = anyhow::Error::from(VerificationError::InvalidProof).context("failed to verify");
+        assert_eq!(ErrorCode::of(&err), ErrorCode::InvalidProof);
+        assert_eq!(
+            ErrorCode::of(&anyhow::anyhow!("no code")),
+            ErrorCode::Unknown
+        );
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn build_errors() {
+        let err = anyhow::Error::from(risc0_build::BuildError::DockerBuildFailed)
+            .context("failed to build guest");
+        assert_eq!(ErrorCode::of(&err), ErrorCode::DockerBuildFailed);
+
+        let io_err = std::io::Error::from(std::io::ErrorKind::NotFound);
+        let err = anyhow::Error::from(risc0_build::BuildError::DockerUnavailable(Some(io_err)));
+        assert_eq!(ErrorCode::of(&err), ErrorCode::DockerUnavailable);
+    }
+}
@@ -0,0 +1,240 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! C interface to the zkVM.
+//!
+//! Functions which can fail return a pointer to a [Risc0Error], which is null on success. The
+//! caller branches on the stable [ErrorCode] and [ErrorCategory](crate::ErrorCategory) returned
+//! by [risc0_error_code], reads the message with [risc0_error_message], and releases the error
+//! with [risc0_error_free].
+//!
+//! ```c
+//! typedef struct Risc0Error Risc0Error;
+//!
+//! typedef struct {
+//!     uint32_t code;
+//!     uint32_t category;
+//! } Risc0ErrorCode;
+//!
+//! Risc0Error *risc0_receipt_verify(const uint8_t *receipt, size_t receipt_len,
+//!                                  const uint32_t image_id[8]);
+//! Risc0ErrorCode risc0_error_code(const Risc0Error *err);
+//! size_t risc0_error_message(const Risc0Error *err, char *buf, size_t buf_len);
+//! void risc0_error_free(Risc0Error *err);
+//! ```
+
+use core::{ffi::c_char, ptr, slice};
+
+use anyhow::{bail, Result};
+use risc0_zkp::{core::digest::Digest, verify::VerificationError};
+
+use crate::{ErrorCode, Receipt};
+
+/// An error returned across the C interface.
+///
+/// This is opaque to C, and must be released with [risc0_error_free].
+pub struct Risc0Error(anyhow::Error);
+
+impl Risc0Error {
+    /// Move the error to the heap, to be returned to C.
+    pub fn into_raw(err: anyhow::Error) -> *mut Self {
+        Box::into_raw(Box::new(Self(err)))
+    }
+
+    fn from_result(result: Result<()>) -> *mut Self {
+        match result {
+            Ok(()) => ptr::null_mut(),
+            Err(err) => Self::into_raw(err),
+        }
+    }
+}
+
+/// The [ErrorCode] and [ErrorCategory](crate::ErrorCategory) of a [Risc0Error], as numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub struct Risc0ErrorCode {
+    /// See [ErrorCode::code].
+    pub code: u32,
+
+    /// See [ErrorCategory::code](crate::ErrorCategory::code).
+    pub category: u32,
+}
+
+impl From<ErrorCode> for Risc0ErrorCode {
+    fn from(code: ErrorCode) -> Self {
+        Self {
+            code: code.code(),
+            category: code.category().code(),
+        }
+    }
+}
+
+/// Verify a bincode-encoded [Receipt] against an image ID of eight words.
+///
+/// Returns null if the receipt is valid. A receipt which cannot be decoded is reported as
+/// [ErrorCode::ReceiptFormat].
+///
+/// # Safety
+///
+/// `receipt` must point to `receipt_len` readable bytes, and `image_id` to eight readable words.
+#[no_mangle]
+pub unsafe extern "C" fn risc0_receipt_verify(
+    receipt: *const u8,
+    receipt_len: usize,
+    image_id: *const u32,
+) -> *mut Risc0Error {
+    Risc0Error::from_result(receipt_verify(receipt, receipt_len, image_id))
+}
+
+unsafe fn receipt_verify(
+    receipt: *const u8,
+    receipt_len: usize,
+    image_id: *const u32,
+) -> Result<()> {
+    if receipt.is_null() || image_id.is_null() {
+        bail!("null pointer passed to risc0_receipt_verify");
+    }
+    let receipt = slice::from_raw_parts(receipt, receipt_len);
+    let image_id: [u32; 8] = slice::from_raw_parts(image_id, 8).try_into()?;
+    let receipt: Receipt = bincode::deserialize(receipt).map_err(|err| {
+        anyhow::Error::from(VerificationError::ReceiptFormatError)
+            .context(format!("failed to decode receipt: {err}"))
+    })?;
+    receipt.verify(Digest::from(image_id))?;
+    Ok(())
+}
+
+/// Returns the [ErrorCode] and [ErrorCategory](crate::ErrorCategory) of an error, which are
+/// both zero for an error without an assigned code.
+///
+/// # Safety
+///
+/// `err` must be null or a [Risc0Error] which has not been freed. A null error has the code of
+/// [ErrorCode::Unknown].
+#[no_mangle]
+pub unsafe extern "C" fn risc0_error_code(err: *const Risc0Error) -> Risc0ErrorCode {
+    match err.as_ref() {
+        Some(err) => ErrorCode::of(&err.0).into(),
+        None => ErrorCode::Unknown.into(),
+    }
+}
+
+/// Write the message of an error, including its causes, to `buf` as a NUL-terminated string.
+///
+/// The message is truncated to fit in `buf_len` bytes. Returns the length of the whole message,
+/// without the NUL, so a result of `buf_len` or more means that the message was truncated.
+///
+/// # Safety
+///
+/// `err` must be a [Risc0Error] which has not been freed, and `buf` must be null or point to
+/// `buf_len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn risc0_error_message(
+    err: *const Risc0Error,
+    buf: *mut c_char,
+    buf_len: usize,
+) -> usize {
+    let Some(err) = err.as_ref() else {
+        return 0;
+    };
+    let msg = format!("{:#}", err.0);
+    if !buf.is_null() && buf_len > 0 {
+        let len = msg.len().min(buf_len - 1);
+        ptr::copy_nonoverlapping(msg.as_ptr(), buf.cast(), len);
+        *buf.add(len) = 0;
+    }
+    msg.len()
+}
+
+/// Release an error returned by the C interface.
+///
+/// # Safety
+///
+/// `err` must be null or a [Risc0Error] which has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn risc0_error_free(err: *mut Risc0Error) {
+    if !err.is_null() {
+        drop(Box::from_raw(err));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ptr;
+    use std::ffi::CStr;
+
+    use risc0_zkp::verify::VerificationError;
+
+    use super::{
+        risc0_error_code, risc0_error_free, risc0_error_message, risc0_receipt_verify, Risc0Error,
+        Risc0ErrorCode,
+    };
+
+    #[test]
+    fn error_code() {
+        let err = Risc0Error::into_raw(
+            anyhow::Error::from(VerificationError::InvalidProof).context("failed to verify"),
+        );
+        unsafe {
+            assert_eq!(
+                risc0_error_code(err),
+                Risc0ErrorCode {
+                    code: 3004,
+                    category: 3
+                }
+            );
+
+            let mut buf = [0 as core::ffi::c_char; 64];
+            let len = risc0_error_message(err, buf.as_mut_ptr(), buf.len());
+            let msg = CStr::from_ptr(buf.as_ptr()).to_str().unwrap();
+            assert_eq!(len, msg.len());
+            assert!(msg.starts_with("failed to verify: "));
+
+            let len = risc0_error_message(err, buf.as_mut_ptr(), 7);
+            assert!(len > 7);
+            assert_eq!(CStr::from_ptr(buf.as_ptr()).to_str().unwrap(), "failed");
+
+            risc0_error_free(err);
+            assert_eq!(
+                risc0_error_code(ptr::null()),
+                Risc0ErrorCode {
+                    code: 0,
+                    category: 0
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn receipt_verify() {
+        let image_id = [0u32; 8];
+        let receipt = [1u8, 2, 3];
+        unsafe {
+            let err = risc0_receipt_verify(receipt.as_ptr(), receipt.len(), image_id.as_ptr());
+            assert!(!err.is_null());
+            assert_eq!(
+                risc0_error_code(err),
+                Risc0ErrorCode {
+                    code: 3000,
+                    category: 3
+                }
+            );
+            risc0_error_free(err);
+
+            let err = risc0_receipt_verify(ptr::null(), 0, image_id.as_ptr());
+            assert_eq!(risc0_error_code(err).code, 0);
+            risc0_error_free(err);
+        }
+    }
+}
@@ -0,0 +1,254 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::alloc::{alloc, dealloc, handle_alloc_error};
+use core::{alloc::Layout, cell::Cell, fmt, hash::Hash, ptr::NonNull, slice};
+
+use super::{ArenaMap, ArenaVec};
+
+/// Alignment of the region reserved by an [Arena]. Allocations with a larger alignment are padded.
+const ARENA_ALIGN: usize = 16;
+
+/// A bump allocator over a single region of memory reserved when it is created.
+///
+/// Allocating from an arena only advances an offset, and nothing is freed until the arena is
+/// [reset](Arena::reset), which releases every allocation at once. Values placed in the arena with
+/// [Arena::alloc] are never dropped; the collections created with [Arena::vec] and [Arena::map]
+/// drop their elements as usual.
+///
+/// Allocations panic if the region is exhausted, so size the arena for the largest phase of the
+/// guest that uses it.
+pub struct Arena {
+    start: NonNull<u8>,
+    capacity: usize,
+    used: Cell<usize>,
+}
+
+impl Arena {
+    /// Reserve an arena of `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let start = if capacity == 0 {
+            NonNull::new(ARENA_ALIGN as *mut u8).unwrap()
+        } else {
+            let layout = Self::layout(capacity);
+            // SAFETY: the layout has a non-zero size.
+            NonNull::new(unsafe { alloc(layout) }).unwrap_or_else(|| handle_alloc_error(layout))
+        };
+        Self {
+            start,
+            capacity,
+            used: Cell::new(0),
+        }
+    }
+
+    fn layout(capacity: usize) -> Layout {
+        Layout::from_size_align(capacity, ARENA_ALIGN).expect("arena capacity overflow")
+    }
+
+    /// The size of the region reserved by this arena, in bytes.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of bytes allocated since the arena was created or last reset, including padding.
+    pub fn used(&self) -> usize {
+        self.used.get()
+    }
+
+    /// The number of bytes that remain available.
+    pub fn remaining(&self) -> usize {
+        self.capacity - self.used.get()
+    }
+
+    /// Allocate memory for the given layout, or return `None` if the arena is exhausted.
+    pub fn try_alloc_layout(&self, layout: Layout) -> Option<NonNull<u8>> {
+        let base = self.start.as_ptr() as usize;
+        let unaligned = base.checked_add(self.used.get())?;
+        let aligned = unaligned.checked_add(layout.align() - 1)? & !(layout.align() - 1);
+        let offset = aligned - base;
+        let end = offset.checked_add(layout.size())?;
+        if end > self.capacity {
+            return None;
+        }
+        self.used.set(end);
+        // SAFETY: offset is at most capacity, so the pointer is within or one past the region.
+        Some(unsafe { NonNull::new_unchecked(self.start.as_ptr().add(offset)) })
+    }
+
+    pub(crate) fn alloc_layout(&self, layout: Layout) -> NonNull<u8> {
+        self.try_alloc_layout(layout).unwrap_or_else(|| {
+            panic!(
+                "arena exhausted: {} bytes requested, {} of {} bytes remaining",
+                layout.size(),
+                self.remaining(),
+                self.capacity
+            )
+        })
+    }
+
+    /// Grow the allocation at `ptr` from `old_size` to `new_size` bytes without moving it.
+    ///
+    /// This succeeds only if it is the most recent allocation and the arena has room.
+    pub(crate) fn try_grow_in_place(
+        &self,
+        ptr: NonNull<u8>,
+        old_size: usize,
+        new_size: usize,
+    ) -> bool {
+        let offset = ptr.as_ptr() as usize - self.start.as_ptr() as usize;
+        if offset + old_size != self.used.get() || new_size > self.capacity - offset {
+            return false;
+        }
+        self.used.set(offset + new_size);
+        true
+    }
+
+    /// Move `value` into the arena, returning a reference to it.
+    ///
+    /// The value is never dropped. Panics if the arena is exhausted.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc<T>(&self, value: T) -> &mut T {
+        let ptr = self.alloc_layout(Layout::new::<T>()).cast::<T>().as_ptr();
+        // SAFETY: the pointer is aligned and valid for a T, and no other reference to it exists.
+        unsafe {
+            ptr.write(value);
+            &mut *ptr
+        }
+    }
+
+    /// Move `value` into the arena, or return it if the arena is exhausted.
+    #[allow(clippy::mut_from_ref)]
+    pub fn try_alloc<T>(&self, value: T) -> Result<&mut T, T> {
+        let Some(ptr) = self.try_alloc_layout(Layout::new::<T>()) else {
+            return Err(value);
+        };
+        let ptr = ptr.cast::<T>().as_ptr();
+        // SAFETY: the pointer is aligned and valid for a T, and no other reference to it exists.
+        unsafe {
+            ptr.write(value);
+            Ok(&mut *ptr)
+        }
+    }
+
+    /// Copy `src` into the arena, returning a reference to the copy.
+    ///
+    /// Panics if the arena is exhausted.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_copy<T: Copy>(&self, src: &[T]) -> &mut [T] {
+        let ptr = self
+            .alloc_layout(Layout::for_value(src))
+            .cast::<T>()
+            .as_ptr();
+        // SAFETY: the allocation is aligned and large enough for src.len() elements, and does not
+        // overlap src.
+        unsafe {
+            ptr.copy_from_nonoverlapping(src.as_ptr(), src.len());
+            slice::from_raw_parts_mut(ptr, src.len())
+        }
+    }
+
+    /// Create an empty [ArenaVec] in this arena with room for `capacity` elements.
+    pub fn vec<T>(&self, capacity: usize) -> ArenaVec<'_, T> {
+        ArenaVec::with_capacity_in(capacity, self)
+    }
+
+    /// Create an empty [ArenaMap] in this arena with room for `capacity` entries.
+    pub fn map<K: Hash + Eq, V>(&self, capacity: usize) -> ArenaMap<'_, K, V> {
+        ArenaMap::with_capacity_in(capacity, self)
+    }
+
+    /// Release every allocation made from this arena, so that its region can be reused.
+    pub fn reset(&mut self) {
+        self.used.set(0);
+    }
+
+    /// Run `f` with this arena, and reset it afterwards.
+    ///
+    /// Nothing allocated by `f` can outlive the call, so the arena can be reused for the next
+    /// phase of the guest.
+    pub fn scope<R>(&mut self, f: impl FnOnce(&Arena) -> R) -> R {
+        let result = f(self);
+        self.reset();
+        result
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        if self.capacity != 0 {
+            // SAFETY: the region was allocated in with_capacity with the same layout.
+            unsafe { dealloc(self.start.as_ptr(), Self::layout(self.capacity)) }
+        }
+    }
+}
+
+impl fmt::Debug for Arena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Arena")
+            .field("capacity", &self.capacity)
+            .field("used", &self.used.get())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::alloc::Layout;
+
+    use super::Arena;
+
+    #[test]
+    fn alloc() {
+        let mut arena = Arena::with_capacity(64);
+        let a = arena.alloc(1u8);
+        let b = arena.alloc(2u32);
+        assert_eq!((*a, *b), (1, 2));
+        assert_eq!(b as *mut u32 as usize % 4, 0);
+        assert_eq!(arena.used(), 8);
+
+        let words = arena.alloc_slice_copy(&[3u32; 4]);
+        assert_eq!(words, &[3; 4]);
+        assert_eq!(arena.remaining(), 40);
+
+        assert!(arena.try_alloc([0u8; 41]).is_err());
+        assert!(arena.try_alloc([0u8; 40]).is_ok());
+        assert_eq!(arena.remaining(), 0);
+
+        arena.reset();
+        assert_eq!(arena.used(), 0);
+        let aligned = arena
+            .try_alloc_layout(Layout::from_size_align(4, 32).unwrap())
+            .unwrap();
+        assert_eq!(aligned.as_ptr() as usize % 32, 0);
+    }
+
+    #[test]
+    fn scope() {
+        let mut arena = Arena::with_capacity(16);
+        let sum = arena.scope(|arena| {
+            let mut vec = arena.vec(4);
+            vec.extend([1u32, 2, 3, 4]);
+            vec.iter().sum::<u32>()
+        });
+        assert_eq!(sum, 10);
+        assert_eq!(arena.used(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "arena exhausted")]
+    fn exhausted() {
+        let arena = Arena::with_capacity(0);
+        arena.alloc(0u8);
+    }
+}
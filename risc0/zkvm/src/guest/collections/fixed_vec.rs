@@ -0,0 +1,206 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::{
+    fmt,
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+    ptr, slice,
+};
+
+/// A vector with room for `N` elements stored inline, which never allocates.
+///
+/// Pushing beyond the capacity panics, or returns the value with [FixedVec::try_push].
+pub struct FixedVec<T, const N: usize> {
+    buf: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> FixedVec<T, N> {
+    /// Create an empty vector.
+    pub const fn new() -> Self {
+        Self {
+            // SAFETY: an array of MaybeUninit does not require initialization.
+            buf: unsafe { MaybeUninit::<[MaybeUninit<T>; N]>::uninit().assume_init() },
+            len: 0,
+        }
+    }
+
+    /// The number of elements in the vector.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the vector contains no elements.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether the vector holds `N` elements.
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// The number of elements the vector can hold.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Append an element to the back of the vector.
+    ///
+    /// Panics if the vector is full.
+    pub fn push(&mut self, value: T) {
+        if self.try_push(value).is_err() {
+            panic!("FixedVec capacity of {N} exceeded");
+        }
+    }
+
+    /// Append an element to the back of the vector, or return it if the vector is full.
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        if self.len == N {
+            return Err(value);
+        }
+        self.buf[self.len].write(value);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Remove the last element and return it, or `None` if the vector is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        // SAFETY: the slot at the old last index is initialized, and is no longer part of the vector.
+        Some(unsafe { self.buf[self.len].assume_init_read() })
+    }
+
+    /// Shorten the vector to `len` elements, dropping the rest.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+        let tail: *mut [T] = &mut self.as_mut_slice()[len..];
+        self.len = len;
+        // SAFETY: the tail is initialized and no longer part of the vector.
+        unsafe { ptr::drop_in_place(tail) };
+    }
+
+    /// Remove all elements.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// The elements of the vector.
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: the first len elements are initialized.
+        unsafe { slice::from_raw_parts(self.buf.as_ptr().cast(), self.len) }
+    }
+
+    /// The elements of the vector.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: the first len elements are initialized, and self is borrowed mutably.
+        unsafe { slice::from_raw_parts_mut(self.buf.as_mut_ptr().cast(), self.len) }
+    }
+}
+
+impl<T, const N: usize> Drop for FixedVec<T, N> {
+    fn drop(&mut self) {
+        // SAFETY: the elements are initialized and the vector is not used afterwards.
+        unsafe { ptr::drop_in_place(self.as_mut_slice()) }
+    }
+}
+
+impl<T, const N: usize> Default for FixedVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for FixedVec<T, N> {
+    fn clone(&self) -> Self {
+        let mut clone = Self::new();
+        for value in self.iter() {
+            clone.push(value.clone());
+        }
+        clone
+    }
+}
+
+impl<T, const N: usize> Deref for FixedVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for FixedVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T, const N: usize> Extend<T> for FixedVec<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for FixedVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+
+    use super::FixedVec;
+
+    #[test]
+    fn push_pop() {
+        let mut vec = FixedVec::<u32, 3>::new();
+        vec.extend([1, 2]);
+        assert_eq!(vec.try_push(3), Ok(()));
+        assert!(vec.is_full());
+        assert_eq!(vec.try_push(4), Err(4));
+        assert_eq!(vec.as_slice(), &[1, 2, 3]);
+        assert_eq!(vec.pop(), Some(3));
+        assert_eq!(vec.clone().as_slice(), &[1, 2]);
+        vec.clear();
+        assert_eq!(vec.pop(), None);
+    }
+
+    #[test]
+    fn drop_elements() {
+        let value = Rc::new(());
+        let mut vec = FixedVec::<_, 4>::new();
+        vec.extend([value.clone(), value.clone(), value.clone()]);
+        vec.truncate(1);
+        assert_eq!(Rc::strong_count(&value), 2);
+        drop(vec);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "FixedVec capacity of 1 exceeded")]
+    fn overflow() {
+        let mut vec = FixedVec::<u8, 1>::new();
+        vec.extend([1, 2]);
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::hash::{BuildHasherDefault, Hasher};
+
+/// Odd constant derived from the golden ratio, used to mix each word into the state.
+const SEED: u32 = 0x9e37_79b9;
+
+/// A multiplicative hash over 32-bit words, costing a few cycles per word in the zkVM.
+///
+/// Each word is combined with the state by a rotation, an exclusive or and a multiplication, as in
+/// the hash used by the Rust compiler. It is not resistant to hash flooding.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WordHasher {
+    hash: u32,
+}
+
+/// A [core::hash::BuildHasher] for [WordHasher], to use it with the standard collections, e.g.
+/// `HashMap<K, V, BuildWordHasher>`.
+pub type BuildWordHasher = BuildHasherDefault<WordHasher>;
+
+impl WordHasher {
+    #[inline]
+    fn add_word(&mut self, word: u32) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for WordHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let mut words = bytes.chunks_exact(4);
+        for word in words.by_ref() {
+            self.add_word(u32::from_le_bytes(word.try_into().unwrap()));
+        }
+        let rest = words.remainder();
+        if !rest.is_empty() {
+            let mut word = [0u8; 4];
+            word[..rest.len()].copy_from_slice(rest);
+            self.add_word(u32::from_le_bytes(word) ^ ((rest.len() as u32) << 24));
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add_word(i as u32);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add_word(i as u32);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add_word(i);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add_word(i as u32);
+        self.add_word((i >> 32) as u32);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        if usize::BITS == 32 {
+            self.add_word(i as u32);
+        } else {
+            self.write_u64(i as u64);
+        }
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::hash::{BuildHasher, Hash, Hasher};
+
+    use super::{BuildWordHasher, WordHasher};
+
+    fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+        BuildWordHasher::default().hash_one(value)
+    }
+
+    #[test]
+    fn word_hasher() {
+        assert_eq!(hash(&1u32), hash(&1u32));
+        assert_ne!(hash(&1u32), hash(&2u32));
+        assert_ne!(hash("ab"), hash("ab\0"));
+
+        // Bytes are hashed as little-endian words, so a u32 and its bytes agree.
+        let mut words = WordHasher::default();
+        words.write_u32(0x0403_0201);
+        let mut bytes = WordHasher::default();
+        bytes.write(&[1, 2, 3, 4]);
+        assert_eq!(words.finish(), bytes.finish());
+    }
+}
@@ -0,0 +1,303 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::{
+    alloc::Layout,
+    borrow::Borrow,
+    fmt,
+    hash::{BuildHasher, Hash},
+    mem,
+};
+
+use super::{Arena, ArenaVec, BuildWordHasher};
+
+/// A hash map allocated from an [Arena], hashing its keys with [WordHasher](super::WordHasher).
+///
+/// Entries are stored in a single table with linear probing, which is kept at most half full. When
+/// it would exceed that, the table moves to one of twice the size in the same arena, and the old
+/// table is not reclaimed until the arena is reset, so pass a capacity hint to [Arena::map] where
+/// the number of entries is known.
+pub struct ArenaMap<'a, K, V> {
+    slots: ArenaVec<'a, Option<(K, V)>>,
+    len: usize,
+}
+
+impl<'a, K: Hash + Eq, V> ArenaMap<'a, K, V> {
+    pub(crate) fn with_capacity_in(capacity: usize, arena: &'a Arena) -> Self {
+        Self {
+            slots: Self::new_slots(arena, capacity),
+            len: 0,
+        }
+    }
+
+    /// The number of bytes of an [Arena] used by a map created with room for `capacity` entries,
+    /// including any padding needed to align its table, as long as it does not grow.
+    ///
+    /// This can be used to size an arena with [Arena::with_capacity].
+    pub fn bytes_for(capacity: usize) -> usize {
+        let layout =
+            Layout::array::<Option<(K, V)>>(Self::num_slots(capacity)).expect("capacity overflow");
+        layout.size() + layout.align() - 1
+    }
+
+    fn num_slots(capacity: usize) -> usize {
+        capacity
+            .max(1)
+            .checked_mul(2)
+            .and_then(usize::checked_next_power_of_two)
+            .expect("capacity overflow")
+    }
+
+    fn new_slots(arena: &'a Arena, capacity: usize) -> ArenaVec<'a, Option<(K, V)>> {
+        let num_slots = Self::num_slots(capacity);
+        let mut slots = arena.vec(num_slots);
+        slots.extend((0..num_slots).map(|_| None));
+        slots
+    }
+
+    /// The number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of entries the map can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.slots.len() / 2
+    }
+
+    fn slot_index<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        BuildWordHasher::default().hash_one(key) as usize & (self.slots.len() - 1)
+    }
+
+    /// Find the slot holding `key`, or else the empty slot where it would be inserted.
+    fn find<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mask = self.slots.len() - 1;
+        let mut idx = self.slot_index(key);
+        loop {
+            match &self.slots[idx] {
+                None => return Err(idx),
+                Some((k, _)) if Borrow::<Q>::borrow(k) == key => return Ok(idx),
+                Some(_) => idx = (idx + 1) & mask,
+            }
+        }
+    }
+
+    /// Move the entries to a table of twice the size.
+    fn grow(&mut self) {
+        let slots = Self::new_slots(self.slots.arena(), self.slots.len());
+        let mut old_slots = mem::replace(&mut self.slots, slots);
+        for (key, value) in old_slots.iter_mut().filter_map(Option::take) {
+            let Err(idx) = self.find(&key) else {
+                unreachable!("duplicate key in ArenaMap")
+            };
+            self.slots[idx] = Some((key, value));
+        }
+    }
+
+    /// Insert an entry for a key which is not in the map, at the empty slot `idx` returned by
+    /// [Self::find], returning the slot it ends up in.
+    fn insert_new(&mut self, idx: usize, key: K, value: V) -> usize {
+        let idx = if self.len < self.capacity() {
+            idx
+        } else {
+            self.grow();
+            let Err(idx) = self.find(&key) else {
+                unreachable!("duplicate key in ArenaMap")
+            };
+            idx
+        };
+        self.slots[idx] = Some((key, value));
+        self.len += 1;
+        idx
+    }
+
+    /// Insert a value for `key`, returning the previous value if there was one.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.find(&key) {
+            Ok(idx) => Some(mem::replace(
+                &mut self.slots[idx].as_mut().unwrap().1,
+                value,
+            )),
+            Err(idx) => {
+                self.insert_new(idx, key, value);
+                None
+            }
+        }
+    }
+
+    /// Return the value for `key`, inserting `default` first if there is none.
+    pub fn entry_or_insert(&mut self, key: K, default: V) -> &mut V {
+        let idx = match self.find(&key) {
+            Ok(idx) => idx,
+            Err(idx) => self.insert_new(idx, key, default),
+        };
+        &mut self.slots[idx].as_mut().unwrap().1
+    }
+
+    /// The value for `key`, if any.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.find(key).ok()?;
+        self.slots[idx].as_ref().map(|(_, v)| v)
+    }
+
+    /// The value for `key`, if any.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.find(key).ok()?;
+        self.slots[idx].as_mut().map(|(_, v)| v)
+    }
+
+    /// Whether the map contains a value for `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).is_ok()
+    }
+
+    /// Remove the entry for `key`, returning its value if there was one.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut hole = self.find(key).ok()?;
+        let (_, value) = self.slots[hole].take().unwrap();
+        self.len -= 1;
+
+        // Shift back the entries which follow in the same run, so that lookups probing past the
+        // hole still find them.
+        let mask = self.slots.len() - 1;
+        let mut idx = hole;
+        loop {
+            idx = (idx + 1) & mask;
+            let Some((k, _)) = &self.slots[idx] else {
+                break;
+            };
+            let home = self.slot_index(k);
+            // The entry at idx may fill the hole unless its home slot lies cyclically in (hole, idx].
+            let reachable = if hole <= idx {
+                hole < home && home <= idx
+            } else {
+                hole < home || home <= idx
+            };
+            if !reachable {
+                self.slots[hole] = self.slots[idx].take();
+                hole = idx;
+            }
+        }
+        Some(value)
+    }
+
+    /// Iterate over the entries of the map, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.slots
+            .iter()
+            .filter_map(|slot| slot.as_ref().map(|(k, v)| (k, v)))
+    }
+
+    /// Iterate over the keys of the map, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// Iterate over the values of the map, in no particular order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+}
+
+impl<K: Hash + Eq + fmt::Debug, V: fmt::Debug> fmt::Debug for ArenaMap<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec::Vec};
+
+    use super::{super::Arena, ArenaMap};
+
+    #[test]
+    fn insert_get() {
+        let arena = Arena::with_capacity(4096);
+        let mut map = arena.map::<String, u32>(2);
+        assert_eq!(map.insert("a".into(), 1), None);
+        assert_eq!(map.insert("b".into(), 2), None);
+        assert_eq!(map.insert("a".into(), 3), Some(1));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("a"), Some(&3));
+        assert_eq!(map.get("c"), None);
+        *map.get_mut("b").unwrap() += 1;
+        *map.entry_or_insert("c".into(), 0) += 5;
+        assert_eq!(map.get("b"), Some(&3));
+        assert_eq!(map.get("c"), Some(&5));
+        assert!(map.capacity() >= 3);
+    }
+
+    #[test]
+    fn bytes_for() {
+        // An arena of exactly the reported size fits the map, after another allocation which
+        // leaves its table unaligned.
+        let bytes = ArenaMap::<u32, u64>::bytes_for(100);
+        let arena = Arena::with_capacity(1 + bytes);
+        arena.alloc(0u8);
+        let mut map = arena.map::<u32, u64>(100);
+        for i in 0..100 {
+            map.insert(i, i.into());
+        }
+        assert_eq!(map.len(), 100);
+        assert!(arena.used() <= 1 + bytes);
+    }
+
+    #[test]
+    fn grow_and_remove() {
+        let arena = Arena::with_capacity(64 * 1024);
+        let mut map = arena.map::<u32, u32>(1);
+        for i in 0..1000 {
+            map.insert(i, i * 2);
+        }
+        assert_eq!(map.len(), 1000);
+        for i in (0..1000).step_by(3) {
+            assert_eq!(map.remove(&i), Some(i * 2));
+        }
+        assert_eq!(map.remove(&0), None);
+        for i in 0..1000 {
+            let expected = (i % 3 != 0).then_some(i * 2);
+            assert_eq!(map.get(&i).copied(), expected, "key {i}");
+        }
+        let mut keys: Vec<u32> = map.keys().copied().collect();
+        keys.sort();
+        assert_eq!(keys.len(), map.len());
+        assert_eq!(map.values().sum::<u32>(), keys.iter().map(|k| k * 2).sum());
+    }
+}
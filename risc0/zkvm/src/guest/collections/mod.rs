@@ -0,0 +1,71 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Collections and an arena allocator tuned for the zkVM.
+//!
+//! The default guest allocator is a bump allocator which never reclaims memory, and every cycle
+//! spent in the guest is paid for again when proving. This makes some common patterns with the
+//! standard collections surprisingly expensive:
+//!
+//! * A `Vec` which grows by pushing reallocates each time it doubles, copying its contents and
+//!   leaving each of the previous buffers allocated for the rest of the execution.
+//! * `std::collections::HashMap` hashes its keys with SipHash, which is designed to resist hash
+//!   flooding and costs far more cycles per key than the lookup itself.
+//!
+//! This module provides alternatives which take a capacity hint up front:
+//!
+//! * [Arena] reserves a single region and hands out allocations from it by bumping an offset.
+//!   Everything allocated from it is released at once by [Arena::reset] or [Arena::scope], so
+//!   the region can be reused by later phases of the guest.
+//! * [ArenaVec] is a vector allocated from an [Arena]. While it is the most recent allocation of
+//!   its arena it grows in place, without copying.
+//! * [ArenaMap] is an open addressing hash map allocated from an [Arena], using [WordHasher].
+//! * [FixedVec] is a vector with a fixed capacity stored inline, which never allocates.
+//! * [WordHasher] is a multiplicative hash over 32-bit words, which can also be used with the
+//!   standard collections through [BuildWordHasher].
+//!
+//! [WordHasher] is not resistant to hash flooding. In the zkVM the inputs are chosen by the
+//! prover, who could only use collisions to make its own proof more expensive.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use risc0_zkvm::guest::{collections::Arena, env};
+//!
+//! let values: Vec<u32> = env::read();
+//!
+//! let mut arena = Arena::with_capacity(64 * 1024);
+//! let distinct = arena.scope(|arena| {
+//!     let mut counts = arena.map::<u32, u32>(values.len());
+//!     for value in values.iter() {
+//!         *counts.entry_or_insert(*value, 0) += 1;
+//!     }
+//!     counts.len()
+//! });
+//! env::commit(&distinct);
+//! ```
+
+mod arena;
+mod fixed_vec;
+mod hash;
+mod map;
+mod vec;
+
+pub use self::{
+    arena::Arena,
+    fixed_vec::FixedVec,
+    hash::{BuildWordHasher, WordHasher},
+    map::ArenaMap,
+    vec::ArenaVec,
+};
@@ -0,0 +1,249 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::{
+    alloc::Layout,
+    fmt,
+    mem::{size_of, ManuallyDrop},
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    slice,
+};
+
+use super::Arena;
+
+/// A vector whose buffer is allocated from an [Arena].
+///
+/// When it runs out of room, an `ArenaVec` grows in place if its buffer is the most recent
+/// allocation of the arena, and otherwise moves to a buffer of twice the size. The old buffer is
+/// not reclaimed until the arena is reset, so pass a capacity hint to [Arena::vec] where the size
+/// is known.
+pub struct ArenaVec<'a, T> {
+    arena: &'a Arena,
+    ptr: NonNull<T>,
+    len: usize,
+    cap: usize,
+}
+
+impl<'a, T> ArenaVec<'a, T> {
+    pub(crate) fn with_capacity_in(capacity: usize, arena: &'a Arena) -> Self {
+        let mut vec = Self {
+            arena,
+            ptr: NonNull::dangling(),
+            len: 0,
+            cap: if size_of::<T>() == 0 { usize::MAX } else { 0 },
+        };
+        vec.reserve(capacity);
+        vec
+    }
+
+    /// The arena this vector is allocated from.
+    pub fn arena(&self) -> &'a Arena {
+        self.arena
+    }
+
+    /// The number of elements in the vector.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the vector contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of elements the vector can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Ensure there is room for at least `additional` more elements.
+    ///
+    /// Panics if the arena is exhausted.
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.len.checked_add(additional).expect("capacity overflow");
+        if required <= self.cap {
+            return;
+        }
+        let new_cap = required.max(self.cap * 2);
+        let new_layout = Layout::array::<T>(new_cap).expect("capacity overflow");
+        if self.cap != 0
+            && self.arena.try_grow_in_place(
+                self.ptr.cast(),
+                self.cap * size_of::<T>(),
+                new_layout.size(),
+            )
+        {
+            self.cap = new_cap;
+            return;
+        }
+        let ptr = self.arena.alloc_layout(new_layout).cast::<T>();
+        // SAFETY: the new buffer is a fresh allocation with room for new_cap > len elements.
+        unsafe {
+            ptr.as_ptr()
+                .copy_from_nonoverlapping(self.ptr.as_ptr(), self.len)
+        };
+        self.ptr = ptr;
+        self.cap = new_cap;
+    }
+
+    /// Append an element to the back of the vector.
+    pub fn push(&mut self, value: T) {
+        if self.len == self.cap {
+            self.reserve(1);
+        }
+        // SAFETY: len < cap, so the slot is within the buffer and uninitialized.
+        unsafe { self.ptr.as_ptr().add(self.len).write(value) };
+        self.len += 1;
+    }
+
+    /// Remove the last element and return it, or `None` if the vector is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        // SAFETY: the slot at the old last index is initialized, and is no longer part of the vector.
+        Some(unsafe { self.ptr.as_ptr().add(self.len).read() })
+    }
+
+    /// Shorten the vector to `len` elements, dropping the rest.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+        let tail = ptr::slice_from_raw_parts_mut(
+            // SAFETY: len < self.len, so the offset is within the buffer.
+            unsafe { self.ptr.as_ptr().add(len) },
+            self.len - len,
+        );
+        self.len = len;
+        // SAFETY: the tail is initialized and no longer part of the vector.
+        unsafe { ptr::drop_in_place(tail) };
+    }
+
+    /// Remove all elements, keeping the buffer.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// The elements of the vector.
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: the first len elements are initialized.
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    /// The elements of the vector.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: the first len elements are initialized, and self is borrowed mutably.
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+
+    /// Convert the vector into a slice which lives as long as the arena borrow.
+    ///
+    /// The elements are no longer dropped.
+    pub fn into_slice(self) -> &'a mut [T] {
+        let this = ManuallyDrop::new(self);
+        // SAFETY: the buffer belongs to the arena, which cannot be reset while borrowed for 'a.
+        unsafe { slice::from_raw_parts_mut(this.ptr.as_ptr(), this.len) }
+    }
+}
+
+impl<T> Drop for ArenaVec<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the elements are initialized and the vector is not used afterwards.
+        unsafe { ptr::drop_in_place(self.as_mut_slice()) }
+    }
+}
+
+impl<T> Deref for ArenaVec<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T> DerefMut for ArenaVec<'_, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T> Extend<T> for ArenaVec<'_, T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArenaVec<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+
+    use super::super::Arena;
+
+    #[test]
+    fn grow_in_place() {
+        let arena = Arena::with_capacity(1024);
+        let mut vec = arena.vec::<u32>(2);
+        let ptr = vec.as_ptr();
+        vec.extend(0..10);
+        assert_eq!(vec.as_ptr(), ptr);
+        assert_eq!(vec.len(), 10);
+        assert_eq!(arena.used(), vec.capacity() * 4);
+        assert_eq!(vec.pop(), Some(9));
+        assert_eq!(&vec[..3], &[0, 1, 2]);
+    }
+
+    #[test]
+    fn grow_by_moving() {
+        let arena = Arena::with_capacity(1024);
+        let mut vec = arena.vec::<u32>(2);
+        vec.extend([1, 2]);
+        let other = arena.alloc(0u32);
+        vec.push(3);
+        *other = 4;
+        assert_eq!(vec.as_slice(), &[1, 2, 3]);
+        assert_eq!(arena.used(), 8 + 4 + 16);
+    }
+
+    #[test]
+    fn drop_elements() {
+        let arena = Arena::with_capacity(1024);
+        let value = Rc::new(());
+        let mut vec = arena.vec(4);
+        for _ in 0..4 {
+            vec.push(value.clone());
+        }
+        vec.truncate(1);
+        assert_eq!(Rc::strong_count(&value), 2);
+        drop(vec);
+        assert_eq!(Rc::strong_count(&value), 1);
+
+        let mut zsts = arena.vec(0);
+        zsts.extend((0..100).map(|_| ()));
+        assert_eq!(zsts.len(), 100);
+        assert_eq!(arena.used(), 4 * core::mem::size_of::<Rc<()>>());
+    }
+}
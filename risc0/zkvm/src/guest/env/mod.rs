@@ -28,7 +28,8 @@
 //!
 //! The zkVM supports verification of RISC Zero [receipts] in a guest program,
 //! enabling [proof composition]. This can be achieved using the [verify()] and
-//! [verify_integrity] functions.
+//! [verify_integrity] functions. The assumptions added by these calls can be
+//! inspected with [assumptions()] and [assumptions_digest].
 //!
 //! ## Input and Output
 //!
@@ -63,6 +64,7 @@
 //! The zkVM provides utility functions to log messages to the debug console and
 //! to measure the number of processor cycles that have occurred since the guest
 //! began. These can be achieved using the [log] and [cycle_count] functions.
+//! Heap usage can be measured with `heap_stats`.
 //!
 //! [receipts]: crate::Receipt
 //! [proof composition]:https://www.risczero.com/blog/proof-composition
@@ -70,12 +72,14 @@
 //!     https://dev.risczero.com/api/zkvm/optimization#when-reading-data-as-raw-bytes-use-envread_slice
 
 mod read;
+mod stream;
 mod verify;
 mod write;
 
 use alloc::{
     alloc::{alloc, Layout},
     vec,
+    vec::Vec,
 };
 
 use bytemuck::Pod;
@@ -91,25 +95,40 @@ use risc0_zkvm_platform::{
 use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{
-    sha::{
-        rust_crypto::{Digest as _, Sha256},
-        Digest, Digestible,
-    },
-    Assumptions, MaybePruned, Output,
+    receipt_claim::JournalHasher,
+    schema::{JournalSchema, JournalType},
+    sha::{Digest, Digestible},
+    Assumption, Assumptions, JournalHash, MaybePruned, Output,
 };
 
+#[cfg(target_os = "zkvm")]
+pub use risc0_zkvm_platform::heap::HeapStats;
+
 pub use self::{
     read::{FdReader, Read},
-    verify::{verify, verify_assumption, verify_integrity, VerifyIntegrityError},
+    stream::JournalStream,
+    verify::{
+        assumptions, assumptions_digest, verify, verify_assumption, verify_integrity,
+        VerifyIntegrityError,
+    },
     write::{FdWriter, Write},
 };
 
-static mut HASHER: OnceCell<Sha256> = OnceCell::new();
+static mut HASHER: OnceCell<JournalHasher> = OnceCell::new();
+
+/// The [JournalHash] used by [HASHER], selected with [set_journal_hash].
+static mut JOURNAL_HASH: JournalHash = JournalHash::Sha256;
+
+/// Whether any bytes have been written to the journal.
+static mut JOURNAL_WRITTEN: bool = false;
 
 /// Digest of the running list of [Assumptions], generated by the [self::verify] and
 /// [self::verify_integrity] calls made by the guest.
 static mut ASSUMPTIONS_DIGEST: MaybePruned<Assumptions> = MaybePruned::Pruned(Digest::ZERO);
 
+/// The [Assumption]s added by the guest, in the order they were added.
+static mut ASSUMPTIONS: Vec<Assumption> = Vec::new();
+
 /// A random 16 byte value initialized to random data, provided by the host, on
 /// guest start and upon resuming from a pause. Setting this value ensures that
 /// the total memory image has at least 128 bits of entropy, preventing
@@ -119,7 +138,7 @@ static mut MEMORY_IMAGE_ENTROPY: [u32; 4] = [0u32; 4];
 /// Initialize globals before program main
 pub(crate) fn init() {
     unsafe {
-        HASHER.set(Sha256::new()).unwrap();
+        HASHER.set(JournalHasher::new(JOURNAL_HASH)).unwrap();
         syscall::sys_rand(
             MEMORY_IMAGE_ENTROPY.as_mut_ptr(),
             MEMORY_IMAGE_ENTROPY.len(),
@@ -131,10 +150,11 @@ pub(crate) fn init() {
 pub(crate) fn finalize(halt: bool, user_exit: u8) {
     unsafe {
         let hasher = HASHER.take();
-        let journal_digest: Digest = hasher.unwrap().finalize().as_slice().try_into().unwrap();
+        let journal_digest = hasher.unwrap().finalize();
         let output = Output {
             journal: MaybePruned::Pruned(journal_digest),
             assumptions: MaybePruned::Pruned(ASSUMPTIONS_DIGEST.digest()),
+            journal_hash: JOURNAL_HASH,
         };
         let output_words: [u32; 8] = output.digest().into();
 
@@ -371,6 +391,104 @@ pub fn commit_slice<T: Pod>(slice: &[T]) {
     journal().write_slice(slice);
 }
 
+/// Commit the given words to the journal.
+///
+/// Data in the journal is included in the receipt and is available to the
+/// verifier. It is considered "public" data.
+///
+/// Each word is committed as 4 little-endian bytes, without any framing. This
+/// is intended for guests which construct their own canonical encoding of the
+/// journal, such as ABI-encoded outputs for consumption by a Solidity
+/// contract. To commit raw bytes, use [commit_slice].
+///
+/// # Example
+///
+/// ```no_run
+/// use risc0_zkvm::guest::env;
+///
+/// let words = [1u32, 2, 3, 4];
+/// env::commit_words(&words);
+/// ```
+pub fn commit_words(words: &[u32]) {
+    commit_slice(words);
+}
+
+/// Select the hash function used to digest the journal in the receipt claim.
+///
+/// By default the journal is digested with SHA-256. Verifiers that recompute the journal digest
+/// inside a circuit, such as an on-chain verifier of a SNARK-wrapped receipt, may instead prefer
+/// [JournalHash::Poseidon2]. The choice is recorded in the claim, and verifiers must expect it
+/// using `VerifierContext::with_journal_hash`; [Receipt::verify](crate::Receipt::verify) rejects it
+/// otherwise.
+///
+/// This must be called before anything is committed to the journal, and panics otherwise.
+///
+/// Composition only supports [JournalHash::Sha256]: a receipt for a guest that selects any
+/// other hash cannot be verified by another guest with [verify], and a conditional receipt
+/// for such a guest cannot be resolved.
+///
+/// # Example
+///
+/// ```no_run
+/// use risc0_zkvm::{guest::env, JournalHash};
+///
+/// env::set_journal_hash(JournalHash::Poseidon2);
+/// env::commit(&42u32);
+/// ```
+pub fn set_journal_hash(hash: JournalHash) {
+    unsafe {
+        assert!(
+            !JOURNAL_WRITTEN,
+            "set_journal_hash must be called before committing to the journal"
+        );
+        JOURNAL_HASH = hash;
+        syscall::sys_journal_hash(hash.as_u32());
+        *HASHER.get_mut().unwrap() = JournalHasher::new(hash);
+    }
+}
+
+/// Commit the given data to the journal, preceded by the digest of its [JournalSchema].
+///
+/// The journal can then be decoded by verifiers which do not share the type `T`, using the
+/// schema declared with [journal_schema!](crate::journal_schema!). This must be the only commit
+/// made by the guest, so that the journal holds exactly the schema digest and the data.
+///
+/// # Example
+///
+/// ```ignore
+/// use risc0_zkvm::guest::env;
+///
+/// env::commit_with_schema(&Output { value: 42, owner: "alice".into() });
+/// ```
+pub fn commit_with_schema<T: JournalType + Serialize>(data: &T) {
+    commit(&JournalSchema::of::<T>().digest());
+    commit(data);
+}
+
+/// Start streaming an output that is too large to commit to the journal.
+///
+/// Data written to the returned [JournalStream] is sent to the host as it is produced, rather
+/// than being buffered in guest memory, and is hashed incrementally. When
+/// [JournalStream::finish] is called, the digest and length of the streamed data are committed
+/// to the journal as a [StreamCommitment][crate::StreamCommitment], which the verifier can check
+/// the streamed data against. On the host, the stream is received by the writer passed to
+/// `ExecutorEnvBuilder::journal_stream`.
+///
+/// # Example
+///
+/// ```no_run
+/// use risc0_zkvm::guest::env::{self, Write};
+///
+/// let mut stream = env::commit_stream();
+/// for chunk in [[1u8; 1024], [2u8; 1024]] {
+///     stream.write_slice(&chunk);
+/// }
+/// let commitment = stream.finish();
+/// ```
+pub fn commit_stream() -> JournalStream {
+    JournalStream::new()
+}
+
 /// Return the number of processor cycles that have occurred since the guest
 /// began.
 ///
@@ -379,6 +497,28 @@ pub fn cycle_count() -> u64 {
     sys_cycle_count()
 }
 
+/// Return statistics about heap allocations made by the guest so far.
+///
+/// This is useful for tuning memory-hungry guests. The default bump-pointer allocator never
+/// reclaims memory, so it reports the high-water mark of the heap. With the `heap-embedded-alloc`
+/// feature, freed memory is reclaimed, and a peak much smaller than the total shows how much the
+/// guest saves over the bump-pointer allocator.
+///
+/// This is only available in the guest.
+///
+/// # Example
+///
+/// ```ignore
+/// use risc0_zkvm::guest::env;
+///
+/// let stats = env::heap_stats();
+/// env::log(&format!("heap peak: {} bytes", stats.peak));
+/// ```
+#[cfg(target_os = "zkvm")]
+pub fn heap_stats() -> HeapStats {
+    risc0_zkvm_platform::heap::stats()
+}
+
 /// Print a message to the debug console.
 pub fn log(msg: &str) {
     let msg = msg.as_bytes();
@@ -400,7 +540,10 @@ pub fn stderr() -> FdWriter<impl for<'a> Fn(&'a [u8])> {
 /// Return a writer for the JOURNAL.
 pub fn journal() -> FdWriter<impl for<'a> Fn(&'a [u8])> {
     FdWriter::new(fileno::JOURNAL, |bytes| {
-        unsafe { HASHER.get_mut().unwrap_unchecked().update(bytes) };
+        unsafe {
+            JOURNAL_WRITTEN |= !bytes.is_empty();
+            HASHER.get_mut().unwrap_unchecked().update(bytes)
+        };
     })
 }
 
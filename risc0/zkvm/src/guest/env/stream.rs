@@ -0,0 +1,102 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytemuck::Pod;
+use risc0_zkvm_platform::{fileno, syscall::sys_write, WORD_SIZE};
+use serde::Serialize;
+
+use super::Write;
+use crate::{
+    serde::{Serializer, WordWrite},
+    sha::{
+        rust_crypto::{Digest as _, Sha256},
+        Digest,
+    },
+    StreamCommitment,
+};
+
+/// Writer for outputs that are too large to commit to the journal.
+///
+/// Bytes written to the stream are sent to the host on the `JOURNAL_STREAM` file descriptor and
+/// hashed incrementally. Calling [JournalStream::finish] commits a [StreamCommitment] to the
+/// journal in place of the streamed bytes. Returned by [super::commit_stream].
+pub struct JournalStream {
+    hasher: Sha256,
+    len: u64,
+}
+
+impl JournalStream {
+    pub(crate) fn new() -> Self {
+        Self {
+            hasher: Sha256::new(),
+            len: 0,
+        }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        unsafe { sys_write(fileno::JOURNAL_STREAM, bytes.as_ptr(), bytes.len()) }
+        self.hasher.update(bytes);
+        self.len += bytes.len() as u64;
+    }
+
+    /// Finish the stream, committing the [StreamCommitment] to the streamed bytes to the journal.
+    pub fn finish(self) -> StreamCommitment {
+        let digest: Digest = self.hasher.finalize().as_slice().try_into().unwrap();
+        let commitment = StreamCommitment {
+            digest,
+            len: self.len,
+        };
+        super::commit(&commitment);
+        commitment
+    }
+}
+
+impl Write for JournalStream {
+    fn write<T: Serialize>(&mut self, val: T) {
+        val.serialize(&mut Serializer::new(self)).unwrap();
+    }
+
+    fn write_slice<T: Pod>(&mut self, buf: &[T]) {
+        self.write_bytes(bytemuck::cast_slice(buf));
+    }
+}
+
+impl WordWrite for JournalStream {
+    fn write_words(&mut self, words: &[u32]) -> crate::serde::Result<()> {
+        self.write_bytes(bytemuck::cast_slice(words));
+        Ok(())
+    }
+
+    fn write_padded_bytes(&mut self, bytes: &[u8]) -> crate::serde::Result<()> {
+        self.write_bytes(bytes);
+        let unaligned = bytes.len() % WORD_SIZE;
+        if unaligned != 0 {
+            let pad_bytes = WORD_SIZE - unaligned;
+            self.write_bytes(&[0u8; WORD_SIZE][..pad_bytes]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for JournalStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.write_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::vec::Vec;
 use core::{convert::Infallible, fmt};
 
 use bytemuck::Pod;
@@ -20,7 +21,7 @@ use risc0_zkvm_platform::syscall::sys_verify_integrity;
 
 use crate::{sha::Digestible, Assumption, MaybePruned, PrunedValueError, ReceiptClaim};
 
-use super::ASSUMPTIONS_DIGEST;
+use super::{ASSUMPTIONS, ASSUMPTIONS_DIGEST};
 
 /// Verify there exists a receipt for an execution with `image_id` and `journal`.
 ///
@@ -53,13 +54,10 @@ pub fn verify(image_id: impl Into<Digest>, journal: &[impl Pod]) -> Result<(), I
         // Use the zero digest as the control root, which indicates that the assumption is a zkVM
         // assumption to be verified with the same control root as the current execution.
         sys_verify_integrity(claim_digest.as_ref(), Digest::ZERO.as_ref());
-        ASSUMPTIONS_DIGEST.add(
-            Assumption {
-                claim: claim_digest,
-                control_root: Digest::ZERO,
-            }
-            .into(),
-        );
+        add_assumption(Assumption {
+            claim: claim_digest,
+            control_root: Digest::ZERO,
+        });
     }
 
     Ok(())
@@ -96,13 +94,10 @@ pub fn verify_integrity(claim: &ReceiptClaim) -> Result<(), VerifyIntegrityError
         // Use the zero digest as the control root, which indicates that the assumption is a zkVM
         // assumption to be verified with the same control root as the current execution.
         sys_verify_integrity(claim_digest.as_ref(), Digest::ZERO.as_ref());
-        ASSUMPTIONS_DIGEST.add(
-            Assumption {
-                claim: claim_digest,
-                control_root: Digest::ZERO,
-            }
-            .into(),
-        );
+        add_assumption(Assumption {
+            claim: claim_digest,
+            control_root: Digest::ZERO,
+        });
     }
 
     Ok(())
@@ -164,14 +159,46 @@ impl std::error::Error for VerifyIntegrityError {}
 pub fn verify_assumption(claim: Digest, control_root: Digest) -> Result<(), Infallible> {
     unsafe {
         sys_verify_integrity(claim.as_ref(), control_root.as_ref());
-        ASSUMPTIONS_DIGEST.add(
-            Assumption {
-                claim,
-                control_root,
-            }
-            .into(),
-        );
+        add_assumption(Assumption {
+            claim,
+            control_root,
+        });
     }
 
     Ok(())
 }
+
+/// Record an assumption added by one of the verify calls.
+unsafe fn add_assumption(assumption: Assumption) {
+    ASSUMPTIONS_DIGEST.add(assumption.clone().into());
+    ASSUMPTIONS.push(assumption);
+}
+
+/// Returns the assumptions added so far by calls to [verify], [verify_integrity] and
+/// [verify_assumption], in the order they were added.
+///
+/// Every assumption must be resolved before the receipt for this execution is unconditional, so
+/// this is the exact list of sub-proofs this execution relies upon. Guests can check it against
+/// the set of proofs their protocol requires, or commit [assumptions_digest] to the journal.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use risc0_zkvm::guest::env;
+///
+/// env::verify(HELLO_WORLD_ID, b"hello world".as_slice()).unwrap();
+/// assert_eq!(env::assumptions().len(), 1);
+/// env::commit(&env::assumptions_digest());
+/// ```
+pub fn assumptions() -> Vec<Assumption> {
+    unsafe { ASSUMPTIONS.clone() }
+}
+
+/// Returns the digest of the assumptions added so far, as it will be recorded in the
+/// [Output](crate::Output) of the receipt claim.
+///
+/// This is equal to the digest of an [Assumptions](crate::Assumptions) list holding
+/// [assumptions] with the most recently added first.
+pub fn assumptions_digest() -> Digest {
+    unsafe { ASSUMPTIONS_DIGEST.digest() }
+}
@@ -77,14 +77,23 @@
 
 #![deny(missing_docs)]
 
+pub mod collections;
 pub mod env;
+#[doc(hidden)]
+pub mod test;
+#[cfg(all(target_os = "zkvm", feature = "unwind"))]
+mod unwind;
+
 pub use risc0_zkp::core::hash::sha;
 
 #[cfg(target_os = "zkvm")]
 use core::arch::asm;
 
+use risc0_zkp::core::digest::DIGEST_BYTES;
 use risc0_zkvm_platform::syscall::sys_panic;
 
+#[cfg(all(target_os = "zkvm", feature = "unwind"))]
+pub use self::unwind::catch_unwind;
 pub use crate::entry;
 
 #[cfg(target_os = "zkvm")]
@@ -118,6 +127,10 @@ pub fn abort(msg: &str) -> ! {
 /// When `std` is enabled, the entrypoint will be linked automatically and this macro is not
 /// required.
 ///
+/// The macro also embeds the digest of the guest source inputs in the ELF when the guest is built
+/// with `GuestOptions::source_digest` in `risc0-build`. It is read by the host with
+/// `risc0_binfmt::source_digest`.
+///
 /// # Example
 ///
 /// ```ignore
@@ -137,14 +150,66 @@ macro_rules! entry {
         // Include generated main in a module so we don't conflict
         // with any other definitions of "main" in this file.
         mod zkvm_generated_main {
+            // Evaluated when compiling the guest itself, rather than the risc0-zkvm crate, which
+            // may be shared by guests built with different digests.
+            const SOURCE_DIGEST_HEX: Option<&str> = option_env!("RISC0_SOURCE_DIGEST");
+
+            #[used]
+            #[link_section = ".risc0.source_digest"]
+            static SOURCE_DIGEST: [u8; $crate::guest::source_digest_len(SOURCE_DIGEST_HEX)] =
+                $crate::guest::decode_source_digest(SOURCE_DIGEST_HEX);
+
             #[no_mangle]
             fn main() {
+                // Keep the source digest from being discarded by the linker.
+                if !SOURCE_DIGEST.is_empty() {
+                    core::hint::black_box(&SOURCE_DIGEST);
+                }
                 super::ZKVM_ENTRY()
             }
         }
     };
 }
 
+/// Length of the source digest embedded by [entry!], given the `RISC0_SOURCE_DIGEST` environment
+/// variable set by `risc0-build`. Guests built without it are unaffected.
+#[doc(hidden)]
+pub const fn source_digest_len(hex: Option<&str>) -> usize {
+    match hex {
+        Some(_) => DIGEST_BYTES,
+        None => 0,
+    }
+}
+
+/// Decode the hex encoded source digest set by `risc0-build`, if any.
+#[doc(hidden)]
+pub const fn decode_source_digest<const N: usize>(hex: Option<&str>) -> [u8; N] {
+    let mut digest = [0u8; N];
+    let Some(hex) = hex else {
+        return digest;
+    };
+    let hex = hex.as_bytes();
+    assert!(
+        hex.len() == 2 * N,
+        "RISC0_SOURCE_DIGEST must be a hex encoded digest"
+    );
+    let mut i = 0;
+    while i < N {
+        digest[i] = hex_nibble(hex[2 * i]) << 4 | hex_nibble(hex[2 * i + 1]);
+        i += 1;
+    }
+    digest
+}
+
+const fn hex_nibble(c: u8) -> u8 {
+    match c {
+        b'0'..=b'9' => c - b'0',
+        b'a'..=b'f' => c - b'a' + 10,
+        b'A'..=b'F' => c - b'A' + 10,
+        _ => panic!("RISC0_SOURCE_DIGEST must be a hex encoded digest"),
+    }
+}
+
 #[cfg(target_os = "zkvm")]
 #[no_mangle]
 unsafe extern "C" fn __start() -> ! {
@@ -201,11 +266,26 @@ pub fn memory_barrier<T>(ptr: *const T) {
     core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst)
 }
 
+// The panic runtime of std aborts on the zkvm target, so `std::panic::catch_unwind`
+// cannot catch a panic there. Unwinding is instead provided by the `unwind` feature,
+// which requires a no_std guest and `guest::catch_unwind`.
+#[cfg(all(target_os = "zkvm", panic = "unwind", not(feature = "unwind")))]
+compile_error!("guests built with panic=unwind require the `unwind` feature of risc0-zkvm");
+#[cfg(all(target_os = "zkvm", feature = "unwind", feature = "std"))]
+compile_error!(
+    "the `unwind` feature of risc0-zkvm cannot be combined with `std`: the panic runtime of std \
+     aborts on the zkvm target, so panics must be caught with `risc0_zkvm::guest::catch_unwind` \
+     in a no_std guest"
+);
+
 // When std is not linked, register a panic handler here so the user does not
 // have to. If std is linked, it will define the panic handler instead. This
 // panic handler must not be included.
 #[cfg(all(target_os = "zkvm", not(feature = "std")))]
 #[panic_handler]
 fn panic_impl(panic_info: &core::panic::PanicInfo) -> ! {
+    #[cfg(feature = "unwind")]
+    unwind::begin_panic(panic_info);
+    #[cfg(not(feature = "unwind"))]
     risc0_zkvm_platform::rust_rt::panic_fault(panic_info);
 }
@@ -0,0 +1,82 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Support for running tests inside the zkVM, used by the code generated by `#[risc0_test]`.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::guest::env;
+
+/// The test index sent by the host to request the descriptions of the tests, instead of running
+/// one.
+pub const LIST_TESTS: u32 = u32::MAX;
+
+/// A test registered by `#[risc0_test]`.
+pub struct Test {
+    /// The name of the test function.
+    pub name: &'static str,
+
+    /// The test function.
+    pub run: fn(),
+
+    /// Whether the test is marked `#[ignore]`.
+    pub ignore: bool,
+
+    /// Whether the test is marked `#[should_panic]`, and the message the panic is expected to
+    /// contain, if any.
+    pub should_panic: Option<Option<&'static str>>,
+}
+
+/// The description of a test, sent to the host when it lists the tests.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TestDesc {
+    /// The name of the test function.
+    pub name: String,
+
+    /// Whether the test is marked `#[ignore]`.
+    pub ignore: bool,
+
+    /// Whether the test is marked `#[should_panic]`, and the message the panic is expected to
+    /// contain, if any.
+    pub should_panic: Option<Option<String>>,
+}
+
+/// Run the test selected by the host.
+///
+/// The host sends the index of the test to run, or [LIST_TESTS] to have a [TestDesc] of each test
+/// committed to the journal. A test fails by panicking, which aborts the guest.
+pub fn run(tests: &[Test]) {
+    let index: u32 = env::read();
+    if index == LIST_TESTS {
+        let descs: Vec<TestDesc> = tests
+            .iter()
+            .map(|test| TestDesc {
+                name: test.name.to_string(),
+                ignore: test.ignore,
+                should_panic: test.should_panic.map(|expected| expected.map(String::from)),
+            })
+            .collect();
+        env::commit(&descs);
+        return;
+    }
+    let test = tests
+        .get(index as usize)
+        .unwrap_or_else(|| panic!("no test with index {index}"));
+    (test.run)();
+}
@@ -0,0 +1,40 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Unwinding runtime for guests built with `panic=unwind`.
+//!
+//! The panic runtime of `std` aborts on the zkVM target, so `std::panic::catch_unwind` never
+//! catches a panic there. This module instead provides the unwinder and the personality routine
+//! from the [unwinding] crate to `no_std` guests. The unwinder finds the frame description entries
+//! through the `__executable_start`, `__etext` and `__eh_frame` symbols, which `risc0-build`
+//! defines when `GuestOptions::panic_unwind` is set.
+
+use alloc::{boxed::Box, string::ToString};
+use core::{any::Any, panic::PanicInfo};
+
+/// Invokes `f`, capturing the cause of a panic if one occurs.
+///
+/// This is the guest counterpart of `std::panic::catch_unwind`. On a panic, the payload is the
+/// formatted panic message as a [String](alloc::string::String).
+pub fn catch_unwind<R, F: FnOnce() -> R>(f: F) -> Result<R, Box<dyn Any + Send>> {
+    unwinding::panic::catch_unwind(f)
+}
+
+/// Unwinds the stack to the innermost [catch_unwind], or faults if there is none.
+pub(crate) fn begin_panic(panic_info: &PanicInfo) -> ! {
+    let msg = panic_info.to_string();
+    // Only returns if no frame catches the panic.
+    let _ = unwinding::panic::begin_panic(Box::new(msg));
+    risc0_zkvm_platform::rust_rt::panic_fault(panic_info);
+}
@@ -14,9 +14,10 @@
 
 use std::path::Path;
 
-use anyhow::{anyhow, bail, Result};
+use anyhow::{anyhow, bail, ensure, Result};
 use bytes::Bytes;
 use prost::Message;
+use risc0_zkp::core::digest::Digest;
 
 use super::{
     malformed_err, pb, Asset, AssetRequest, ConnectionWrapper, Connector, ParentProcessConnector,
@@ -24,7 +25,10 @@ use super::{
 };
 use crate::{
     get_version,
-    host::{api::SegmentInfo, client::prove::get_r0vm_path},
+    host::{
+        api::SegmentInfo,
+        client::{method_registry::MethodRegistry, prove::get_r0vm_path},
+    },
     receipt::{AssumptionReceipt, SegmentReceipt, SuccinctReceipt},
     ExecutorEnv, Journal, ProveInfo, ProverOpts, Receipt, ReceiptClaim,
 };
@@ -32,6 +36,13 @@ use crate::{
 /// A client implementation for interacting with a zkVM server.
 pub struct Client {
     connector: Box<dyn Connector>,
+    registry: MethodRegistry,
+}
+
+// The guest program to run, either sent to the server or resolved by it.
+enum Binary {
+    Asset(Asset),
+    ImageId(Digest),
 }
 
 impl Default for Client {
@@ -61,7 +72,19 @@ impl Client {
     /// Construct a [Client] using the specified [Connector] to establish a
     /// connection with the server.
     pub fn with_connector(connector: Box<dyn Connector>) -> Self {
-        Self { connector }
+        Self {
+            connector,
+            registry: MethodRegistry::new(),
+        }
+    }
+
+    /// Use the given [MethodRegistry] to resolve image IDs passed to
+    /// [prove_image_id](Self::prove_image_id) and [execute_image_id](Self::execute_image_id).
+    ///
+    /// Methods which are not in this registry are resolved by the server.
+    pub fn with_registry(mut self, registry: MethodRegistry) -> Self {
+        self.registry = registry;
+        self
     }
 
     /// Prove the specified ELF binary.
@@ -70,13 +93,35 @@ impl Client {
         env: &ExecutorEnv<'_>,
         opts: &ProverOpts,
         binary: Asset,
+    ) -> Result<ProveInfo> {
+        self.prove_binary(env, opts, Binary::Asset(binary))
+    }
+
+    /// Prove the method with the specified image ID.
+    ///
+    /// The ELF binary is taken from the registry set by [with_registry](Self::with_registry) if
+    /// present, or otherwise from the registry of the server.
+    pub fn prove_image_id(
+        &self,
+        env: &ExecutorEnv<'_>,
+        opts: &ProverOpts,
+        image_id: Digest,
+    ) -> Result<ProveInfo> {
+        self.prove_binary(env, opts, self.resolve_image_id(image_id))
+    }
+
+    fn prove_binary(
+        &self,
+        env: &ExecutorEnv<'_>,
+        opts: &ProverOpts,
+        binary: Binary,
     ) -> Result<ProveInfo> {
         let mut conn = self.connect()?;
 
         let request = pb::api::ServerRequest {
             kind: Some(pb::api::server_request::Kind::Prove(
                 pb::api::ProveRequest {
-                    env: Some(self.make_execute_env(env, binary.try_into()?)?),
+                    env: Some(self.make_execute_env(env, binary)?),
                     opts: Some(opts.clone().into()),
                     receipt_out: Some(pb::api::AssetRequest {
                         kind: Some(pb::api::asset_request::Kind::Inline(())),
@@ -95,6 +140,7 @@ impl Client {
 
         let prove_info_bytes = asset.as_bytes()?;
         let prove_info_pb = pb::core::ProveInfo::decode(prove_info_bytes)?;
+        env.nondeterministic.set(prove_info_pb.nondeterministic);
         prove_info_pb.try_into()
     }
 
@@ -106,6 +152,37 @@ impl Client {
         segments_out: AssetRequest,
         segment_callback: F,
     ) -> Result<SessionInfo>
+    where
+        F: FnMut(SegmentInfo, Asset) -> Result<()>,
+    {
+        self.execute_binary(env, Binary::Asset(binary), segments_out, segment_callback)
+    }
+
+    /// Execute the method with the specified image ID.
+    ///
+    /// The ELF binary is taken from the registry set by [with_registry](Self::with_registry) if
+    /// present, or otherwise from the registry of the server.
+    pub fn execute_image_id<F>(
+        &self,
+        env: &ExecutorEnv<'_>,
+        image_id: Digest,
+        segments_out: AssetRequest,
+        segment_callback: F,
+    ) -> Result<SessionInfo>
+    where
+        F: FnMut(SegmentInfo, Asset) -> Result<()>,
+    {
+        let binary = self.resolve_image_id(image_id);
+        self.execute_binary(env, binary, segments_out, segment_callback)
+    }
+
+    fn execute_binary<F>(
+        &self,
+        env: &ExecutorEnv<'_>,
+        binary: Binary,
+        segments_out: AssetRequest,
+        segment_callback: F,
+    ) -> Result<SessionInfo>
     where
         F: FnMut(SegmentInfo, Asset) -> Result<()>,
     {
@@ -114,7 +191,7 @@ impl Client {
         let request = pb::api::ServerRequest {
             kind: Some(pb::api::server_request::Kind::Execute(
                 pb::api::ExecuteRequest {
-                    env: Some(self.make_execute_env(env, binary.try_into()?)?),
+                    env: Some(self.make_execute_env(env, binary)?),
                     segments_out: Some(segments_out.try_into()?),
                 },
             )),
@@ -447,13 +524,29 @@ impl Client {
         Ok(conn)
     }
 
+    fn resolve_image_id(&self, image_id: Digest) -> Binary {
+        match self.registry.get_by_image_id(&image_id) {
+            Some(method) => Binary::Asset(Asset::Inline(method.elf.clone().into())),
+            None => Binary::ImageId(image_id),
+        }
+    }
+
     fn make_execute_env(
         &self,
         env: &ExecutorEnv<'_>,
-        binary: pb::api::Asset,
+        binary: Binary,
     ) -> Result<pb::api::ExecutorEnv> {
+        ensure!(
+            env.cancellation_token.is_none(),
+            "cancellation tokens are not supported by the remote executor; use a timeout instead"
+        );
+        let (binary, image_id) = match binary {
+            Binary::Asset(asset) => (Some(asset.try_into()?), None),
+            Binary::ImageId(image_id) => (None, Some(image_id.into())),
+        };
         Ok(pb::api::ExecutorEnv {
-            binary: Some(binary),
+            binary,
+            image_id,
             env_vars: env.env_vars.clone(),
             args: env.args.clone(),
             slice_ios: env.slice_io.borrow().inner.keys().cloned().collect(),
@@ -461,6 +554,11 @@ impl Client {
             write_fds: env.posix_io.borrow().write_fds(),
             segment_limit_po2: env.segment_limit_po2,
             session_limit: env.session_limit,
+            input_digest: env.input_digest.map(Into::into),
+            timeout_ms: env
+                .timeout
+                .map(|timeout| timeout.as_millis().try_into().unwrap_or(u64::MAX)),
+            resource_limits: Some(env.resource_limits.clone().into()),
             trace_events: (!env.trace.is_empty()).then_some(()),
             pprof_out: env
                 .pprof_out
@@ -20,7 +20,7 @@ use risc0_binfmt::SystemState;
 use risc0_zkp::core::digest::Digest;
 use serde::Serialize;
 
-use super::{malformed_err, path_to_string, pb, Asset, AssetRequest};
+use super::{malformed_err, path_to_string, pb, Asset, AssetRequest, RemoteError};
 use crate::{
     receipt::{
         merkle::MerkleProof, segment::decode_receipt_claim_from_seal, CompositeReceipt,
@@ -28,8 +28,9 @@ use crate::{
         SuccinctReceipt,
     },
     receipt_claim::Unknown,
-    Assumption, Assumptions, ExitCode, Groth16Receipt, Input, Journal, MaybePruned, Output,
-    ProveInfo, ProverOpts, Receipt, ReceiptClaim, ReceiptKind, SessionStats, TraceEvent,
+    Assumption, Assumptions, ErrorCode, ExitCode, Groth16Receipt, Input, Journal, JournalHash,
+    MaybePruned, Output, ProveInfo, ProverOpts, Receipt, ReceiptClaim, ReceiptKind, ResourceLimits,
+    SessionStats, TraceEvent,
 };
 
 mod ver {
@@ -199,13 +200,18 @@ impl From<anyhow::Error> for pb::api::GenericError {
     fn from(err: anyhow::Error) -> Self {
         Self {
             reason: err.to_string(),
+            code: ErrorCode::of(&err).code(),
         }
     }
 }
 
 impl From<pb::api::GenericError> for anyhow::Error {
     fn from(err: pb::api::GenericError) -> Self {
-        anyhow::Error::msg(err.reason)
+        RemoteError {
+            code: err.code,
+            reason: err.reason,
+        }
+        .into()
     }
 }
 
@@ -227,6 +233,7 @@ impl TryFrom<pb::api::ProverOpts> for ProverOpts {
                 .into_iter()
                 .map(TryInto::try_into)
                 .collect::<Result<_>>()?,
+            verify_segments: !opts.skip_segment_verification,
         })
     }
 }
@@ -238,6 +245,31 @@ impl From<ProverOpts> for pb::api::ProverOpts {
             prove_guest_errors: opts.prove_guest_errors,
             receipt_kind: opts.receipt_kind as i32,
             control_ids: opts.control_ids.into_iter().map(Into::into).collect(),
+            skip_segment_verification: !opts.verify_segments,
+        }
+    }
+}
+
+impl From<ResourceLimits> for pb::api::ResourceLimits {
+    fn from(limits: ResourceLimits) -> Self {
+        Self {
+            max_cycles: limits.max_cycles,
+            max_memory_pages: limits.max_memory_pages.map(|x| x as u64),
+            max_journal_bytes: limits.max_journal_bytes.map(|x| x as u64),
+            max_syscalls: limits.max_syscalls,
+        }
+    }
+}
+
+impl From<pb::api::ResourceLimits> for ResourceLimits {
+    fn from(limits: pb::api::ResourceLimits) -> Self {
+        // Limits beyond the address space of the server can't be reached, so they saturate.
+        let to_usize = |x: u64| usize::try_from(x).unwrap_or(usize::MAX);
+        Self {
+            max_cycles: limits.max_cycles,
+            max_memory_pages: limits.max_memory_pages.map(to_usize),
+            max_journal_bytes: limits.max_journal_bytes.map(to_usize),
+            max_syscalls: limits.max_syscalls,
         }
     }
 }
@@ -296,6 +328,7 @@ impl From<ProveInfo> for pb::core::ProveInfo {
         Self {
             receipt: Some(value.receipt.into()),
             stats: Some(value.stats.into()),
+            nondeterministic: None,
         }
     }
 }
@@ -811,6 +844,7 @@ impl From<Output> for pb::core::Output {
         Self {
             journal: Some(value.journal.into()),
             assumptions: Some(value.assumptions.into()),
+            journal_hash: value.journal_hash.as_u32(),
         }
     }
 }
@@ -822,6 +856,11 @@ impl TryFrom<pb::core::Output> for Output {
         Ok(Self {
             journal: value.journal.ok_or(malformed_err())?.try_into()?,
             assumptions: value.assumptions.ok_or(malformed_err())?.try_into()?,
+            journal_hash: match value.journal_hash {
+                0 => JournalHash::Sha256,
+                1 => JournalHash::Poseidon2,
+                _ => return Err(malformed_err()),
+            },
         })
     }
 }
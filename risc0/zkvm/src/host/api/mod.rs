@@ -309,6 +309,21 @@ fn malformed_err() -> anyhow::Error {
     anyhow!("Malformed error")
 }
 
+/// An error reported by the remote end of a connection, along with its [crate::ErrorCode].
+#[derive(Debug)]
+pub(crate) struct RemoteError {
+    pub code: u32,
+    pub reason: String,
+}
+
+impl std::fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+impl std::error::Error for RemoteError {}
+
 impl pb::api::Asset {
     fn as_bytes(&self) -> Result<Bytes> {
         let bytes = match self.kind.as_ref().ok_or(malformed_err())? {
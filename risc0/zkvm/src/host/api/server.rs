@@ -16,6 +16,7 @@ use std::{
     error::Error as StdError,
     io::{BufReader, Error as IoError, ErrorKind as IoErrorKind, Read, Write},
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use anyhow::{anyhow, bail, Result};
@@ -25,7 +26,10 @@ use prost::Message;
 use super::{malformed_err, path_to_string, pb, ConnectionWrapper, Connector, TcpConnector};
 use crate::{
     get_prover_server, get_version,
-    host::{client::slice_io::SliceIo, server::session::NullSegmentRef},
+    host::{
+        client::{method_registry::MethodRegistry, slice_io::SliceIo},
+        server::session::NullSegmentRef,
+    },
     Assumption, ExecutorEnv, ExecutorImpl, InnerAssumptionReceipt, ProverOpts, Receipt,
     ReceiptClaim, Segment, SegmentReceipt, SuccinctReceipt, TraceCallback, TraceEvent,
     VerifierContext,
@@ -34,6 +38,7 @@ use crate::{
 /// A server implementation for handling requests by clients of the zkVM.
 pub struct Server {
     connector: Box<dyn Connector>,
+    registry: MethodRegistry,
 }
 struct PosixIoProxy {
     fd: u32,
@@ -191,7 +196,10 @@ impl TraceCallback for TraceProxy {
 impl Server {
     /// Construct a new [Server] with the specified [Connector].
     pub fn new(connector: Box<dyn Connector>) -> Self {
-        Self { connector }
+        Self {
+            connector,
+            registry: MethodRegistry::new(),
+        }
     }
 
     /// Construct a new [Server] which will connect to the specified TCP/IP
@@ -201,6 +209,13 @@ impl Server {
         Self::new(Box::new(connector))
     }
 
+    /// Use the given [MethodRegistry] to resolve requests which specify an image ID in place of an
+    /// ELF binary.
+    pub fn with_registry(mut self, registry: MethodRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
     /// Start the [Server] and run until all requests are complete.
     pub fn run(&self) -> Result<()> {
         tracing::debug!("connect");
@@ -257,15 +272,14 @@ impl Server {
     ) -> Result<()> {
         fn inner(
             conn: &mut ConnectionWrapper,
+            registry: &MethodRegistry,
             request: pb::api::ExecuteRequest,
         ) -> Result<pb::api::ServerReply> {
             let env_request = request.env.ok_or(malformed_err())?;
             let env = build_env(conn, &env_request)?;
 
-            let binary = env_request.binary.ok_or(malformed_err())?;
-
             let segments_out = request.segments_out.ok_or(malformed_err())?;
-            let bytes = binary.as_bytes()?;
+            let bytes = load_binary(registry, &env_request)?;
             let mut exec = ExecutorImpl::from_elf(env, &bytes)?;
 
             let session = exec.run_with_callback(|segment| {
@@ -317,11 +331,10 @@ impl Server {
             })
         }
 
-        let msg = inner(&mut conn, request).unwrap_or_else(|err| pb::api::ServerReply {
-            kind: Some(pb::api::server_reply::Kind::Error(pb::api::GenericError {
-                reason: err.to_string(),
-            })),
-        });
+        let msg =
+            inner(&mut conn, &self.registry, request).unwrap_or_else(|err| pb::api::ServerReply {
+                kind: Some(pb::api::server_reply::Kind::Error(err.into())),
+            });
 
         tracing::trace!("tx: {msg:?}");
         conn.send(msg)
@@ -330,20 +343,22 @@ impl Server {
     fn on_prove(&self, mut conn: ConnectionWrapper, request: pb::api::ProveRequest) -> Result<()> {
         fn inner(
             conn: &mut ConnectionWrapper,
+            registry: &MethodRegistry,
             request: pb::api::ProveRequest,
         ) -> Result<pb::api::ServerReply> {
             let env_request = request.env.ok_or(malformed_err())?;
             let env = build_env(conn, &env_request)?;
 
-            let binary = env_request.binary.ok_or(malformed_err())?;
-            let bytes = binary.as_bytes()?;
+            let bytes = load_binary(registry, &env_request)?;
 
             let opts: ProverOpts = request.opts.ok_or(malformed_err())?.try_into()?;
             let prover = get_prover_server(&opts)?;
             let ctx = VerifierContext::default();
+            let nondeterministic = env.nondeterministic.clone();
             let prove_info = prover.prove_with_ctx(env, &ctx, &bytes)?;
 
-            let prove_info: pb::core::ProveInfo = prove_info.into();
+            let mut prove_info: pb::core::ProveInfo = prove_info.into();
+            prove_info.nondeterministic = nondeterministic.get();
             let prove_info_bytes = prove_info.encode_to_vec();
             let asset = pb::api::Asset::from_bytes(
                 &request.receipt_out.ok_or(malformed_err())?,
@@ -362,11 +377,10 @@ impl Server {
             })
         }
 
-        let msg = inner(&mut conn, request).unwrap_or_else(|err| pb::api::ServerReply {
-            kind: Some(pb::api::server_reply::Kind::Error(pb::api::GenericError {
-                reason: err.to_string(),
-            })),
-        });
+        let msg =
+            inner(&mut conn, &self.registry, request).unwrap_or_else(|err| pb::api::ServerReply {
+                kind: Some(pb::api::server_reply::Kind::Error(err.into())),
+            });
 
         tracing::trace!("tx: {msg:?}");
         conn.send(msg)
@@ -404,11 +418,7 @@ impl Server {
         }
 
         let msg = inner(request).unwrap_or_else(|err| pb::api::ProveSegmentReply {
-            kind: Some(pb::api::prove_segment_reply::Kind::Error(
-                pb::api::GenericError {
-                    reason: err.to_string(),
-                },
-            )),
+            kind: Some(pb::api::prove_segment_reply::Kind::Error(err.into())),
         });
 
         tracing::trace!("tx: {msg:?}");
@@ -440,9 +450,7 @@ impl Server {
         }
 
         let msg = inner(request).unwrap_or_else(|err| pb::api::LiftReply {
-            kind: Some(pb::api::lift_reply::Kind::Error(pb::api::GenericError {
-                reason: err.to_string(),
-            })),
+            kind: Some(pb::api::lift_reply::Kind::Error(err.into())),
         });
 
         tracing::debug!("tx: {msg:?}");
@@ -478,9 +486,7 @@ impl Server {
         }
 
         let msg = inner(request).unwrap_or_else(|err| pb::api::JoinReply {
-            kind: Some(pb::api::join_reply::Kind::Error(pb::api::GenericError {
-                reason: err.to_string(),
-            })),
+            kind: Some(pb::api::join_reply::Kind::Error(err.into())),
         });
 
         tracing::debug!("tx: {msg:?}");
@@ -529,9 +535,7 @@ impl Server {
         }
 
         let msg = inner(request).unwrap_or_else(|err| pb::api::ResolveReply {
-            kind: Some(pb::api::resolve_reply::Kind::Error(pb::api::GenericError {
-                reason: err.to_string(),
-            })),
+            kind: Some(pb::api::resolve_reply::Kind::Error(err.into())),
         });
 
         tracing::debug!("tx: {msg:?}");
@@ -570,11 +574,7 @@ impl Server {
         }
 
         let msg = inner(request).unwrap_or_else(|err| pb::api::IdentityP254Reply {
-            kind: Some(pb::api::identity_p254_reply::Kind::Error(
-                pb::api::GenericError {
-                    reason: err.to_string(),
-                },
-            )),
+            kind: Some(pb::api::identity_p254_reply::Kind::Error(err.into())),
         });
 
         tracing::debug!("tx: {msg:?}");
@@ -610,11 +610,7 @@ impl Server {
         }
 
         let msg = inner(request).unwrap_or_else(|err| pb::api::CompressReply {
-            kind: Some(pb::api::compress_reply::Kind::Error(
-                pb::api::GenericError {
-                    reason: err.to_string(),
-                },
-            )),
+            kind: Some(pb::api::compress_reply::Kind::Error(err.into())),
         });
 
         tracing::debug!("tx: {msg:?}");
@@ -622,12 +618,27 @@ impl Server {
     }
 }
 
+fn load_binary(registry: &MethodRegistry, request: &pb::api::ExecutorEnv) -> Result<Bytes> {
+    if let Some(binary) = &request.binary {
+        return binary.as_bytes();
+    }
+    let image_id = request
+        .image_id
+        .clone()
+        .ok_or(malformed_err())?
+        .try_into()?;
+    let method = registry
+        .get_by_image_id(&image_id)
+        .ok_or_else(|| anyhow!("unknown image ID: {image_id}"))?;
+    Ok(method.elf.clone().into())
+}
+
 fn build_env<'a>(
     conn: &ConnectionWrapper,
     request: &pb::api::ExecutorEnv,
 ) -> Result<ExecutorEnv<'a>> {
     let mut env_builder = ExecutorEnv::builder();
-    env_builder.env_vars(request.env_vars.clone());
+    env_builder.env_vars(request.env_vars.clone().into_iter().collect());
     env_builder.args(&request.args);
     for fd in request.read_fds.iter() {
         let proxy = PosixIoProxy::new(*fd, conn.try_clone()?);
@@ -646,6 +657,15 @@ fn build_env<'a>(
         env_builder.segment_limit_po2(segment_limit_po2);
     }
     env_builder.session_limit(request.session_limit);
+    if let Some(input_digest) = request.input_digest.clone() {
+        env_builder.input_digest(input_digest.try_into()?);
+    }
+    if let Some(timeout_ms) = request.timeout_ms {
+        env_builder.timeout(Duration::from_millis(timeout_ms));
+    }
+    if let Some(limits) = request.resource_limits.clone() {
+        env_builder.resource_limits(limits.into());
+    }
     if request.trace_events.is_some() {
         let proxy = TraceProxy::new(conn.try_clone()?);
         env_builder.trace_callback(proxy);
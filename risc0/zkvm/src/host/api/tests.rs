@@ -32,8 +32,8 @@ use test_log::test;
 use super::{Asset, AssetRequest, ConnectionWrapper, Connector, TcpConnection};
 use crate::{
     receipt::SuccinctReceipt, recursion::MerkleGroup, ApiClient, ApiServer, ExecutorEnv,
-    InnerReceipt, ProverOpts, Receipt, ReceiptClaim, SegmentReceipt, SessionInfo,
-    SuccinctReceiptVerifierParameters, VerifierContext,
+    InnerReceipt, MethodRegistry, ProverOpts, Receipt, ReceiptClaim, ResourceLimits,
+    SegmentReceipt, SessionInfo, SuccinctReceiptVerifierParameters, VerifierContext,
 };
 
 struct TestClientConnector {
@@ -143,11 +143,19 @@ impl TestClient {
 }
 
 fn with_server<T, F: FnOnce() -> Result<T>>(addr: SocketAddr, f: F) -> T {
+    with_registry_server(addr, MethodRegistry::new(), f)
+}
+
+fn with_registry_server<T, F: FnOnce() -> Result<T>>(
+    addr: SocketAddr,
+    registry: MethodRegistry,
+    f: F,
+) -> T {
     let addr = addr.to_string();
     let handle = thread::Builder::new()
         .name("server".into())
         .spawn(move || {
-            let server = ApiServer::new_tcp(addr);
+            let server = ApiServer::new_tcp(addr).with_registry(registry);
             server.run().unwrap();
         })
         .unwrap();
@@ -181,6 +189,53 @@ fn prove() {
     receipt.verify(MULTI_TEST_ID).unwrap();
 }
 
+#[test]
+fn execute_resource_limits() {
+    let env = ExecutorEnv::builder()
+        .resource_limits(ResourceLimits {
+            max_journal_bytes: Some(4),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    let client = TestClient::new();
+    let err = with_server(client.addr, || {
+        Ok(client
+            .client
+            .execute(
+                &env,
+                Asset::Inline(HELLO_COMMIT_ELF.into()),
+                AssetRequest::Path(client.get_work_path()),
+                |_, _| Ok(()),
+            )
+            .unwrap_err())
+    });
+    assert!(err.to_string().contains("journal size quota exceeded"));
+}
+
+#[test]
+fn execute_image_id() {
+    let env = ExecutorEnv::builder()
+        .write(&MultiTestSpec::DoNothing)
+        .unwrap()
+        .build()
+        .unwrap();
+    let mut registry = MethodRegistry::new();
+    let image_id = registry.insert("MULTI_TEST", MULTI_TEST_ELF).unwrap();
+    assert_eq!(image_id, MULTI_TEST_ID.into());
+
+    let client = TestClient::new();
+    let session = with_registry_server(client.addr, registry, || {
+        client.client.execute_image_id(
+            &env,
+            image_id,
+            AssetRequest::Path(client.get_work_path()),
+            |_, _| Ok(()),
+        )
+    });
+    assert_eq!(session.exit_code, crate::ExitCode::Halted(0));
+}
+
 #[test]
 fn prove_segment_elf() {
     let env = ExecutorEnv::builder()
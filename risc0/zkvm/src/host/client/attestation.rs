@@ -0,0 +1,386 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export of verified receipts as [in-toto] statements carrying [SLSA provenance].
+//!
+//! A [Statement] is built from a receipt with [Statement::from_receipt] and signed into a
+//! [SignedStatement] by an [AttestationSigner]. With the `ed25519` feature, `Ed25519Signer`
+//! signs with a local Ed25519 key. Keys held elsewhere, such as in a KMS or HSM, are used by
+//! implementing [AttestationSigner] to forward the message to be signed.
+//!
+//! [in-toto]: https://github.com/in-toto/attestation/blob/main/spec/v1/statement.md
+//! [SLSA provenance]: https://slsa.dev/spec/v1.0/provenance
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "ed25519")]
+use anyhow::anyhow;
+use anyhow::{bail, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use risc0_binfmt::Digestible;
+use risc0_zkp::core::digest::Digest;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+
+use crate::Receipt;
+
+/// Type of an in-toto [Statement].
+pub const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+
+/// Type of the SLSA [Provenance] predicate.
+pub const PROVENANCE_PREDICATE_TYPE: &str = "https://slsa.dev/provenance/v1";
+
+/// Build type of the [Provenance] of a zkVM receipt.
+pub const RECEIPT_BUILD_TYPE: &str = "urn:risc0:zkvm:receipt:v1";
+
+/// Payload type of a [SignedStatement], as registered for in-toto statements.
+pub const IN_TOTO_PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+
+/// An in-toto statement attesting to the output of a zkVM execution.
+///
+/// The subject of the statement is the journal, identified by its SHA-256 digest, and the
+/// builder is the guest, identified by its image ID.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Statement {
+    /// Always [STATEMENT_TYPE].
+    #[serde(rename = "_type")]
+    pub statement_type: String,
+
+    /// The artifacts the statement is about.
+    pub subject: Vec<Subject>,
+
+    /// Always [PROVENANCE_PREDICATE_TYPE].
+    #[serde(rename = "predicateType")]
+    pub predicate_type: String,
+
+    /// The provenance of the subject.
+    pub predicate: Provenance,
+}
+
+/// An artifact attested to by a [Statement].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Subject {
+    /// The name of the artifact.
+    pub name: String,
+
+    /// Digests of the artifact, keyed by algorithm.
+    pub digest: BTreeMap<String, String>,
+}
+
+/// A SLSA provenance predicate, describing how the subject of a [Statement] was produced.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Provenance {
+    /// The inputs of the execution.
+    pub build_definition: BuildDefinition,
+
+    /// The entity that ran the execution.
+    pub run_details: RunDetails,
+}
+
+/// The inputs of the execution described by a [Provenance].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildDefinition {
+    /// Always [RECEIPT_BUILD_TYPE].
+    pub build_type: String,
+
+    /// The image ID of the guest, and the digest of the claim proven by the receipt.
+    pub external_parameters: BTreeMap<String, String>,
+}
+
+/// The entity that ran the execution described by a [Provenance].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunDetails {
+    /// The builder, i.e. the guest.
+    pub builder: Builder,
+}
+
+/// The builder of the subject of a [Statement].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Builder {
+    /// The identifier of the builder, of the form `urn:risc0:image-id:<image ID>`.
+    pub id: String,
+}
+
+impl Statement {
+    /// Verify the receipt against the given image ID, and construct a [Statement] attesting that
+    /// the guest with that image ID committed the journal of the receipt.
+    pub fn from_receipt(receipt: &Receipt, image_id: impl Into<Digest>) -> Result<Self> {
+        let image_id = image_id.into();
+        receipt.verify(image_id)?;
+        let claim = receipt.claim()?.digest();
+        Ok(Self::new(image_id, &receipt.journal.bytes, claim))
+    }
+
+    fn new(image_id: Digest, journal: &[u8], claim: Digest) -> Self {
+        let digest = BTreeMap::from([("sha256".to_string(), hex::encode(Sha256::digest(journal)))]);
+        let external_parameters = BTreeMap::from([
+            ("imageId".to_string(), image_id.to_string()),
+            ("claimDigest".to_string(), claim.to_string()),
+        ]);
+        Self {
+            statement_type: STATEMENT_TYPE.to_string(),
+            subject: vec![Subject {
+                name: "journal".to_string(),
+                digest,
+            }],
+            predicate_type: PROVENANCE_PREDICATE_TYPE.to_string(),
+            predicate: Provenance {
+                build_definition: BuildDefinition {
+                    build_type: RECEIPT_BUILD_TYPE.to_string(),
+                    external_parameters,
+                },
+                run_details: RunDetails {
+                    builder: Builder {
+                        id: format!("urn:risc0:image-id:{image_id}"),
+                    },
+                },
+            },
+        }
+    }
+
+    /// Sign this statement with the given [AttestationSigner], producing a DSSE envelope.
+    pub fn sign(&self, signer: &dyn AttestationSigner) -> Result<SignedStatement> {
+        let payload = serde_json::to_vec(self)?;
+        let sig = signer.sign(&pae(IN_TOTO_PAYLOAD_TYPE, &payload))?;
+        Ok(SignedStatement {
+            payload_type: IN_TOTO_PAYLOAD_TYPE.to_string(),
+            payload: BASE64.encode(payload),
+            signatures: vec![Signature {
+                keyid: signer.key_id(),
+                sig: BASE64.encode(sig),
+            }],
+        })
+    }
+}
+
+/// A key used to sign a [Statement].
+///
+/// This is the integration point for signing services: the message is the DSSE
+/// pre-authentication encoding of the statement, and the returned bytes are stored, base64
+/// encoded, as the signature in the envelope.
+pub trait AttestationSigner {
+    /// An identifier of the key, recorded alongside the signature.
+    fn key_id(&self) -> Option<String> {
+        None
+    }
+
+    /// Sign the given message, which is the DSSE pre-authentication encoding of the statement.
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// An [AttestationSigner] signing with an Ed25519 key.
+///
+/// The key ID is the hex-encoded SHA-256 digest of the public key.
+#[cfg(feature = "ed25519")]
+pub struct Ed25519Signer {
+    key_pair: ring::signature::Ed25519KeyPair,
+}
+
+#[cfg(feature = "ed25519")]
+impl Ed25519Signer {
+    /// Construct an [Ed25519Signer] from a PKCS#8 v2 encoded key pair.
+    pub fn from_pkcs8(pkcs8: &[u8]) -> Result<Self> {
+        let key_pair = ring::signature::Ed25519KeyPair::from_pkcs8(pkcs8)
+            .map_err(|err| anyhow!("invalid Ed25519 key: {err}"))?;
+        Ok(Self { key_pair })
+    }
+
+    /// The public key, used to verify the signatures made by this signer.
+    pub fn public_key(&self) -> &[u8] {
+        use ring::signature::KeyPair as _;
+
+        self.key_pair.public_key().as_ref()
+    }
+}
+
+#[cfg(feature = "ed25519")]
+impl AttestationSigner for Ed25519Signer {
+    fn key_id(&self) -> Option<String> {
+        Some(hex::encode(Sha256::digest(self.public_key())))
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+        Ok(self.key_pair.sign(message).as_ref().to_vec())
+    }
+}
+
+/// A [Statement] wrapped in a signed [DSSE] envelope, as consumed by in-toto tooling.
+///
+/// [DSSE]: https://github.com/secure-systems-lab/dsse/blob/master/envelope.md
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedStatement {
+    /// Always [IN_TOTO_PAYLOAD_TYPE].
+    pub payload_type: String,
+
+    /// The base64-encoded JSON serialization of the [Statement].
+    pub payload: String,
+
+    /// The signatures over the pre-authentication encoding of the payload.
+    pub signatures: Vec<Signature>,
+}
+
+/// A signature in a [SignedStatement].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Signature {
+    /// The identifier of the signing key, if known.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub keyid: Option<String>,
+
+    /// The base64-encoded signature.
+    pub sig: String,
+}
+
+impl SignedStatement {
+    /// Decode the [Statement] in this envelope.
+    ///
+    /// The signatures are not checked; use [SignedStatement::message] to obtain the bytes they
+    /// sign.
+    pub fn statement(&self) -> Result<Statement> {
+        if self.payload_type != IN_TOTO_PAYLOAD_TYPE {
+            bail!("unexpected payload type: {}", self.payload_type);
+        }
+        Ok(serde_json::from_slice(&BASE64.decode(&self.payload)?)?)
+    }
+
+    /// The message signed by each of the signatures in this envelope.
+    pub fn message(&self) -> Result<Vec<u8>> {
+        Ok(pae(&self.payload_type, &BASE64.decode(&self.payload)?))
+    }
+}
+
+// The DSSE pre-authentication encoding of the given payload.
+fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut message = format!(
+        "DSSEv1 {} {payload_type} {} ",
+        payload_type.len(),
+        payload.len()
+    )
+    .into_bytes();
+    message.extend_from_slice(payload);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use base64::Engine as _;
+    use risc0_zkp::core::digest::Digest;
+    use sha2::{Digest as _, Sha256};
+
+    use super::{AttestationSigner, Statement, BASE64};
+
+    struct ReverseSigner;
+
+    impl AttestationSigner for ReverseSigner {
+        fn key_id(&self) -> Option<String> {
+            Some("test".to_string())
+        }
+
+        fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+            Ok(message.iter().rev().copied().collect())
+        }
+    }
+
+    #[test]
+    fn sign() {
+        let image_id = Digest::from([1; 8]);
+        let statement = Statement::new(image_id, b"journal", Digest::from([2; 8]));
+        assert_eq!(
+            statement.subject[0].digest["sha256"],
+            hex::encode(Sha256::digest(b"journal"))
+        );
+        assert_eq!(
+            statement.predicate.run_details.builder.id,
+            format!("urn:risc0:image-id:{image_id}")
+        );
+
+        let signed = statement.sign(&ReverseSigner).unwrap();
+        assert_eq!(signed.statement().unwrap(), statement);
+
+        let message = signed.message().unwrap();
+        assert!(message.starts_with(b"DSSEv1 28 application/vnd.in-toto+json "));
+        let sig = BASE64.decode(&signed.signatures[0].sig).unwrap();
+        assert_eq!(sig, ReverseSigner.sign(&message).unwrap());
+    }
+
+    #[cfg(feature = "prove")]
+    #[test]
+    fn from_receipt() {
+        use risc0_binfmt::Digestible;
+        use risc0_zkvm_methods::{multi_test::MultiTestSpec, MULTI_TEST_ELF, MULTI_TEST_ID};
+
+        use crate::{get_prover_server, ExecutorEnv, ProverOpts};
+
+        let env = ExecutorEnv::builder()
+            .write(&MultiTestSpec::Echo {
+                bytes: b"attested".to_vec(),
+            })
+            .unwrap()
+            .build()
+            .unwrap();
+        let receipt = get_prover_server(&ProverOpts::fast())
+            .unwrap()
+            .prove(env, MULTI_TEST_ELF)
+            .unwrap()
+            .receipt;
+
+        let statement = Statement::from_receipt(&receipt, MULTI_TEST_ID).unwrap();
+        assert_eq!(
+            statement.subject[0].digest["sha256"],
+            hex::encode(Sha256::digest(&receipt.journal.bytes))
+        );
+        let image_id = Digest::from(MULTI_TEST_ID);
+        assert_eq!(
+            statement.predicate.run_details.builder.id,
+            format!("urn:risc0:image-id:{image_id}")
+        );
+        assert_eq!(
+            statement.predicate.build_definition.external_parameters["claimDigest"],
+            receipt.claim().unwrap().digest().to_string()
+        );
+
+        // A receipt is only attested for the image it was proven for.
+        assert!(Statement::from_receipt(&receipt, Digest::from([1; 8])).is_err());
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn ed25519() {
+        use ring::{
+            rand::SystemRandom,
+            signature::{UnparsedPublicKey, ED25519},
+        };
+
+        use super::Ed25519Signer;
+
+        let pkcs8 = ring::signature::Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let signer = Ed25519Signer::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let statement = Statement::new(Digest::from([1; 8]), b"journal", Digest::from([2; 8]));
+        let signed = statement.sign(&signer).unwrap();
+        assert_eq!(
+            signed.signatures[0].keyid,
+            Some(hex::encode(Sha256::digest(signer.public_key())))
+        );
+
+        let message = signed.message().unwrap();
+        let sig = BASE64.decode(&signed.signatures[0].sig).unwrap();
+        let public_key = UnparsedPublicKey::new(&ED25519, signer.public_key());
+        public_key.verify(&message, &sig).unwrap();
+        assert!(public_key.verify(b"forged", &sig).is_err());
+    }
+}
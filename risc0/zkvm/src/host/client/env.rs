@@ -15,13 +15,19 @@
 //! This module defines the [ExecutorEnv] and [ExecutorEnvBuilder].
 
 use std::{
-    cell::RefCell,
-    collections::HashMap,
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, HashMap},
+    fmt,
     io::{BufRead, BufReader, Cursor, Read, Write},
     mem,
+    ops::Range,
     path::{Path, PathBuf},
     rc::Rc,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 
 use anyhow::Result;
@@ -37,10 +43,11 @@ use crate::Assumption;
 use crate::{
     host::client::{
         posix_io::PosixIo,
-        slice_io::{slice_io_from_fn, SliceIo, SliceIoTable},
+        slice_io::{slice_io_from_fn, OracleHandler, SliceIo, SliceIoTable},
     },
+    oracle::{Oracle, OracleCall},
     serde::to_vec,
-    AssumptionReceipt, TraceCallback,
+    AssumptionReceipt, Input, TraceCallback,
 };
 
 /// A builder pattern used to construct an [ExecutorEnv].
@@ -81,7 +88,7 @@ impl SegmentPath {
 /// guest environment is set up prior to guest program execution.
 #[derive(Default)]
 pub struct ExecutorEnv<'a> {
-    pub(crate) env_vars: HashMap<String, String>,
+    pub(crate) env_vars: BTreeMap<String, String>,
     pub(crate) args: Vec<String>,
     pub(crate) segment_limit_po2: Option<u32>,
     pub(crate) session_limit: Option<u64>,
@@ -92,7 +99,205 @@ pub struct ExecutorEnv<'a> {
     pub(crate) assumptions: Rc<RefCell<AssumptionReceipts>>,
     pub(crate) segment_path: Option<SegmentPath>,
     pub(crate) pprof_out: Option<PathBuf>,
+    pub(crate) coverage_out: Option<PathBuf>,
     pub(crate) input_digest: Option<Digest>,
+    pub(crate) commit_input_digest: bool,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) cancellation_token: Option<CancellationToken>,
+    pub(crate) resource_limits: ResourceLimits,
+    pub(crate) journal_sink: Option<Rc<RefCell<dyn Write + 'a>>>,
+    pub(crate) oracle_calls: Rc<RefCell<Vec<OracleCall>>>,
+    pub(crate) watchpoints: Vec<Watchpoint>,
+    pub(crate) heap_sanitizer: bool,
+    pub(crate) host_io: bool,
+    // Whether the guest called a nondeterministic syscall, such as `sys_random` or
+    // `sys_cycle_count`. Set once the environment is executed, locally or by an r0vm server, and
+    // left unset by remote provers that do not report it.
+    pub(crate) nondeterministic: Rc<Cell<Option<bool>>>,
+}
+
+/// Quotas on the resources a single session may use, e.g. when a shared service executes
+/// untrusted guest programs.
+///
+/// Execution fails with a [ResourceLimitExceeded] error as soon as a quota is exceeded. A
+/// session which is paused and resumed is counted as a whole, rather than per run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ResourceLimits {
+    /// Maximum number of user cycles.
+    pub max_cycles: Option<u64>,
+
+    /// Maximum number of distinct memory pages accessed, including page table pages.
+    pub max_memory_pages: Option<usize>,
+
+    /// Maximum number of bytes written to the journal.
+    pub max_journal_bytes: Option<usize>,
+
+    /// Maximum number of syscalls made by the guest.
+    pub max_syscalls: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Return [ResourceLimits] with max_cycles set to the given value.
+    pub fn with_max_cycles(self, max_cycles: u64) -> Self {
+        Self {
+            max_cycles: Some(max_cycles),
+            ..self
+        }
+    }
+
+    /// Return [ResourceLimits] with max_memory_pages set to the given value.
+    pub fn with_max_memory_pages(self, max_memory_pages: usize) -> Self {
+        Self {
+            max_memory_pages: Some(max_memory_pages),
+            ..self
+        }
+    }
+
+    /// Return [ResourceLimits] with max_journal_bytes set to the given value.
+    pub fn with_max_journal_bytes(self, max_journal_bytes: usize) -> Self {
+        Self {
+            max_journal_bytes: Some(max_journal_bytes),
+            ..self
+        }
+    }
+
+    /// Return [ResourceLimits] with max_syscalls set to the given value.
+    pub fn with_max_syscalls(self, max_syscalls: u64) -> Self {
+        Self {
+            max_syscalls: Some(max_syscalls),
+            ..self
+        }
+    }
+}
+
+/// Error returned by the executor when a session exceeds one of its [ResourceLimits].
+///
+/// The error can be recovered from the [anyhow::Error] returned by the executor with
+/// [downcast_ref](anyhow::Error::downcast_ref).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ResourceLimitExceeded {
+    /// The session exceeded [ResourceLimits::max_cycles].
+    Cycles {
+        /// The configured limit.
+        limit: u64,
+    },
+
+    /// The session exceeded [ResourceLimits::max_memory_pages].
+    MemoryPages {
+        /// The configured limit.
+        limit: usize,
+    },
+
+    /// The session exceeded [ResourceLimits::max_journal_bytes].
+    JournalBytes {
+        /// The configured limit.
+        limit: usize,
+    },
+
+    /// The session exceeded [ResourceLimits::max_syscalls].
+    Syscalls {
+        /// The configured limit.
+        limit: u64,
+    },
+}
+
+impl fmt::Display for ResourceLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cycles { limit } => write!(f, "cycle quota exceeded: limit {limit}"),
+            Self::MemoryPages { limit } => write!(f, "memory page quota exceeded: limit {limit}"),
+            Self::JournalBytes { limit } => {
+                write!(f, "journal size quota exceeded: limit {limit} bytes")
+            }
+            Self::Syscalls { limit } => write!(f, "syscall quota exceeded: limit {limit}"),
+        }
+    }
+}
+
+impl std::error::Error for ResourceLimitExceeded {}
+
+/// A handle used to cancel execution from another thread.
+///
+/// Cancellation is cooperative: the executor checks the token between basic blocks, and ends the
+/// session with [ExitCode::SystemSplit][crate::ExitCode::SystemSplit]. The token stays cancelled
+/// until [CancellationToken::reset] is called.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Construct a new token, which is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request that execution using this token stops.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if cancellation has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Clear a previous cancellation request, e.g. before resuming execution.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+/// A range of guest memory whose accesses are reported by the executor.
+///
+/// # Example
+///
+/// ```
+/// use risc0_zkvm::{WatchAction, Watchpoint};
+///
+/// let watchpoint = Watchpoint::new(0x1000..0x1004)
+///     .stores_only()
+///     .action(WatchAction::Break);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub(crate) range: Range<u32>,
+    pub(crate) stores_only: bool,
+    pub(crate) action: WatchAction,
+}
+
+/// What the executor does when a [Watchpoint] is hit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WatchAction {
+    /// Log the access, along with the program counter of the instruction, and continue.
+    #[default]
+    Log,
+
+    /// Fail execution with an error describing the access.
+    Break,
+}
+
+impl Watchpoint {
+    /// Construct a [Watchpoint] on loads and stores to any byte in the given range of addresses.
+    pub fn new(range: Range<u32>) -> Self {
+        Self {
+            range,
+            stores_only: false,
+            action: WatchAction::default(),
+        }
+    }
+
+    /// Ignore loads, and only report stores.
+    pub fn stores_only(mut self) -> Self {
+        self.stores_only = true;
+        self
+    }
+
+    /// Set the [WatchAction] taken when the watchpoint is hit.
+    pub fn action(mut self, action: WatchAction) -> Self {
+        self.action = action;
+        self
+    }
 }
 
 impl<'a> ExecutorEnv<'a> {
@@ -108,6 +313,23 @@ impl<'a> ExecutorEnv<'a> {
     pub fn builder() -> ExecutorEnvBuilder<'a> {
         ExecutorEnvBuilder::default()
     }
+
+    /// Returns true if the execution is determined by the image, the input written to the
+    /// environment, its environment variables and arguments, the input digest it commits and its
+    /// limits, and has no side effects on the host other than writing to stdout and stderr, so
+    /// that its result can be memoized.
+    ///
+    /// The guest may still call nondeterministic syscalls, which is only known once it has run,
+    /// and is recorded in `nondeterministic`.
+    pub(crate) fn is_pure(&self) -> bool {
+        !self.host_io
+            && self.journal_sink.is_none()
+            && self.assumptions.borrow().cached.is_empty()
+            && self.trace.is_empty()
+            && self.watchpoints.is_empty()
+            && self.pprof_out.is_none()
+            && self.coverage_out.is_none()
+    }
 }
 
 impl<'a> ExecutorEnvBuilder<'a> {
@@ -134,12 +356,22 @@ impl<'a> ExecutorEnvBuilder<'a> {
                 .with_read_fd(fileno::STDIN, reader);
         }
 
+        if inner.commit_input_digest && inner.input_digest.is_none() {
+            inner.input_digest = Some(Input::digest_bytes(&inner.input));
+        }
+
         if inner.pprof_out.is_none() {
             if let Ok(env_var) = std::env::var("RISC0_PPROF_OUT") {
                 inner.pprof_out = Some(env_var.into());
             }
         }
 
+        if inner.coverage_out.is_none() {
+            if let Ok(env_var) = std::env::var("RISC0_COVERAGE_OUT") {
+                inner.coverage_out = Some(env_var.into());
+            }
+        }
+
         Ok(inner)
     }
 
@@ -169,6 +401,71 @@ impl<'a> ExecutorEnvBuilder<'a> {
         self
     }
 
+    /// Set a wall-clock limit on each run of the executor.
+    ///
+    /// Once the timeout elapses, the executor stops between basic blocks and returns a session
+    /// with an exit code of [ExitCode::SystemSplit][crate::ExitCode::SystemSplit]. Running the
+    /// executor again resumes execution, with a new timeout.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use risc0_zkvm::ExecutorEnv;
+    ///
+    /// let env = ExecutorEnv::builder()
+    ///     .timeout(Duration::from_secs(30))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.inner.timeout = Some(timeout);
+        self
+    }
+
+    /// Set [ResourceLimits] enforced on each session.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use risc0_zkvm::{ExecutorEnv, ResourceLimits};
+    ///
+    /// let env = ExecutorEnv::builder()
+    ///     .resource_limits(
+    ///         ResourceLimits::default()
+    ///             .with_max_cycles(64 * 1024 * 1024)
+    ///             .with_max_syscalls(10_000),
+    ///     )
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn resource_limits(&mut self, limits: ResourceLimits) -> &mut Self {
+        self.inner.resource_limits = limits;
+        self
+    }
+
+    /// Set a [CancellationToken] used to interrupt execution.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use risc0_zkvm::{CancellationToken, ExecutorEnv};
+    ///
+    /// let token = CancellationToken::new();
+    /// let env = ExecutorEnv::builder()
+    ///     .cancellation_token(token.clone())
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// // Elsewhere, e.g. on another thread:
+    /// token.cancel();
+    /// ```
+    pub fn cancellation_token(&mut self, token: CancellationToken) -> &mut Self {
+        self.inner.cancellation_token = Some(token);
+        self
+    }
+
     /// Add environment variables to the guest environment.
     ///
     /// # Example
@@ -187,7 +484,7 @@ impl<'a> ExecutorEnvBuilder<'a> {
     ///     .unwrap();
     /// ```
     pub fn env_vars(&mut self, vars: HashMap<String, String>) -> &mut Self {
-        self.inner.env_vars = vars;
+        self.inner.env_vars = vars.into_iter().collect();
         self
     }
 
@@ -306,6 +603,44 @@ impl<'a> ExecutorEnvBuilder<'a> {
         self.write_fd(fileno::STDOUT, writer)
     }
 
+    /// Stream the journal to the given writer as the guest commits to it.
+    ///
+    /// By default, the journal is accumulated in host memory and returned in the session. With a
+    /// sink, the journal is not retained: the session only records its digest, computed
+    /// incrementally with the [JournalHash](crate::JournalHash) selected by the guest, and
+    /// receipts proven from it have an empty journal, which must be set from the sink's contents
+    /// before verification. The maximum journal size can be enforced with
+    /// [ResourceLimits::max_journal_bytes].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use risc0_zkvm::{ExecutorEnv, ResourceLimits};
+    ///
+    /// let mut journal = Vec::new();
+    /// let env = ExecutorEnv::builder()
+    ///     .journal_sink(&mut journal)
+    ///     .resource_limits(ResourceLimits::default().with_max_journal_bytes(1024 * 1024))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn journal_sink(&mut self, writer: impl Write + 'a) -> &mut Self {
+        self.inner.journal_sink = Some(Rc::new(RefCell::new(writer)));
+        self
+    }
+
+    /// Receive the outputs streamed by the guest with `env::commit_stream`.
+    ///
+    /// Streamed bytes are written to the given writer as they are produced, and are not retained
+    /// in host memory. The journal only contains a [StreamCommitment][crate::StreamCommitment]
+    /// to each stream, which can be checked against the received bytes with
+    /// [StreamCommitment::matches][crate::StreamCommitment::matches], or as they are received by
+    /// passing a [StreamVerifier][crate::StreamVerifier] as the writer. Without a writer, streamed
+    /// bytes are discarded.
+    pub fn journal_stream(&mut self, writer: impl Write + 'a) -> &mut Self {
+        self.write_fd(fileno::JOURNAL_STREAM, writer)
+    }
+
     /// Add a posix-style standard error.
     pub fn stderr(&mut self, writer: impl Write + 'a) -> &mut Self {
         self.write_fd(fileno::STDERR, writer)
@@ -313,18 +648,24 @@ impl<'a> ExecutorEnvBuilder<'a> {
 
     /// Add a posix-style file descriptor for reading.
     pub fn read_fd(&mut self, fd: u32, reader: impl BufRead + 'a) -> &mut Self {
+        self.inner.host_io = true;
         self.inner.posix_io.borrow_mut().with_read_fd(fd, reader);
         self
     }
 
     /// Add a posix-style file descriptor for writing.
+    ///
+    /// File descriptors up to `fileno::RESERVED` are used by the zkVM, and should only be
+    /// registered through the dedicated methods, such as [ExecutorEnvBuilder::stderr].
     pub fn write_fd(&mut self, fd: u32, writer: impl Write + 'a) -> &mut Self {
+        self.inner.host_io |= fd != fileno::STDOUT && fd != fileno::STDERR;
         self.inner.posix_io.borrow_mut().with_write_fd(fd, writer);
         self
     }
 
     /// Add a handler for simple I/O handling.
     pub fn slice_io(&mut self, channel: &str, handler: impl SliceIo + 'a) -> &mut Self {
+        self.inner.host_io = true;
         self.inner
             .slice_io
             .borrow_mut()
@@ -338,6 +679,7 @@ impl<'a> ExecutorEnvBuilder<'a> {
         channel: C,
         callback: impl Fn(Bytes) -> Result<Bytes> + 'a,
     ) -> &mut Self {
+        self.inner.host_io = true;
         self.inner
             .slice_io
             .borrow_mut()
@@ -345,6 +687,43 @@ impl<'a> ExecutorEnvBuilder<'a> {
         self
     }
 
+    /// Add the host implementation of an oracle declared with the `#[risc0_zkvm::oracle]`
+    /// attribute.
+    ///
+    /// Each call made by the guest through an [oracle::Client](crate::oracle::Client) is
+    /// dispatched to the given implementation, and recorded in the oracle calls of the session.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use risc0_zkvm::ExecutorEnv;
+    ///
+    /// #[risc0_zkvm::oracle]
+    /// pub trait Clock {
+    ///     fn now(&mut self) -> u64;
+    /// }
+    ///
+    /// struct FixedClock(u64);
+    ///
+    /// impl Clock for FixedClock {
+    ///     fn now(&mut self) -> u64 {
+    ///         self.0
+    ///     }
+    /// }
+    ///
+    /// let env = ExecutorEnv::builder()
+    ///     .oracle::<dyn Clock>(Box::new(FixedClock(1_700_000_000)))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn oracle<O: Oracle + ?Sized + 'a>(&mut self, oracle: Box<O>) -> &mut Self {
+        let handler = OracleHandler {
+            oracle,
+            calls: self.inner.oracle_calls.clone(),
+        };
+        self.slice_io(O::CHANNEL.as_str(), handler)
+    }
+
     /// Add an [AssumptionReceipt] to the [ExecutorEnv], for use in [composition].
     ///
     /// During execution, when the guest calls `env::verify` or `env::verify_integrity`, this
@@ -383,9 +762,65 @@ impl<'a> ExecutorEnvBuilder<'a> {
         self
     }
 
+    /// Enable coverage reporting and output results to the specified path.
+    ///
+    /// The report counts the guest instructions executed, mapped back to the guest source, in the
+    /// LCOV tracefile format. This requires the guest to be built with debug info. Coverage is
+    /// accumulated over every run of the executor, and written when each run completes.
+    pub fn enable_coverage<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.inner.coverage_out = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Add a [Watchpoint] on a range of guest memory.
+    ///
+    /// Every load and store made by the guest is compared against the watchpoints, so execution
+    /// is slower while any are set.
+    pub fn watch(&mut self, watchpoint: Watchpoint) -> &mut Self {
+        self.inner.watchpoints.push(watchpoint);
+        self
+    }
+
+    /// Check guest accesses to heap memory, failing execution on an access to memory that has
+    /// been freed or was never allocated.
+    ///
+    /// The guest must be built with the `heap-sanitizer` feature of `risc0-zkvm`, which reports
+    /// each allocation and free to the host. Only the default bump-pointer allocator makes these
+    /// reports. Without them, this option has no effect.
+    pub fn enable_heap_sanitizer(&mut self) -> &mut Self {
+        self.inner.heap_sanitizer = true;
+        self
+    }
+
     /// Set the input digest.
     pub fn input_digest(&mut self, digest: Digest) -> &mut Self {
         self.inner.input_digest = Some(digest);
         self
     }
+
+    /// Bind a digest of the input written to the guest into the [crate::ReceiptClaim].
+    ///
+    /// When enabled, the digest of all data provided via [ExecutorEnvBuilder::write],
+    /// [ExecutorEnvBuilder::write_slice], and [ExecutorEnvBuilder::write_frame] is computed with
+    /// [Input::digest_bytes] when the [ExecutorEnv] is built, and recorded as the input of the
+    /// resulting claim. A verifier can then check the input along with the image ID and journal
+    /// using [crate::Receipt::verify_with_input]. Data provided through [ExecutorEnvBuilder::stdin]
+    /// or other file descriptors is not covered. An explicitly set
+    /// [ExecutorEnvBuilder::input_digest] takes precedence.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use risc0_zkvm::ExecutorEnv;
+    ///
+    /// let env = ExecutorEnv::builder()
+    ///     .write_slice(&[1u32, 2, 3])
+    ///     .commit_input_digest(true)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn commit_input_digest(&mut self, enable: bool) -> &mut Self {
+        self.inner.commit_input_digest = enable;
+        self
+    }
 }
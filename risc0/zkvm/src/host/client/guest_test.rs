@@ -0,0 +1,347 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Host side of the `#[risc0_test]` guest test harness.
+
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hasher},
+};
+
+use anyhow::{bail, Result};
+use risc0_binfmt::compute_image_id;
+
+use crate::{
+    default_executor, default_prover,
+    guest::test::{TestDesc, LIST_TESTS},
+    ExecutorEnv,
+};
+
+/// Runs the tests of a guest built with `#[risc0_test]`, each in its own execution.
+///
+/// A random sample of the tests can also be proven, and the receipts verified, to check that the
+/// tests behave the same way under the prover. The size of the sample defaults to the value of the
+/// `RISC0_TEST_PROVE_SAMPLE` environment variable, or zero if it is not set.
+///
+/// Tests marked `#[ignore]` are reported but not run. Tests marked `#[should_panic]` pass only if
+/// the guest panics, with the expected message if one is given, and are never proven.
+///
+/// # Example
+///
+/// ```ignore
+/// #[test]
+/// fn guest_tests() {
+///     let report = GuestTestRunner::new(GUEST_TESTS_ELF).run().unwrap();
+///     println!("{report}");
+///     report.assert_passed();
+/// }
+/// ```
+pub struct GuestTestRunner<'a> {
+    elf: &'a [u8],
+    prove_sample: usize,
+    seed: Option<u64>,
+}
+
+/// The outcome of a single guest test.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct GuestTestResult {
+    /// The name of the test function.
+    pub name: String,
+
+    /// Whether the test was proven, in addition to being executed.
+    pub proved: bool,
+
+    /// Whether the test is marked `#[ignore]`, and so was not run.
+    pub ignored: bool,
+
+    /// The error which caused the test to fail, if any.
+    pub error: Option<String>,
+}
+
+/// The outcomes of the tests run by a [GuestTestRunner].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct GuestTestReport {
+    /// The outcome of each test, in the order they are declared.
+    pub results: Vec<GuestTestResult>,
+
+    /// The seed used to sample the tests that were proven.
+    pub seed: u64,
+}
+
+impl<'a> GuestTestRunner<'a> {
+    /// Construct a [GuestTestRunner] for the given guest ELF.
+    pub fn new(elf: &'a [u8]) -> Self {
+        let prove_sample = std::env::var("RISC0_TEST_PROVE_SAMPLE")
+            .ok()
+            .and_then(|sample| sample.parse().ok())
+            .unwrap_or(0);
+        Self {
+            elf,
+            prove_sample,
+            seed: None,
+        }
+    }
+
+    /// Prove the given number of randomly chosen tests, in addition to executing every test.
+    pub fn prove_sample(mut self, prove_sample: usize) -> Self {
+        self.prove_sample = prove_sample;
+        self
+    }
+
+    /// Use the given seed to choose the tests to prove, to reproduce an earlier run.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Returns the names of the tests in the guest, in the order they are declared.
+    pub fn list(&self) -> Result<Vec<String>> {
+        Ok(self.describe()?.into_iter().map(|desc| desc.name).collect())
+    }
+
+    fn describe(&self) -> Result<Vec<TestDesc>> {
+        let env = ExecutorEnv::builder().write(&LIST_TESTS)?.build()?;
+        let session = default_executor().execute(env, self.elf)?;
+        Ok(session.journal.decode()?)
+    }
+
+    /// Run every test in the guest, and return the outcome of each.
+    ///
+    /// Returns an error if the tests could not be listed; the failure of individual tests is
+    /// recorded in the [GuestTestReport], which displays like the output of `cargo test`.
+    pub fn run(&self) -> Result<GuestTestReport> {
+        let descs = self.describe()?;
+        let seed = self
+            .seed
+            .unwrap_or_else(|| RandomState::new().build_hasher().finish());
+        // Only tests which are expected to succeed can be proven.
+        let provable: Vec<usize> = (0..descs.len())
+            .filter(|&index| !descs[index].ignore && descs[index].should_panic.is_none())
+            .collect();
+        let proved: Vec<usize> = sample(provable.len(), self.prove_sample, seed)
+            .into_iter()
+            .map(|index| provable[index])
+            .collect();
+
+        tracing::info!("running {} guest tests", descs.len());
+        let mut results = Vec::with_capacity(descs.len());
+        for (index, desc) in descs.into_iter().enumerate() {
+            let proved = proved.contains(&index);
+            let error = if desc.ignore {
+                None
+            } else {
+                check_outcome(self.run_test(index as u32, proved), &desc).err()
+            };
+            let result = GuestTestResult {
+                name: desc.name,
+                proved,
+                ignored: desc.ignore,
+                error,
+            };
+            tracing::info!("{result}");
+            results.push(result);
+        }
+        Ok(GuestTestReport { results, seed })
+    }
+
+    fn run_test(&self, index: u32, prove: bool) -> Result<()> {
+        let env = ExecutorEnv::builder().write(&index)?.build()?;
+        if !prove {
+            default_executor().execute(env, self.elf)?;
+            return Ok(());
+        }
+        let receipt = default_prover().prove(env, self.elf)?.receipt;
+        receipt.verify(compute_image_id(self.elf)?)?;
+        Ok(())
+    }
+}
+
+// Compare the outcome of a test with the outcome expected by its `#[should_panic]` attribute.
+fn check_outcome(outcome: Result<()>, desc: &TestDesc) -> Result<(), String> {
+    let outcome = outcome.map_err(|err| format!("{err:#}"));
+    match (&desc.should_panic, outcome) {
+        (None, outcome) => outcome,
+        (Some(_), Ok(())) => Err("test did not panic as expected".to_string()),
+        (Some(Some(expected)), Err(err)) if !err.contains(expected.as_str()) => Err(format!(
+            "panic did not contain expected string \"{expected}\": {err}"
+        )),
+        (Some(_), Err(_)) => Ok(()),
+    }
+}
+
+impl GuestTestReport {
+    /// Returns the tests which failed.
+    pub fn failures(&self) -> impl Iterator<Item = &GuestTestResult> {
+        self.results.iter().filter(|result| result.error.is_some())
+    }
+
+    /// Returns an error describing each failed test, if any test failed.
+    pub fn check(&self) -> Result<()> {
+        let failures: Vec<_> = self
+            .failures()
+            .map(|result| format!("{}: {}", result.name, result.error.as_ref().unwrap()))
+            .collect();
+        if !failures.is_empty() {
+            bail!(
+                "{} of {} guest tests failed (seed {}):\n{}",
+                failures.len(),
+                self.results.len(),
+                self.seed,
+                failures.join("\n")
+            );
+        }
+        Ok(())
+    }
+
+    /// Panics with a description of each failed test, if any test failed.
+    pub fn assert_passed(&self) {
+        if let Err(err) = self.check() {
+            panic!("{err}");
+        }
+    }
+}
+
+impl fmt::Display for GuestTestResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = if self.proved { " (proved)" } else { "" };
+        let outcome = if self.ignored {
+            "ignored"
+        } else if self.error.is_none() {
+            "ok"
+        } else {
+            "FAILED"
+        };
+        write!(f, "test {}{mode} ... {outcome}", self.name)
+    }
+}
+
+impl fmt::Display for GuestTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "running {} guest tests", self.results.len())?;
+        for result in self.results.iter() {
+            writeln!(f, "{result}")?;
+        }
+        let failed = self.failures().count();
+        let ignored = self.results.iter().filter(|result| result.ignored).count();
+        let outcome = if failed == 0 { "ok" } else { "FAILED" };
+        write!(
+            f,
+            "\ntest result: {outcome}. {} passed; {failed} failed; {ignored} ignored (seed {})",
+            self.results.len() - failed - ignored,
+            self.seed
+        )
+    }
+}
+
+// Choose `count` distinct indices below `len`, using a xorshift generator seeded with `seed`.
+fn sample(len: usize, count: usize, seed: u64) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..len).collect();
+    let mut state = seed | 1;
+    let count = count.min(len);
+    for i in 0..count {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let j = i + (state % (len - i) as u64) as usize;
+        indices.swap(i, j);
+    }
+    indices.truncate(count);
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "prove")]
+    use super::GuestTestRunner;
+    use super::{check_outcome, sample};
+    use crate::guest::test::TestDesc;
+
+    #[test]
+    fn sample_distinct() {
+        let mut chosen = sample(10, 4, 42);
+        assert_eq!(chosen, sample(10, 4, 42));
+        chosen.sort();
+        chosen.dedup();
+        assert_eq!(chosen.len(), 4);
+        assert!(chosen.iter().all(|&index| index < 10));
+
+        assert_eq!(sample(3, 5, 42).len(), 3);
+        assert!(sample(0, 1, 42).is_empty());
+    }
+
+    #[test]
+    fn should_panic() {
+        let desc = |should_panic: Option<Option<&str>>| TestDesc {
+            name: "test".to_string(),
+            ignore: false,
+            should_panic: should_panic.map(|expected| expected.map(String::from)),
+        };
+        let panicked = || Err(anyhow::anyhow!("guest panicked: boom"));
+
+        assert!(check_outcome(Ok(()), &desc(None)).is_ok());
+        assert!(check_outcome(panicked(), &desc(None)).is_err());
+        assert_eq!(
+            check_outcome(Ok(()), &desc(Some(None))).unwrap_err(),
+            "test did not panic as expected"
+        );
+        assert!(check_outcome(panicked(), &desc(Some(None))).is_ok());
+        assert!(check_outcome(panicked(), &desc(Some(Some("boom")))).is_ok());
+        assert!(check_outcome(panicked(), &desc(Some(Some("bang"))))
+            .unwrap_err()
+            .starts_with("panic did not contain expected string \"bang\""));
+    }
+
+    #[cfg(feature = "prove")]
+    #[test]
+    fn guest_tests() {
+        use risc0_zkvm_methods::GUEST_TESTS_ELF;
+
+        let runner = GuestTestRunner::new(GUEST_TESTS_ELF)
+            .prove_sample(1)
+            .seed(7);
+        assert_eq!(
+            runner.list().unwrap(),
+            ["passes", "fails", "ignored", "panics"]
+        );
+
+        let report = runner.run().unwrap();
+        assert_eq!(report.seed, 7);
+        assert_eq!(report.results.len(), 4);
+        assert_eq!(
+            report.results.iter().filter(|result| result.proved).count(),
+            1
+        );
+        let failures: Vec<_> = report
+            .failures()
+            .map(|result| result.name.as_str())
+            .collect();
+        assert_eq!(failures, ["fails"]);
+        assert!(report.results[2].ignored);
+        assert!(!report.results[2].proved && !report.results[3].proved);
+
+        let err = report.check().unwrap_err().to_string();
+        assert!(err.contains("1 of 4 guest tests failed (seed 7)"));
+        assert!(err.contains("deliberately failing test"));
+
+        let output = report.to_string();
+        assert!(output.contains("test passes"));
+        assert!(output.contains("test fails"));
+        assert!(output.contains("test ignored ... ignored"));
+        assert!(output.contains("test panics ... ok"));
+        assert!(output.ends_with("test result: FAILED. 2 passed; 1 failed; 1 ignored (seed 7)"));
+    }
+}
@@ -0,0 +1,235 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A registry of guest methods, mapping names to image IDs and ELF binaries.
+
+use std::{fs, path::Path};
+
+use anyhow::{bail, ensure, Context, Result};
+use hex::FromHex;
+use risc0_binfmt::compute_image_id;
+use risc0_build::GuestListEntry;
+use risc0_zkp::core::digest::Digest;
+use serde::Deserialize;
+
+/// A guest method known to a [MethodRegistry].
+#[derive(Clone, Debug)]
+pub struct MethodEntry {
+    /// The name of the method, e.g. `MULTI_TEST`.
+    pub name: String,
+
+    /// The image ID of the method.
+    pub image_id: Digest,
+
+    /// The ELF binary of the method.
+    pub elf: Vec<u8>,
+}
+
+/// A registry of guest methods, mapping names to image IDs and ELF binaries.
+///
+/// A registry can be loaded from the `methods.json` written by `risc0-build` next to the
+/// generated `methods.rs`, built from a `GUEST_LIST`, or built programmatically. The same registry
+/// is used by the `ApiServer` to resolve requests by image ID, by `r0vm` to run
+/// methods by name, and by the [ApiClient](crate::ApiClient) to prove methods by image ID.
+#[derive(Clone, Debug, Default)]
+pub struct MethodRegistry {
+    methods: Vec<MethodEntry>,
+}
+
+#[derive(Deserialize)]
+struct ManifestEntry {
+    name: String,
+    image_id: String,
+    path: String,
+}
+
+impl MethodRegistry {
+    /// Construct an empty [MethodRegistry].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a [MethodRegistry] from a `methods.json` written by `risc0-build`.
+    ///
+    /// The ELF of each method is read from its path, which is relative to the directory containing
+    /// the manifest unless it is absolute. The image ID of each ELF is computed and checked against
+    /// the one listed in the manifest, so that a stale or tampered manifest is rejected.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let manifest = fs::read(path).with_context(|| format!("failed to read {path:?}"))?;
+        let entries: Vec<ManifestEntry> = serde_json::from_slice(&manifest)
+            .with_context(|| format!("failed to parse {path:?}"))?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+
+        let mut registry = Self::new();
+        for entry in entries {
+            let elf_path = dir.join(&entry.path);
+            let elf =
+                fs::read(&elf_path).with_context(|| format!("failed to read {elf_path:?}"))?;
+            let image_id = compute_image_id(&elf)?;
+            let expected = Digest::from_hex(&entry.image_id)?;
+            ensure!(
+                image_id == expected,
+                "image ID of {elf_path:?} is {image_id}, but {path:?} lists {expected} for method {}",
+                entry.name
+            );
+            registry.insert_entry(MethodEntry {
+                name: entry.name,
+                image_id,
+                elf,
+            })?;
+        }
+        Ok(registry)
+    }
+
+    /// Construct a [MethodRegistry] from the `GUEST_LIST` generated by `risc0-build`.
+    pub fn from_guest_list(guest_list: &[GuestListEntry]) -> Result<Self> {
+        let mut registry = Self::new();
+        for guest in guest_list {
+            registry.insert_entry(MethodEntry {
+                name: guest.name.to_string(),
+                image_id: guest.image_id.into(),
+                elf: guest.elf.to_vec(),
+            })?;
+        }
+        Ok(registry)
+    }
+
+    /// Add the given ELF under the given name, returning its image ID.
+    pub fn insert(&mut self, name: impl Into<String>, elf: impl Into<Vec<u8>>) -> Result<Digest> {
+        let elf = elf.into();
+        let image_id = compute_image_id(&elf)?;
+        self.insert_entry(MethodEntry {
+            name: name.into(),
+            image_id,
+            elf,
+        })?;
+        Ok(image_id)
+    }
+
+    /// Add the given [MethodEntry].
+    ///
+    /// Returns an error if a different method is already registered with the same name or image
+    /// ID.
+    pub fn insert_entry(&mut self, entry: MethodEntry) -> Result<()> {
+        let existing = self
+            .methods
+            .iter()
+            .find(|method| method.name == entry.name || method.image_id == entry.image_id);
+        if let Some(method) = existing {
+            if method.name == entry.name && method.image_id == entry.image_id {
+                return Ok(());
+            }
+            bail!(
+                "method {} ({}) conflicts with registered method {} ({})",
+                entry.name,
+                entry.image_id,
+                method.name,
+                method.image_id
+            );
+        }
+        self.methods.push(entry);
+        Ok(())
+    }
+
+    /// Returns the method with the given name, if registered.
+    pub fn get(&self, name: &str) -> Option<&MethodEntry> {
+        self.methods.iter().find(|method| method.name == name)
+    }
+
+    /// Returns the method with the given image ID, if registered.
+    pub fn get_by_image_id(&self, image_id: &Digest) -> Option<&MethodEntry> {
+        self.methods
+            .iter()
+            .find(|method| &method.image_id == image_id)
+    }
+
+    /// Returns an iterator over the registered methods, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &MethodEntry> {
+        self.methods.iter()
+    }
+
+    /// Returns the number of registered methods.
+    pub fn len(&self) -> usize {
+        self.methods.len()
+    }
+
+    /// Returns true if no methods are registered.
+    pub fn is_empty(&self) -> bool {
+        self.methods.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkp::core::digest::Digest;
+    use risc0_zkvm_methods::{MULTI_TEST_ELF, MULTI_TEST_ID};
+
+    use super::{MethodEntry, MethodRegistry};
+
+    fn entry(name: &str, image_id: u32) -> MethodEntry {
+        MethodEntry {
+            name: name.to_string(),
+            image_id: Digest::from([image_id; 8]),
+            elf: vec![image_id as u8],
+        }
+    }
+
+    #[test]
+    fn lookup() {
+        let mut registry = MethodRegistry::new();
+        registry.insert_entry(entry("A", 1)).unwrap();
+        registry.insert_entry(entry("B", 2)).unwrap();
+        registry.insert_entry(entry("A", 1)).unwrap();
+        assert_eq!(registry.len(), 2);
+
+        assert_eq!(registry.get("B").unwrap().elf, vec![2]);
+        assert_eq!(
+            registry
+                .get_by_image_id(&Digest::from([1; 8]))
+                .unwrap()
+                .name,
+            "A"
+        );
+        assert!(registry.get("C").is_none());
+
+        assert!(registry.insert_entry(entry("A", 3)).is_err());
+        assert!(registry.insert_entry(entry("C", 2)).is_err());
+        assert_eq!(registry.len(), 2);
+    }
+
+    #[test]
+    fn load() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.elf"), MULTI_TEST_ELF).unwrap();
+        let path = dir.path().join("methods.json");
+        let write_manifest = |image_id: Digest| {
+            let manifest = serde_json::json!([{
+                "name": "A",
+                "image_id": image_id.to_string(),
+                "path": "a.elf",
+            }]);
+            std::fs::write(&path, manifest.to_string()).unwrap();
+        };
+
+        write_manifest(MULTI_TEST_ID.into());
+        let registry = MethodRegistry::load(&path).unwrap();
+        assert_eq!(registry.get("A").unwrap().elf, MULTI_TEST_ELF);
+
+        // The listed image ID must match the ELF.
+        write_manifest(Digest::from([1; 8]));
+        let err = MethodRegistry::load(&path).unwrap_err();
+        assert!(err.to_string().contains("image ID"));
+    }
+}
@@ -12,7 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod attestation;
 pub(crate) mod env;
+pub(crate) mod guest_test;
+pub(crate) mod method_registry;
 pub(crate) mod posix_io;
 pub(crate) mod prove;
+pub(crate) mod receipt_store;
 pub(crate) mod slice_io;
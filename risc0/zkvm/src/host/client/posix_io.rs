@@ -15,7 +15,7 @@
 use std::{
     cell::RefCell,
     collections::BTreeMap,
-    io::{stderr, stdout, Cursor, Read, Write},
+    io::{sink, stderr, stdout, Cursor, Read, Write},
     rc::Rc,
 };
 
@@ -38,7 +38,8 @@ impl<'a> Default for PosixIo<'a> {
         let mut new = Self::new();
         new.with_read_fd(fileno::STDIN, Cursor::new(vec![]))
             .with_write_fd(fileno::STDOUT, stdout())
-            .with_write_fd(fileno::STDERR, stderr());
+            .with_write_fd(fileno::STDERR, stderr())
+            .with_write_fd(fileno::JOURNAL_STREAM, sink());
         new
     }
 }
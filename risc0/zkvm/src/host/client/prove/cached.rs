@@ -0,0 +1,164 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{Prover, ProverOpts};
+use crate::{
+    compute_image_id,
+    host::client::receipt_store::{ReceiptKey, ReceiptStore},
+    sha::{Digest, Digestible, Impl, Sha256},
+    ExecutorEnv, Input, ProveInfo, Receipt, SessionStats, VerifierContext,
+};
+
+/// A [Prover] that memoizes the receipts produced by another [Prover].
+///
+/// Receipts are stored in a [ReceiptStore], keyed by the image ID, the digest of the input
+/// written with [ExecutorEnvBuilder::write][crate::ExecutorEnvBuilder::write] and related
+/// methods, the input digest committed to the claim, the environment variables and arguments, the
+/// segment and session limits, the [ResourceLimits](crate::ResourceLimits), and the [ProverOpts].
+/// Limits are part of the key so that a receipt is never returned for a request whose limits the
+/// execution would have exceeded. A request matching a
+/// stored receipt returns it without executing or proving the guest again, which suits services
+/// that receive duplicate requests for the same computation.
+///
+/// Only executions that are determined by those values are memoized. Requests whose environment
+/// reads from host file descriptors, has I/O or oracle handlers, streams its journal, carries
+/// assumptions, or observes the execution with trace callbacks, watchpoints, profiling or coverage
+/// are passed to the inner prover every time. So are guests that call a nondeterministic syscall,
+/// such as `sys_random` or `sys_cycle_count`, which is detected as they run, and requests to an
+/// inner prover that does not report it, such as Bonsai. On a cache hit, the guest does not run,
+/// so nothing is written to its stdout or stderr.
+///
+/// Stored receipts are verified before being returned, so a corrupted store entry results in the
+/// computation being proven again.
+pub struct CachedProver<P> {
+    inner: P,
+    store: Box<dyn ReceiptStore>,
+}
+
+impl<P: Prover> CachedProver<P> {
+    /// Construct a [CachedProver] that memoizes the receipts of `inner` in `store`.
+    pub fn new(inner: P, store: impl ReceiptStore + 'static) -> Self {
+        Self {
+            inner,
+            store: Box::new(store),
+        }
+    }
+
+    fn lookup(
+        &self,
+        key: &Digest,
+        image_id: Digest,
+        ctx: &VerifierContext,
+    ) -> Result<Option<ProveInfo>> {
+        let Some(entry) = self
+            .store
+            .get_receipt::<CacheEntry>(&ReceiptKey::cache(*key))?
+        else {
+            return Ok(None);
+        };
+        let receipt = entry.receipt;
+        let valid = receipt.verify_integrity_with_context(ctx).is_ok()
+            && receipt
+                .claim()?
+                .as_value()
+                .is_ok_and(|claim| claim.pre.digest() == image_id);
+        if !valid {
+            tracing::warn!("ignoring invalid cached receipt {key}");
+            return Ok(None);
+        }
+        Ok(Some(ProveInfo {
+            receipt,
+            stats: SessionStats {
+                segments: entry.segments,
+                total_cycles: entry.total_cycles,
+                user_cycles: entry.user_cycles,
+            },
+        }))
+    }
+}
+
+impl<P: Prover> Prover for CachedProver<P> {
+    fn get_name(&self) -> String {
+        format!("cached({})", self.inner.get_name())
+    }
+
+    fn prove_with_ctx(
+        &self,
+        env: ExecutorEnv<'_>,
+        ctx: &VerifierContext,
+        elf: &[u8],
+        opts: &ProverOpts,
+    ) -> Result<ProveInfo> {
+        if !env.is_pure() {
+            return self.inner.prove_with_ctx(env, ctx, elf, opts);
+        }
+
+        let image_id = compute_image_id(elf)?;
+        let key = cache_key(image_id, &env, opts)?;
+        if let Some(info) = self.lookup(&key, image_id, ctx)? {
+            tracing::debug!("using cached receipt {key}");
+            return Ok(info);
+        }
+
+        let nondeterministic = env.nondeterministic.clone();
+        let info = self.inner.prove_with_ctx(env, ctx, elf, opts)?;
+        if nondeterministic.get() != Some(false) {
+            tracing::debug!("not caching receipt of nondeterministic execution {key}");
+            return Ok(info);
+        }
+        let entry = CacheEntry {
+            receipt: info.receipt.clone(),
+            segments: info.stats.segments,
+            total_cycles: info.stats.total_cycles,
+            user_cycles: info.stats.user_cycles,
+        };
+        self.store.put_receipt(&ReceiptKey::cache(key), &entry)?;
+        Ok(info)
+    }
+
+    fn compress(&self, opts: &ProverOpts, receipt: &Receipt) -> Result<Receipt> {
+        self.inner.compress(opts, receipt)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    receipt: Receipt,
+    segments: usize,
+    total_cycles: u64,
+    user_cycles: u64,
+}
+
+fn cache_key(image_id: Digest, env: &ExecutorEnv, opts: &ProverOpts) -> Result<Digest> {
+    let limits = &env.resource_limits;
+    let key = (
+        image_id,
+        Input::digest_bytes(&env.input),
+        (env.input_digest, env.commit_input_digest),
+        &env.env_vars,
+        &env.args,
+        (env.segment_limit_po2, env.session_limit),
+        (
+            limits.max_cycles,
+            limits.max_memory_pages,
+            limits.max_journal_bytes,
+            limits.max_syscalls,
+        ),
+        opts,
+    );
+    Ok(*Impl::hash_bytes(&bincode::serialize(&key)?))
+}
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 pub(crate) mod bonsai;
+pub(crate) mod cached;
 pub(crate) mod external;
 #[cfg(feature = "prove")]
 pub(crate) mod local;
@@ -163,6 +164,8 @@ pub struct ProverOpts {
     /// programs that are allowed to run and is a key field in the
     /// [SuccinctReceiptVerifierParameters][crate::SuccinctReceiptVerifierParameters].
     pub control_ids: Vec<Digest>,
+    /// Set with [ProverOpts::with_verify_segments].
+    pub(crate) verify_segments: bool,
 }
 
 /// An enumeration of receipt kinds that can be requested to be generated.
@@ -196,6 +199,7 @@ impl Default for ProverOpts {
             prove_guest_errors: false,
             receipt_kind: ReceiptKind::Composite,
             control_ids: ALLOWED_CONTROL_IDS.to_vec(),
+            verify_segments: true,
         }
     }
 }
@@ -209,6 +213,7 @@ impl ProverOpts {
             prove_guest_errors: false,
             receipt_kind: ReceiptKind::Composite,
             control_ids: SHA256_CONTROL_IDS.to_vec(),
+            verify_segments: true,
         }
     }
 
@@ -220,6 +225,7 @@ impl ProverOpts {
             prove_guest_errors: false,
             receipt_kind: ReceiptKind::Composite,
             control_ids: ALLOWED_CONTROL_IDS.to_vec(),
+            verify_segments: true,
         }
     }
 
@@ -231,6 +237,7 @@ impl ProverOpts {
             prove_guest_errors: false,
             receipt_kind: ReceiptKind::Succinct,
             control_ids: ALLOWED_CONTROL_IDS.to_vec(),
+            verify_segments: true,
         }
     }
 
@@ -244,6 +251,7 @@ impl ProverOpts {
             prove_guest_errors: false,
             receipt_kind: ReceiptKind::Groth16,
             control_ids: ALLOWED_CONTROL_IDS.to_vec(),
+            verify_segments: true,
         }
     }
 
@@ -279,6 +287,19 @@ impl ProverOpts {
         }
     }
 
+    /// Return [ProverOpts] with verify_segments set to the given value.
+    ///
+    /// When true, which is the default, each [SegmentReceipt][crate::SegmentReceipt] is verified
+    /// as soon as it is proven, along with its continuity with the previous segment. This detects
+    /// faulty hardware or prover bugs early in a long proving job, rather than when the final
+    /// receipt fails verification.
+    pub fn with_verify_segments(self, verify_segments: bool) -> Self {
+        Self {
+            verify_segments,
+            ..self
+        }
+    }
+
     #[cfg(feature = "prove")]
     pub(crate) fn hash_suite(
         &self,
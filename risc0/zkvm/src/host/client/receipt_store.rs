@@ -0,0 +1,287 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Persistent storage of receipts, keyed by receipt type and the digest of their claim.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use risc0_zkp::core::digest::Digest;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// The key of an entry in a [ReceiptStore].
+///
+/// Receipts of different types can share a claim digest. For example, the only
+/// [SegmentReceipt](crate::SegmentReceipt) of a single-segment session has the same claim as the
+/// final [Receipt](crate::Receipt). The key therefore includes the type of the stored receipt,
+/// and is rendered as `<type>/<hex claim digest>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ReceiptKey {
+    kind: &'static str,
+    digest: Digest,
+}
+
+impl ReceiptKey {
+    /// Key for a [SegmentReceipt](crate::SegmentReceipt) with the given claim digest.
+    pub fn segment(claim: Digest) -> Self {
+        Self::new("segment", claim)
+    }
+
+    /// Key for a [SuccinctReceipt](crate::SuccinctReceipt) with the given claim digest.
+    pub fn succinct(claim: Digest) -> Self {
+        Self::new("succinct", claim)
+    }
+
+    /// Key for a [Receipt](crate::Receipt) with the given claim digest.
+    pub fn receipt(claim: Digest) -> Self {
+        Self::new("receipt", claim)
+    }
+
+    pub(crate) fn cache(key: Digest) -> Self {
+        Self::new("cache", key)
+    }
+
+    fn new(kind: &'static str, digest: Digest) -> Self {
+        Self { kind, digest }
+    }
+
+    /// The receipt type component of this key, e.g. `segment`.
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    /// The claim digest component of this key.
+    pub fn digest(&self) -> &Digest {
+        &self.digest
+    }
+}
+
+impl std::fmt::Display for ReceiptKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.kind, hex::encode(self.digest))
+    }
+}
+
+/// A store of serialized receipts, keyed by [ReceiptKey].
+///
+/// Any receipt type may be stored, including [SegmentReceipt](crate::SegmentReceipt) and
+/// [Receipt](crate::Receipt). Use [put_receipt](Self::put_receipt) and
+/// [get_receipt](Self::get_receipt) to store and fetch receipts by value.
+pub trait ReceiptStore {
+    /// Store the serialized receipt under the given key, replacing any existing entry.
+    fn put(&self, key: &ReceiptKey, receipt: &[u8]) -> Result<()>;
+
+    /// Fetch the serialized receipt stored under the given key, if any.
+    fn get(&self, key: &ReceiptKey) -> Result<Option<Vec<u8>>>;
+}
+
+impl<'a> dyn ReceiptStore + 'a {
+    /// Serialize and store the receipt under the given key.
+    pub fn put_receipt<R: Serialize>(&self, key: &ReceiptKey, receipt: &R) -> Result<()> {
+        self.put(key, &bincode::serialize(receipt)?)
+    }
+
+    /// Fetch and deserialize the receipt stored under the given key, if any.
+    pub fn get_receipt<R: DeserializeOwned>(&self, key: &ReceiptKey) -> Result<Option<R>> {
+        self.get(key)?
+            .map(|bytes| Ok(bincode::deserialize(&bytes)?))
+            .transpose()
+    }
+}
+
+/// A [ReceiptStore] which keeps each receipt in a file in a local directory.
+///
+/// Receipts are stored in one subdirectory per receipt type.
+pub struct FileReceiptStore {
+    dir: PathBuf,
+}
+
+impl FileReceiptStore {
+    /// Construct a [FileReceiptStore] in the given directory, creating it if needed.
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, key: &ReceiptKey) -> PathBuf {
+        self.dir.join(format!("{key}.bincode"))
+    }
+}
+
+impl ReceiptStore for FileReceiptStore {
+    fn put(&self, key: &ReceiptKey, receipt: &[u8]) -> Result<()> {
+        let dir = self.dir.join(key.kind());
+        fs::create_dir_all(&dir)?;
+        // Write to a temporary file first so that readers never observe a partial receipt.
+        let mut file = tempfile::NamedTempFile::new_in(&dir)?;
+        std::io::Write::write_all(&mut file, receipt)?;
+        file.persist(self.path(key))?;
+        Ok(())
+    }
+
+    fn get(&self, key: &ReceiptKey) -> Result<Option<Vec<u8>>> {
+        match fs::read(self.path(key)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// A client for an object store, such as Amazon S3 or Google Cloud Storage.
+///
+/// This is the minimal interface needed by [ObjectReceiptStore], and is intended to be
+/// implemented on top of the SDK for the chosen object store. For example, using the AWS SDK:
+///
+/// ```ignore
+/// struct S3Client {
+///     client: aws_sdk_s3::Client,
+///     bucket: String,
+///     runtime: tokio::runtime::Runtime,
+/// }
+///
+/// impl ObjectClient for S3Client {
+///     fn put_object(&self, key: &str, bytes: &[u8]) -> anyhow::Result<()> {
+///         let request = self
+///             .client
+///             .put_object()
+///             .bucket(&self.bucket)
+///             .key(key)
+///             .body(bytes.to_vec().into());
+///         self.runtime.block_on(request.send())?;
+///         Ok(())
+///     }
+///
+///     fn get_object(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+///         let request = self.client.get_object().bucket(&self.bucket).key(key);
+///         match self.runtime.block_on(request.send()) {
+///             Ok(output) => Ok(Some(self.runtime.block_on(output.body.collect())?.to_vec())),
+///             Err(err) if err.as_service_error().is_some_and(|e| e.is_no_such_key()) => Ok(None),
+///             Err(err) => Err(err.into()),
+///         }
+///     }
+/// }
+///
+/// let store = ObjectReceiptStore::new(s3_client, "receipts/");
+/// ```
+pub trait ObjectClient {
+    /// Upload an object with the given key.
+    fn put_object(&self, key: &str, bytes: &[u8]) -> Result<()>;
+
+    /// Download the object with the given key, if it exists.
+    fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>>;
+}
+
+/// A [ReceiptStore] which keeps each receipt as an object in an object store.
+pub struct ObjectReceiptStore<C: ObjectClient> {
+    client: C,
+    prefix: String,
+}
+
+impl<C: ObjectClient> ObjectReceiptStore<C> {
+    /// Construct an [ObjectReceiptStore] which stores receipts with keys starting with `prefix`.
+    pub fn new(client: C, prefix: impl Into<String>) -> Self {
+        Self {
+            client,
+            prefix: prefix.into(),
+        }
+    }
+
+    fn object_key(&self, key: &ReceiptKey) -> String {
+        format!("{}{key}.bincode", self.prefix)
+    }
+}
+
+impl<C: ObjectClient> ReceiptStore for ObjectReceiptStore<C> {
+    fn put(&self, key: &ReceiptKey, receipt: &[u8]) -> Result<()> {
+        self.client.put_object(&self.object_key(key), receipt)
+    }
+
+    fn get(&self, key: &ReceiptKey) -> Result<Option<Vec<u8>>> {
+        self.client.get_object(&self.object_key(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashMap};
+
+    use anyhow::Result;
+    use risc0_zkp::core::digest::Digest;
+
+    use super::{FileReceiptStore, ObjectClient, ObjectReceiptStore, ReceiptKey, ReceiptStore};
+
+    #[derive(Default)]
+    struct MemoryClient(RefCell<HashMap<String, Vec<u8>>>);
+
+    impl ObjectClient for MemoryClient {
+        fn put_object(&self, key: &str, bytes: &[u8]) -> Result<()> {
+            self.0.borrow_mut().insert(key.to_string(), bytes.to_vec());
+            Ok(())
+        }
+
+        fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>> {
+            Ok(self.0.borrow().get(key).cloned())
+        }
+    }
+
+    fn round_trip(store: &dyn ReceiptStore) {
+        let claim = Digest::from([1, 2, 3, 4, 5, 6, 7, 8]);
+        let key = ReceiptKey::receipt(claim);
+        assert_eq!(store.get_receipt::<Vec<u32>>(&key).unwrap(), None);
+        store.put_receipt(&key, &vec![1u32, 2, 3]).unwrap();
+        assert_eq!(
+            store.get_receipt::<Vec<u32>>(&key).unwrap(),
+            Some(vec![1, 2, 3])
+        );
+        store.put_receipt(&key, &vec![4u32]).unwrap();
+        assert_eq!(store.get_receipt::<Vec<u32>>(&key).unwrap(), Some(vec![4]));
+        assert_eq!(store.get(&ReceiptKey::receipt(Digest::ZERO)).unwrap(), None);
+
+        // Entries of different receipt types with the same claim digest are kept apart.
+        assert_eq!(store.get(&ReceiptKey::segment(claim)).unwrap(), None);
+        store
+            .put_receipt(&ReceiptKey::segment(claim), &vec![5u32])
+            .unwrap();
+        assert_eq!(store.get_receipt::<Vec<u32>>(&key).unwrap(), Some(vec![4]));
+        assert_eq!(
+            store
+                .get_receipt::<Vec<u32>>(&ReceiptKey::segment(claim))
+                .unwrap(),
+            Some(vec![5])
+        );
+    }
+
+    #[test]
+    fn file_store() {
+        let dir = tempfile::tempdir().unwrap();
+        round_trip(&FileReceiptStore::new(dir.path().join("receipts")).unwrap());
+    }
+
+    #[test]
+    fn object_store() {
+        let store = ObjectReceiptStore::new(MemoryClient::default(), "receipts/");
+        round_trip(&store);
+        assert!(store
+            .client
+            .0
+            .borrow()
+            .keys()
+            .all(|key| key.starts_with("receipts/")));
+    }
+}
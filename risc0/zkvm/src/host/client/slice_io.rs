@@ -17,6 +17,8 @@ use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
 use anyhow::Result;
 use bytes::Bytes;
 
+use crate::oracle::{handle_call, Oracle, OracleCall};
+
 /// An I/O handler that returns arbitrary data to the guest.
 ///
 /// On the guest side, use `env::send_recv_slice`.
@@ -67,6 +69,22 @@ impl<'a> SliceIoTable<'a> {
     }
 }
 
+// Handles calls to an oracle, recording each call.
+pub(crate) struct OracleHandler<O: Oracle + ?Sized> {
+    pub oracle: Box<O>,
+    pub calls: Rc<RefCell<Vec<OracleCall>>>,
+}
+
+impl<O: Oracle + ?Sized> SliceIo for OracleHandler<O> {
+    fn handle_io(&mut self, _syscall: &str, from_guest: Bytes) -> Result<Bytes> {
+        let request: Vec<u32> = bytemuck::pod_collect_to_vec(&from_guest);
+        let call = handle_call(self.oracle.as_mut(), &request)?;
+        let result = Bytes::copy_from_slice(bytemuck::cast_slice(&call.result));
+        self.calls.borrow_mut().push(call);
+        Ok(result)
+    }
+}
+
 impl<'a> SliceIo for Rc<RefCell<dyn SliceIo + 'a>> {
     fn handle_io(&mut self, syscall: &str, from_guest: Bytes) -> Result<Bytes> {
         self.borrow_mut().handle_io(syscall, from_guest)
@@ -276,9 +276,11 @@ pub struct CompressResult {
 pub struct ExecutorEnv {
     #[prost(message, optional, tag = "1")]
     pub binary: ::core::option::Option<Asset>,
-    #[prost(map = "string, string", tag = "2")]
-    pub env_vars:
-        ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>,
+    #[prost(btree_map = "string, string", tag = "2")]
+    pub env_vars: ::prost::alloc::collections::BTreeMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
     #[prost(string, repeated, tag = "3")]
     pub slice_ios: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
     #[prost(uint32, repeated, tag = "4")]
@@ -300,6 +302,30 @@ pub struct ExecutorEnv {
     pub assumptions: ::prost::alloc::vec::Vec<AssumptionReceipt>,
     #[prost(string, tag = "12")]
     pub segment_path: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "13")]
+    pub input_digest: ::core::option::Option<super::base::Digest>,
+    /// The image ID of a method in the registry of the server, used in place of
+    /// `binary`.
+    #[prost(message, optional, tag = "14")]
+    pub image_id: ::core::option::Option<super::base::Digest>,
+    /// Wall-clock limit on execution, after which the session is interrupted.
+    #[prost(uint64, optional, tag = "15")]
+    pub timeout_ms: ::core::option::Option<u64>,
+    /// Quotas on the resources used by the session.
+    #[prost(message, optional, tag = "16")]
+    pub resource_limits: ::core::option::Option<ResourceLimits>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResourceLimits {
+    #[prost(uint64, optional, tag = "1")]
+    pub max_cycles: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub max_memory_pages: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "3")]
+    pub max_journal_bytes: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "4")]
+    pub max_syscalls: ::core::option::Option<u64>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -331,6 +357,8 @@ pub struct ProverOpts {
     pub receipt_kind: i32,
     #[prost(message, repeated, tag = "4")]
     pub control_ids: ::prost::alloc::vec::Vec<super::base::Digest>,
+    #[prost(bool, tag = "5")]
+    pub skip_segment_verification: bool,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -588,6 +616,9 @@ pub mod generic_reply {
 pub struct GenericError {
     #[prost(string, tag = "1")]
     pub reason: ::prost::alloc::string::String,
+    /// A stable code identifying the error, as given by `risc0_zkvm::ErrorCode`.
+    #[prost(uint32, tag = "2")]
+    pub code: u32,
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -6,6 +6,8 @@ pub struct ProveInfo {
     pub receipt: ::core::option::Option<Receipt>,
     #[prost(message, optional, tag = "2")]
     pub stats: ::core::option::Option<SessionStats>,
+    #[prost(bool, optional, tag = "3")]
+    pub nondeterministic: ::core::option::Option<bool>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -182,6 +184,9 @@ pub struct Output {
     /// MaybePruned<Assumptions>
     #[prost(message, optional, tag = "2")]
     pub assumptions: ::core::option::Option<MaybePruned>,
+    /// 0 = SHA-256, 1 = Poseidon2
+    #[prost(uint32, tag = "3")]
+    pub journal_hash: u32,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
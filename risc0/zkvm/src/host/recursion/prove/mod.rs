@@ -36,11 +36,11 @@ use serde::Serialize;
 use crate::{
     receipt::{
         merkle::{MerkleGroup, MerkleProof},
-        SegmentReceipt, SuccinctReceipt, SuccinctReceiptVerifierParameters,
+        seal_codec, SegmentReceipt, SuccinctReceipt, SuccinctReceiptVerifierParameters,
     },
     receipt_claim::{Assumption, MaybePruned, Merge},
     sha::Digestible,
-    ProverOpts, ReceiptClaim,
+    JournalHash, ProverOpts, ReceiptClaim,
 };
 
 use risc0_circuit_recursion::prove::Program;
@@ -338,7 +338,8 @@ impl Prover {
 
         // Read the output fields in the rv32im seal to get the po2. We need this po2 to chose
         // which lift program we are going to run.
-        let mut iop = ReadIOP::new(&segment.seal, inner_hash_suite.rng.as_ref());
+        let seal = seal_codec::decompress(&segment.seal)?;
+        let mut iop = ReadIOP::new(&seal, inner_hash_suite.rng.as_ref());
         iop.read_field_elem_slice::<BabyBearElem>(risc0_circuit_rv32im::CircuitImpl::OUTPUT_SIZE);
         let po2 = *iop.read_u32s(1).first().unwrap() as usize;
 
@@ -352,7 +353,7 @@ impl Prover {
         let which = po2 - MIN_CYCLES_PO2;
         let inner_control_id = POSEIDON2_CONTROL_IDS[which];
         prover.add_seal(
-            &segment.seal,
+            &seal,
             &inner_control_id,
             &allowed_ids.get_proof(&inner_control_id, inner_hash_suite.hashfn.as_ref())?,
         )?;
@@ -446,6 +447,11 @@ impl Prover {
             .as_ref()
             .ok_or(anyhow!("cannot resolve conditional receipt with no output"))?
             .clone();
+        ensure!(
+            output.journal_hash == JournalHash::Sha256,
+            "resolve recursion program only supports SHA-256 journal digests; received {:?}",
+            output.journal_hash
+        );
 
         // Unwrap the MaybePruned assumptions list and resolve the corroborated assumption,
         // removing the head and leaving the tail of the list.
@@ -515,6 +521,9 @@ impl Prover {
     }
 
     /// Add a recursion seal (i.e. STARK proof) to input tape of the recursion program.
+    ///
+    /// Seals compressed with [Receipt::compress](crate::Receipt::compress) are decompressed
+    /// first.
     pub fn add_seal(
         &mut self,
         seal: &[u32],
@@ -522,7 +531,7 @@ impl Prover {
         control_inclusion_proof: &MerkleProof,
     ) -> Result<()> {
         tracing::debug!("Control ID = {:?}", control_id);
-        self.add_input(seal);
+        self.add_input(&seal_codec::decompress(seal)?);
         tracing::debug!("index = {:?}", control_inclusion_proof.index);
         self.add_input(bytemuck::cast_slice(&[BabyBearElem::new(
             control_inclusion_proof.index,
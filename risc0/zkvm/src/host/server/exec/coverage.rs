@@ -0,0 +1,163 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Support for measuring coverage of the guest.
+//!
+//! This counts the number of times each guest instruction is executed, and maps instructions back
+//! to guest source lines using the DWARF debug info of the ELF. The result is reported in the
+//! LCOV tracefile format, which is understood by `genhtml`, `grcov` and most coverage services.
+//! Guests built without debug info produce an empty report.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+};
+
+use addr2line::{
+    object::{File, Object, ObjectSection, SectionKind},
+    ObjectContext,
+};
+use anyhow::Result;
+use elf::{abi::STT_FUNC, endian::LittleEndian, ElfBytes};
+use risc0_zkvm_platform::WORD_SIZE;
+use rustc_demangle::demangle;
+
+use crate::{TraceCallback, TraceEvent};
+
+/// Records the guest instructions executed, for reporting coverage of the guest source.
+pub struct Coverage {
+    ctx: ObjectContext,
+
+    // Address ranges of the executable sections of the ELF.
+    text: Vec<(u64, u64)>,
+
+    // Function names by start address.
+    functions: BTreeMap<u64, String>,
+
+    // Execution count by program counter.
+    hits: HashMap<u32, u64>,
+}
+
+// Coverage of a single source file.
+#[derive(Default)]
+struct FileCoverage {
+    // Execution count by line.
+    lines: BTreeMap<u32, u64>,
+
+    // Line and execution count by function name.
+    functions: BTreeMap<String, (u32, u64)>,
+}
+
+impl Coverage {
+    /// Construct a [Coverage] for the given RISC-V ELF.
+    pub fn new(elf_data: &[u8]) -> Result<Self> {
+        let file = File::parse(elf_data)?;
+        let ctx = ObjectContext::new(&file)?;
+        let text = file
+            .sections()
+            .filter(|section| section.kind() == SectionKind::Text)
+            .map(|section| (section.address(), section.address() + section.size()))
+            .collect();
+
+        let mut functions = BTreeMap::new();
+        let elf = ElfBytes::<LittleEndian>::minimal_parse(elf_data)?;
+        if let Some((symtab, strtab)) = elf.symbol_table()? {
+            for sym in symtab {
+                if sym.st_symtype() == STT_FUNC {
+                    let name = strtab.get(sym.st_name as usize)?;
+                    functions.insert(sym.st_value, format!("{:#}", demangle(name)));
+                }
+            }
+        }
+
+        Ok(Self {
+            ctx,
+            text,
+            functions,
+            hits: HashMap::new(),
+        })
+    }
+
+    /// Returns the number of times the instruction at the given address was executed.
+    pub fn hits(&self, pc: u32) -> u64 {
+        self.hits.get(&pc).copied().unwrap_or_default()
+    }
+
+    /// Report the coverage of the guest source in the LCOV tracefile format.
+    ///
+    /// Every source line with instructions in the ELF is reported, including lines which were
+    /// never executed. The count for a line is the greatest count of its instructions.
+    pub fn to_lcov(&self) -> Result<String> {
+        let mut files: BTreeMap<String, FileCoverage> = BTreeMap::new();
+        for &(start, end) in &self.text {
+            for addr in (start..end).step_by(WORD_SIZE) {
+                let Some((file, line)) = self.lookup(addr)? else {
+                    continue;
+                };
+                let count = self.hits(addr as u32);
+                let lines = &mut files.entry(file).or_default().lines;
+                let entry = lines.entry(line).or_default();
+                *entry = (*entry).max(count);
+            }
+        }
+        for (&addr, name) in &self.functions {
+            if let Some((file, line)) = self.lookup(addr)? {
+                let count = self.hits(addr as u32);
+                let file = files.entry(file).or_default();
+                file.functions.insert(name.clone(), (line, count));
+            }
+        }
+
+        let mut out = String::from("TN:\n");
+        for (path, file) in files {
+            writeln!(out, "SF:{path}")?;
+            for (name, (line, _)) in &file.functions {
+                writeln!(out, "FN:{line},{name}")?;
+            }
+            for (name, (_, count)) in &file.functions {
+                writeln!(out, "FNDA:{count},{name}")?;
+            }
+            let functions_hit = file.functions.values().filter(|(_, c)| *c > 0).count();
+            writeln!(out, "FNF:{}", file.functions.len())?;
+            writeln!(out, "FNH:{functions_hit}")?;
+            for (line, count) in &file.lines {
+                writeln!(out, "DA:{line},{count}")?;
+            }
+            let lines_hit = file.lines.values().filter(|c| **c > 0).count();
+            writeln!(out, "LF:{}", file.lines.len())?;
+            writeln!(out, "LH:{lines_hit}")?;
+            writeln!(out, "end_of_record")?;
+        }
+        Ok(out)
+    }
+
+    fn lookup(&self, addr: u64) -> Result<Option<(String, u32)>> {
+        let Some(location) = self.ctx.find_location(addr)? else {
+            return Ok(None);
+        };
+        Ok(location
+            .file
+            .zip(location.line)
+            .map(|(file, line)| (file.to_string(), line)))
+    }
+}
+
+impl TraceCallback for Coverage {
+    fn trace_callback(&mut self, event: TraceEvent) -> Result<()> {
+        if let TraceEvent::InstructionStart { pc, .. } = event {
+            *self.hits.entry(pc).or_default() += 1;
+        }
+        Ok(())
+    }
+}
@@ -12,30 +12,51 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{cell::RefCell, io::Write, mem, rc::Rc, sync::Arc, time::Instant};
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeSet,
+    io::{self, Write},
+    mem,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
 use anyhow::{Context as _, Result};
+use elf::{endian::LittleEndian, ElfBytes};
 use risc0_binfmt::{MemoryImage, Program};
 use risc0_circuit_rv32im::prove::emu::{
     addr::ByteAddr,
     exec::{
-        Executor, Syscall as NewSyscall, SyscallContext as NewSyscallContext,
+        Executor, SessionLimitExceeded, Syscall as NewSyscall, SyscallContext as NewSyscallContext,
         DEFAULT_SEGMENT_LIMIT_PO2,
     },
 };
 use risc0_core::scope;
 use risc0_zkp::core::digest::Digest;
-use risc0_zkvm_platform::{fileno, memory::GUEST_MAX_MEM, PAGE_SIZE};
+use risc0_zkvm_platform::{
+    fileno,
+    memory::GUEST_MAX_MEM,
+    syscall::nr::{SYS_HEAP_ALLOC, SYS_HEAP_FREE, SYS_JOURNAL_HASH},
+    PAGE_SIZE,
+};
 use tempfile::tempdir;
 
 use crate::{
-    host::client::env::SegmentPath, Assumptions, ExecutorEnv, FileSegmentRef, Output, Segment,
-    SegmentRef, Session,
+    host::client::env::SegmentPath, receipt_claim::JournalHasher, Assumptions, ExecutorEnv,
+    FileSegmentRef, JournalHash, MaybePruned, Output, ResourceLimitExceeded, Segment, SegmentRef,
+    Session,
 };
 
 use super::{
+    coverage::Coverage,
+    memcheck::{MemoryChecker, SysHeap},
     profiler::Profiler,
-    syscall::{SyscallContext, SyscallTable},
+    syscall::{SysJournalHash, SyscallContext, SyscallTable},
 };
 
 // The Executor provides an implementation for the execution phase.
@@ -46,8 +67,22 @@ pub struct ExecutorImpl<'a> {
     image: MemoryImage,
     pub(crate) syscall_table: SyscallTable<'a>,
     profiler: Option<Rc<RefCell<Profiler>>>,
+    coverage: Option<Rc<RefCell<Coverage>>>,
+    memory_checker: Option<Rc<RefCell<MemoryChecker>>>,
+    compressed: bool,
+    journal_hash: Rc<Cell<JournalHash>>,
+
+    // Resources used by the session so far, kept across runs so that the [ResourceLimits] apply
+    // to the session as a whole when it is paused and resumed.
+    syscall_count: Cell<u64>,
+    user_cycles: u64,
+    pages: Rc<RefCell<BTreeSet<u32>>>,
+    journal_bytes: Rc<Cell<usize>>,
 }
 
+// ELF header flag set by toolchains which emit RV32C compressed instructions.
+const EF_RISCV_RVC: u32 = 0x0001;
+
 impl<'a> ExecutorImpl<'a> {
     /// Construct a new [ExecutorImpl] from a [MemoryImage] and entry point.
     ///
@@ -57,7 +92,7 @@ impl<'a> ExecutorImpl<'a> {
     /// the guest program is executed to determine how its proof should be
     /// divided into subparts.
     pub fn new(env: ExecutorEnv<'a>, image: MemoryImage) -> Result<Self> {
-        Self::with_details(env, image, None)
+        Self::with_details(env, image, None, None)
     }
 
     /// Construct a new [ExecutorImpl] from the ELF binary of the guest program
@@ -76,8 +111,13 @@ impl<'a> ExecutorImpl<'a> {
     ///     .unwrap();
     /// let mut exec = ExecutorImpl::from_elf(env, BENCH_ELF).unwrap();
     /// ```
+    ///
+    /// ELFs built for rv32imc, i.e. using RV32C compressed instructions, can be executed, but
+    /// their sessions cannot be proven: see [Session::compressed].
     pub fn from_elf(mut env: ExecutorEnv<'a>, elf: &[u8]) -> Result<Self> {
         let program = Program::load_elf(elf, GUEST_MAX_MEM as u32)?;
+        let compressed =
+            ElfBytes::<LittleEndian>::minimal_parse(elf)?.ehdr.e_flags & EF_RISCV_RVC != 0;
         let image = MemoryImage::new(&program, PAGE_SIZE as u32)?;
 
         let profiler = if env.pprof_out.is_some() {
@@ -88,20 +128,53 @@ impl<'a> ExecutorImpl<'a> {
             None
         };
 
-        Self::with_details(env, image, profiler)
+        let coverage = if env.coverage_out.is_some() {
+            let coverage = Rc::new(RefCell::new(Coverage::new(elf)?));
+            env.trace.push(coverage.clone());
+            Some(coverage)
+        } else {
+            None
+        };
+
+        let mut exec = Self::with_details(env, image, profiler, coverage)?;
+        exec.compressed = compressed;
+        Ok(exec)
     }
 
     fn with_details(
         env: ExecutorEnv<'a>,
         image: MemoryImage,
         profiler: Option<Rc<RefCell<Profiler>>>,
+        coverage: Option<Rc<RefCell<Coverage>>>,
     ) -> Result<Self> {
-        let syscall_table = SyscallTable::from_env(&env);
+        let mut syscall_table = SyscallTable::from_env(&env);
+
+        // The heap state is kept across runs, as the guest may be resumed after a pause.
+        let memory_checker =
+            MemoryChecker::from_env(&env).map(|checker| Rc::new(RefCell::new(checker)));
+        if let Some(checker) = &memory_checker {
+            syscall_table
+                .with_syscall(SYS_HEAP_ALLOC, SysHeap(Some(checker.clone())))
+                .with_syscall(SYS_HEAP_FREE, SysHeap(Some(checker.clone())));
+        }
+
+        // The guest reports its choice of journal hash, which is also kept across runs.
+        let journal_hash = Rc::new(Cell::new(JournalHash::default()));
+        syscall_table.with_syscall(SYS_JOURNAL_HASH, SysJournalHash(journal_hash.clone()));
+
         Ok(Self {
             env,
             image,
             syscall_table,
             profiler,
+            coverage,
+            memory_checker,
+            compressed: false,
+            journal_hash,
+            syscall_count: Cell::new(0),
+            user_cycles: 0,
+            pages: Rc::new(RefCell::new(BTreeSet::new())),
+            journal_bytes: Rc::new(Cell::new(0)),
         })
     }
 
@@ -124,7 +197,14 @@ impl<'a> ExecutorImpl<'a> {
     {
         scope!("execute");
 
-        let journal = Journal::default();
+        let limits = self.env.resource_limits.clone();
+        let journal = Journal::new(
+            self.journal_bytes.clone(),
+            limits.max_journal_bytes,
+            self.env.journal_sink.clone(),
+            self.journal_hash.clone(),
+        );
+        self.env.oracle_calls.borrow_mut().clear();
         self.env
             .posix_io
             .borrow_mut()
@@ -135,7 +215,21 @@ impl<'a> ExecutorImpl<'a> {
             .segment_limit_po2
             .unwrap_or(DEFAULT_SEGMENT_LIMIT_PO2 as u32) as usize;
 
+        // The cycle quota is enforced as a session limit on the cycles remaining from earlier runs,
+        // and reported as a quota error when it is the tighter of the two.
+        let remaining_cycles = limits
+            .max_cycles
+            .map(|max_cycles| max_cycles.saturating_sub(self.user_cycles));
+        let session_limit = match (self.env.session_limit, remaining_cycles) {
+            (Some(session_limit), Some(remaining)) => Some(session_limit.min(remaining)),
+            (session_limit, remaining) => session_limit.or(remaining),
+        };
+        let cycle_quota = limits
+            .max_cycles
+            .filter(|_| remaining_cycles == session_limit);
+
         let mut refs = Vec::new();
+        let memory_checker = self.memory_checker.clone();
         let mut exec = Executor::new(
             self.image.clone(),
             self,
@@ -143,31 +237,54 @@ impl<'a> ExecutorImpl<'a> {
             self.env.trace.clone(),
         );
 
+        let watchdog = self.env.timeout.map(Watchdog::new);
+        if watchdog.is_some() || self.env.cancellation_token.is_some() {
+            let expired = watchdog.as_ref().map(|watchdog| watchdog.expired.clone());
+            let token = self.env.cancellation_token.clone();
+            exec.set_interrupt(move || {
+                expired
+                    .as_ref()
+                    .is_some_and(|expired| expired.load(Ordering::Relaxed))
+                    || token.as_ref().is_some_and(|token| token.is_cancelled())
+            });
+        }
+
+        if let Some(checker) = memory_checker {
+            exec.set_memory_check(move |access| checker.borrow_mut().check(access));
+        }
+        if let Some(limit) = limits.max_memory_pages {
+            let pages = self.pages.clone();
+            exec.set_page_check(move |page_idx| {
+                let mut pages = pages.borrow_mut();
+                if pages.insert(page_idx) && pages.len() > limit {
+                    return Err(ResourceLimitExceeded::MemoryPages { limit }.into());
+                }
+                Ok(())
+            });
+        }
+        exec.set_compressed(self.compressed);
+
         let start_time = Instant::now();
-        let result = exec.run(segment_limit_po2, self.env.session_limit, |inner| {
+        let result = exec.run(segment_limit_po2, session_limit, |inner| {
             let output = inner
                 .exit_code
                 .expects_output()
                 .then(|| -> Option<Result<_>> {
                     inner
                         .output_digest
-                        .and_then(|digest| {
-                            (digest != Digest::ZERO).then(|| journal.buf.borrow().clone())
-                        })
+                        .and_then(|digest| (digest != Digest::ZERO).then(|| journal.output()))
                         .map(|journal| {
-                            Ok(Output {
-                                journal: journal.into(),
-                                assumptions: Assumptions(
-                                    self.env
-                                        .assumptions
-                                        .borrow()
-                                        .accessed
-                                        .iter()
-                                        .map(|(a, _)| a.clone().into())
-                                        .collect::<Vec<_>>(),
-                                )
-                                .into(),
-                            })
+                            let assumptions = Assumptions(
+                                self.env
+                                    .assumptions
+                                    .borrow()
+                                    .accessed
+                                    .iter()
+                                    .map(|(a, _)| a.clone().into())
+                                    .collect::<Vec<_>>(),
+                            );
+                            Ok(Output::new(journal, assumptions.into())
+                                .with_journal_hash(self.journal_hash.get()))
                         })
                 })
                 .flatten()
@@ -181,18 +298,32 @@ impl<'a> ExecutorImpl<'a> {
             let segment_ref = callback(segment)?;
             refs.push(segment_ref);
             Ok(())
+        });
+        let result = result.map_err(|err| match cycle_quota {
+            Some(limit) if err.is::<SessionLimitExceeded>() => {
+                ResourceLimitExceeded::Cycles { limit }.into()
+            }
+            _ => quota_error(err),
         })?;
         let elapsed = start_time.elapsed();
+        drop(watchdog);
+        self.user_cycles += result.user_cycles;
 
         // Set the session_journal to the committed data iff the guest set a non-zero output.
-        let session_journal = result
+        // A streamed journal is not retained, and only its digest is recorded.
+        let (session_journal, journal_digest) = match result
             .output_digest
-            .and_then(|digest| (digest != Digest::ZERO).then(|| journal.buf.take()));
+            .and_then(|digest| (digest != Digest::ZERO).then(|| journal.output()))
+        {
+            Some(MaybePruned::Value(bytes)) => (Some(bytes), None),
+            Some(MaybePruned::Pruned(digest)) => (None, Some(digest)),
+            None => (None, None),
+        };
         if !result.exit_code.expects_output() && session_journal.is_some() {
             tracing::debug!(
                 "dropping non-empty journal due to exit code {:?}: 0x{}",
                 result.exit_code,
-                hex::encode(journal.buf.borrow().as_slice())
+                hex::encode(session_journal.as_deref().unwrap_or_default())
             );
         };
 
@@ -205,9 +336,14 @@ impl<'a> ExecutorImpl<'a> {
             std::fs::write(self.env.pprof_out.as_ref().unwrap(), report)?;
         }
 
+        if let Some(coverage) = &self.coverage {
+            let report = coverage.borrow().to_lcov()?;
+            std::fs::write(self.env.coverage_out.as_ref().unwrap(), report)?;
+        }
+
         self.image = result.post_image.clone();
 
-        let session = Session::new(
+        let mut session = Session::new(
             refs,
             self.env.input_digest.unwrap_or_default(),
             session_journal,
@@ -219,6 +355,10 @@ impl<'a> ExecutorImpl<'a> {
             result.pre_state,
             result.post_state,
         );
+        session.journal_digest = journal_digest;
+        session.journal_hash = self.journal_hash.get();
+        session.oracle_calls = self.env.oracle_calls.take();
+        session.compressed = self.compressed;
 
         tracing::info!("execution time: {elapsed:?}");
         session.log();
@@ -269,6 +409,13 @@ impl<'a> NewSyscall for ExecutorImpl<'a> {
         ctx: &mut dyn NewSyscallContext,
         into_guest: &mut [u32],
     ) -> Result<(u32, u32)> {
+        if let Some(limit) = self.env.resource_limits.max_syscalls {
+            if self.syscall_count.get() >= limit {
+                return Err(ResourceLimitExceeded::Syscalls { limit }.into());
+            }
+        }
+        self.syscall_count.set(self.syscall_count.get() + 1);
+
         let mut ctx = ContextAdapter {
             ctx,
             syscall_table: self.syscall_table.clone(),
@@ -281,18 +428,127 @@ impl<'a> NewSyscall for ExecutorImpl<'a> {
     }
 }
 
-// Capture the journal output in a buffer that we can access afterwards.
-#[derive(Clone, Default)]
-struct Journal {
+// Sets a flag once a timeout has elapsed, unless dropped first.
+struct Watchdog {
+    expired: Arc<AtomicBool>,
+    stop: Option<mpsc::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    fn new(timeout: Duration) -> Self {
+        let expired = Arc::new(AtomicBool::new(false));
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = thread::spawn({
+            let expired = expired.clone();
+            move || {
+                if let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(timeout) {
+                    tracing::debug!("execution timed out after {timeout:?}");
+                    expired.store(true, Ordering::Relaxed);
+                }
+            }
+        });
+        Self {
+            expired,
+            stop: Some(stop),
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        // Disconnecting the channel wakes the watchdog thread.
+        drop(self.stop.take());
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+// Surface a quota error raised as an I/O error, e.g. by the journal, as a [ResourceLimitExceeded].
+fn quota_error(err: anyhow::Error) -> anyhow::Error {
+    let quota = err
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<io::Error>())
+        .find_map(|err| err.get_ref()?.downcast_ref::<ResourceLimitExceeded>())
+        .cloned();
+    match quota {
+        Some(quota) => quota.into(),
+        None => err,
+    }
+}
+
+// Capture the journal output in a buffer that we can access afterwards, or stream it to a sink
+// while hashing it with the journal hash selected by the guest.
+#[derive(Clone)]
+struct Journal<'a> {
     buf: Rc<RefCell<Vec<u8>>>,
+    // Bytes written by the session so far, including earlier runs.
+    len: Rc<Cell<usize>>,
+    limit: Option<usize>,
+    sink: Option<(Rc<RefCell<dyn Write + 'a>>, Rc<RefCell<Option<JournalHasher>>>)>,
+    hash: Rc<Cell<JournalHash>>,
+}
+
+impl<'a> Journal<'a> {
+    fn new(
+        len: Rc<Cell<usize>>,
+        limit: Option<usize>,
+        sink: Option<Rc<RefCell<dyn Write + 'a>>>,
+        hash: Rc<Cell<JournalHash>>,
+    ) -> Self {
+        Self {
+            buf: Default::default(),
+            len,
+            limit,
+            sink: sink.map(|sink| (sink, Default::default())),
+            hash,
+        }
+    }
+
+    // The journal written so far, or its digest if it was streamed to a sink.
+    fn output(&self) -> MaybePruned<Vec<u8>> {
+        match &self.sink {
+            Some((_, hasher)) => {
+                let hasher = hasher.borrow().clone();
+                let hasher = hasher.unwrap_or_else(|| JournalHasher::new(self.hash.get()));
+                MaybePruned::Pruned(hasher.finalize())
+            }
+            None => self.buf.borrow().clone().into(),
+        }
+    }
 }
 
-impl Write for Journal {
+impl<'a> Write for Journal<'a> {
     fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
-        self.buf.borrow_mut().write(bytes)
+        if let Some(limit) = self.limit {
+            if self.len.get() + bytes.len() > limit {
+                return Err(io::Error::other(ResourceLimitExceeded::JournalBytes {
+                    limit,
+                }));
+            }
+        }
+        let written = match &self.sink {
+            Some((sink, hasher)) => {
+                let written = sink.borrow_mut().write(bytes)?;
+                // The guest selects the journal hash before it commits anything.
+                hasher
+                    .borrow_mut()
+                    .get_or_insert_with(|| JournalHasher::new(self.hash.get()))
+                    .update(&bytes[..written]);
+                written
+            }
+            None => self.buf.borrow_mut().write(bytes)?,
+        };
+        self.len.set(self.len.get() + written);
+        Ok(written)
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
-        self.buf.borrow_mut().flush()
+        match &self.sink {
+            Some((sink, _)) => sink.borrow_mut().flush(),
+            None => self.buf.borrow_mut().flush(),
+        }
     }
 }
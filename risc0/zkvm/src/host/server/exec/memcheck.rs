@@ -0,0 +1,213 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checks on the memory accesses made by the guest: watchpoints, and the heap sanitizer.
+
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use anyhow::{bail, Result};
+use risc0_circuit_rv32im::prove::emu::exec::MemoryAccess;
+use risc0_zkvm_platform::{
+    memory::GUEST_MAX_MEM,
+    syscall::{
+        nr::SYS_HEAP_FREE,
+        reg_abi::{REG_A3, REG_A4},
+    },
+    WORD_SIZE,
+};
+
+use crate::{ExecutorEnv, WatchAction, Watchpoint};
+
+use super::syscall::{Syscall, SyscallContext};
+
+pub(crate) struct MemoryChecker {
+    watchpoints: Vec<Watchpoint>,
+    heap: Option<HeapShadow>,
+}
+
+impl MemoryChecker {
+    /// Returns a [MemoryChecker] for the watchpoints and heap sanitizer set in the environment,
+    /// or `None` if there is nothing to check.
+    pub(crate) fn from_env(env: &ExecutorEnv<'_>) -> Option<Self> {
+        if env.watchpoints.is_empty() && !env.heap_sanitizer {
+            return None;
+        }
+        Some(Self {
+            watchpoints: env.watchpoints.clone(),
+            heap: env.heap_sanitizer.then(HeapShadow::default),
+        })
+    }
+
+    pub(crate) fn check(&mut self, access: &MemoryAccess) -> Result<()> {
+        let kind = if access.store { "store" } else { "load" };
+        let end = access.addr + access.size;
+        for watchpoint in self.watchpoints.iter() {
+            if (watchpoint.stores_only && !access.store)
+                || end <= watchpoint.range.start
+                || watchpoint.range.end <= access.addr
+            {
+                continue;
+            }
+            match watchpoint.action {
+                WatchAction::Log => tracing::info!(
+                    "watchpoint 0x{:08x}..0x{:08x}: {kind} of {} bytes at 0x{:08x} by pc 0x{:08x}",
+                    watchpoint.range.start,
+                    watchpoint.range.end,
+                    access.size,
+                    access.addr,
+                    access.pc
+                ),
+                WatchAction::Break => bail!(
+                    "watchpoint 0x{:08x}..0x{:08x}: {kind} of {} bytes at 0x{:08x} by pc 0x{:08x}",
+                    watchpoint.range.start,
+                    watchpoint.range.end,
+                    access.size,
+                    access.addr,
+                    access.pc
+                ),
+            }
+        }
+
+        if let Some(heap) = &self.heap {
+            if let Some(error) = heap.check(access.addr, end) {
+                bail!(
+                    "{error}: {kind} of {} bytes at 0x{:08x} by pc 0x{:08x}",
+                    access.size,
+                    access.addr,
+                    access.pc
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The state of each block of guest heap memory, as reported by the guest allocator.
+#[derive(Default)]
+struct HeapShadow {
+    /// The lowest address allocated, taken to be the start of the heap.
+    start: Option<u32>,
+
+    /// The start and end of each allocated block.
+    live: BTreeMap<u32, u32>,
+
+    /// The start and end of each freed block. Freed memory is never reused.
+    freed: BTreeMap<u32, u32>,
+}
+
+impl HeapShadow {
+    fn alloc(&mut self, ptr: u32, size: u32) {
+        // Allocations are word-aligned, so the tail of the last word of a block is never part of
+        // another block. Including it allows word-sized accesses by e.g. memcpy.
+        let end = (ptr + size).next_multiple_of(WORD_SIZE as u32);
+        self.start = Some(self.start.map_or(ptr, |start| start.min(ptr)));
+        self.live.insert(ptr, end);
+    }
+
+    fn free(&mut self, ptr: u32) -> Result<()> {
+        match self.live.remove(&ptr) {
+            Some(end) => {
+                self.freed.insert(ptr, end);
+                Ok(())
+            }
+            None if self.freed.contains_key(&ptr) => bail!("heap double free at 0x{ptr:08x}"),
+            None => bail!("heap free of unallocated pointer 0x{ptr:08x}"),
+        }
+    }
+
+    /// Returns a description of the error, if accessing the given range is not allowed.
+    fn check(&self, addr: u32, end: u32) -> Option<&'static str> {
+        let start = self.start?;
+        if addr < start || addr >= GUEST_MAX_MEM as u32 {
+            return None;
+        }
+        let contains = |blocks: &BTreeMap<u32, u32>, addr: u32| {
+            blocks
+                .range(..=addr)
+                .next_back()
+                .is_some_and(|(_, block_end)| addr < *block_end)
+        };
+        if contains(&self.live, addr) && contains(&self.live, end - 1) {
+            return None;
+        }
+        if contains(&self.freed, addr) {
+            Some("heap-use-after-free")
+        } else {
+            Some("access to unallocated heap memory")
+        }
+    }
+}
+
+/// Receives reports of heap allocations and frees from guests built with the `heap-sanitizer`
+/// feature. The reports are ignored unless the heap sanitizer is enabled.
+pub(crate) struct SysHeap(pub Option<Rc<RefCell<MemoryChecker>>>);
+
+impl Syscall for SysHeap {
+    fn syscall(
+        &mut self,
+        syscall: &str,
+        ctx: &mut dyn SyscallContext,
+        _to_guest: &mut [u32],
+    ) -> Result<(u32, u32)> {
+        let ptr = ctx.load_register(REG_A3);
+        let size = ctx.load_register(REG_A4);
+        if let Some(checker) = &self.0 {
+            if let Some(heap) = checker.borrow_mut().heap.as_mut() {
+                if syscall == SYS_HEAP_FREE.as_str() {
+                    tracing::trace!("sys_heap_free(0x{ptr:08x}, {size})");
+                    heap.free(ptr)?;
+                } else {
+                    tracing::trace!("sys_heap_alloc(0x{ptr:08x}, {size})");
+                    heap.alloc(ptr, size);
+                }
+            }
+        }
+        Ok((0, 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HeapShadow;
+
+    #[test]
+    fn heap_shadow() {
+        let mut heap = HeapShadow::default();
+        assert_eq!(heap.check(0x1000, 0x1004), None);
+
+        heap.alloc(0x1000, 6);
+        heap.alloc(0x1010, 4);
+        assert_eq!(heap.check(0x1000, 0x1004), None);
+        assert_eq!(heap.check(0x1004, 0x1008), None);
+        assert_eq!(
+            heap.check(0x1008, 0x100c),
+            Some("access to unallocated heap memory")
+        );
+        assert_eq!(
+            heap.check(0x1006, 0x100a),
+            Some("access to unallocated heap memory")
+        );
+        assert_eq!(
+            heap.check(0x1014, 0x1018),
+            Some("access to unallocated heap memory")
+        );
+        assert_eq!(heap.check(0x0ffc, 0x1000), None);
+
+        heap.free(0x1000).unwrap();
+        assert_eq!(heap.check(0x1000, 0x1004), Some("heap-use-after-free"));
+        assert_eq!(heap.check(0x1010, 0x1014), None);
+        assert!(heap.free(0x1000).is_err());
+        assert!(heap.free(0x1020).is_err());
+    }
+}
@@ -19,7 +19,9 @@
 //! contains an execution trace of the specified program.
 
 pub(crate) mod compose;
+pub(crate) mod coverage;
 pub(crate) mod executor;
+pub(crate) mod memcheck;
 pub(crate) mod profiler;
 mod proto;
 pub(crate) mod syscall;
@@ -21,7 +21,7 @@
 
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt::Write,
     hash::{Hash, Hasher},
     rc::Rc,
@@ -94,10 +94,10 @@ fn extract_call_stack_op(insn: u32) -> Option<CallStackOp> {
 #[derive(Clone, Debug, Default)]
 struct CallNode {
     /// Counter by program counter with the current call stack.
-    pub(crate) counts: HashMap<u32, usize>,
+    pub(crate) counts: BTreeMap<u32, usize>,
 
     /// Nodes representing further calls from this context.
-    pub(crate) calls: HashMap<u32, Rc<RefCell<CallNode>>>,
+    pub(crate) calls: BTreeMap<u32, Rc<RefCell<CallNode>>>,
 }
 
 impl CallNode {
@@ -17,7 +17,12 @@
 mod fork;
 mod pipe;
 
-use std::{cell::RefCell, cmp::min, collections::HashMap, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    cmp::min,
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
 
 use anyhow::{anyhow, bail, Result};
 use bytes::Bytes;
@@ -26,8 +31,9 @@ use risc0_zkvm_platform::{
     fileno,
     syscall::{
         nr::{
-            SYS_ARGC, SYS_ARGV, SYS_CYCLE_COUNT, SYS_EXECUTE_ZKR, SYS_FORK, SYS_GETENV, SYS_LOG,
-            SYS_PANIC, SYS_PIPE, SYS_RANDOM, SYS_READ, SYS_VERIFY_INTEGRITY, SYS_WRITE,
+            SYS_ARGC, SYS_ARGV, SYS_CYCLE_COUNT, SYS_EXECUTE_ZKR, SYS_FORK, SYS_GETENV,
+            SYS_HEAP_ALLOC, SYS_HEAP_FREE, SYS_JOURNAL_HASH, SYS_LOG, SYS_PANIC, SYS_PIPE,
+            SYS_RANDOM, SYS_READ, SYS_VERIFY_INTEGRITY, SYS_WRITE,
         },
         reg_abi::{REG_A3, REG_A4, REG_A5},
         SyscallName,
@@ -38,10 +44,10 @@ use risc0_zkvm_platform::{
 use crate::{
     host::{
         client::{posix_io::PosixIo, slice_io::SliceIo},
-        server::exec::compose::SysCompose,
+        server::exec::{compose::SysCompose, memcheck::SysHeap},
     },
     sha::{Digest, DIGEST_BYTES},
-    ExecutorEnv,
+    ExecutorEnv, JournalHash,
 };
 
 use self::{fork::SysFork, pipe::SysPipe};
@@ -108,16 +114,22 @@ impl<'a> SyscallTable<'a> {
         let mut this = Self::new(env.posix_io.clone());
 
         let sys_compose = SysCompose::new(env.assumptions.clone());
+        if env.nondeterministic.get().is_none() {
+            env.nondeterministic.set(Some(false));
+        }
 
         this.with_syscall(SYS_ARGC, Args(env.args.clone()))
             .with_syscall(SYS_ARGV, Args(env.args.clone()))
-            .with_syscall(SYS_CYCLE_COUNT, SysCycleCount)
+            .with_syscall(SYS_CYCLE_COUNT, SysCycleCount(env.nondeterministic.clone()))
             .with_syscall(SYS_FORK, SysFork)
             .with_syscall(SYS_GETENV, SysGetenv(env.env_vars.clone()))
+            .with_syscall(SYS_HEAP_ALLOC, SysHeap(None))
+            .with_syscall(SYS_HEAP_FREE, SysHeap(None))
+            .with_syscall(SYS_JOURNAL_HASH, SysJournalHash::default())
             .with_syscall(SYS_LOG, SysLog)
             .with_syscall(SYS_PANIC, SysPanic)
             .with_syscall(SYS_PIPE, SysPipe::default())
-            .with_syscall(SYS_RANDOM, SysRandom)
+            .with_syscall(SYS_RANDOM, SysRandom(env.nondeterministic.clone()))
             .with_syscall(SYS_READ, SysRead)
             .with_syscall(SYS_VERIFY_INTEGRITY, sys_compose.clone())
             .with_syscall(SYS_EXECUTE_ZKR, sys_compose.clone())
@@ -146,7 +158,8 @@ impl<'a> SyscallTable<'a> {
     }
 }
 
-pub(crate) struct SysCycleCount;
+/// Reports the current cycle, and records that the execution depends on it.
+pub(crate) struct SysCycleCount(pub Rc<Cell<Option<bool>>>);
 impl Syscall for SysCycleCount {
     fn syscall(
         &mut self,
@@ -154,6 +167,7 @@ impl Syscall for SysCycleCount {
         ctx: &mut dyn SyscallContext,
         _to_guest: &mut [u32],
     ) -> Result<(u32, u32)> {
+        self.0.set(Some(true));
         let cycle = ctx.get_cycle();
         let hi = (cycle >> 32) as u32;
         let lo = cycle as u32;
@@ -161,7 +175,26 @@ impl Syscall for SysCycleCount {
     }
 }
 
-pub(crate) struct SysGetenv(pub HashMap<String, String>);
+/// Records the [JournalHash] selected by the guest, to be reported in its claim.
+#[derive(Default)]
+pub(crate) struct SysJournalHash(pub Rc<Cell<JournalHash>>);
+impl Syscall for SysJournalHash {
+    fn syscall(
+        &mut self,
+        _syscall: &str,
+        ctx: &mut dyn SyscallContext,
+        _to_guest: &mut [u32],
+    ) -> Result<(u32, u32)> {
+        let hash = ctx.load_register(REG_A3);
+        let hash = JournalHash::from_u32(hash)
+            .ok_or_else(|| anyhow!("Guest selected an unsupported journal hash: {hash}"))?;
+        tracing::debug!("sys_journal_hash({hash:?})");
+        self.0.set(hash);
+        Ok((0, 0))
+    }
+}
+
+pub(crate) struct SysGetenv(pub BTreeMap<String, String>);
 impl Syscall for SysGetenv {
     fn syscall(
         &mut self,
@@ -202,7 +235,8 @@ impl Syscall for SysPanic {
     }
 }
 
-pub(crate) struct SysRandom;
+/// Provides random bytes to the guest, and records that the execution depends on them.
+pub(crate) struct SysRandom(pub Rc<Cell<Option<bool>>>);
 impl Syscall for SysRandom {
     fn syscall(
         &mut self,
@@ -211,6 +245,7 @@ impl Syscall for SysRandom {
         to_guest: &mut [u32],
     ) -> Result<(u32, u32)> {
         tracing::debug!("SYS_RANDOM: {}", to_guest.len());
+        self.0.set(Some(true));
         let mut rand_buf = vec![0u8; to_guest.len() * WORD_SIZE];
         getrandom::getrandom(rand_buf.as_mut_slice())?;
         bytemuck::cast_slice_mut(to_guest).clone_from_slice(rand_buf.as_slice());
@@ -81,6 +81,7 @@ impl<'a, 'b> ChildExecutor<'a, 'b> {
         // remove the ability to write to the JOURNAL from a child process.
         let mut posix_io = ctx.syscall_table().posix_io.borrow().clone();
         posix_io.write_fds.remove(&fileno::JOURNAL);
+        posix_io.write_fds.remove(&fileno::JOURNAL_STREAM);
         syscall_table.posix_io = Rc::new(RefCell::new(posix_io));
 
         // avoid the possibility of fork bombs.
@@ -17,13 +17,14 @@ use std::{
     io::Cursor,
     str::from_utf8,
     sync::Mutex,
+    time::Duration,
 };
 
 use anyhow::Result;
 use bytes::Bytes;
 use risc0_binfmt::{MemoryImage, Program};
 use risc0_zkvm_methods::{
-    multi_test::{MultiTestSpec, SYS_MULTI_TEST, SYS_MULTI_TEST_WORDS},
+    multi_test::{MultiTestSpec, TestOracle, SYS_MULTI_TEST, SYS_MULTI_TEST_WORDS},
     BLST_ELF, HEAP_ELF, HELLO_COMMIT_ELF, MULTI_TEST_ELF, RAND_ELF, SLICE_IO_ELF, STANDARD_LIB_ELF,
     ZKVM_527_ELF,
 };
@@ -39,9 +40,11 @@ use crate::{
         },
         testutils,
     },
+    oracle::{Oracle, OracleCall},
     serde::to_vec,
     sha::{Digest, Digestible},
-    ExecutorEnv, ExecutorImpl, ExitCode,
+    CancellationToken, ExecutorEnv, ExecutorImpl, ExitCode, JournalHash, MaybePruned,
+    ResourceLimitExceeded, ResourceLimits, StreamCommitment, StreamVerifier,
 };
 
 fn run_test(spec: MultiTestSpec) {
@@ -485,6 +488,28 @@ fn large_io_words() {
     assert_eq!(actual, expected);
 }
 
+#[test]
+fn commit_words() {
+    let words = vec![0x0403_0201, 0xdead_beef, 0];
+    let env = ExecutorEnv::builder()
+        .write(&MultiTestSpec::CommitWords {
+            words: words.clone(),
+        })
+        .unwrap()
+        .build()
+        .unwrap();
+    let session = ExecutorImpl::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_eq!(session.exit_code, ExitCode::Halted(0));
+
+    // The words are committed as their little-endian bytes, without a length prefix.
+    let journal = &session.journal.unwrap().bytes;
+    assert_eq!(journal[..4], [1, 2, 3, 4]);
+    assert_eq!(journal, bytemuck::cast_slice::<u32, u8>(&words));
+}
+
 #[test]
 fn large_io_bytes() {
     const FD: u32 = 123;
@@ -509,6 +534,364 @@ fn large_io_bytes() {
     assert_eq!(&buf, actual);
 }
 
+#[test]
+fn cancellation_token() {
+    let token = CancellationToken::new();
+    let env = ExecutorEnv::builder()
+        .write(&MultiTestSpec::BusyLoop { cycles: 1 << 16 })
+        .unwrap()
+        .cancellation_token(token.clone())
+        .build()
+        .unwrap();
+    let mut exec = ExecutorImpl::from_elf(env, MULTI_TEST_ELF).unwrap();
+
+    token.cancel();
+    let session = exec.run().unwrap();
+    assert_eq!(session.exit_code, ExitCode::SystemSplit);
+    assert!(session.journal.is_none());
+    for segment in session.segments.iter() {
+        assert_eq!(
+            segment.resolve().unwrap().inner.exit_code,
+            ExitCode::SystemSplit
+        );
+    }
+
+    // An interrupted session has no provable claim.
+    assert!(session
+        .claim()
+        .unwrap_err()
+        .to_string()
+        .contains("interrupted"));
+
+    // Execution resumes where it was interrupted.
+    token.reset();
+    let session = exec.run().unwrap();
+    assert_eq!(session.exit_code, ExitCode::Halted(0));
+}
+
+#[test]
+fn timeout() {
+    let env = ExecutorEnv::builder()
+        .write(&MultiTestSpec::BusyLoop { cycles: 1 << 32 })
+        .unwrap()
+        .timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    let session = ExecutorImpl::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_eq!(session.exit_code, ExitCode::SystemSplit);
+    assert!(session.user_cycles < 1 << 32);
+}
+
+#[test]
+fn resource_limits() {
+    fn run(elf: &[u8], spec: MultiTestSpec, limits: ResourceLimits) -> ResourceLimitExceeded {
+        let env = ExecutorEnv::builder()
+            .write(&spec)
+            .unwrap()
+            .resource_limits(limits)
+            .build()
+            .unwrap();
+        let err = ExecutorImpl::from_elf(env, elf)
+            .unwrap()
+            .run()
+            .err()
+            .unwrap();
+        err.downcast_ref::<ResourceLimitExceeded>().unwrap().clone()
+    }
+
+    let busy_loop = || MultiTestSpec::BusyLoop { cycles: 1 << 16 };
+    assert_eq!(
+        run(
+            MULTI_TEST_ELF,
+            busy_loop(),
+            ResourceLimits {
+                max_cycles: Some(1 << 14),
+                ..Default::default()
+            }
+        ),
+        ResourceLimitExceeded::Cycles { limit: 1 << 14 }
+    );
+    assert_eq!(
+        run(
+            MULTI_TEST_ELF,
+            busy_loop(),
+            ResourceLimits {
+                max_syscalls: Some(0),
+                ..Default::default()
+            }
+        ),
+        ResourceLimitExceeded::Syscalls { limit: 0 }
+    );
+    assert_eq!(
+        run(
+            MULTI_TEST_ELF,
+            busy_loop(),
+            ResourceLimits {
+                max_memory_pages: Some(4),
+                ..Default::default()
+            }
+        ),
+        ResourceLimitExceeded::MemoryPages { limit: 4 }
+    );
+    assert_eq!(
+        run(
+            HELLO_COMMIT_ELF,
+            MultiTestSpec::DoNothing,
+            ResourceLimits {
+                max_journal_bytes: Some(4),
+                ..Default::default()
+            }
+        ),
+        ResourceLimitExceeded::JournalBytes { limit: 4 }
+    );
+
+    // Limits which are not reached have no effect.
+    let env = ExecutorEnv::builder()
+        .resource_limits(ResourceLimits {
+            max_cycles: Some(1 << 20),
+            max_memory_pages: Some(1 << 10),
+            max_journal_bytes: Some(11),
+            max_syscalls: Some(10),
+        })
+        .build()
+        .unwrap();
+    let session = ExecutorImpl::from_elf(env, HELLO_COMMIT_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_eq!(session.journal.unwrap().bytes, b"hello world");
+}
+
+#[test]
+fn resource_limits_pause_resume() {
+    fn executor(limits: ResourceLimits) -> ExecutorImpl<'static> {
+        let env = ExecutorEnv::builder()
+            .write(&MultiTestSpec::PauseResume(0))
+            .unwrap()
+            .resource_limits(limits)
+            .build()
+            .unwrap();
+        ExecutorImpl::from_elf(env, MULTI_TEST_ELF).unwrap()
+    }
+
+    // The cycles used before the pause count towards the limit after resuming.
+    let user_cycles = executor(ResourceLimits::default()).run().unwrap().user_cycles;
+    let mut exec = executor(ResourceLimits::default().with_max_cycles(user_cycles));
+    assert_eq!(exec.run().unwrap().exit_code, ExitCode::Paused(0));
+    let err = exec.run().unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ResourceLimitExceeded>(),
+        Some(&ResourceLimitExceeded::Cycles { limit: user_cycles })
+    );
+
+    // As do the syscalls: find the fewest which let the guest reach the pause.
+    let mut exec = (0..)
+        .map(|limit| executor(ResourceLimits::default().with_max_syscalls(limit)))
+        .find_map(|mut exec| exec.run().is_ok().then_some(exec))
+        .unwrap();
+    let err = exec.run().unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ResourceLimitExceeded>(),
+        Some(ResourceLimitExceeded::Syscalls { .. })
+    ));
+
+    // And the bytes committed to the journal.
+    let env = ExecutorEnv::builder()
+        .write(&MultiTestSpec::PauseResumeCommit(b"hello".to_vec()))
+        .unwrap()
+        .resource_limits(ResourceLimits::default().with_max_journal_bytes(8))
+        .build()
+        .unwrap();
+    let mut exec = ExecutorImpl::from_elf(env, MULTI_TEST_ELF).unwrap();
+    assert_eq!(exec.run().unwrap().exit_code, ExitCode::Paused(0));
+    let err = exec.run().unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ResourceLimitExceeded>(),
+        Some(&ResourceLimitExceeded::JournalBytes { limit: 8 })
+    );
+}
+
+#[test]
+fn journal_sink() {
+    let mut journal = Vec::new();
+    let env = ExecutorEnv::builder()
+        .journal_sink(&mut journal)
+        .build()
+        .unwrap();
+    let session = ExecutorImpl::from_elf(env, HELLO_COMMIT_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    let digest: Digest = Sha256::digest(b"hello world")
+        .as_slice()
+        .try_into()
+        .unwrap();
+    assert!(session.journal.is_none());
+    assert_eq!(session.journal_digest, Some(digest));
+    assert_eq!(
+        session
+            .claim()
+            .unwrap()
+            .output
+            .value()
+            .unwrap()
+            .unwrap()
+            .journal,
+        MaybePruned::Pruned(digest)
+    );
+    assert_eq!(journal, b"hello world");
+
+    // The maximum journal size applies to streamed journals.
+    let env = ExecutorEnv::builder()
+        .journal_sink(Vec::new())
+        .resource_limits(ResourceLimits {
+            max_journal_bytes: Some(4),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    let err = ExecutorImpl::from_elf(env, HELLO_COMMIT_ELF)
+        .unwrap()
+        .run()
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ResourceLimitExceeded>(),
+        Some(&ResourceLimitExceeded::JournalBytes { limit: 4 })
+    );
+}
+
+#[test]
+fn journal_sink_poseidon2() {
+    // A streamed journal is digested with the journal hash selected by the guest.
+    let data = b"poseidon2 journal".to_vec();
+    let mut journal = Vec::new();
+    let env = ExecutorEnv::builder()
+        .write(&MultiTestSpec::Poseidon2Journal(data.clone()))
+        .unwrap()
+        .journal_sink(&mut journal)
+        .build()
+        .unwrap();
+    let session = ExecutorImpl::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    let digest = JournalHash::Poseidon2.digest(&data);
+    assert_eq!(session.journal_hash, JournalHash::Poseidon2);
+    assert_eq!(session.journal_digest, Some(digest));
+
+    // The output matches the digest reported by the guest.
+    let segment = session.segments.last().unwrap().resolve().unwrap();
+    let output = segment.output.unwrap();
+    assert_eq!(output.journal, MaybePruned::Pruned(digest));
+    assert_eq!(segment.inner.output_digest, Some(output.digest()));
+    assert_eq!(journal, data);
+}
+
+#[test]
+fn watchpoint() {
+    use risc0_zkvm_platform::memory::{GUEST_MIN_MEM, STACK_TOP};
+
+    use crate::{WatchAction, Watchpoint};
+
+    let stack = Watchpoint::new(GUEST_MIN_MEM as u32..STACK_TOP).stores_only();
+
+    let env = ExecutorEnv::builder()
+        .watch(stack.clone().action(WatchAction::Break))
+        .build()
+        .unwrap();
+    let err = ExecutorImpl::from_elf(env, HELLO_COMMIT_ELF)
+        .unwrap()
+        .run()
+        .unwrap_err();
+    assert!(err.to_string().contains("watchpoint"), "{err}");
+
+    // Logged accesses do not change the result of execution.
+    let env = ExecutorEnv::builder().watch(stack).build().unwrap();
+    let session = ExecutorImpl::from_elf(env, HELLO_COMMIT_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_eq!(session.journal.unwrap().bytes, b"hello world");
+}
+
+#[test]
+fn replay() {
+    use crate::{Divergence, ExecutionRecord};
+
+    let exec = |slice: &[u8]| {
+        let env = ExecutorEnv::builder()
+            .write_slice(&[slice.len() as u32])
+            .write_slice(slice)
+            .build()
+            .unwrap();
+        ExecutorImpl::from_elf(env, SLICE_IO_ELF).unwrap()
+    };
+
+    let session = exec(b"xyz").run().unwrap();
+    let archived = ExecutionRecord::new(&session).unwrap();
+
+    // Randomness is taken from the archive, so an identical replay does not diverge.
+    assert_eq!(exec(b"xyz").replay(&archived).unwrap(), None);
+
+    let divergence = exec(b"xyw").replay(&archived).unwrap().unwrap();
+    assert!(
+        matches!(divergence, Divergence::Syscall { segment: 0, .. }),
+        "{divergence}"
+    );
+}
+
+#[test]
+fn oracle() {
+    struct Impl;
+
+    impl TestOracle for Impl {
+        fn add(&mut self, a: u32, b: u32) -> u32 {
+            a + b
+        }
+
+        fn greet(&mut self, name: String) -> String {
+            format!("hello {name}")
+        }
+    }
+
+    let run = |commit| {
+        let env = ExecutorEnv::builder()
+            .write(&MultiTestSpec::Oracle { commit })
+            .unwrap()
+            .oracle::<dyn TestOracle>(Box::new(Impl))
+            .build()
+            .unwrap();
+        ExecutorImpl::from_elf(env, MULTI_TEST_ELF)
+            .unwrap()
+            .run()
+            .unwrap()
+    };
+
+    let session = run(false);
+    let calls = &session.oracle_calls;
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0].method, "add");
+    assert_eq!(calls[0].args, to_vec(&(2u32, 3u32)).unwrap());
+    assert_eq!(calls[0].result, to_vec(&5u32).unwrap());
+    assert_eq!(calls[1].method, "greet");
+    assert_eq!(
+        calls[1].channel,
+        <dyn TestOracle as Oracle>::CHANNEL.as_str()
+    );
+    let journal: (u32, String) = session.journal.as_ref().unwrap().decode().unwrap();
+    assert_eq!(journal, (5, "hello zkVM".to_string()));
+
+    // Recorded calls are in the journal only where the guest commits them.
+    let session = run(true);
+    let journal: ((u32, String), Vec<OracleCall>) =
+        session.journal.as_ref().unwrap().decode().unwrap();
+    assert_eq!(journal.0, (5, "hello zkVM".to_string()));
+    assert_eq!(journal.1, session.oracle_calls);
+}
+
 mod sys_verify {
     use risc0_zkvm_methods::{
         multi_test::MultiTestSpec, HELLO_COMMIT_ELF, HELLO_COMMIT_ID, MULTI_TEST_ELF, MULTI_TEST_ID,
@@ -516,7 +899,9 @@ mod sys_verify {
     use test_log::test;
 
     use crate::{
-        serde::to_vec, sha::Digestible, ExecutorEnv, ExecutorEnvBuilder, ExecutorImpl, ExitCode,
+        serde::to_vec,
+        sha::{Digest, Digestible},
+        Assumption, Assumptions, ExecutorEnv, ExecutorEnvBuilder, ExecutorImpl, ExitCode,
         MaybePruned, ReceiptClaim, Session,
     };
 
@@ -591,6 +976,39 @@ mod sys_verify {
             .is_err());
     }
 
+    #[test]
+    fn commit_assumptions_digest() {
+        let hello_commit_session = exec_hello_commit();
+        let assumption = hello_commit_session.claim().unwrap();
+
+        let spec = &MultiTestSpec::CommitAssumptionsDigest {
+            image_id: HELLO_COMMIT_ID.into(),
+            journal: hello_commit_session.journal.clone().unwrap().bytes,
+        };
+        let env = ExecutorEnv::builder()
+            .write(&spec)
+            .unwrap()
+            .add_assumption(assumption.clone())
+            .build()
+            .unwrap();
+        let session = ExecutorImpl::from_elf(env, MULTI_TEST_ELF)
+            .unwrap()
+            .run()
+            .unwrap();
+        assert_eq!(session.exit_code, ExitCode::Halted(0));
+
+        // The digest committed by the guest is the one recorded in the claim.
+        let committed: Digest = session.journal.as_ref().unwrap().decode().unwrap();
+        let claim = session.claim().unwrap();
+        let output = claim.output.as_value().unwrap().as_ref().unwrap();
+        assert_eq!(committed, output.assumptions.digest());
+        let assumption = Assumption {
+            claim: assumption.digest(),
+            control_root: Digest::ZERO,
+        };
+        assert_eq!(committed, Assumptions(vec![assumption.into()]).digest());
+    }
+
     #[test]
     fn sys_verify_halt_codes() {
         for code in [0u8, 1, 2, 255] {
@@ -918,6 +1336,40 @@ fn fault() {
     assert!(err.to_string().contains("StoreAccessFault"));
 }
 
+#[test]
+fn coverage() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("lcov.info");
+    let env = ExecutorEnv::builder()
+        .write(&MultiTestSpec::Profiler)
+        .unwrap()
+        .enable_coverage(&path)
+        .build()
+        .unwrap();
+    ExecutorImpl::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+
+    let lcov = std::fs::read_to_string(path).unwrap();
+    let record = lcov
+        .split("end_of_record\n")
+        .find(|record| record.contains("multi_test.rs"))
+        .unwrap();
+    let lines: Vec<(u32, u64)> = record
+        .lines()
+        .filter_map(|line| line.strip_prefix("DA:"))
+        .map(|da| {
+            let (line, count) = da.split_once(',').unwrap();
+            (line.parse().unwrap(), count.parse().unwrap())
+        })
+        .collect();
+    // Some lines of the guest ran, and others, for the tests not selected, did not.
+    assert!(lines.iter().any(|(_, count)| *count > 0));
+    assert!(lines.iter().any(|(_, count)| *count == 0));
+    assert!(record.contains("profile_test_func1"));
+}
+
 #[test]
 fn profiler() {
     let mut profiler = Profiler::new(MULTI_TEST_ELF, Some("multi_test.elf")).unwrap();
@@ -1150,6 +1602,47 @@ fn sys_fork_journal_panic() {
     run_test(MultiTestSpec::SysForkJournalPanic);
 }
 
+#[test]
+fn commit_stream() {
+    let spec = MultiTestSpec::CommitStream {
+        chunks: 4,
+        chunk_len: 1000,
+    };
+    let expected: Vec<u8> = (0..4u8).flat_map(|i| [i; 1000]).collect();
+
+    let mut verifier = StreamVerifier::new(Vec::new());
+    let env = ExecutorEnv::builder()
+        .write(&spec)
+        .unwrap()
+        .journal_stream(&mut verifier)
+        .build()
+        .unwrap();
+    let session = ExecutorImpl::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+
+    // Only the commitment is in the journal, and the streamed bytes match it.
+    let journal = session.journal.unwrap();
+    let commitment: StreamCommitment = journal.decode().unwrap();
+    assert_eq!(commitment.len, expected.len() as u64);
+    assert!(journal.bytes.len() < expected.len());
+    assert_eq!(verifier.verify(&commitment).unwrap(), expected);
+
+    // Without a writer on the host, the stream is discarded and only the commitment is kept.
+    let env = ExecutorEnv::builder()
+        .write(&spec)
+        .unwrap()
+        .build()
+        .unwrap();
+    let session = ExecutorImpl::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    let commitment: StreamCommitment = session.journal.unwrap().decode().unwrap();
+    assert!(commitment.matches(&expected));
+}
+
 #[test]
 fn heap_alloc() {
     let env = ExecutorEnv::builder()
@@ -1162,6 +1655,30 @@ fn heap_alloc() {
         .run()
         .unwrap();
     assert_eq!(session.exit_code, ExitCode::Halted(0));
+
+    // Each iteration frees its 10 MiB buffer before the next one is allocated.
+    let (peak, total): (u32, u32) = session.journal.unwrap().decode().unwrap();
+    let alloc_size = 10 * 1024 * 1024;
+    assert!(peak >= alloc_size && peak < 2 * alloc_size);
+    assert!(total >= 6 * alloc_size);
+}
+
+#[test]
+fn heap_alignment_padding() {
+    let env = ExecutorEnv::builder()
+        .write(&MultiTestSpec::HeapAlignment)
+        .unwrap()
+        .build()
+        .unwrap();
+    let session = ExecutorImpl::from_elf(env, MULTI_TEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+
+    // The padding inserted to align the word is counted along with the allocations.
+    let (growth, span): (u32, u32) = session.journal.unwrap().decode().unwrap();
+    assert_eq!(span, 256 + 4);
+    assert!(growth >= span, "{growth} < {span}");
 }
 
 #[test]
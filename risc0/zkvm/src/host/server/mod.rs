@@ -15,6 +15,7 @@
 pub(crate) mod exec;
 #[cfg(feature = "prove")]
 pub(crate) mod prove;
+pub(crate) mod replay;
 pub(crate) mod session;
 #[cfg(test)]
 mod testutils;
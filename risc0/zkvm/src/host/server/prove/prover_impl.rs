@@ -12,13 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use risc0_circuit_rv32im::prove::SegmentProver;
+use risc0_zkp::adapter::ProofSystemVersion;
 
 use super::ProverServer;
 use crate::{
     host::{
-        client::prove::ReceiptKind,
+        client::{prove::ReceiptKind, receipt_store::ReceiptKey},
         prove_info::ProveInfo,
         recursion::{identity_p254, join, lift, resolve},
     },
@@ -26,8 +27,9 @@ use crate::{
         segment::decode_receipt_claim_from_seal, InnerReceipt, SegmentReceipt, SuccinctReceipt,
     },
     receipt_claim::{MaybePruned, Merge, Unknown},
-    sha::Digestible,
-    CompositeReceipt, Output, ProverOpts, Receipt, ReceiptClaim, Segment, Session, VerifierContext,
+    sha::{Digest, Digestible},
+    CompositeReceipt, ExitCode, Output, ProverOpts, Receipt, ReceiptClaim, Segment, Session,
+    VerifierContext,
 };
 
 /// An implementation of a Prover that runs locally.
@@ -54,13 +56,48 @@ impl ProverServer for ProverImpl {
             session.journal.as_ref().map(hex::encode),
             session.segments.len()
         );
+        ensure!(
+            session.exit_code != ExitCode::SystemSplit,
+            "cannot prove an interrupted session; resume execution until it halts or pauses"
+        );
+        ensure!(
+            !session.compressed,
+            "cannot prove a session of a guest built with RV32C compressed instructions; \
+             rebuild it for rv32im"
+        );
+        if self.opts.receipt_kind != ReceiptKind::Composite {
+            ensure!(
+                segment_proof_system_version(ctx)? == ProofSystemVersion::default(),
+                "the recursion programs only verify segment seals of the default proof system \
+                 version; request a composite receipt to prove with another version"
+            );
+        }
         let mut segments = Vec::new();
+        let mut prev_post_state: Option<Digest> = None;
         for segment_ref in session.segments.iter() {
             let segment = segment_ref.resolve()?;
             for hook in &session.hooks {
                 hook.on_pre_prove_segment(&segment);
             }
-            segments.push(self.prove_segment(ctx, &segment)?);
+            let segment_receipt = self.prove_segment(ctx, &segment)?;
+            if self.opts.verify_segments {
+                // The seal was verified by prove_segment, so only the continuation remains.
+                let pre_state = segment_receipt.claim.pre.digest();
+                ensure!(
+                    prev_post_state.map_or(true, |post_state| post_state == pre_state),
+                    "segment {} failed verification: pre-state does not match post-state of \
+                     previous segment",
+                    segment.index
+                );
+                prev_post_state = Some(segment_receipt.claim.post.digest());
+            }
+            if let Some(store) = &session.receipt_store {
+                store.put_receipt(
+                    &ReceiptKey::segment(segment_receipt.claim.digest()),
+                    &segment_receipt,
+                )?;
+            }
+            segments.push(segment_receipt);
             for hook in &session.hooks {
                 hook.on_post_prove_segment(&segment);
             }
@@ -81,9 +118,12 @@ impl ProverServer for ProverImpl {
                 &session
                     .journal
                     .as_ref()
-                    .map(|journal| Output {
-                        journal: MaybePruned::Pruned(journal.digest()),
+                    .map(|journal| journal.digest_with(session.journal_hash))
+                    .or(session.journal_digest)
+                    .map(|digest| Output {
+                        journal: MaybePruned::Pruned(digest),
                         assumptions: assumptions.into(),
+                        journal_hash: session.journal_hash,
                     })
                     .into(),
             )
@@ -158,6 +198,10 @@ impl ProverServer for ProverImpl {
             );
         }
 
+        if let Some(store) = &session.receipt_store {
+            store.put_receipt(&ReceiptKey::receipt(receipt.claim()?.digest()), &receipt)?;
+        }
+
         Ok(ProveInfo {
             receipt,
             stats: session.stats(),
@@ -165,7 +209,9 @@ impl ProverServer for ProverImpl {
     }
 
     fn prove_segment(&self, ctx: &VerifierContext, segment: &Segment) -> Result<SegmentReceipt> {
-        let seal = self.segment_prover.prove_segment(&segment.inner)?;
+        let seal = self
+            .segment_prover
+            .prove_segment_with_version(&segment.inner, segment_proof_system_version(ctx)?)?;
 
         let mut claim = decode_receipt_claim_from_seal(&seal)?;
         claim.output = segment.output.clone().into();
@@ -184,7 +230,9 @@ impl ProverServer for ProverImpl {
             claim,
             verifier_parameters,
         };
-        receipt.verify_integrity_with_context(ctx)?;
+        if self.opts.verify_segments {
+            verify_segment(ctx, &receipt)?;
+        }
 
         Ok(receipt)
     }
@@ -216,3 +264,22 @@ impl ProverServer for ProverImpl {
         identity_p254(a)
     }
 }
+
+// Verifies a segment receipt as soon as it is proven, so that faulty hardware or a prover bug is
+// reported before the rest of the session is proven.
+pub(super) fn verify_segment(ctx: &VerifierContext, receipt: &SegmentReceipt) -> Result<()> {
+    receipt
+        .verify_integrity_with_context(ctx)
+        .with_context(|| format!("segment {} failed verification", receipt.index))
+}
+
+/// The [ProofSystemVersion] with which segments are to be proven to verify under `ctx`.
+fn segment_proof_system_version(ctx: &VerifierContext) -> Result<ProofSystemVersion> {
+    let params = ctx.segment_verifier_parameters.as_ref().ok_or(anyhow!(
+        "segment receipt verifier parameters missing from context"
+    ))?;
+    ProofSystemVersion::from_info(&params.proof_system_info).ok_or(anyhow!(
+        "unsupported proof system: {}",
+        params.proof_system_info
+    ))
+}
@@ -13,10 +13,12 @@
 // limitations under the License.
 
 use anyhow::Result;
-use risc0_binfmt::MemoryImage;
+use risc0_binfmt::{compute_image_id, source_digest, MemoryImage};
 use risc0_circuit_rv32im::prove::emu::testutil;
-use risc0_zkp::{core::digest::Digest, verify::VerificationError};
-use risc0_zkvm_methods::{multi_test::MultiTestSpec, MULTI_TEST_ELF, MULTI_TEST_ID};
+use risc0_zkp::{adapter::ProofSystemVersion, core::digest::Digest, verify::VerificationError};
+use risc0_zkvm_methods::{
+    multi_test::MultiTestSpec, MULTI_TEST_ELF, MULTI_TEST_ID, SOURCE_DIGEST_ELF, SOURCE_DIGEST_ID,
+};
 use risc0_zkvm_platform::{memory, PAGE_SIZE, WORD_SIZE};
 use test_log::test;
 
@@ -24,7 +26,10 @@ use super::get_prover_server;
 use crate::{
     host::server::testutils,
     serde::{from_slice, to_vec},
-    ExecutorEnv, ExecutorImpl, ExitCode, ProveInfo, ProverOpts, Receipt, Session, VerifierContext,
+    sha::Digestible,
+    CachedProver, ExecutorEnv, ExecutorImpl, ExitCode, FileReceiptStore, Input, JournalHash,
+    ProveInfo, ProverOpts, Receipt, ReceiptKey, ReceiptStore, SegmentReceipt, Session,
+    VerifierContext,
 };
 
 fn prove_session_fast(session: &Session) -> Receipt {
@@ -63,6 +68,50 @@ fn prove_nothing_succinct() {
         .unwrap(); // ensure that we got a succinct receipt.
 }
 
+#[test]
+fn into_succinct() {
+    let receipt = prove_nothing("poseidon2").unwrap().receipt;
+    let segment_claims: Vec<Digest> = receipt
+        .inner
+        .composite()
+        .unwrap()
+        .segments
+        .iter()
+        .map(|segment| segment.claim.digest())
+        .collect();
+
+    let opts = ProverOpts::succinct();
+    let (succinct, claims) = receipt.clone().into_succinct(&opts).unwrap();
+    succinct.inner.succinct().unwrap();
+    succinct.verify(MULTI_TEST_ID).unwrap();
+    assert_eq!(succinct.journal, receipt.journal);
+    assert_eq!(claims, segment_claims);
+
+    let (again, claims) = succinct.clone().into_succinct(&opts).unwrap();
+    assert_eq!(again, succinct);
+    assert!(claims.is_empty());
+}
+
+#[test]
+fn receipt_encoding_unchanged() {
+    let receipt = prove_nothing("poseidon2").unwrap().receipt;
+
+    // Receipts serialized by previous releases: the metadata holds only the verifier parameters.
+    let fields = (
+        receipt.inner.clone(),
+        receipt.journal.clone(),
+        receipt.metadata.verifier_parameters,
+    );
+
+    let bytes = bincode::serialize(&fields).unwrap();
+    let decoded: Receipt = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(decoded, receipt);
+
+    let bytes = borsh::to_vec(&fields).unwrap();
+    let decoded: Receipt = borsh::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, receipt);
+}
+
 #[test]
 fn hashfn_poseidon2() {
     prove_nothing("poseidon2").unwrap();
@@ -457,6 +506,64 @@ fn continuation() {
     }
 }
 
+#[test]
+fn verify_segments() {
+    let program = testutil::simple_loop();
+    let image = MemoryImage::new(&program, PAGE_SIZE as u32).unwrap();
+
+    let env = ExecutorEnv::builder()
+        .segment_limit_po2(14) // 16k cycles
+        .build()
+        .unwrap();
+    let mut exec = ExecutorImpl::new(env, image).unwrap();
+    let mut session = exec.run().unwrap();
+    assert_eq!(session.segments.len(), 2);
+
+    // Each segment proves fine on its own, but they no longer form a continuation, which is
+    // reported as soon as the out-of-order segment is proven.
+    session.segments.reverse();
+    let prover = get_prover_server(&ProverOpts::fast()).unwrap();
+    let err = prover
+        .prove_session(&VerifierContext::default(), &session)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "segment 0 failed verification: pre-state does not match post-state of previous segment"
+    );
+
+    // Skipping the verification proves the broken continuation, which then fails to verify.
+    let prover = get_prover_server(&ProverOpts::fast().with_verify_segments(false)).unwrap();
+    let receipt = prover
+        .prove_session(&VerifierContext::default(), &session)
+        .unwrap()
+        .receipt;
+    assert!(receipt
+        .verify_integrity_with_context(&VerifierContext::default())
+        .is_err());
+}
+
+#[test]
+fn verify_segments_corrupted_seal() {
+    let program = testutil::simple_loop();
+    let image = MemoryImage::new(&program, PAGE_SIZE as u32).unwrap();
+    let env = ExecutorEnv::builder().build().unwrap();
+    let session = ExecutorImpl::new(env, image).unwrap().run().unwrap();
+    let segment = session.segments[0].resolve().unwrap();
+
+    let ctx = VerifierContext::default();
+    let prover = get_prover_server(&ProverOpts::fast()).unwrap();
+    let mut receipt = prover.prove_segment(&ctx, &segment).unwrap();
+    super::prover_impl::verify_segment(&ctx, &receipt).unwrap();
+
+    // A word of the seal corrupted by faulty hardware is reported as soon as the segment is
+    // proven.
+    let idx = receipt.seal.len() / 2;
+    receipt.seal[idx] ^= 1;
+    let err = super::prover_impl::verify_segment(&ctx, &receipt).unwrap_err();
+    assert_eq!(err.to_string(), "segment 0 failed verification");
+}
+
 #[test]
 fn sys_input() {
     use hex::FromHex;
@@ -476,6 +583,389 @@ fn sys_input() {
     prove_session_fast(&session);
 }
 
+#[test]
+fn commit_input_digest() {
+    let spec = MultiTestSpec::DoNothing;
+    let input: Vec<u8> = bytemuck::cast_slice(&to_vec(&spec).unwrap()).to_vec();
+    let env = ExecutorEnv::builder()
+        .write(&spec)
+        .unwrap()
+        .commit_input_digest(true)
+        .build()
+        .unwrap();
+    let mut exec = ExecutorImpl::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let session = exec.run().unwrap();
+    assert_eq!(session.input, Input::digest_bytes(&input));
+
+    let receipt = prove_session_fast(&session);
+    receipt.verify_with_input(MULTI_TEST_ID, &input).unwrap();
+    assert!(matches!(
+        receipt.verify(MULTI_TEST_ID).unwrap_err(),
+        VerificationError::ClaimDigestMismatch { .. }
+    ));
+    assert!(matches!(
+        receipt
+            .verify_with_input(MULTI_TEST_ID, &[0u8; 4])
+            .unwrap_err(),
+        VerificationError::ClaimDigestMismatch { .. }
+    ));
+}
+
+#[test]
+fn compressed_elf() {
+    // Set EF_RISCV_RVC in the e_flags of the ELF header.
+    let mut elf = MULTI_TEST_ELF.to_vec();
+    elf[36] |= 1;
+    let env = ExecutorEnv::builder()
+        .write(&MultiTestSpec::DoNothing)
+        .unwrap()
+        .build()
+        .unwrap();
+    let session = ExecutorImpl::from_elf(env, &elf).unwrap().run().unwrap();
+    assert!(session.compressed);
+
+    let err = get_prover_server(&ProverOpts::fast())
+        .unwrap()
+        .prove_session(&VerifierContext::default(), &session)
+        .unwrap_err();
+    assert!(err.to_string().contains("compressed instructions"));
+}
+
+#[test]
+fn proof_system_versions() {
+    let session = ExecutorImpl::from_elf(
+        ExecutorEnv::builder()
+            .write(&MultiTestSpec::DoNothing)
+            .unwrap()
+            .build()
+            .unwrap(),
+        MULTI_TEST_ELF,
+    )
+    .unwrap()
+    .run()
+    .unwrap();
+
+    for version in [ProofSystemVersion::V2, ProofSystemVersion::V3] {
+        let ctx = VerifierContext::default().with_proof_system_version(version);
+        let receipt = get_prover_server(&ProverOpts::fast())
+            .unwrap()
+            .prove_session(&ctx, &session)
+            .unwrap()
+            .receipt;
+        receipt.verify_with_context(&ctx, MULTI_TEST_ID).unwrap();
+        assert!(receipt.verify(MULTI_TEST_ID).is_err());
+
+        // The recursion programs only verify segments of the default version, so a succinct
+        // receipt cannot be proven with another one.
+        let err = get_prover_server(&ProverOpts::succinct())
+            .unwrap()
+            .prove_session(&ctx, &session)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("request a composite receipt"));
+    }
+}
+
+#[test]
+fn embedded_source_digest() {
+    let session = ExecutorImpl::from_elf(ExecutorEnv::default(), SOURCE_DIGEST_ELF)
+        .unwrap()
+        .run()
+        .unwrap();
+    let receipt = prove_session_fast(&session);
+    receipt.verify(SOURCE_DIGEST_ID).unwrap();
+
+    // The digest is embedded in the loaded image, which the image ID in the claim commits to,
+    // and the journal holds only the data committed by the guest.
+    assert_ne!(source_digest(SOURCE_DIGEST_ELF).unwrap(), None);
+    assert_eq!(compute_image_id(SOURCE_DIGEST_ELF).unwrap(), SOURCE_DIGEST_ID.into());
+    let value: (u32, [u32; 3]) = receipt.journal.decode_with_schema().unwrap();
+    assert_eq!(value, (42, [1, 2, 3]));
+
+    // Guests built without it have none.
+    assert_eq!(source_digest(MULTI_TEST_ELF).unwrap(), None);
+}
+
+#[test]
+fn poseidon2_journal() {
+    let data = b"poseidon2 journal".to_vec();
+    let env = ExecutorEnv::builder()
+        .write(&MultiTestSpec::Poseidon2Journal(data.clone()))
+        .unwrap()
+        .build()
+        .unwrap();
+    let mut exec = ExecutorImpl::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let session = exec.run().unwrap();
+    assert_eq!(session.journal_hash, JournalHash::Poseidon2);
+
+    let receipt = prove_session_fast(&session);
+    assert_eq!(receipt.journal.bytes, data);
+    receipt
+        .verify_integrity_with_context(&VerifierContext::default())
+        .unwrap();
+
+    // The journal hash must be expected by the verifier.
+    assert!(matches!(
+        receipt.verify(MULTI_TEST_ID).unwrap_err(),
+        VerificationError::ClaimDigestMismatch { .. }
+    ));
+    let ctx = VerifierContext::default().with_journal_hash(JournalHash::Poseidon2);
+    receipt.verify_with_context(&ctx, MULTI_TEST_ID).unwrap();
+
+    // The journal hash survives serialization of the receipt.
+    let decoded: Receipt = bincode::deserialize(&bincode::serialize(&receipt).unwrap()).unwrap();
+    decoded.verify_with_context(&ctx, MULTI_TEST_ID).unwrap();
+}
+
+#[test]
+fn compressed_receipt() {
+    let env = ExecutorEnv::builder()
+        .write(&MultiTestSpec::DoNothing)
+        .unwrap()
+        .build()
+        .unwrap();
+    let mut exec = ExecutorImpl::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let session = exec.run().unwrap();
+    let receipt = prove_session_fast(&session);
+
+    let mut compressed = receipt.clone();
+    compressed.compress();
+    tracing::info!(
+        "composite seal: {} -> {} bytes",
+        receipt.seal_size(),
+        compressed.seal_size()
+    );
+    assert!(compressed.seal_size() < receipt.seal_size());
+    compressed.verify(MULTI_TEST_ID).unwrap();
+
+    compressed.decompress().unwrap();
+    assert_eq!(compressed.seal_size(), receipt.seal_size());
+    assert_eq!(
+        compressed.inner.composite().unwrap().segments[0].seal,
+        receipt.inner.composite().unwrap().segments[0].seal
+    );
+}
+
+#[test]
+fn compressed_receipt_lift() {
+    let receipt = prove_nothing("poseidon2").unwrap().receipt;
+    let mut compressed = receipt.clone();
+    compressed.compress();
+
+    // Recursion accepts compressed seals without an explicit decompression step.
+    let opts = ProverOpts::succinct();
+    let succinct = get_prover_server(&opts)
+        .unwrap()
+        .compress(&opts, &compressed)
+        .unwrap();
+    succinct.inner.succinct().unwrap();
+    succinct.verify(MULTI_TEST_ID).unwrap();
+
+    let mut compressed = succinct.clone();
+    compressed.compress();
+    tracing::info!(
+        "succinct seal: {} -> {} bytes",
+        succinct.seal_size(),
+        compressed.seal_size()
+    );
+    assert!(compressed.seal_size() < succinct.seal_size());
+    compressed.verify(MULTI_TEST_ID).unwrap();
+}
+
+#[test]
+fn cached_prover() {
+    use std::{cell::Cell, rc::Rc};
+
+    use crate::Prover;
+
+    struct CountingProver(Rc<Cell<usize>>);
+
+    impl Prover for CountingProver {
+        fn get_name(&self) -> String {
+            "counting".to_string()
+        }
+
+        fn prove_with_ctx(
+            &self,
+            env: ExecutorEnv<'_>,
+            ctx: &VerifierContext,
+            elf: &[u8],
+            opts: &ProverOpts,
+        ) -> Result<ProveInfo> {
+            self.0.set(self.0.get() + 1);
+            get_prover_server(opts)?.prove_with_ctx(env, ctx, elf)
+        }
+
+        fn compress(&self, opts: &ProverOpts, receipt: &Receipt) -> Result<Receipt> {
+            get_prover_server(opts)?.compress(opts, receipt)
+        }
+    }
+
+    let env = || {
+        ExecutorEnv::builder()
+            .write(&MultiTestSpec::DoNothing)
+            .unwrap()
+            .build()
+            .unwrap()
+    };
+    let calls = Rc::new(Cell::new(0));
+    let dir = tempfile::tempdir().unwrap();
+    let prover = CachedProver::new(
+        CountingProver(calls.clone()),
+        FileReceiptStore::new(dir.path()).unwrap(),
+    );
+    let opts = ProverOpts::fast();
+
+    let first = prover
+        .prove_with_opts(env(), MULTI_TEST_ELF, &opts)
+        .unwrap()
+        .receipt;
+    let second = prover
+        .prove_with_opts(env(), MULTI_TEST_ELF, &opts)
+        .unwrap()
+        .receipt;
+    assert_eq!(calls.get(), 1);
+    assert_eq!(first, second);
+    second.verify(MULTI_TEST_ID).unwrap();
+
+    // Committing the input digest changes the claim, so the receipt is not shared.
+    let mut builder = ExecutorEnv::builder();
+    builder.write(&MultiTestSpec::DoNothing).unwrap();
+    let committed = prover
+        .prove_with_opts(
+            builder.commit_input_digest(true).build().unwrap(),
+            MULTI_TEST_ELF,
+            &opts,
+        )
+        .unwrap()
+        .receipt;
+    assert_eq!(calls.get(), 2);
+    let input = |receipt: &Receipt| receipt.claim().unwrap().value().unwrap().input.digest();
+    assert_ne!(input(&committed), input(&first));
+    committed.verify(MULTI_TEST_ID).unwrap();
+
+    // As do the limits of the execution.
+    let mut builder = ExecutorEnv::builder();
+    builder.write(&MultiTestSpec::DoNothing).unwrap();
+    prover
+        .prove_with_opts(
+            builder.session_limit(Some(1 << 24)).build().unwrap(),
+            MULTI_TEST_ELF,
+            &opts,
+        )
+        .unwrap();
+    assert_eq!(calls.get(), 3);
+
+    // Executions which depend on host I/O are always proven.
+    let env = ExecutorEnv::builder()
+        .write(&MultiTestSpec::DoNothing)
+        .unwrap()
+        .io_callback("unused", |_| unreachable!())
+        .build()
+        .unwrap();
+    prover.prove_with_opts(env, MULTI_TEST_ELF, &opts).unwrap();
+    assert_eq!(calls.get(), 4);
+
+    // As are executions which are observed by a trace callback, so that it runs every time.
+    let events = Rc::new(Cell::new(0));
+    for expected in [5, 6] {
+        let counter = events.clone();
+        let env = ExecutorEnv::builder()
+            .write(&MultiTestSpec::DoNothing)
+            .unwrap()
+            .trace_callback(move |_| {
+                counter.set(counter.get() + 1);
+                Ok(())
+            })
+            .build()
+            .unwrap();
+        prover.prove_with_opts(env, MULTI_TEST_ELF, &opts).unwrap();
+        assert_eq!(calls.get(), expected);
+    }
+    assert!(events.get() > 0);
+
+    // And executions in which the guest reads random bytes from the host.
+    let env = || {
+        ExecutorEnv::builder()
+            .write(&MultiTestSpec::DoRandom)
+            .unwrap()
+            .build()
+            .unwrap()
+    };
+    let first = prover
+        .prove_with_opts(env(), MULTI_TEST_ELF, &opts)
+        .unwrap()
+        .receipt;
+    let second = prover
+        .prove_with_opts(env(), MULTI_TEST_ELF, &opts)
+        .unwrap()
+        .receipt;
+    assert_eq!(calls.get(), 8);
+    assert_ne!(first.journal, second.journal);
+}
+
+#[test]
+fn receipt_store() {
+    let env = ExecutorEnv::builder()
+        .write(&MultiTestSpec::DoNothing)
+        .unwrap()
+        .build()
+        .unwrap();
+    let mut exec = ExecutorImpl::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let mut session = exec.run().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    session.set_receipt_store(FileReceiptStore::new(dir.path()).unwrap());
+    let receipt = prove_session_fast(&session);
+
+    let store: &dyn ReceiptStore = session.receipt_store.as_deref().unwrap();
+    let stored: Receipt = store
+        .get_receipt(&ReceiptKey::receipt(session.claim().unwrap().digest()))
+        .unwrap()
+        .unwrap();
+    stored.verify(MULTI_TEST_ID).unwrap();
+    assert_eq!(stored.seal_size(), receipt.seal_size());
+
+    for segment in receipt.inner.composite().unwrap().segments.iter() {
+        let stored: SegmentReceipt = store
+            .get_receipt(&ReceiptKey::segment(segment.claim.digest()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.seal, segment.seal);
+    }
+}
+
+#[test]
+fn receipt_store_single_segment() {
+    let env = ExecutorEnv::builder()
+        .write(&MultiTestSpec::DoNothing)
+        .unwrap()
+        .build()
+        .unwrap();
+    let mut exec = ExecutorImpl::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let mut session = exec.run().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    session.set_receipt_store(FileReceiptStore::new(dir.path()).unwrap());
+    let receipt = prove_session_fast(&session);
+
+    // With a single segment, the segment receipt and the final receipt share a claim digest.
+    let segments = &receipt.inner.composite().unwrap().segments;
+    assert_eq!(segments.len(), 1);
+    let claim = session.claim().unwrap().digest();
+    assert_eq!(segments[0].claim.digest(), claim);
+
+    let store: &dyn ReceiptStore = session.receipt_store.as_deref().unwrap();
+    let stored_segment: SegmentReceipt = store
+        .get_receipt(&ReceiptKey::segment(claim))
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored_segment.seal, segments[0].seal);
+    let stored_receipt: Receipt = store
+        .get_receipt(&ReceiptKey::receipt(claim))
+        .unwrap()
+        .unwrap();
+    stored_receipt.verify(MULTI_TEST_ID).unwrap();
+}
+
 #[cfg(feature = "docker")]
 #[cfg(target_arch = "x86_64")]
 mod docker {
@@ -989,7 +1479,7 @@ fn run_unconstrained() -> Result<()> {
 mod soundness {
     use risc0_circuit_rv32im::{prove::emu::exec::DEFAULT_SEGMENT_LIMIT_PO2, CIRCUIT};
     use risc0_zkp::{
-        adapter::TapsProvider,
+        adapter::{ProofSystemVersion, TapsProvider},
         field::{
             baby_bear::{BabyBear, BabyBearExtElem},
             ExtElem,
@@ -1030,4 +1520,29 @@ mod soundness {
         let security = soundness::toy_model_security::<CpuHal<BabyBear>>(taps, coeffs_size);
         assert_eq!(security, 98.32892);
     }
+
+    #[test]
+    fn distinct_queries() {
+        let cycles: usize = 1 << DEFAULT_SEGMENT_LIMIT_PO2;
+        let ext_size = BabyBearExtElem::EXT_SIZE;
+        let coeffs_size = cycles * ext_size;
+        let taps = CIRCUIT.get_taps();
+
+        // Sampling the queries without replacement can only improve soundness.
+        let proven = soundness::proven::<CpuHal<BabyBear>>(taps, coeffs_size);
+        let proven_distinct = soundness::proven_with_version::<CpuHal<BabyBear>>(
+            taps,
+            coeffs_size,
+            ProofSystemVersion::V3,
+        );
+        assert!(proven_distinct >= proven);
+
+        let toy_model = soundness::toy_model_security::<CpuHal<BabyBear>>(taps, coeffs_size);
+        let toy_model_distinct = soundness::toy_model_security_with_version::<CpuHal<BabyBear>>(
+            taps,
+            coeffs_size,
+            ProofSystemVersion::V3,
+        );
+        assert!(toy_model_distinct >= toy_model);
+    }
 }
@@ -0,0 +1,370 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deterministic replay audits of archived executions.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    rc::Rc,
+};
+
+use anyhow::{bail, Result};
+use risc0_binfmt::{ExitCode, SystemState};
+use risc0_zkvm_platform::syscall::nr::SYS_RANDOM;
+use serde::{Deserialize, Serialize};
+
+use super::exec::syscall::{Syscall, SyscallContext};
+use crate::{
+    sha::{Digest, Digestible, Impl, Sha256},
+    ExecutorImpl, Segment, Session,
+};
+
+/// A compact record of an execution, which can be archived and later checked against a replay
+/// of the same execution with [ExecutorImpl::replay].
+///
+/// Memory pages are recorded by their digests, so the record is much smaller than the segments
+/// it is taken from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionRecord {
+    /// The records of the segments of the execution, in order.
+    pub segments: Vec<SegmentRecord>,
+
+    /// The [ExitCode] of the execution.
+    pub exit_code: ExitCode,
+
+    /// The digest of the journal, if any.
+    pub journal_digest: Option<Digest>,
+}
+
+/// The record of a [Segment] within an [ExecutionRecord].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SegmentRecord {
+    /// The boundaries of the segment.
+    pub boundary: SegmentBoundary,
+
+    /// The digest of each memory page loaded by the segment, by page index, as of the start of
+    /// the segment.
+    pub pages: BTreeMap<u32, Digest>,
+
+    /// The results returned to the guest by the host for each syscall made in the segment.
+    pub syscalls: Vec<SyscallResult>,
+}
+
+/// The state at which a segment started and ended.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SegmentBoundary {
+    /// The system state at the start of the segment.
+    pub pre_state: SystemState,
+
+    /// The system state at the end of the segment.
+    pub post_state: SystemState,
+
+    /// The number of instruction cycles executed in the segment.
+    pub insn_cycles: usize,
+
+    /// The [ExitCode] of the segment.
+    pub exit_code: ExitCode,
+}
+
+/// The result returned by the host for a syscall.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyscallResult {
+    /// The values of the `a0` and `a1` registers returned to the guest.
+    pub regs: (u32, u32),
+
+    /// The data written to guest memory.
+    pub to_guest: Vec<u32>,
+}
+
+/// The first point at which a replayed execution diverges from its [ExecutionRecord].
+///
+/// In each variant, `expected` is the value in the archived record and `actual` is the value
+/// observed in the replay.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Divergence {
+    /// The executions were split into a different number of segments.
+    SegmentCount {
+        /// The number of archived segments.
+        expected: usize,
+        /// The number of replayed segments.
+        actual: usize,
+    },
+
+    /// A memory page loaded by a segment differs, or was only loaded by one of the executions.
+    Page {
+        /// The index of the segment.
+        segment: usize,
+        /// The index of the page.
+        page_idx: u32,
+        /// The archived digest of the page.
+        expected: Option<Digest>,
+        /// The replayed digest of the page.
+        actual: Option<Digest>,
+    },
+
+    /// A syscall returned a different result, or was only made in one of the executions.
+    Syscall {
+        /// The index of the segment.
+        segment: usize,
+        /// The index of the syscall within the segment.
+        index: usize,
+        /// The archived result.
+        expected: Option<SyscallResult>,
+        /// The replayed result.
+        actual: Option<SyscallResult>,
+    },
+
+    /// A segment started or ended in a different state.
+    Boundary {
+        /// The index of the segment.
+        segment: usize,
+        /// The archived boundary.
+        expected: SegmentBoundary,
+        /// The replayed boundary.
+        actual: SegmentBoundary,
+    },
+
+    /// The executions ended with a different [ExitCode].
+    ExitCode {
+        /// The archived exit code.
+        expected: ExitCode,
+        /// The replayed exit code.
+        actual: ExitCode,
+    },
+
+    /// The executions committed a different journal.
+    Journal {
+        /// The archived journal digest.
+        expected: Option<Digest>,
+        /// The replayed journal digest.
+        actual: Option<Digest>,
+    },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SegmentCount { expected, actual } => {
+                write!(f, "expected {expected} segments, replay produced {actual}")
+            }
+            Self::Page {
+                segment,
+                page_idx,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "segment {segment}: page {page_idx:#x} expected {expected:?}, found {actual:?}"
+            ),
+            Self::Syscall {
+                segment,
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "segment {segment}: syscall {index} expected {expected:?}, found {actual:?}"
+            ),
+            Self::Boundary {
+                segment,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "segment {segment}: boundary expected {expected:?}, found {actual:?}"
+            ),
+            Self::ExitCode { expected, actual } => {
+                write!(f, "exit code expected {expected:?}, found {actual:?}")
+            }
+            Self::Journal { expected, actual } => {
+                write!(f, "journal digest expected {expected:?}, found {actual:?}")
+            }
+        }
+    }
+}
+
+impl SegmentRecord {
+    fn new(segment: &Segment) -> Self {
+        let inner = &segment.inner;
+        Self {
+            boundary: SegmentBoundary {
+                pre_state: inner.pre_state.clone(),
+                post_state: inner.post_state.clone(),
+                insn_cycles: inner.insn_cycles,
+                exit_code: inner.exit_code,
+            },
+            pages: inner
+                .partial_image
+                .pages
+                .iter()
+                .map(|(&page_idx, page)| (page_idx, *Impl::hash_bytes(page)))
+                .collect(),
+            syscalls: inner
+                .syscalls
+                .iter()
+                .map(|syscall| SyscallResult {
+                    regs: syscall.regs,
+                    to_guest: syscall.to_guest.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    fn diff(&self, actual: &Self, segment: usize) -> Option<Divergence> {
+        // Pages are checked first as they are the inputs to the segment, and syscall results
+        // before the boundary since a differing result is the likely cause of a differing end
+        // state.
+        let page_idxs = self.pages.keys().chain(actual.pages.keys());
+        for &page_idx in page_idxs.collect::<BTreeSet<_>>() {
+            let expected = self.pages.get(&page_idx).copied();
+            let actual = actual.pages.get(&page_idx).copied();
+            if expected != actual {
+                return Some(Divergence::Page {
+                    segment,
+                    page_idx,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        for index in 0..self.syscalls.len().max(actual.syscalls.len()) {
+            let expected = self.syscalls.get(index);
+            let actual = actual.syscalls.get(index);
+            if expected != actual {
+                return Some(Divergence::Syscall {
+                    segment,
+                    index,
+                    expected: expected.cloned(),
+                    actual: actual.cloned(),
+                });
+            }
+        }
+
+        (self.boundary != actual.boundary).then(|| Divergence::Boundary {
+            segment,
+            expected: self.boundary.clone(),
+            actual: actual.boundary.clone(),
+        })
+    }
+}
+
+impl ExecutionRecord {
+    /// Record the given [Session], resolving each of its segments.
+    pub fn new(session: &Session) -> Result<Self> {
+        let segments = session
+            .segments
+            .iter()
+            .map(|segment| Ok(SegmentRecord::new(&segment.resolve()?)))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            segments,
+            exit_code: session.exit_code,
+            journal_digest: session
+                .journal
+                .as_ref()
+                .map(|journal| journal.digest())
+                .or(session.journal_digest),
+        })
+    }
+
+    /// Compare this archived record against the record of a replay, returning the first
+    /// divergence, if any.
+    pub fn diff(&self, actual: &Self) -> Option<Divergence> {
+        for (segment, (expected, actual)) in self.segments.iter().zip(&actual.segments).enumerate()
+        {
+            if let Some(divergence) = expected.diff(actual, segment) {
+                return Some(divergence);
+            }
+        }
+        if self.segments.len() != actual.segments.len() {
+            return Some(Divergence::SegmentCount {
+                expected: self.segments.len(),
+                actual: actual.segments.len(),
+            });
+        }
+        if self.exit_code != actual.exit_code {
+            return Some(Divergence::ExitCode {
+                expected: self.exit_code,
+                actual: actual.exit_code,
+            });
+        }
+        (self.journal_digest != actual.journal_digest).then(|| Divergence::Journal {
+            expected: self.journal_digest,
+            actual: actual.journal_digest,
+        })
+    }
+}
+
+// Wraps each syscall handler during a replay, counting the syscalls made so that each can be
+// matched with its archived result. Randomness is not an input the replay can reproduce, so
+// `sys_random` returns the archived result instead of calling its handler.
+struct ReplaySyscall<'a> {
+    inner: Rc<RefCell<dyn Syscall + 'a>>,
+    archived: Rc<Vec<SyscallResult>>,
+    position: Rc<Cell<usize>>,
+}
+
+impl<'a> Syscall for ReplaySyscall<'a> {
+    fn syscall(
+        &mut self,
+        syscall: &str,
+        ctx: &mut dyn SyscallContext,
+        to_guest: &mut [u32],
+    ) -> Result<(u32, u32)> {
+        let index = self.position.get();
+        self.position.set(index + 1);
+        if syscall != SYS_RANDOM.as_str() {
+            return self.inner.borrow_mut().syscall(syscall, ctx, to_guest);
+        }
+        match self.archived.get(index) {
+            Some(result) if result.to_guest.len() == to_guest.len() => {
+                to_guest.copy_from_slice(&result.to_guest);
+                Ok(result.regs)
+            }
+            _ => bail!("replay diverged at syscall {index}: no matching archived {syscall} result"),
+        }
+    }
+}
+
+impl<'a> ExecutorImpl<'a> {
+    /// Replay the execution, and check it against the given archived record, returning the
+    /// first divergence, if any.
+    ///
+    /// The executor must be constructed from the same program and with the same inputs as the
+    /// archived execution. Segments are split at the same points only if the segment limit is
+    /// also the same. Random values requested by the guest are taken from the archived record.
+    pub fn replay(&mut self, archived: &ExecutionRecord) -> Result<Option<Divergence>> {
+        let results = archived
+            .segments
+            .iter()
+            .flat_map(|segment| segment.syscalls.iter().cloned())
+            .collect();
+        let results = Rc::new(results);
+        let position = Rc::new(Cell::new(0));
+        for handler in self.syscall_table.inner.values_mut() {
+            *handler = Rc::new(RefCell::new(ReplaySyscall {
+                inner: handler.clone(),
+                archived: results.clone(),
+                position: position.clone(),
+            }));
+        }
+
+        let session = self.run()?;
+        Ok(archived.diff(&ExecutionRecord::new(&session)?))
+    }
+}
@@ -23,10 +23,14 @@ use risc0_circuit_rv32im::prove::segment::Segment as CircuitSegment;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    host::{client::env::SegmentPath, prove_info::SessionStats},
+    host::{
+        client::{env::SegmentPath, receipt_store::ReceiptStore},
+        prove_info::SessionStats,
+    },
+    oracle::OracleCall,
     sha::Digest,
-    Assumption, AssumptionReceipt, Assumptions, ExitCode, Journal, MaybePruned, Output,
-    ReceiptClaim,
+    Assumption, AssumptionReceipt, Assumptions, ExitCode, Journal, JournalHash, MaybePruned,
+    Output, ReceiptClaim,
 };
 
 #[derive(Clone, Default, Serialize, Deserialize, Debug)]
@@ -46,7 +50,8 @@ pub struct Session {
     /// The constituent [Segment]s of the Session. The final [Segment] will have
     /// an [ExitCode] of [Halted](ExitCode::Halted), [Paused](ExitCode::Paused),
     /// or [SessionLimit](ExitCode::SessionLimit), and all other [Segment]s (if
-    /// any) will have [ExitCode::SystemSplit].
+    /// any) will have [ExitCode::SystemSplit]. If the host interrupted execution,
+    /// all [Segment]s will have [ExitCode::SystemSplit].
     pub segments: Vec<Box<dyn SegmentRef>>,
 
     /// The input digest.
@@ -55,7 +60,18 @@ pub struct Session {
     /// The data publicly committed by the guest program.
     pub journal: Option<Journal>,
 
+    /// The digest of the journal, if it was streamed to a sink set with
+    /// [journal_sink](crate::ExecutorEnvBuilder::journal_sink) rather than retained in
+    /// [Session::journal].
+    pub journal_digest: Option<Digest>,
+
+    /// The [JournalHash] the guest selected to digest its journal in the [ReceiptClaim].
+    pub journal_hash: JournalHash,
+
     /// The [ExitCode] of the session.
+    ///
+    /// This is [ExitCode::SystemSplit] if the host interrupted execution, e.g. because a timeout
+    /// elapsed or execution was cancelled. Execution can then be resumed where it left off.
     pub exit_code: ExitCode,
 
     /// The final [MemoryImage] at the end of execution.
@@ -64,9 +80,16 @@ pub struct Session {
     /// The list of assumptions made by the guest and resolved by the host.
     pub assumptions: Vec<(Assumption, AssumptionReceipt)>,
 
+    /// The calls made by the guest to oracles registered with
+    /// [oracle](crate::ExecutorEnvBuilder::oracle), in order.
+    pub oracle_calls: Vec<OracleCall>,
+
     /// The hooks to be called during the proving phase.
     pub hooks: Vec<Box<dyn SessionEvents>>,
 
+    /// The store in which receipts are persisted during the proving phase.
+    pub receipt_store: Option<Box<dyn ReceiptStore>>,
+
     /// The number of user cycles without any overhead for continuations or po2
     /// padding.
     pub user_cycles: u64,
@@ -80,6 +103,12 @@ pub struct Session {
 
     /// The system state of the final [MemoryImage] at the end of execution.
     pub post_state: SystemState,
+
+    /// Whether the guest was built for rv32imc, i.e. may use RV32C compressed instructions.
+    ///
+    /// The rv32im circuit only checks 32-bit instructions, so such a session can be executed but
+    /// not proven.
+    pub compressed: bool,
 }
 
 /// The execution trace of a portion of a program.
@@ -148,14 +177,19 @@ impl Session {
             segments,
             input,
             journal: journal.map(Journal::new),
+            journal_digest: None,
+            journal_hash: JournalHash::default(),
             exit_code,
             post_image,
             assumptions,
+            oracle_calls: Vec::new(),
             hooks: Vec::new(),
+            receipt_store: None,
             user_cycles,
             total_cycles,
             pre_state,
             post_state,
+            compressed: false,
         }
     }
 
@@ -164,19 +198,40 @@ impl Session {
         self.hooks.push(Box::new(hook));
     }
 
+    /// Persist receipts to the given store during the proving phase.
+    ///
+    /// Each [SegmentReceipt](crate::SegmentReceipt) is stored as soon as it is proven, followed
+    /// by the final [Receipt](crate::Receipt) for the session. Receipts are keyed by their type
+    /// and the digest of their claim, so the receipt for this session can be fetched using
+    /// [ReceiptKey::receipt](crate::ReceiptKey::receipt) with the digest of [Session::claim].
+    /// Fake receipts produced in dev mode are not stored.
+    pub fn set_receipt_store<S: ReceiptStore + 'static>(&mut self, store: S) {
+        self.receipt_store = Some(Box::new(store));
+    }
+
     /// Calculate for the [ReceiptClaim] associated with this [Session]. The
     /// [ReceiptClaim] is the claim that will be proven if this [Session]
     /// is passed to the [crate::Prover].
+    ///
+    /// Returns an error if the host interrupted execution, i.e. the session ended in a
+    /// [SystemSplit](ExitCode::SystemSplit). Such a session cannot be proven, and execution
+    /// should be resumed until it halts or pauses.
     pub fn claim(&self) -> Result<ReceiptClaim> {
+        ensure!(
+            self.exit_code != ExitCode::SystemSplit,
+            "Session was interrupted and cannot be proven; resume execution to complete it"
+        );
         // Construct the Output struct for the session, checking internal consistency.
         // NOTE: The Session output is distinct from the final Segment output because in the
         // Session output any proven assumptions are not included.
         let output = if self.exit_code.expects_output() {
             self.journal
                 .as_ref()
+                .map(|journal| journal.bytes.clone().into())
+                .or(self.journal_digest.map(MaybePruned::Pruned))
                 .map(|journal| -> Result<_> {
                     Ok(Output {
-                        journal: journal.bytes.clone().into(),
+                        journal,
                         assumptions: Assumptions(
                             self.assumptions
                                 .iter()
@@ -187,6 +242,7 @@ impl Session {
                                 .collect::<Vec<_>>(),
                         )
                         .into(),
+                        journal_hash: self.journal_hash,
                     })
                 })
                 .transpose()?
@@ -55,8 +55,10 @@
 //! | cuda             |                   | prove, std | Enables CUDA GPU acceleration for the prover. Requires CUDA toolkit to be installed.                                                                         |
 //! | disable-dev-mode | all except rv32im |            | Disables dev mode so that proving and verifying may not be faked. Used to prevent a misplaced `RISC0_DEV_MODE` from breaking security in production systems. |
 //! | metal            | macos             | prove, std | Enables Metal GPU acceleration for the prover.                                                                                                               |
+//! | oracle           | all               |            | Enables the `#[oracle]` attribute for declaring traits the guest calls and the host implements.                                                              |
 //! | prove            | all except rv32im | std        | Enables the prover, incompatible within the zkvm guest.                                                                                                      |
 //! | std              | all               |            | Support for the Rust stdlib.                                                                                                                                 |
+//! | test-macro       | all               |            | Enables the `#[risc0_test]` attribute for running tests inside the zkVM guest.                                                                               |
 //!
 //! [`cargo risczero` tool]: https://crates.io/crates/cargo-risczero
 //! [dev-docs]: https://dev.risczero.com
@@ -72,14 +74,21 @@
 
 extern crate alloc;
 
+mod error_code;
+#[cfg(all(feature = "client", not(target_os = "zkvm")))]
+pub mod ffi;
 pub mod guest;
 #[cfg(not(target_os = "zkvm"))]
 mod host;
+pub mod oracle;
 mod receipt;
 mod receipt_claim;
+pub mod schema;
 pub mod serde;
 pub mod sha;
 
+#[cfg(all(not(target_os = "zkvm"), feature = "client"))]
+pub use host::client::attestation;
 #[cfg(all(not(target_os = "zkvm"), feature = "prove"))]
 pub use host::recursion;
 
@@ -88,10 +97,18 @@ pub use anyhow::Result;
 #[cfg(any(feature = "client", feature = "prove"))]
 pub use bytes::Bytes;
 pub use risc0_binfmt::{ExitCode, InvalidExitCodeError, SystemState};
+#[cfg(feature = "oracle")]
+pub use risc0_zkvm_oracle_macro::oracle;
 pub use risc0_zkvm_platform::{align_up, declare_syscall, memory::GUEST_MAX_MEM, PAGE_SIZE};
+#[cfg(feature = "test-macro")]
+pub use risc0_zkvm_test_macro::risc0_test;
 
+pub use self::error_code::{ErrorCategory, ErrorCode};
+#[cfg(feature = "std")]
+pub use self::receipt_claim::StreamVerifier;
 pub use self::receipt_claim::{
-    Assumption, Assumptions, Input, MaybePruned, Output, PrunedValueError, ReceiptClaim,
+    Assumption, Assumptions, Input, JournalHash, MaybePruned, Output, PrunedValueError,
+    ReceiptClaim, StreamCommitment,
 };
 #[cfg(all(not(target_os = "zkvm"), feature = "prove",))]
 pub use {
@@ -102,6 +119,7 @@ pub use {
         server::{
             exec::{compose::register_zkr, executor::ExecutorImpl},
             prove::{get_prover_server, HalPair, ProverServer},
+            replay::{Divergence, ExecutionRecord, SegmentBoundary, SegmentRecord, SyscallResult},
             session::{
                 FileSegmentRef, NullSegmentRef, Segment, SegmentRef, Session, SessionEvents,
                 SimpleSegmentRef,
@@ -120,10 +138,18 @@ pub use {
             client::Client as ApiClient, Asset, AssetRequest, Connector, SegmentInfo, SessionInfo,
         },
         client::{
-            env::{ExecutorEnv, ExecutorEnvBuilder},
+            env::{
+                CancellationToken, ExecutorEnv, ExecutorEnvBuilder, ResourceLimitExceeded,
+                ResourceLimits, WatchAction, Watchpoint,
+            },
+            guest_test::{GuestTestReport, GuestTestResult, GuestTestRunner},
+            method_registry::{MethodEntry, MethodRegistry},
             prove::{
-                bonsai::BonsaiProver, default_executor, default_prover, external::ExternalProver,
-                Executor, Prover, ProverOpts, ReceiptKind,
+                bonsai::BonsaiProver, cached::CachedProver, default_executor, default_prover,
+                external::ExternalProver, Executor, Prover, ProverOpts, ReceiptKind,
+            },
+            receipt_store::{
+                FileReceiptStore, ObjectClient, ObjectReceiptStore, ReceiptKey, ReceiptStore,
             },
         },
     },
@@ -135,7 +161,7 @@ pub use {
         prove_info::{ProveInfo, SessionStats},
         recursion::{ALLOWED_CONTROL_IDS, ALLOWED_CONTROL_ROOT},
     },
-    risc0_binfmt::compute_image_id,
+    risc0_binfmt::{compute_image_id, source_digest},
     risc0_circuit_rv32im::control_id::POSEIDON2_CONTROL_IDS,
     risc0_groth16::Seal as Groth16Seal,
 };
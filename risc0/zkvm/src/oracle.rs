@@ -0,0 +1,262 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Typed calls from the guest to oracles implemented by the host.
+//!
+//! An oracle is an ordinary trait marked with the `#[risc0_zkvm::oracle]` attribute, which is
+//! enabled by the `oracle` feature. The trait is usually declared in a crate shared by the guest
+//! and the host. The guest calls its methods through a [Client], and the host registers an
+//! implementation with `ExecutorEnvBuilder::oracle`. Calls are carried over
+//! `env::send_recv_slice`, on a channel named after the trait, and are recorded in
+//! `Session::oracle_calls`.
+//!
+//! ```ignore
+//! /// Prices of assets, provided by the host.
+//! #[risc0_zkvm::oracle]
+//! pub trait PriceOracle {
+//!     /// Returns the price of the given asset, in cents.
+//!     fn price(&mut self, symbol: String) -> u64;
+//! }
+//! ```
+//!
+//! In the guest:
+//!
+//! ```ignore
+//! use risc0_zkvm::oracle::Client;
+//!
+//! let mut oracle = Client::<dyn PriceOracle>::new();
+//! let price = oracle.price("ETH".to_string());
+//! ```
+//!
+//! On the host:
+//!
+//! ```ignore
+//! struct Prices;
+//!
+//! impl PriceOracle for Prices {
+//!     fn price(&mut self, symbol: String) -> u64 {
+//!         // ...
+//!     }
+//! }
+//!
+//! let env = ExecutorEnv::builder()
+//!     .oracle::<dyn PriceOracle>(Box::new(Prices))
+//!     .build()?;
+//! ```
+//!
+//! # Committing calls
+//!
+//! The host is untrusted, and oracle results are not checked by the zkVM. The guest must
+//! validate the results it relies on, or commit them so that the verifier can check them.
+//!
+//! A [Client] never writes to the journal by itself. A client constructed with
+//! [Client::recording] keeps an [OracleCall] for each call, which the guest can commit where it
+//! chooses, for example with `env::commit(&oracle.take_calls())`. The verifier then decodes the
+//! calls from the journal at that position, and checks the arguments and results it cares about.
+
+use alloc::string::String;
+use core::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+use crate::serde::Error;
+
+#[doc(hidden)]
+pub use alloc::vec::Vec;
+pub use risc0_zkvm_platform::syscall::SyscallName;
+
+/// An oracle declared with the `#[risc0_zkvm::oracle]` attribute.
+///
+/// This is implemented by the `dyn` type of each declared trait.
+pub trait Oracle {
+    /// The channel used for calls to this oracle.
+    const CHANNEL: SyscallName;
+
+    /// Call the named method with the serialized arguments, returning its serialized result.
+    fn dispatch(&mut self, method: &str, args: &[u32]) -> Result<Vec<u32>, Error>;
+}
+
+/// A record of a call from the guest to an oracle.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OracleCall {
+    /// The channel of the oracle.
+    pub channel: String,
+
+    /// The name of the method called.
+    pub method: String,
+
+    /// The serialized arguments.
+    pub args: Vec<u32>,
+
+    /// The serialized result.
+    pub result: Vec<u32>,
+}
+
+/// The guest side of an oracle, where `O` is the `dyn` type of a trait declared with the
+/// `#[risc0_zkvm::oracle]` attribute.
+///
+/// A [Client] does not write to the journal. See [Client::recording] to commit its calls.
+pub struct Client<O: ?Sized> {
+    calls: Option<Vec<OracleCall>>,
+    phantom: PhantomData<fn(&O)>,
+}
+
+impl<O: ?Sized> Client<O> {
+    /// Construct a [Client] for the oracle.
+    pub const fn new() -> Self {
+        Self {
+            calls: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Construct a [Client] for the oracle which keeps an [OracleCall] for each call.
+    ///
+    /// The calls are returned by [Client::take_calls], so that the guest can commit them, and the
+    /// verifier can check the results returned by the host.
+    pub const fn recording() -> Self {
+        Self {
+            calls: Some(Vec::new()),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the calls recorded since the last call to this function, in the order they were
+    /// made.
+    ///
+    /// This is always empty unless the client was constructed with [Client::recording].
+    pub fn take_calls(&mut self) -> Vec<OracleCall> {
+        self.calls.as_mut().map(core::mem::take).unwrap_or_default()
+    }
+}
+
+impl<O: ?Sized> Default for Client<O> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(target_os = "zkvm")]
+impl<O: Oracle + ?Sized> Client<O> {
+    #[doc(hidden)]
+    pub fn call<A: Serialize, R: serde::de::DeserializeOwned>(
+        &mut self,
+        method: &str,
+        args: &A,
+    ) -> R {
+        let args = crate::serde::to_vec(args).unwrap();
+        let request = encode_call(method, &args).unwrap();
+        let result: &[u32] = crate::guest::env::send_recv_slice(O::CHANNEL, &request);
+        if let Some(calls) = self.calls.as_mut() {
+            calls.push(OracleCall {
+                channel: O::CHANNEL.as_str().into(),
+                method: method.into(),
+                args,
+                result: result.to_vec(),
+            });
+        }
+        crate::serde::from_slice(result).unwrap()
+    }
+}
+
+// A call is encoded as the length of the serialized method name, followed by the serialized
+// method name and then the serialized arguments.
+#[cfg(any(target_os = "zkvm", test))]
+fn encode_call(method: &str, args: &[u32]) -> Result<Vec<u32>, Error> {
+    let method = crate::serde::to_vec(method)?;
+    Ok([&[method.len() as u32], method.as_slice(), args].concat())
+}
+
+/// Handle a call encoded by the guest, returning a record of the call.
+#[cfg(any(all(feature = "client", not(target_os = "zkvm")), test))]
+pub(crate) fn handle_call<O: Oracle + ?Sized>(
+    oracle: &mut O,
+    request: &[u32],
+) -> Result<OracleCall, Error> {
+    let (len, request) = request
+        .split_first()
+        .ok_or(Error::DeserializeUnexpectedEnd)?;
+    let len = *len as usize;
+    if request.len() < len {
+        return Err(Error::DeserializeUnexpectedEnd);
+    }
+    let (method, args) = request.split_at(len);
+    let method: String = crate::serde::from_slice(method)?;
+    let result = oracle.dispatch(&method, args)?;
+    Ok(OracleCall {
+        channel: O::CHANNEL.as_str().into(),
+        method,
+        args: args.to_vec(),
+        result,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, string::String, vec::Vec};
+
+    use risc0_zkvm_platform::declare_syscall;
+
+    use super::{encode_call, handle_call, Oracle, SyscallName};
+    use crate::serde::{from_slice, to_vec, Error};
+
+    declare_syscall!(TEST_ORACLE);
+
+    // The implementation generated by `#[risc0_zkvm::oracle]` for a trait with these methods.
+    struct Impl;
+
+    impl Oracle for Impl {
+        const CHANNEL: SyscallName = TEST_ORACLE;
+
+        fn dispatch(&mut self, method: &str, args: &[u32]) -> Result<Vec<u32>, Error> {
+            match method {
+                "add" => {
+                    let (a, b): (u32, u32) = from_slice(args)?;
+                    to_vec(&(a + b))
+                }
+                "greet" => {
+                    let (name,): (String,) = from_slice(args)?;
+                    to_vec(&format!("hello {name}"))
+                }
+                _ => Err(Error::NotSupported),
+            }
+        }
+    }
+
+    #[test]
+    fn dispatch() {
+        let oracle = &mut Impl;
+        let request = encode_call("add", &to_vec(&(2u32, 3u32)).unwrap()).unwrap();
+        let call = handle_call(oracle, &request).unwrap();
+        assert_eq!(call.method, "add");
+        assert_eq!(call.args, to_vec(&(2u32, 3u32)).unwrap());
+        assert_eq!(from_slice::<u32, _>(&call.result).unwrap(), 5);
+        assert!(call.channel.ends_with("::TEST_ORACLE"));
+        assert_eq!(call.channel, TEST_ORACLE.as_str());
+
+        let request = encode_call("greet", &to_vec(&("zkVM",)).unwrap()).unwrap();
+        let call = handle_call(oracle, &request).unwrap();
+        assert_eq!(from_slice::<String, _>(&call.result).unwrap(), "hello zkVM");
+
+        let request = encode_call("missing", &[]).unwrap();
+        assert_eq!(
+            handle_call(oracle, &request).unwrap_err(),
+            Error::NotSupported
+        );
+        assert_eq!(
+            handle_call(oracle, &[100]).unwrap_err(),
+            Error::DeserializeUnexpectedEnd
+        );
+    }
+}
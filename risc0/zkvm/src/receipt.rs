@@ -17,6 +17,7 @@
 pub(crate) mod composite;
 pub(crate) mod groth16;
 pub(crate) mod merkle;
+pub(crate) mod seal_codec;
 pub(crate) mod segment;
 pub(crate) mod succinct;
 
@@ -27,8 +28,9 @@ use anyhow::Result;
 use borsh::{BorshDeserialize, BorshSerialize};
 use risc0_core::field::baby_bear::BabyBear;
 use risc0_zkp::{
+    adapter::{ProofSystemVersion, ProtocolInfo, PROOF_SYSTEM_INFO},
     core::{
-        digest::Digest,
+        digest::{Digest, DIGEST_BYTES},
         hash::{
             blake2b::Blake2bCpuHashSuite, poseidon2::Poseidon2HashSuite, sha::Sha256HashSuite,
             HashSuite,
@@ -41,9 +43,10 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 // Make succinct receipt available through this `receipt` module.
 use crate::{
     receipt_claim::Unknown,
+    schema::{JournalSchema, JournalType},
     serde::{from_slice, Error},
     sha::{Digestible, Sha256},
-    Assumption, Assumptions, MaybePruned, Output, ReceiptClaim,
+    Assumption, Assumptions, Input, JournalHash, MaybePruned, Output, ReceiptClaim,
 };
 
 pub use self::groth16::{Groth16Receipt, Groth16ReceiptVerifierParameters};
@@ -164,6 +167,47 @@ impl Receipt {
         &self,
         ctx: &VerifierContext,
         image_id: impl Into<Digest>,
+    ) -> Result<(), VerificationError> {
+        self.verify_claim(
+            ctx,
+            ReceiptClaim::ok(image_id, MaybePruned::Pruned(self.journal.digest())),
+        )
+    }
+
+    /// Verify that this receipt proves a successful execution of the zkVM from the given
+    /// `image_id`, run on the given host-provided `input`.
+    ///
+    /// In addition to the checks performed by [Receipt::verify], this ensures the input digest
+    /// recorded in the [ReceiptClaim] matches [Input::digest_bytes] of `input`. This requires the
+    /// prover to have enabled `ExecutorEnvBuilder::commit_input_digest`.
+    pub fn verify_with_input(
+        &self,
+        image_id: impl Into<Digest>,
+        input: &[u8],
+    ) -> Result<(), VerificationError> {
+        self.verify_with_input_and_context(&VerifierContext::default(), image_id, input)
+    }
+
+    /// Verify that this receipt proves a successful execution of the zkVM from the given
+    /// `image_id`, run on the given host-provided `input`.
+    ///
+    /// See [Receipt::verify_with_input].
+    pub fn verify_with_input_and_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        input: &[u8],
+    ) -> Result<(), VerificationError> {
+        let mut expected_claim =
+            ReceiptClaim::ok(image_id, MaybePruned::Pruned(self.journal.digest()));
+        expected_claim.input = MaybePruned::Pruned(Input::digest_bytes(input));
+        self.verify_claim(ctx, expected_claim)
+    }
+
+    fn verify_claim(
+        &self,
+        ctx: &VerifierContext,
+        mut expected_claim: ReceiptClaim,
     ) -> Result<(), VerificationError> {
         if self.inner.verifier_parameters() != self.metadata.verifier_parameters {
             return Err(VerificationError::VerifierParametersMismatch {
@@ -177,8 +221,12 @@ impl Receipt {
 
         // Check that the claim on the verified receipt matches what was expected. Since we have
         // constrained all field in the ReceiptClaim, we can directly construct the expected digest
-        // and do not need to open the claim digest on the inner receipt.
-        let expected_claim = ReceiptClaim::ok(image_id, MaybePruned::Pruned(self.journal.digest()));
+        // and do not need to open the claim digest on the inner receipt. The journal hash is bound
+        // into the output digest, and only the one expected by the context is accepted.
+        if let MaybePruned::Value(Some(output)) = &mut expected_claim.output {
+            output.journal = MaybePruned::Pruned(self.journal.digest_with(ctx.journal_hash));
+            output.journal_hash = ctx.journal_hash;
+        }
         if expected_claim.digest() != self.inner.claim()?.digest() {
             tracing::debug!(
                 "receipt claim does not match expected claim:\nreceipt: {:#?}\nexpected: {:#?}",
@@ -226,13 +274,20 @@ impl Receipt {
             .as_value()
             .map_err(|_| VerificationError::ReceiptFormatError)?;
 
+        // An unpruned output records the journal hash it was digested with, and that record is
+        // bound into the output digest. Otherwise, the hash expected by the context is used.
+        let journal_hash = match &claim.output {
+            MaybePruned::Value(Some(output)) => output.journal_hash,
+            _ => ctx.journal_hash,
+        };
         let expected_output = claim.exit_code.expects_output().then(|| Output {
-            journal: MaybePruned::Pruned(self.journal.digest()),
+            journal: MaybePruned::Pruned(self.journal.digest_with(journal_hash)),
             // TODO(#982): It would be reasonable for this method to allow integrity verification
             // for receipts that have a non-empty assumptions list, but it is not supported here
             // because we don't have a enough information to open the assumptions list unless we
             // require it be empty.
             assumptions: Assumptions(vec![]).into(),
+            journal_hash,
         });
 
         if claim.output.digest() != expected_output.digest() {
@@ -261,6 +316,55 @@ impl Receipt {
     pub fn seal_size(&self) -> usize {
         self.inner.seal_size()
     }
+
+    /// Compress the STARK seals of this receipt, to reduce its size for storage or transport.
+    ///
+    /// Compression is lossless and compressed seals are flagged by their header, so a compressed
+    /// receipt can be verified directly, or used as an input to recursion (e.g.
+    /// `Prover::compress`, which is unrelated to this codec). Each word is entropy coded by its
+    /// magnitude, so the saving depends on how many small values the seal holds; Merkle digests
+    /// and random field elements, which make up most of a seal, barely shrink. Groth16 seals are
+    /// already compact and are left unchanged.
+    pub fn compress(&mut self) {
+        self.inner.compress()
+    }
+
+    /// Decompress the seals of this receipt, restoring them to the form produced by the prover.
+    pub fn decompress(&mut self) -> Result<(), VerificationError> {
+        self.inner.decompress()
+    }
+
+    /// Replace the segment receipts of a composite receipt with a single [SuccinctReceipt]
+    /// proving the same claim, using recursion.
+    ///
+    /// Returns the aggregated receipt along with the digest of the [ReceiptClaim] of each
+    /// original segment, in order, to be stored alongside the receipt as a record of the segments
+    /// it was built from. Recursion only joins segments that form a continuation, and the
+    /// aggregated claim is checked against the claim of the segments. Receipts that are already
+    /// succinct are returned unchanged, with no segment claims. The recursion prover is selected
+    /// by `opts`, whose hash function must match the one the segments were proven with.
+    #[cfg(feature = "prove")]
+    pub fn into_succinct(self, opts: &crate::ProverOpts) -> Result<(Self, Vec<Digest>)> {
+        let composite = match &self.inner {
+            InnerReceipt::Composite(composite) => composite,
+            InnerReceipt::Succinct(_) => return Ok((self, Vec::new())),
+            _ => anyhow::bail!("only composite receipts can be converted to succinct receipts"),
+        };
+        let segment_claims = composite
+            .segments
+            .iter()
+            .map(|segment| segment.claim.digest())
+            .collect();
+
+        let prover = crate::get_prover_server(opts)?;
+        let succinct = prover.composite_to_succinct(composite)?;
+        anyhow::ensure!(
+            succinct.claim.digest() == composite.claim()?.digest(),
+            "aggregated claim does not match the claim of the segments"
+        );
+        let receipt = Receipt::new(InnerReceipt::Succinct(succinct), self.journal.bytes);
+        Ok((receipt, segment_claims))
+    }
 }
 
 /// A record of the public commitments for a proven zkVM execution.
@@ -286,6 +390,31 @@ impl Journal {
     pub fn decode<T: DeserializeOwned>(&self) -> Result<T, Error> {
         from_slice(&self.bytes)
     }
+
+    /// The digest of the [JournalSchema] this journal was committed with, i.e. its first eight
+    /// words.
+    ///
+    /// This is only meaningful for journals committed with `env::commit_with_schema`.
+    pub fn schema_digest(&self) -> Result<Digest, Error> {
+        if self.bytes.len() < DIGEST_BYTES {
+            return Err(Error::DeserializeUnexpectedEnd);
+        }
+        Ok(Digest::try_from(&self.bytes[..DIGEST_BYTES]).unwrap())
+    }
+
+    /// Digest of the journal bytes computed with the given [JournalHash].
+    ///
+    /// This is the journal digest recorded in the [Output] of a guest that selected `hash`.
+    pub fn digest_with(&self, hash: JournalHash) -> Digest {
+        hash.digest(&self.bytes)
+    }
+
+    /// Decode a journal committed with `env::commit_with_schema`, after checking that it was
+    /// committed with the schema of `T`.
+    pub fn decode_with_schema<T: JournalType + DeserializeOwned>(&self) -> Result<T, Error> {
+        JournalSchema::of::<T>().validate(self)?;
+        from_slice(&self.bytes[DIGEST_BYTES..])
+    }
 }
 
 impl risc0_binfmt::Digestible for Journal {
@@ -391,6 +520,24 @@ impl InnerReceipt {
             Self::Fake(_) => 0,
         }
     }
+
+    /// Compress the STARK seals of this receipt. See [Receipt::compress].
+    pub fn compress(&mut self) {
+        match self {
+            Self::Composite(receipt) => receipt.compress(),
+            Self::Succinct(receipt) => receipt.compress(),
+            Self::Groth16(_) | Self::Fake(_) => {}
+        }
+    }
+
+    /// Decompress the STARK seals of this receipt. See [Receipt::decompress].
+    pub fn decompress(&mut self) -> Result<(), VerificationError> {
+        match self {
+            Self::Composite(receipt) => receipt.decompress(),
+            Self::Succinct(receipt) => receipt.decompress(),
+            Self::Groth16(_) | Self::Fake(_) => Ok(()),
+        }
+    }
 }
 
 /// A fake receipt for testing and development.
@@ -664,6 +811,24 @@ impl InnerAssumptionReceipt {
             Self::Fake(_) => 0,
         }
     }
+
+    /// Compress the STARK seals of this receipt. See [Receipt::compress].
+    pub fn compress(&mut self) {
+        match self {
+            Self::Composite(receipt) => receipt.compress(),
+            Self::Succinct(receipt) => receipt.compress(),
+            Self::Groth16(_) | Self::Fake(_) => {}
+        }
+    }
+
+    /// Decompress the STARK seals of this receipt. See [Receipt::decompress].
+    pub fn decompress(&mut self) -> Result<(), VerificationError> {
+        match self {
+            Self::Composite(receipt) => receipt.decompress(),
+            Self::Succinct(receipt) => receipt.decompress(),
+            Self::Groth16(_) | Self::Fake(_) => Ok(()),
+        }
+    }
 }
 
 impl From<InnerReceipt> for InnerAssumptionReceipt {
@@ -677,7 +842,21 @@ impl From<InnerReceipt> for InnerAssumptionReceipt {
     }
 }
 
+/// The [ProofSystemVersion] identified by the given info string, if this verifier supports it.
+///
+/// The info string comes from the trusted [VerifierContext], so any known version is accepted;
+/// the default context selects the version used by the prover in this release.
+pub(crate) fn proof_system_version(
+    info: ProtocolInfo,
+) -> Result<ProofSystemVersion, VerificationError> {
+    ProofSystemVersion::from_info(&info).ok_or(VerificationError::ProofSystemInfoMismatch {
+        expected: PROOF_SYSTEM_INFO,
+        received: info,
+    })
+}
+
 /// Context available to the verification process.
+#[derive(Clone)]
 #[non_exhaustive]
 pub struct VerifierContext {
     /// A registry of hash functions to be used by the verification process.
@@ -691,6 +870,12 @@ pub struct VerifierContext {
 
     /// Parameters for verification of [Groth16Receipt].
     pub groth16_verifier_parameters: Option<Groth16ReceiptVerifierParameters>,
+
+    /// The [JournalHash] the guest is expected to have selected with `env::set_journal_hash`.
+    ///
+    /// [Receipt::verify] and related methods reject receipts whose journal was digested with
+    /// any other hash. Defaults to [JournalHash::Sha256].
+    pub journal_hash: JournalHash,
 }
 
 impl VerifierContext {
@@ -701,6 +886,7 @@ impl VerifierContext {
             segment_verifier_parameters: None,
             succinct_verifier_parameters: None,
             groth16_verifier_parameters: None,
+            journal_hash: JournalHash::default(),
         }
     }
 
@@ -746,6 +932,25 @@ impl VerifierContext {
         self
     }
 
+    /// Return [VerifierContext] expecting segment receipts proven with the given
+    /// [ProofSystemVersion].
+    ///
+    /// The prover reads this context too, so proving a composite receipt with it selects the
+    /// version of the segment seals. Segments lifted into succinct or Groth16 receipts are always
+    /// proven with the default version, which is the one the recursion programs verify.
+    pub fn with_proof_system_version(mut self, version: ProofSystemVersion) -> Self {
+        if let Some(params) = self.segment_verifier_parameters.as_mut() {
+            params.proof_system_info = version.info();
+        }
+        self
+    }
+
+    /// Return [VerifierContext] expecting journals digested with the given [JournalHash].
+    pub fn with_journal_hash(mut self, journal_hash: JournalHash) -> Self {
+        self.journal_hash = journal_hash;
+        self
+    }
+
     /// Parameters for verification of [CompositeReceipt].
     ///
     /// Made up of the verifier parameters for each other receipt type. Returns none if any of the
@@ -766,6 +971,7 @@ impl Default for VerifierContext {
             segment_verifier_parameters: Some(Default::default()),
             succinct_verifier_parameters: Some(Default::default()),
             groth16_verifier_parameters: Some(Default::default()),
+            journal_hash: JournalHash::default(),
         }
     }
 }
@@ -179,6 +179,7 @@ impl CompositeReceipt {
             .map(|output| Output {
                 journal: output.journal.clone(),
                 assumptions: vec![].into(),
+                journal_hash: output.journal_hash,
             })
             .into();
 
@@ -222,6 +223,25 @@ impl CompositeReceipt {
         // NOTE: This sum cannot overflow because all seals are in memory.
         self.segments.iter().map(|s| s.seal_size()).sum()
     }
+
+    /// Compress the seals of this receipt. See [Receipt::compress][crate::Receipt::compress].
+    pub fn compress(&mut self) {
+        self.segments.iter_mut().for_each(SegmentReceipt::compress);
+        self.assumption_receipts
+            .iter_mut()
+            .for_each(InnerAssumptionReceipt::compress);
+    }
+
+    /// Decompress the seals of this receipt.
+    pub fn decompress(&mut self) -> Result<(), VerificationError> {
+        for segment in self.segments.iter_mut() {
+            segment.decompress()?;
+        }
+        for assumption in self.assumption_receipts.iter_mut() {
+            assumption.decompress()?;
+        }
+        Ok(())
+    }
 }
 
 /// Verifier parameters for [CompositeReceipt][super::CompositeReceipt].
@@ -0,0 +1,413 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lossless compression of STARK seals.
+//!
+//! A compressed seal starts with a header of two words: [COMPRESSED_SEAL_MAGIC] and the length
+//! of the uncompressed seal. Uncompressed seals always start with a BabyBear field element, which
+//! is less than the field modulus, so the magic word (which is not) acts as the compression flag.
+//!
+//! The body entropy codes each word as its bit width followed by the bits below its leading one.
+//! Widths are coded with a canonical Huffman code built for the seal, whose code lengths are
+//! stored at the start of the body. Random field elements cost slightly less than 32 bits, and
+//! the many small values in a seal (e.g. trace columns holding bits, bytes and 16-bit limbs,
+//! globals and zero padding) cost only a few bits each.
+//!
+//! Most of a seal is Merkle digests and random field elements, which are close to incompressible,
+//! so the codec does not target a particular ratio. The saving is the share of small values in
+//! the opened trace rows, and is larger for seals of circuits with many narrow columns.
+
+use alloc::{borrow::Cow, vec, vec::Vec};
+
+use risc0_zkp::verify::VerificationError;
+
+/// Leading word of a compressed seal. The low byte is the codec version.
+const COMPRESSED_SEAL_MAGIC: u32 = 0xc0de_5e02;
+
+/// Upper bound on the number of words in a seal, well above the size of any seal produced by a
+/// supported circuit. Compressed seals claiming to be larger are rejected before decoding.
+const MAX_SEAL_WORDS: usize = 1 << 22;
+
+/// Number of distinct bit widths of a word, from 0 to 32.
+const SYMBOLS: usize = u32::BITS as usize + 1;
+
+/// Longest allowed Huffman code, in bits.
+const MAX_CODE_LEN: usize = 15;
+
+/// Number of bits used to store each code length in the header.
+const CODE_LEN_BITS: u32 = 4;
+
+/// Returns true if the given seal is compressed.
+pub(crate) fn is_compressed(seal: &[u32]) -> bool {
+    seal.first() == Some(&COMPRESSED_SEAL_MAGIC)
+}
+
+/// Compress the given seal. Seals which are already compressed are returned as-is.
+pub(crate) fn compress(seal: &[u32]) -> Vec<u32> {
+    if is_compressed(seal) {
+        return seal.to_vec();
+    }
+    let mut counts = [0u64; SYMBOLS];
+    for word in seal {
+        counts[bit_width(*word) as usize] += 1;
+    }
+    let lens = code_lengths(&counts);
+    let codes = canonical_codes(&lens);
+
+    let mut writer = BitWriter::new(vec![COMPRESSED_SEAL_MAGIC, seal.len() as u32]);
+    for len in lens {
+        writer.write(len as u32, CODE_LEN_BITS);
+    }
+    for word in seal {
+        let width = bit_width(*word);
+        let (code, len) = (codes[width as usize], lens[width as usize]);
+        // Codes are written most significant bit first, so they can be decoded bit by bit.
+        for i in (0..len).rev() {
+            writer.write((code >> i) & 1, 1);
+        }
+        if width > 1 {
+            writer.write(word & !(1 << (width - 1)), width - 1);
+        }
+    }
+    writer.finish()
+}
+
+/// Decompress the given seal, borrowing it if it is not compressed.
+pub(crate) fn decompress(seal: &[u32]) -> Result<Cow<'_, [u32]>, VerificationError> {
+    let [COMPRESSED_SEAL_MAGIC, len, body @ ..] = seal else {
+        if is_compressed(seal) {
+            return Err(VerificationError::ReceiptFormatError);
+        }
+        return Ok(Cow::Borrowed(seal));
+    };
+    // Every word costs at least one bit of the body, so a short body cannot claim a long seal.
+    let len = *len as usize;
+    if len > MAX_SEAL_WORDS || len > body.len() * u32::BITS as usize {
+        return Err(VerificationError::ReceiptFormatError);
+    }
+
+    let mut reader = BitReader::new(body);
+    let mut lens = [0u8; SYMBOLS];
+    for len in lens.iter_mut() {
+        *len = reader.read(CODE_LEN_BITS)? as u8;
+    }
+    let decoder = Decoder::new(&lens)?;
+
+    let mut out = Vec::with_capacity(len);
+    while out.len() < len {
+        let width = decoder.decode(&mut reader)?;
+        out.push(match width {
+            0 => 0,
+            width => (1 << (width - 1)) | reader.read(width - 1)?,
+        });
+    }
+    if !reader.is_empty() {
+        return Err(VerificationError::ReceiptFormatError);
+    }
+    Ok(Cow::Owned(out))
+}
+
+fn bit_width(word: u32) -> u32 {
+    u32::BITS - word.leading_zeros()
+}
+
+/// Compute Huffman code lengths for the given symbol counts, limited to [MAX_CODE_LEN] bits.
+///
+/// Unused symbols get a length of zero. A lone used symbol gets a one bit code, so that every
+/// coded word consumes input.
+fn code_lengths(counts: &[u64; SYMBOLS]) -> [u8; SYMBOLS] {
+    let mut counts = *counts;
+    loop {
+        let lens = huffman_lengths(&counts);
+        if lens.iter().all(|&len| len as usize <= MAX_CODE_LEN) {
+            return lens;
+        }
+        // Flatten the distribution until the tree is shallow enough, keeping used symbols used.
+        for count in counts.iter_mut().filter(|count| **count > 0) {
+            *count = count.div_ceil(2);
+        }
+    }
+}
+
+fn huffman_lengths(counts: &[u64; SYMBOLS]) -> [u8; SYMBOLS] {
+    // Each node is (weight, parent). Leaves are the first SYMBOLS nodes.
+    let mut nodes: Vec<(u64, Option<usize>)> = counts.iter().map(|&count| (count, None)).collect();
+    let mut roots: Vec<usize> = (0..SYMBOLS).filter(|&i| counts[i] > 0).collect();
+    let mut lens = [0u8; SYMBOLS];
+    if roots.len() == 1 {
+        lens[roots[0]] = 1;
+        return lens;
+    }
+    while roots.len() > 1 {
+        roots.sort_by_key(|&i| core::cmp::Reverse(nodes[i].0));
+        let (a, b) = (roots.pop().unwrap(), roots.pop().unwrap());
+        let parent = nodes.len();
+        nodes.push((nodes[a].0 + nodes[b].0, None));
+        nodes[a].1 = Some(parent);
+        nodes[b].1 = Some(parent);
+        roots.push(parent);
+    }
+    for (symbol, len) in lens.iter_mut().enumerate() {
+        let mut node = symbol;
+        while let Some(parent) = nodes[node].1 {
+            *len += 1;
+            node = parent;
+        }
+    }
+    lens
+}
+
+/// Assign canonical Huffman codes to the given code lengths.
+fn canonical_codes(lens: &[u8; SYMBOLS]) -> [u32; SYMBOLS] {
+    let mut bl_count = [0u32; MAX_CODE_LEN + 1];
+    for &len in lens.iter().filter(|&&len| len > 0) {
+        bl_count[len as usize] += 1;
+    }
+    let mut next_code = [0u32; MAX_CODE_LEN + 1];
+    let mut code = 0;
+    for len in 1..=MAX_CODE_LEN {
+        code = (code + bl_count[len - 1]) << 1;
+        next_code[len] = code;
+    }
+    let mut codes = [0u32; SYMBOLS];
+    for (symbol, &len) in lens.iter().enumerate().filter(|(_, &len)| len > 0) {
+        codes[symbol] = next_code[len as usize];
+        next_code[len as usize] += 1;
+    }
+    codes
+}
+
+/// Decoder for a canonical Huffman code over bit widths.
+struct Decoder {
+    bl_count: [u32; MAX_CODE_LEN + 1],
+    symbols: Vec<u32>,
+}
+
+impl Decoder {
+    fn new(lens: &[u8; SYMBOLS]) -> Result<Self, VerificationError> {
+        let mut bl_count = [0u32; MAX_CODE_LEN + 1];
+        for &len in lens.iter().filter(|&&len| len > 0) {
+            bl_count[len as usize] += 1;
+        }
+        // Reject over-subscribed codes, in which some codes would be prefixes of others.
+        let kraft: u32 = (1..=MAX_CODE_LEN)
+            .map(|len| bl_count[len] << (MAX_CODE_LEN - len))
+            .sum();
+        if kraft > 1 << MAX_CODE_LEN {
+            return Err(VerificationError::ReceiptFormatError);
+        }
+        let mut symbols: Vec<u32> = (0..SYMBOLS as u32)
+            .filter(|&symbol| lens[symbol as usize] > 0)
+            .collect();
+        symbols.sort_by_key(|&symbol| lens[symbol as usize]);
+        Ok(Self { bl_count, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u32, VerificationError> {
+        let (mut code, mut first, mut index) = (0u32, 0u32, 0u32);
+        for len in 1..=MAX_CODE_LEN {
+            code |= reader.read(1)?;
+            let count = self.bl_count[len];
+            if code < first + count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(VerificationError::ReceiptFormatError)
+    }
+}
+
+struct BitWriter {
+    out: Vec<u32>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn new(out: Vec<u32>) -> Self {
+        Self {
+            out,
+            acc: 0,
+            bits: 0,
+        }
+    }
+
+    fn write(&mut self, value: u32, width: u32) {
+        debug_assert!(width == u32::BITS || value >> width == 0);
+        self.acc |= (value as u64) << self.bits;
+        self.bits += width;
+        if self.bits >= u32::BITS {
+            self.out.push(self.acc as u32);
+            self.acc >>= u32::BITS;
+            self.bits -= u32::BITS;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u32> {
+        if self.bits > 0 {
+            self.out.push(self.acc as u32);
+        }
+        self.out
+    }
+}
+
+struct BitReader<'a> {
+    words: &'a [u32],
+    acc: u64,
+    bits: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(words: &'a [u32]) -> Self {
+        Self {
+            words,
+            acc: 0,
+            bits: 0,
+        }
+    }
+
+    fn read(&mut self, width: u32) -> Result<u32, VerificationError> {
+        if self.bits < width {
+            let (word, rest) = self
+                .words
+                .split_first()
+                .ok_or(VerificationError::ReceiptFormatError)?;
+            self.acc |= (*word as u64) << self.bits;
+            self.bits += u32::BITS;
+            self.words = rest;
+        }
+        let value = (self.acc & ((1u64 << width) - 1)) as u32;
+        self.acc >>= width;
+        self.bits -= width;
+        Ok(value)
+    }
+
+    /// Returns true if all words have been read, leaving only zero padding.
+    fn is_empty(&self) -> bool {
+        self.words.is_empty() && self.acc == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, decompress, is_compressed, COMPRESSED_SEAL_MAGIC, MAX_SEAL_WORDS};
+    use risc0_zkp::verify::VerificationError;
+
+    /// Deterministic stand-in for a random number generator.
+    fn xorshift(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    fn field_elems(len: usize) -> Vec<u32> {
+        let mut state = 0x1234_5678_9abc_def0;
+        (0..len)
+            .map(|_| (xorshift(&mut state) % 0x7800_0001) as u32)
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let seals: [Vec<u32>; 6] = [
+            vec![],
+            vec![0; 100],
+            vec![1; 100],
+            field_elems(1000),
+            (0..77).map(|i| u32::MAX - i).collect(),
+            (0..4096).map(|i| i * i).collect(),
+        ];
+        for seal in seals {
+            let compressed = compress(&seal);
+            assert!(is_compressed(&compressed));
+            assert!(!is_compressed(&seal));
+            assert_eq!(compress(&compressed), compressed);
+            assert_eq!(decompress(&compressed).unwrap(), seal.as_slice());
+            assert_eq!(decompress(&seal).unwrap(), seal.as_slice());
+        }
+    }
+
+    #[test]
+    fn field_elements_shrink() {
+        let seal = field_elems(4096);
+        assert!(compress(&seal).len() < seal.len());
+        assert!(compress(&[0; 1024]).len() < 48);
+
+        // Small values, such as trace columns holding bits and bytes, shrink much further.
+        let mut state = 1;
+        let trace: Vec<u32> = (0..4096)
+            .map(|i| match i % 4 {
+                0 => (xorshift(&mut state) % 2) as u32,
+                1 => (xorshift(&mut state) % 256) as u32,
+                2 => (xorshift(&mut state) % 0x1_0000) as u32,
+                _ => (xorshift(&mut state) % 0x7800_0001) as u32,
+            })
+            .collect();
+        assert!(compress(&trace).len() * 3 < trace.len() * 2);
+    }
+
+    #[test]
+    fn truncated() {
+        let seal: Vec<u32> = (0..100).collect();
+        let compressed = compress(&seal);
+        for len in 1..compressed.len() {
+            assert_eq!(
+                decompress(&compressed[..len]).unwrap_err(),
+                VerificationError::ReceiptFormatError
+            );
+        }
+    }
+
+    #[test]
+    fn oversized_length() {
+        // Regression test: the length in the header must be bounded before decoding, and a
+        // short body must not expand into a long seal.
+        for len in [u32::MAX, MAX_SEAL_WORDS as u32 + 1, 1 << 20, 97] {
+            let mut compressed = compress(&[0; 2]);
+            compressed[1] = len;
+            assert_eq!(
+                decompress(&compressed).unwrap_err(),
+                VerificationError::ReceiptFormatError
+            );
+        }
+    }
+
+    #[test]
+    fn fuzz() {
+        let mut state = 0x0bad_5eed;
+        let seals = [vec![0; 64], field_elems(64), (0..64).collect::<Vec<u32>>()];
+        for _ in 0..10_000 {
+            let seal = &seals[xorshift(&mut state) as usize % seals.len()];
+            let mut compressed = compress(seal);
+            // Corrupt a few bits, possibly including the length.
+            for _ in 0..1 + xorshift(&mut state) % 4 {
+                let index = 1 + xorshift(&mut state) as usize % (compressed.len() - 1);
+                compressed[index] ^= 1 << (xorshift(&mut state) % 32);
+            }
+            if let Ok(decompressed) = decompress(&compressed) {
+                assert!(decompressed.len() <= compressed.len() * 32);
+            }
+
+            // Arbitrary bodies must not panic either.
+            let body: Vec<u32> = (0..xorshift(&mut state) % 16)
+                .map(|_| xorshift(&mut state) as u32)
+                .collect();
+            let len = (xorshift(&mut state) % 512) as u32;
+            let _ = decompress(&[&[COMPRESSED_SEAL_MAGIC, len], body.as_slice()].concat());
+        }
+    }
+}
@@ -31,7 +31,7 @@ use risc0_zkp::{
 use serde::{Deserialize, Serialize};
 
 // Make succinct receipt available through this `receipt` module.
-use super::VerifierContext;
+use super::{proof_system_version, seal_codec, VerifierContext};
 use crate::{sha, MaybePruned, ReceiptClaim};
 
 /// A receipt attesting to the execution of a Segment.
@@ -82,13 +82,8 @@ impl SegmentReceipt {
 
         // Check that the proof system and circuit info strings match what is implemented by this
         // function. Info strings are used a version identifiers, and this verify implementation
-        // supports exactly one proof systema and circuit version at a time.
-        if params.proof_system_info != PROOF_SYSTEM_INFO {
-            return Err(VerificationError::ProofSystemInfoMismatch {
-                expected: PROOF_SYSTEM_INFO,
-                received: params.proof_system_info,
-            });
-        }
+        // supports exactly one proof system and circuit version at a time.
+        let version = proof_system_version(params.proof_system_info)?;
         if params.circuit_info != CircuitImpl::CIRCUIT_INFO {
             return Err(VerificationError::CircuitInfoMismatch {
                 expected: CircuitImpl::CIRCUIT_INFO,
@@ -108,11 +103,12 @@ impl SegmentReceipt {
             .suites
             .get(&self.hashfn)
             .ok_or(VerificationError::InvalidHashSuite)?;
-        risc0_zkp::verify::verify(&CIRCUIT, suite, &self.seal, check_code)?;
+        let seal = seal_codec::decompress(&self.seal)?;
+        risc0_zkp::verify::verify_with_version(&CIRCUIT, suite, version, &seal, check_code)?;
 
         // Receipt is consistent with the claim encoded on the seal. Now check against the
         // claim on the struct.
-        let decoded_claim = decode_receipt_claim_from_seal(&self.seal)?;
+        let decoded_claim = decode_receipt_claim_from_seal(&seal)?;
         if decoded_claim.digest::<sha::Impl>() != self.claim.digest::<sha::Impl>() {
             tracing::debug!(
                 "decoded segment receipt claim does not match claim field:\ndecoded: {:#?},\nexpected: {:#?}",
@@ -136,6 +132,19 @@ impl SegmentReceipt {
     pub fn seal_size(&self) -> usize {
         core::mem::size_of_val(self.seal.as_slice())
     }
+
+    /// Compress the seal of this receipt. See [Receipt::compress][crate::Receipt::compress].
+    pub fn compress(&mut self) {
+        self.seal = seal_codec::compress(&self.seal);
+    }
+
+    /// Decompress the seal of this receipt, if it is compressed.
+    pub fn decompress(&mut self) -> Result<(), VerificationError> {
+        if seal_codec::is_compressed(&self.seal) {
+            self.seal = seal_codec::decompress(&self.seal)?.into_owned();
+        }
+        Ok(())
+    }
 }
 
 /// Verifier parameters used to verify a [SegmentReceipt].
@@ -27,7 +27,7 @@ use risc0_zkp::{
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    receipt::{merkle::MerkleProof, VerifierContext},
+    receipt::{merkle::MerkleProof, proof_system_version, seal_codec, VerifierContext},
     receipt_claim::{MaybePruned, Unknown},
     sha,
 };
@@ -97,13 +97,8 @@ where
 
         // Check that the proof system and circuit info strings match what is implemented by this
         // function. Info strings are used a version identifiers, and this verify implementation
-        // supports exactly one proof systema and circuit version at a time.
-        if params.proof_system_info != PROOF_SYSTEM_INFO {
-            return Err(VerificationError::ProofSystemInfoMismatch {
-                expected: PROOF_SYSTEM_INFO,
-                received: params.proof_system_info,
-            });
-        }
+        // supports exactly one proof system and circuit version at a time.
+        let version = proof_system_version(params.proof_system_info)?;
         if params.circuit_info != CircuitImpl::CIRCUIT_INFO {
             return Err(VerificationError::CircuitInfoMismatch {
                 expected: CircuitImpl::CIRCUIT_INFO,
@@ -133,11 +128,12 @@ where
 
         // Verify the receipt itself is correct, and therefore the encoded globals are
         // reliable.
-        risc0_zkp::verify::verify(&CIRCUIT, suite, &self.seal, check_code)?;
+        let seal = seal_codec::decompress(&self.seal)?;
+        risc0_zkp::verify::verify_with_version(&CIRCUIT, suite, version, &seal, check_code)?;
 
         // Extract the globals from the seal
         let output_elems: &[BabyBearElem] =
-            bytemuck::checked::cast_slice(&self.seal[..CircuitImpl::OUTPUT_SIZE]);
+            bytemuck::checked::cast_slice(&seal[..CircuitImpl::OUTPUT_SIZE]);
         let mut seal_claim = VecDeque::new();
         for elem in output_elems {
             seal_claim.push_back(elem.as_u32())
@@ -190,6 +186,19 @@ where
         core::mem::size_of_val(self.seal.as_slice())
     }
 
+    /// Compress the seal of this receipt. See [Receipt::compress][crate::Receipt::compress].
+    pub fn compress(&mut self) {
+        self.seal = seal_codec::compress(&self.seal);
+    }
+
+    /// Decompress the seal of this receipt, if it is compressed.
+    pub fn decompress(&mut self) -> Result<(), VerificationError> {
+        if seal_codec::is_compressed(&self.seal) {
+            self.seal = seal_codec::decompress(&self.seal)?.into_owned();
+        }
+        Ok(())
+    }
+
     #[cfg(feature = "prove")]
     pub(crate) fn control_root(&self) -> anyhow::Result<Digest> {
         let hash_suite = risc0_zkp::core::hash::hash_suite_from_name(&self.hashfn)
@@ -28,7 +28,11 @@ use risc0_binfmt::{
     read_sha_halfs, tagged_list, tagged_list_cons, tagged_struct, write_sha_halfs, Digestible,
     ExitCode, InvalidExitCodeError,
 };
-use risc0_zkp::core::digest::Digest;
+use risc0_core::field::{baby_bear::BabyBearElem, Elem as _};
+use risc0_zkp::core::{
+    digest::{Digest, DIGEST_WORDS},
+    hash::poseidon2,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -83,6 +87,7 @@ impl ReceiptClaim {
             output: Some(Output {
                 journal: journal.into(),
                 assumptions: MaybePruned::Pruned(Digest::ZERO),
+                journal_hash: JournalHash::Sha256,
             })
             .into(),
         }
@@ -105,6 +110,7 @@ impl ReceiptClaim {
             output: Some(Output {
                 journal: journal.into(),
                 assumptions: MaybePruned::Pruned(Digest::ZERO),
+                journal_hash: JournalHash::Sha256,
             })
             .into(),
         }
@@ -241,6 +247,17 @@ pub struct Input {
     pub(crate) x: Unknown,
 }
 
+impl Input {
+    /// Compute the digest recorded as the input of a [ReceiptClaim] for the given host-provided
+    /// input bytes.
+    ///
+    /// This is the digest bound into the claim when committing to the input is enabled on the
+    /// executor environment, and can be used by a verifier to check the input it expects.
+    pub fn digest_bytes(bytes: &[u8]) -> Digest {
+        *sha::Impl::hash_bytes(bytes)
+    }
+}
+
 impl Digestible for Input {
     /// Hash the [Input] to get a digest of the struct.
     fn digest<S: Sha256>(&self) -> Digest {
@@ -248,9 +265,161 @@ impl Digestible for Input {
     }
 }
 
+/// Hash function used to digest the journal in the [Output] of a [ReceiptClaim].
+///
+/// SHA-256 is the default and is the cheapest to check on most chains. Poseidon2 over the BabyBear
+/// field is much cheaper to evaluate inside a zkVM guest or a SNARK circuit, which makes it the
+/// better choice for verifiers that recompute the journal digest in such an environment. The
+/// choice is bound into the [Output] digest, so a receipt for one cannot be passed off as a
+/// receipt for the other.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+#[non_exhaustive]
+pub enum JournalHash {
+    /// SHA-256, as used by the rest of the [ReceiptClaim].
+    #[default]
+    Sha256,
+
+    /// Poseidon2 over the BabyBear field, absorbing the journal as 16-bit little-endian limbs
+    /// followed by its length in bytes.
+    Poseidon2,
+}
+
+impl JournalHash {
+    /// Compute the digest of the given journal.
+    pub fn digest(&self, journal: &[u8]) -> Digest {
+        let mut hasher = JournalHasher::new(*self);
+        hasher.update(journal);
+        hasher.finalize()
+    }
+
+    pub(crate) fn as_u32(&self) -> u32 {
+        match self {
+            Self::Sha256 => 0,
+            Self::Poseidon2 => 1,
+        }
+    }
+
+    pub(crate) fn from_u32(hash: u32) -> Option<Self> {
+        match hash {
+            0 => Some(Self::Sha256),
+            1 => Some(Self::Poseidon2),
+            _ => None,
+        }
+    }
+}
+
+/// Incremental hasher for the journal, used by the guest as bytes are committed.
+#[derive(Clone)]
+pub(crate) enum JournalHasher {
+    Sha256(sha::rust_crypto::Sha256),
+    Poseidon2(Poseidon2Journal),
+}
+
+impl JournalHasher {
+    pub(crate) fn new(hash: JournalHash) -> Self {
+        match hash {
+            JournalHash::Sha256 => Self::Sha256(sha::rust_crypto::Digest::new()),
+            JournalHash::Poseidon2 => Self::Poseidon2(Poseidon2Journal::new()),
+        }
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => sha::rust_crypto::Digest::update(hasher, bytes),
+            Self::Poseidon2(hasher) => hasher.update(bytes),
+        }
+    }
+
+    pub(crate) fn finalize(self) -> Digest {
+        match self {
+            Self::Sha256(hasher) => sha::rust_crypto::Digest::finalize(hasher)
+                .as_slice()
+                .try_into()
+                .unwrap(),
+            Self::Poseidon2(hasher) => hasher.finalize(),
+        }
+    }
+}
+
+/// Sponge state for [JournalHash::Poseidon2], mirroring the padding rules of
+/// [risc0_zkp::core::hash::poseidon2::unpadded_hash].
+#[derive(Clone)]
+pub(crate) struct Poseidon2Journal {
+    state: [BabyBearElem; poseidon2::CELLS],
+    unmixed: usize,
+    len: u64,
+    pending: Option<u8>,
+}
+
+impl Poseidon2Journal {
+    fn new() -> Self {
+        Self {
+            state: [BabyBearElem::ZERO; poseidon2::CELLS],
+            unmixed: 0,
+            len: 0,
+            pending: None,
+        }
+    }
+
+    fn absorb(&mut self, limb: u32) {
+        self.state[self.unmixed] = BabyBearElem::new(limb);
+        self.unmixed += 1;
+        if self.unmixed == poseidon2::CELLS_RATE {
+            poseidon2::poseidon2_mix(&mut self.state);
+            self.unmixed = 0;
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        self.len += bytes.len() as u64;
+        for &byte in bytes {
+            match self.pending.take() {
+                None => self.pending = Some(byte),
+                Some(low) => self.absorb(u16::from_le_bytes([low, byte]) as u32),
+            }
+        }
+    }
+
+    fn finalize(mut self) -> Digest {
+        if let Some(low) = self.pending.take() {
+            self.absorb(low as u32);
+        }
+        // Absorb the length so that journals differing only by trailing zeros do not collide.
+        for shift in [0, 16, 32, 48] {
+            self.absorb(((self.len >> shift) & 0xffff) as u32);
+        }
+        if self.unmixed != 0 {
+            for elem in &mut self.state[self.unmixed..poseidon2::CELLS_RATE] {
+                *elem = BabyBearElem::ZERO;
+            }
+            poseidon2::poseidon2_mix(&mut self.state);
+        }
+        let mut words = [0u32; DIGEST_WORDS];
+        for (word, elem) in words.iter_mut().zip(&self.state[..poseidon2::CELLS_OUT]) {
+            *word = elem.as_u32_montgomery();
+        }
+        words.into()
+    }
+}
+
 /// Output field in the [ReceiptClaim], committing to a claimed journal and assumptions list.
-#[derive(Clone, Debug, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+///
+/// Outputs using [JournalHash::Sha256] are serialized exactly as they were before the journal
+/// hash was configurable, so existing receipts remain readable.
+#[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq))]
+#[non_exhaustive]
 pub struct Output {
     /// The journal committed to by the guest execution.
     pub journal: MaybePruned<Vec<u8>>,
@@ -264,16 +433,258 @@ pub struct Output {
     /// be trusted to correspond to a genuine execution). The claims can be checked by additional
     /// verifying a [crate::Receipt] for every digest in the assumptions list.
     pub assumptions: MaybePruned<Assumptions>,
+
+    /// Hash function used to digest the journal.
+    ///
+    /// Outputs using [JournalHash::Sha256] keep the digest they had before the hash was
+    /// configurable. Any other choice is recorded in the data fields of the [Output] digest.
+    pub journal_hash: JournalHash,
+}
+
+/// Binary encoding of [Output], used with bincode, borsh and the zkVM serde format.
+///
+/// The first two variants have the same layout as the original `Output { journal, assumptions }`
+/// struct, in which the leading bytes are the variant index of the [MaybePruned] journal. Outputs
+/// using any journal hash other than SHA-256 use the new third variant, which older readers reject
+/// rather than misinterpret.
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+enum OutputRepr {
+    Value(Vec<u8>, MaybePruned<Assumptions>),
+    Pruned(Digest, MaybePruned<Assumptions>),
+    Hashed(JournalHash, MaybePruned<Vec<u8>>, MaybePruned<Assumptions>),
+}
+
+/// Self-describing encoding of [Output], used with human-readable formats such as JSON.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Output")]
+struct OutputFields {
+    journal: MaybePruned<Vec<u8>>,
+    assumptions: MaybePruned<Assumptions>,
+    #[serde(default)]
+    journal_hash: JournalHash,
+}
+
+impl From<Output> for OutputRepr {
+    fn from(output: Output) -> Self {
+        match (output.journal_hash, output.journal) {
+            (JournalHash::Sha256, MaybePruned::Value(journal)) => {
+                Self::Value(journal, output.assumptions)
+            }
+            (JournalHash::Sha256, MaybePruned::Pruned(digest)) => {
+                Self::Pruned(digest, output.assumptions)
+            }
+            (hash, journal) => Self::Hashed(hash, journal, output.assumptions),
+        }
+    }
+}
+
+impl From<OutputRepr> for Output {
+    fn from(repr: OutputRepr) -> Self {
+        let (journal_hash, journal, assumptions) = match repr {
+            OutputRepr::Value(journal, assumptions) => (
+                JournalHash::Sha256,
+                MaybePruned::Value(journal),
+                assumptions,
+            ),
+            OutputRepr::Pruned(digest, assumptions) => (
+                JournalHash::Sha256,
+                MaybePruned::Pruned(digest),
+                assumptions,
+            ),
+            OutputRepr::Hashed(hash, journal, assumptions) => (hash, journal, assumptions),
+        };
+        Self {
+            journal,
+            assumptions,
+            journal_hash,
+        }
+    }
+}
+
+impl Serialize for Output {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            OutputFields {
+                journal: self.journal.clone(),
+                assumptions: self.assumptions.clone(),
+                journal_hash: self.journal_hash,
+            }
+            .serialize(serializer)
+        } else {
+            Serialize::serialize(&OutputRepr::from(self.clone()), serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Output {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let fields = OutputFields::deserialize(deserializer)?;
+            Ok(Self {
+                journal: fields.journal,
+                assumptions: fields.assumptions,
+                journal_hash: fields.journal_hash,
+            })
+        } else {
+            <OutputRepr as Deserialize>::deserialize(deserializer).map(Into::into)
+        }
+    }
+}
+
+impl BorshSerialize for Output {
+    fn serialize<W: borsh::io::Write>(
+        &self,
+        writer: &mut W,
+    ) -> core::result::Result<(), borsh::io::Error> {
+        BorshSerialize::serialize(&OutputRepr::from(self.clone()), writer)
+    }
+}
+
+impl BorshDeserialize for Output {
+    fn deserialize_reader<R: borsh::io::Read>(
+        reader: &mut R,
+    ) -> core::result::Result<Self, borsh::io::Error> {
+        OutputRepr::deserialize_reader(reader).map(Into::into)
+    }
+}
+
+impl Output {
+    /// Construct an [Output] committing to the given journal and assumptions, with the journal
+    /// digested using [JournalHash::Sha256].
+    pub fn new(journal: MaybePruned<Vec<u8>>, assumptions: MaybePruned<Assumptions>) -> Self {
+        Self {
+            journal,
+            assumptions,
+            journal_hash: JournalHash::Sha256,
+        }
+    }
+
+    /// Return [Output] with the journal_hash set to the given value.
+    pub fn with_journal_hash(self, journal_hash: JournalHash) -> Self {
+        Self {
+            journal_hash,
+            ..self
+        }
+    }
+
+    /// Digest of the journal, computed with [Output::journal_hash] if the journal is not pruned.
+    pub fn journal_digest<S: Sha256>(&self) -> Digest {
+        match (&self.journal, self.journal_hash) {
+            (MaybePruned::Value(_), JournalHash::Sha256) => self.journal.digest::<S>(),
+            (MaybePruned::Value(journal), hash) => hash.digest(journal),
+            (MaybePruned::Pruned(digest), _) => *digest,
+        }
+    }
 }
 
 impl Digestible for Output {
     /// Hash the [Output] to get a digest of the struct.
     fn digest<S: Sha256>(&self) -> Digest {
-        tagged_struct::<S>(
-            "risc0.Output",
-            &[self.journal.digest::<S>(), self.assumptions.digest::<S>()],
-            &[],
-        )
+        let down = [self.journal_digest::<S>(), self.assumptions.digest::<S>()];
+        match self.journal_hash {
+            JournalHash::Sha256 => tagged_struct::<S>("risc0.Output", &down, &[]),
+            hash => tagged_struct::<S>("risc0.Output", &down, &[hash.as_u32()]),
+        }
+    }
+}
+
+/// Commitment to an output streamed by the guest with `env::commit_stream`, which is written to
+/// the journal in place of the output itself.
+///
+/// The streamed bytes are received by the host through the `JOURNAL_STREAM` file descriptor, and
+/// can be checked against the commitment with [StreamCommitment::matches].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamCommitment {
+    /// SHA-256 digest of the streamed bytes.
+    pub digest: Digest,
+
+    /// Number of bytes streamed.
+    pub len: u64,
+}
+
+impl StreamCommitment {
+    /// Compute the commitment to the given bytes.
+    pub fn new(bytes: &[u8]) -> Self {
+        Self {
+            digest: *sha::Impl::hash_bytes(bytes),
+            len: bytes.len() as u64,
+        }
+    }
+
+    /// Returns true if this commitment is to the given bytes.
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        *self == Self::new(bytes)
+    }
+}
+
+/// Writer that checks a stream against its [StreamCommitment] as it is received.
+///
+/// Pass it (or a mutable reference to it) to `ExecutorEnvBuilder::journal_stream` to forward the
+/// streamed bytes to `inner` while hashing them, so that streams larger than host memory can be
+/// checked without buffering them. Once execution has finished, call [StreamVerifier::verify]
+/// with the commitment decoded from the journal.
+#[cfg(feature = "std")]
+pub struct StreamVerifier<W> {
+    inner: W,
+    hasher: sha::rust_crypto::Sha256,
+    len: u64,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> StreamVerifier<W> {
+    /// Create a verifier forwarding the stream to `inner`. Use [std::io::sink] to discard it.
+    pub fn new(inner: W) -> Self {
+        use sha::rust_crypto::Digest as _;
+        Self {
+            inner,
+            hasher: sha::rust_crypto::Sha256::new(),
+            len: 0,
+        }
+    }
+
+    /// The commitment to the bytes received so far.
+    pub fn commitment(&self) -> StreamCommitment {
+        use sha::rust_crypto::Digest as _;
+        StreamCommitment {
+            digest: self
+                .hasher
+                .clone()
+                .finalize()
+                .as_slice()
+                .try_into()
+                .unwrap(),
+            len: self.len,
+        }
+    }
+
+    /// Check that the received bytes match `commitment`, returning the inner writer if they do.
+    pub fn verify(self, commitment: &StreamCommitment) -> anyhow::Result<W> {
+        let received = self.commitment();
+        ensure!(
+            received == *commitment,
+            "streamed output does not match its commitment: received {} bytes with digest {}, \
+             expected {} bytes with digest {}",
+            received.len,
+            received.digest,
+            commitment.len,
+            commitment.digest
+        );
+        Ok(self.inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> std::io::Write for StreamVerifier<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        use sha::rust_crypto::Digest as _;
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.len += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
     }
 }
 
@@ -721,9 +1132,16 @@ impl Merge for Input {
 #[cfg(feature = "prove")]
 impl Merge for Output {
     fn merge(&self, other: &Self) -> Result<Self, MergeInequalityError> {
+        if self.journal_hash != other.journal_hash {
+            return Err(MergeInequalityError(
+                self.digest::<sha::Impl>(),
+                other.digest::<sha::Impl>(),
+            ));
+        }
         Ok(Self {
             journal: self.journal.merge(&other.journal)?,
             assumptions: self.assumptions.merge(&other.assumptions)?,
+            journal_hash: self.journal_hash,
         })
     }
 }
@@ -752,7 +1170,10 @@ impl Merge for ReceiptClaim {
 mod tests {
     use hex::FromHex;
 
-    use super::{Assumptions, ExitCode, MaybePruned, Merge, Output, ReceiptClaim, SystemState};
+    use super::{
+        Assumptions, ExitCode, JournalHash, MaybePruned, Merge, Output, ReceiptClaim,
+        StreamCommitment, StreamVerifier, SystemState,
+    };
     use crate::sha::{Digest, Digestible};
 
     /// Testing utility for randomly pruning structs.
@@ -800,6 +1221,7 @@ mod tests {
                     .map(|o| Output {
                         journal: o.journal.rand_prune(),
                         assumptions: o.assumptions.rand_prune(),
+                        journal_hash: o.journal_hash,
                     })
                     .into(),
                 (Self::Pruned(x), _) => Self::Pruned(*x),
@@ -854,6 +1276,7 @@ mod tests {
                     MaybePruned::Pruned(Digest::ZERO),
                     MaybePruned::Pruned(Digest::ZERO),
                 ])),
+                journal_hash: JournalHash::Sha256,
             })),
         });
 
@@ -865,4 +1288,127 @@ mod tests {
             assert_eq!(left.merge(&right).unwrap().digest(), claim.digest());
         }
     }
+
+    #[test]
+    fn journal_hash() {
+        let journal = b"hello world".to_vec();
+        let sha = Output {
+            journal: MaybePruned::Value(journal.clone()),
+            assumptions: MaybePruned::Pruned(Digest::ZERO),
+            journal_hash: JournalHash::Sha256,
+        };
+        let poseidon2 = Output {
+            journal_hash: JournalHash::Poseidon2,
+            ..sha.clone()
+        };
+
+        // The default keeps the digest Outputs had before the journal hash was configurable.
+        assert_eq!(
+            sha.digest(),
+            risc0_binfmt::tagged_struct::<crate::sha::Impl>(
+                "risc0.Output",
+                &[journal.digest(), Digest::ZERO],
+                &[],
+            )
+        );
+        assert_eq!(
+            poseidon2.journal_digest::<crate::sha::Impl>(),
+            JournalHash::Poseidon2.digest(&journal)
+        );
+        assert_ne!(sha.digest(), poseidon2.digest());
+
+        // Trailing zeros and split updates are accounted for.
+        assert_ne!(
+            JournalHash::Poseidon2.digest(b"ab"),
+            JournalHash::Poseidon2.digest(b"ab\0")
+        );
+        let mut hasher = super::JournalHasher::new(JournalHash::Poseidon2);
+        hasher.update(b"hello");
+        hasher.update(b" world");
+        assert_eq!(hasher.finalize(), JournalHash::Poseidon2.digest(&journal));
+
+        for hash in [JournalHash::Sha256, JournalHash::Poseidon2] {
+            assert_eq!(JournalHash::from_u32(hash.as_u32()), Some(hash));
+        }
+    }
+
+    #[test]
+    fn output_encoding() {
+        // The layout of Output before the journal hash was configurable.
+        #[derive(serde::Serialize, borsh::BorshSerialize)]
+        struct LegacyOutput {
+            journal: MaybePruned<Vec<u8>>,
+            assumptions: MaybePruned<Assumptions>,
+        }
+
+        for journal in [
+            MaybePruned::Value(b"hello world".to_vec()),
+            MaybePruned::Pruned(Digest::from([1, 2, 3, 4, 5, 6, 7, 8])),
+        ] {
+            let legacy = LegacyOutput {
+                journal: journal.clone(),
+                assumptions: MaybePruned::Pruned(Digest::ZERO),
+            };
+            let output = Output {
+                journal,
+                assumptions: MaybePruned::Pruned(Digest::ZERO),
+                journal_hash: JournalHash::Sha256,
+            };
+
+            let bytes = bincode::serialize(&legacy).unwrap();
+            assert_eq!(bincode::serialize(&output).unwrap(), bytes);
+            assert_eq!(bincode::deserialize::<Output>(&bytes).unwrap(), output);
+
+            let bytes = borsh::to_vec(&legacy).unwrap();
+            assert_eq!(borsh::to_vec(&output).unwrap(), bytes);
+            assert_eq!(borsh::from_slice::<Output>(&bytes).unwrap(), output);
+
+            let words = crate::serde::to_vec(&legacy).unwrap();
+            assert_eq!(crate::serde::to_vec(&output).unwrap(), words);
+            assert_eq!(
+                crate::serde::from_slice::<Output, _>(&words).unwrap(),
+                output
+            );
+        }
+
+        let output = Output {
+            journal: MaybePruned::Value(b"hello world".to_vec()),
+            assumptions: MaybePruned::Pruned(Digest::ZERO),
+            journal_hash: JournalHash::Poseidon2,
+        };
+        let bytes = bincode::serialize(&output).unwrap();
+        assert_eq!(bincode::deserialize::<Output>(&bytes).unwrap(), output);
+        let bytes = borsh::to_vec(&output).unwrap();
+        assert_eq!(borsh::from_slice::<Output>(&bytes).unwrap(), output);
+    }
+
+    #[test]
+    fn stream_commitment() {
+        let commitment = StreamCommitment::new(b"streamed output");
+        assert_eq!(commitment.len, 15);
+        assert!(commitment.matches(b"streamed output"));
+        assert!(!commitment.matches(b"streamed outpuu"));
+        assert!(!commitment.matches(b""));
+    }
+
+    #[test]
+    fn stream_verifier() {
+        use std::io::Write as _;
+
+        let commitment = StreamCommitment::new(b"streamed output");
+
+        let mut verifier = StreamVerifier::new(Vec::new());
+        verifier.write_all(b"streamed ").unwrap();
+        verifier.write_all(b"output").unwrap();
+        assert_eq!(verifier.commitment(), commitment);
+        assert_eq!(verifier.verify(&commitment).unwrap(), b"streamed output");
+
+        let mut verifier = StreamVerifier::new(std::io::sink());
+        verifier.write_all(b"streamed outpuu").unwrap();
+        assert!(verifier.verify(&commitment).is_err());
+
+        let mut verifier = StreamVerifier::new(std::io::sink());
+        verifier.write_all(b"streamed").unwrap();
+        assert!(verifier.verify(&commitment).is_err());
+    }
 }
@@ -0,0 +1,440 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Self-describing journals, which can be decoded without access to the guest's Rust types.
+//!
+//! A journal type is declared with [journal_schema!](crate::journal_schema!), usually in a crate
+//! shared by the guest and the host, which derives a [JournalSchema] describing its fields. The
+//! guest commits a value of that type with `env::commit_with_schema`, which first commits the
+//! digest of the schema, so that the digest is bound to the receipt claim along with the rest of
+//! the journal.
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//!
+//! risc0_zkvm::journal_schema! {
+//!     /// The output of the guest.
+//!     #[derive(Serialize, Deserialize)]
+//!     pub struct Output {
+//!         pub value: u64,
+//!         pub owner: String,
+//!     }
+//! }
+//! ```
+//!
+//! In the guest:
+//!
+//! ```ignore
+//! env::commit_with_schema(&Output { value: 42, owner: "alice".into() });
+//! ```
+//!
+//! On the host, a consumer sharing the Rust type decodes the journal with
+//! `Journal::decode_with_schema`. Other consumers obtain the [JournalSchema], e.g. as JSON
+//! published alongside the image ID, and decode the journal into a [SchemaValue]:
+//!
+//! ```ignore
+//! let schema: JournalSchema = serde_json::from_str(&published_schema)?;
+//! let value = schema.decode(&receipt.journal)?;
+//! ```
+
+use alloc::{boxed::Box, string::String};
+
+use risc0_binfmt::Digestible;
+use risc0_zkp::core::{
+    digest::{Digest, DIGEST_WORDS},
+    hash::sha::Sha256,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{serde::Error, Journal};
+
+#[doc(hidden)]
+pub use alloc::vec::Vec;
+
+/// Declare a struct that can be committed with a [JournalSchema].
+///
+/// The struct is declared as written, along with an implementation of [JournalType] listing its
+/// fields in order. The type of each field must implement [JournalType].
+///
+/// See the [schema module](crate::schema) for an example.
+#[macro_export]
+macro_rules! journal_schema {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident: $ty:ty
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        impl $crate::schema::JournalType for $name {
+            fn schema_type() -> $crate::schema::SchemaType {
+                $crate::schema::SchemaType::Struct($crate::schema::Vec::from([
+                    $($crate::schema::SchemaField::new(
+                        stringify!($field),
+                        <$ty as $crate::schema::JournalType>::schema_type(),
+                    ),)*
+                ]))
+            }
+        }
+    };
+}
+
+/// The type of a value in a journal, as encoded by the [risc0 serializer](crate::serde).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum SchemaType {
+    /// A `bool`.
+    Bool,
+    /// A `u8`.
+    U8,
+    /// A `u16`.
+    U16,
+    /// A `u32`.
+    U32,
+    /// A `u64`.
+    U64,
+    /// An `i8`.
+    I8,
+    /// An `i16`.
+    I16,
+    /// An `i32`.
+    I32,
+    /// An `i64`.
+    I64,
+    /// A UTF-8 `String`.
+    String,
+    /// A [Digest].
+    Digest,
+    /// An `Option` of the given type.
+    Option(Box<SchemaType>),
+    /// A `Vec` of the given type.
+    Vec(Box<SchemaType>),
+    /// An array of the given type and length.
+    Array(Box<SchemaType>, u32),
+    /// A tuple of the given types.
+    Tuple(Vec<SchemaType>),
+    /// A struct with the given fields, in order.
+    Struct(Vec<SchemaField>),
+}
+
+/// A field of a [SchemaType::Struct].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaField {
+    /// The name of the field.
+    pub name: String,
+
+    /// The type of the field.
+    pub ty: SchemaType,
+}
+
+impl SchemaField {
+    /// Construct a [SchemaField] with the given name and type.
+    pub fn new(name: &str, ty: SchemaType) -> Self {
+        Self {
+            name: name.into(),
+            ty,
+        }
+    }
+}
+
+/// A type which can be described by a [SchemaType].
+pub trait JournalType {
+    /// The [SchemaType] describing this type.
+    fn schema_type() -> SchemaType;
+}
+
+/// Describes the value committed to a journal with `env::commit_with_schema`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalSchema {
+    /// The type of the committed value.
+    pub root: SchemaType,
+}
+
+impl JournalSchema {
+    /// The schema of a journal holding a value of type `T`.
+    pub fn of<T: JournalType + ?Sized>() -> Self {
+        Self {
+            root: T::schema_type(),
+        }
+    }
+
+    /// Check that the journal starts with the digest of this schema.
+    pub fn validate(&self, journal: &Journal) -> Result<(), Error> {
+        let expected = self.digest::<crate::sha::Impl>();
+        let found = journal.schema_digest()?;
+        if found != expected {
+            return Err(Error::Custom(alloc::format!(
+                "journal schema digest mismatch: expected {expected}, found {found}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Decode the value committed to the journal, after checking that it was committed with this
+    /// schema.
+    pub fn decode(&self, journal: &Journal) -> Result<SchemaValue, Error> {
+        self.validate(journal)?;
+        let words = bytemuck::pod_collect_to_vec::<u8, u32>(&journal.bytes);
+        let mut words = &words[DIGEST_WORDS..];
+        self.root.decode(&mut words)
+    }
+}
+
+impl Digestible for JournalSchema {
+    fn digest<S: Sha256>(&self) -> Digest {
+        let words = crate::serde::to_vec(self).unwrap();
+        *S::hash_words(&words)
+    }
+}
+
+/// A value decoded from a journal according to a [JournalSchema].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SchemaValue {
+    /// A [SchemaType::Bool].
+    Bool(bool),
+    /// An unsigned integer.
+    Unsigned(u64),
+    /// A signed integer.
+    Signed(i64),
+    /// A [SchemaType::String].
+    String(String),
+    /// A [SchemaType::Digest].
+    Digest(Digest),
+    /// A [SchemaType::Option].
+    Option(Option<Box<SchemaValue>>),
+    /// A [SchemaType::Vec], [SchemaType::Array] or [SchemaType::Tuple].
+    List(Vec<SchemaValue>),
+    /// A [SchemaType::Struct], as its fields in order.
+    Struct(Vec<(String, SchemaValue)>),
+}
+
+fn take<'a>(words: &mut &'a [u32], len: usize) -> Result<&'a [u32], Error> {
+    if words.len() < len {
+        return Err(Error::DeserializeUnexpectedEnd);
+    }
+    let (head, tail) = words.split_at(len);
+    *words = tail;
+    Ok(head)
+}
+
+fn take_word(words: &mut &[u32]) -> Result<u32, Error> {
+    Ok(take(words, 1)?[0])
+}
+
+fn take_u64(words: &mut &[u32]) -> Result<u64, Error> {
+    let low = take_word(words)? as u64;
+    let high = take_word(words)? as u64;
+    Ok(low | high << 32)
+}
+
+impl SchemaType {
+    fn decode(&self, words: &mut &[u32]) -> Result<SchemaValue, Error> {
+        Ok(match self {
+            Self::Bool => match take_word(words)? {
+                0 => SchemaValue::Bool(false),
+                1 => SchemaValue::Bool(true),
+                _ => return Err(Error::DeserializeBadBool),
+            },
+            Self::U8 | Self::U16 | Self::U32 => SchemaValue::Unsigned(take_word(words)? as u64),
+            Self::U64 => SchemaValue::Unsigned(take_u64(words)?),
+            Self::I8 | Self::I16 | Self::I32 => {
+                SchemaValue::Signed(take_word(words)? as i32 as i64)
+            }
+            Self::I64 => SchemaValue::Signed(take_u64(words)? as i64),
+            Self::String => {
+                let len = take_word(words)? as usize;
+                let padded = take(words, len.div_ceil(4))?;
+                let bytes = &bytemuck::cast_slice::<u32, u8>(padded)[..len];
+                let str = core::str::from_utf8(bytes).map_err(|_| Error::DeserializeBadUtf8)?;
+                SchemaValue::String(str.into())
+            }
+            Self::Digest => {
+                SchemaValue::Digest(Digest::try_from(take(words, DIGEST_WORDS)?).unwrap())
+            }
+            Self::Option(ty) => match take_word(words)? {
+                0 => SchemaValue::Option(None),
+                1 => SchemaValue::Option(Some(Box::new(ty.decode(words)?))),
+                _ => return Err(Error::DeserializeBadOption),
+            },
+            Self::Vec(ty) => {
+                let len = take_word(words)?;
+                SchemaValue::List(
+                    (0..len)
+                        .map(|_| ty.decode(words))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            Self::Array(ty, len) => SchemaValue::List(
+                (0..*len)
+                    .map(|_| ty.decode(words))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Self::Tuple(tys) => SchemaValue::List(
+                tys.iter()
+                    .map(|ty| ty.decode(words))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Self::Struct(fields) => SchemaValue::Struct(
+                fields
+                    .iter()
+                    .map(|field| Ok((field.name.clone(), field.ty.decode(words)?)))
+                    .collect::<Result<_, Error>>()?,
+            ),
+        })
+    }
+}
+
+macro_rules! impl_journal_type {
+    ($($ty:ty => $schema:ident),* $(,)?) => {
+        $(
+            impl JournalType for $ty {
+                fn schema_type() -> SchemaType {
+                    SchemaType::$schema
+                }
+            }
+        )*
+    };
+}
+
+impl_journal_type! {
+    bool => Bool,
+    u8 => U8,
+    u16 => U16,
+    u32 => U32,
+    u64 => U64,
+    i8 => I8,
+    i16 => I16,
+    i32 => I32,
+    i64 => I64,
+    String => String,
+    Digest => Digest,
+}
+
+impl<T: JournalType> JournalType for Option<T> {
+    fn schema_type() -> SchemaType {
+        SchemaType::Option(Box::new(T::schema_type()))
+    }
+}
+
+impl<T: JournalType> JournalType for Vec<T> {
+    fn schema_type() -> SchemaType {
+        SchemaType::Vec(Box::new(T::schema_type()))
+    }
+}
+
+impl<T: JournalType, const N: usize> JournalType for [T; N] {
+    fn schema_type() -> SchemaType {
+        SchemaType::Array(Box::new(T::schema_type()), N as u32)
+    }
+}
+
+macro_rules! impl_journal_type_tuple {
+    ($($name:ident)+) => {
+        impl<$($name: JournalType),+> JournalType for ($($name,)+) {
+            fn schema_type() -> SchemaType {
+                SchemaType::Tuple(Vec::from([$($name::schema_type()),+]))
+            }
+        }
+    };
+}
+
+impl_journal_type_tuple! { A B }
+impl_journal_type_tuple! { A B C }
+impl_journal_type_tuple! { A B C D }
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, string::String, vec, vec::Vec};
+
+    use risc0_binfmt::Digestible;
+    use serde::{Deserialize, Serialize};
+
+    use super::{JournalSchema, SchemaType, SchemaValue};
+    use crate::{
+        serde::{to_vec, Error},
+        sha::Digest,
+        Journal,
+    };
+
+    crate::journal_schema! {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Output {
+            value: u64,
+            owner: String,
+            flags: Option<[bool; 2]>,
+            deltas: Vec<(i32, u8)>,
+        }
+    }
+
+    fn journal(schema: &JournalSchema, output: &Output) -> Journal {
+        let mut words = to_vec(&schema.digest::<crate::sha::Impl>()).unwrap();
+        words.extend(to_vec(output).unwrap());
+        Journal::new(bytemuck::cast_slice(&words).to_vec())
+    }
+
+    #[test]
+    fn decode() {
+        let output = Output {
+            value: 1 << 40,
+            owner: "alice".into(),
+            flags: Some([true, false]),
+            deltas: vec![(-1, 2), (3, 4)],
+        };
+        let schema = JournalSchema::of::<Output>();
+        let journal = journal(&schema, &output);
+
+        assert_eq!(journal.decode_with_schema::<Output>().unwrap(), output);
+        assert_eq!(
+            schema.decode(&journal).unwrap(),
+            SchemaValue::Struct(vec![
+                ("value".into(), SchemaValue::Unsigned(1 << 40)),
+                ("owner".into(), SchemaValue::String("alice".into())),
+                (
+                    "flags".into(),
+                    SchemaValue::Option(Some(Box::new(SchemaValue::List(vec![
+                        SchemaValue::Bool(true),
+                        SchemaValue::Bool(false),
+                    ]))))
+                ),
+                (
+                    "deltas".into(),
+                    SchemaValue::List(vec![
+                        SchemaValue::List(vec![SchemaValue::Signed(-1), SchemaValue::Unsigned(2)]),
+                        SchemaValue::List(vec![SchemaValue::Signed(3), SchemaValue::Unsigned(4)]),
+                    ])
+                ),
+            ])
+        );
+
+        let other = JournalSchema {
+            root: SchemaType::U64,
+        };
+        assert!(matches!(other.decode(&journal), Err(Error::Custom(_))));
+        assert_eq!(
+            Journal::new(vec![0; 4]).schema_digest().unwrap_err(),
+            Error::DeserializeUnexpectedEnd
+        );
+        assert_ne!(other.digest::<crate::sha::Impl>(), Digest::ZERO);
+    }
+}
@@ -0,0 +1,235 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `#[risc0_test]` attribute, which runs the tests in a module inside the RISC Zero zkVM.
+//!
+//! This crate is re-exported by `risc0-zkvm` with the `test-macro` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, ToTokens};
+use syn::{parse_macro_input, parse_quote, Attribute, Error, Expr, Item, ItemMod, Lit, Meta};
+
+/// Run the `#[test]` functions of a module inside the zkVM.
+///
+/// Applied to an inline module in a `#![no_main]` guest binary, this generates the entry point of
+/// the guest, which runs the test selected by the host. The tests are run from the host with
+/// `risc0_zkvm::GuestTestRunner`, which executes each test in its own session and can prove a
+/// random sample of them. When the module is built for the host, the tests are ordinary unit
+/// tests.
+///
+/// `#[ignore]` and `#[should_panic]`, with or without an `expected` message, are honored in both
+/// cases. Other attributes on a test are kept, but do not affect how the guest runs it.
+///
+/// # Example
+///
+/// ```ignore
+/// #![no_main]
+///
+/// #[risc0_zkvm::risc0_test]
+/// mod tests {
+///     #[test]
+///     fn adds() {
+///         assert_eq!(1 + 1, 2);
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn risc0_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return Error::new_spanned(TokenStream2::from(attr), "unexpected arguments")
+            .into_compile_error()
+            .into();
+    }
+    let module = parse_macro_input!(item as ItemMod);
+    expand(module)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(mut module: ItemMod) -> syn::Result<TokenStream2> {
+    if module.content.is_none() {
+        return Err(Error::new_spanned(
+            &module,
+            "#[risc0_test] must be applied to an inline module",
+        ));
+    }
+    let (_, items) = module.content.as_mut().unwrap();
+
+    let mut tests = Vec::new();
+    for item in items.iter_mut() {
+        let Item::Fn(func) = item else {
+            continue;
+        };
+        let Some(pos) = func
+            .attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("test"))
+        else {
+            continue;
+        };
+        if !func.sig.inputs.is_empty() || !matches!(func.sig.output, syn::ReturnType::Default) {
+            return Err(Error::new_spanned(
+                &func.sig,
+                "#[risc0_test] tests must take no arguments and return nothing",
+            ));
+        }
+        // Tests remain ordinary unit tests when the module is built for the host.
+        func.attrs[pos] = parse_quote!(#[cfg_attr(not(target_os = "zkvm"), test)]);
+
+        let mut ignore = false;
+        let mut should_panic = quote!(::core::option::Option::None);
+        for attr in func.attrs.iter_mut() {
+            if attr.path().is_ident("ignore") {
+                ignore = true;
+            } else if attr.path().is_ident("should_panic") {
+                should_panic = match should_panic_expected(attr)? {
+                    Some(expected) => quote! {
+                        ::core::option::Option::Some(::core::option::Option::Some(#expected))
+                    },
+                    None => quote!(::core::option::Option::Some(::core::option::Option::None)),
+                };
+            } else {
+                continue;
+            }
+            // These only apply to unit tests, so the guest build would warn about them.
+            let meta = &attr.meta;
+            *attr = parse_quote!(#[cfg_attr(not(target_os = "zkvm"), #meta)]);
+        }
+
+        let ident = &func.sig.ident;
+        let name = ident.to_string();
+        tests.push(quote! {
+            ::risc0_zkvm::guest::test::Test {
+                name: #name,
+                run: #ident as fn(),
+                ignore: #ignore,
+                should_panic: #should_panic,
+            }
+        });
+    }
+
+    // The dispatcher lives in the module so that it can call private tests.
+    items.push(parse_quote! {
+        #[cfg(target_os = "zkvm")]
+        ::risc0_zkvm::entry!(__risc0_test_main);
+    });
+    items.push(parse_quote! {
+        #[cfg(target_os = "zkvm")]
+        fn __risc0_test_main() {
+            ::risc0_zkvm::guest::test::run(&[#(#tests),*]);
+        }
+    });
+    Ok(module.into_token_stream())
+}
+
+// Returns the message given by `#[should_panic = "..."]` or `#[should_panic(expected = "...")]`.
+fn should_panic_expected(attr: &Attribute) -> syn::Result<Option<String>> {
+    match &attr.meta {
+        Meta::Path(_) => Ok(None),
+        Meta::NameValue(meta) => match &meta.value {
+            Expr::Lit(expr) => match &expr.lit {
+                Lit::Str(expected) => Ok(Some(expected.value())),
+                lit => Err(Error::new_spanned(lit, "expected a string literal")),
+            },
+            value => Err(Error::new_spanned(value, "expected a string literal")),
+        },
+        Meta::List(_) => {
+            let mut expected = None;
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("expected") {
+                    let value: syn::LitStr = meta.value()?.parse()?;
+                    expected = Some(value.value());
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `expected = \"...\"`"))
+                }
+            })?;
+            Ok(expected)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+    use syn::parse_quote;
+
+    use super::expand;
+
+    #[test]
+    fn expands_tests() {
+        let expanded = expand(parse_quote! {
+            mod tests {
+                #[test]
+                fn adds() {}
+
+                fn helper() {}
+            }
+        })
+        .unwrap()
+        .to_string();
+        let test_attr = quote!(#[cfg_attr(not(target_os = "zkvm"), test)]).to_string();
+        assert!(expanded.contains(&test_attr));
+        assert!(expanded.contains(&quote!(name: "adds", run: adds as fn()).to_string()));
+        assert!(!expanded.contains(&quote!(helper as fn()).to_string()));
+    }
+
+    #[test]
+    fn expands_ignore_and_should_panic() {
+        let expanded = expand(parse_quote! {
+            mod tests {
+                #[test]
+                #[ignore]
+                fn skipped() {}
+
+                #[test]
+                #[should_panic(expected = "boom")]
+                fn panics() {}
+            }
+        })
+        .unwrap()
+        .to_string();
+        let ignore_attr = quote!(#[cfg_attr(not(target_os = "zkvm"), ignore)]).to_string();
+        assert!(expanded.contains(&ignore_attr));
+        assert!(expanded.contains(&quote!(ignore: true).to_string()));
+        let expected = quote! {
+            should_panic: ::core::option::Option::Some(::core::option::Option::Some("boom"))
+        };
+        assert!(expanded.contains(&expected.to_string()));
+    }
+
+    #[test]
+    fn rejects_malformed_should_panic() {
+        assert!(expand(parse_quote! {
+            mod tests {
+                #[test]
+                #[should_panic(reason = "boom")]
+                fn panics() {}
+            }
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_arguments() {
+        assert!(expand(parse_quote! {
+            mod tests {
+                #[test]
+                fn adds(x: u32) {}
+            }
+        })
+        .is_err());
+    }
+}
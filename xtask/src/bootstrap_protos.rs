@@ -29,7 +29,9 @@ impl BootstrapProtos {
             )
             .unwrap();
 
+        // Maps are ordered so that encoded requests are reproducible.
         prost_build::Config::new()
+            .btree_map(["."])
             .out_dir("risc0/zkvm/src/host/protos")
             .compile_protos(
                 &[